@@ -0,0 +1,348 @@
+// The condition of breakpoint such as `a > 1 and bar_index >= 10`. The clauses are
+// joined by `and`/`&&` and `or`/`||` where `and` has the higher precedence.
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CmpOp {
+    fn cmp(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            CmpOp::Eq => lhs == rhs,
+            CmpOp::Ne => lhs != rhs,
+            CmpOp::Gt => lhs > rhs,
+            CmpOp::Ge => lhs >= rhs,
+            CmpOp::Lt => lhs < rhs,
+            CmpOp::Le => lhs <= rhs,
+        }
+    }
+}
+
+// The variable with the history offset such as `close[1]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarRef {
+    pub name: String,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Num(f64),
+    Na,
+    Var(VarRef),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    lhs: Operand,
+    cmp: Option<(CmpOp, Operand)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    // The clauses in the inner vector are joined by `and`, the outer by `or`.
+    clauses: Vec<Vec<Clause>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Cmp(CmpOp),
+    And,
+    Or,
+    LBracket,
+    RBracket,
+}
+
+fn take_while<F: Fn(char) -> bool>(chars: &mut Peekable<Chars>, f: F) -> String {
+    let mut s = String::new();
+    while let Some(c) = chars.peek() {
+        if !f(*c) {
+            break;
+        }
+        s.push(*c);
+        chars.next();
+    }
+    s
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.peek().cloned() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' || c == '-' {
+            chars.next();
+            let num = take_while(&mut chars, |c| c.is_ascii_digit() || c == '.');
+            match format!("{}{}", c, num).parse() {
+                Ok(n) => tokens.push(Token::Num(n)),
+                Err(_) => return Err(format!("Invalid number {}{}", c, num)),
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let ident = take_while(&mut chars, |c| c.is_alphanumeric() || c == '_' || c == '.');
+            match ident.as_str() {
+                "and" => tokens.push(Token::And),
+                "or" => tokens.push(Token::Or),
+                _ => tokens.push(Token::Ident(ident)),
+            }
+        } else {
+            let op = take_while(&mut chars, |c| "=!<>&|[]".contains(c));
+            if op.is_empty() {
+                return Err(format!("Unexpected character {}", c));
+            }
+            let mut rest = op.as_str();
+            while !rest.is_empty() {
+                let (token, len) = match rest {
+                    _ if rest.starts_with("==") => (Token::Cmp(CmpOp::Eq), 2),
+                    _ if rest.starts_with("!=") => (Token::Cmp(CmpOp::Ne), 2),
+                    _ if rest.starts_with(">=") => (Token::Cmp(CmpOp::Ge), 2),
+                    _ if rest.starts_with("<=") => (Token::Cmp(CmpOp::Le), 2),
+                    _ if rest.starts_with("&&") => (Token::And, 2),
+                    _ if rest.starts_with("||") => (Token::Or, 2),
+                    _ if rest.starts_with('>') => (Token::Cmp(CmpOp::Gt), 1),
+                    _ if rest.starts_with('<') => (Token::Cmp(CmpOp::Lt), 1),
+                    _ if rest.starts_with('[') => (Token::LBracket, 1),
+                    _ if rest.starts_with(']') => (Token::RBracket, 1),
+                    _ => return Err(format!("Unexpected character {}", c)),
+                };
+                tokens.push(token);
+                rest = &rest[len..];
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Operand::Num(n)),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "na" => Ok(Operand::Na),
+                "true" => Ok(Operand::Num(1f64)),
+                "false" => Ok(Operand::Num(0f64)),
+                _ => {
+                    let mut offset = 0;
+                    if self.peek() == Some(&Token::LBracket) {
+                        self.next();
+                        offset = match (self.next(), self.next()) {
+                            (Some(Token::Num(n)), Some(Token::RBracket))
+                                if n >= 0f64 && n.fract() == 0f64 =>
+                            {
+                                n as usize
+                            }
+                            _ => return Err(format!("Invalid history offset of {}", name)),
+                        };
+                    }
+                    Ok(Operand::Var(VarRef { name, offset }))
+                }
+            },
+            Some(token) => Err(format!("Unexpected token {:?}", token)),
+            None => Err(String::from("Unexpected end of condition")),
+        }
+    }
+
+    fn clause(&mut self) -> Result<Clause, String> {
+        let lhs = self.operand()?;
+        if let Some(Token::Cmp(op)) = self.peek().cloned() {
+            self.next();
+            let rhs = self.operand()?;
+            return Ok(Clause {
+                lhs,
+                cmp: Some((op, rhs)),
+            });
+        }
+        Ok(Clause { lhs, cmp: None })
+    }
+}
+
+// Parse the single operand such as `close` or `a[2]`, None is returned for other expressions.
+pub fn parse_operand(src: &str) -> Option<Operand> {
+    let mut parser = Parser {
+        tokens: tokenize(src).ok()?,
+        pos: 0,
+    };
+    let operand = parser.operand().ok()?;
+    if parser.peek().is_none() {
+        Some(operand)
+    } else {
+        None
+    }
+}
+
+impl Condition {
+    pub fn parse(src: &str) -> Result<Condition, String> {
+        let mut parser = Parser {
+            tokens: tokenize(src)?,
+            pos: 0,
+        };
+        let mut clauses = vec![vec![parser.clause()?]];
+        while let Some(token) = parser.next() {
+            match token {
+                Token::And => clauses.last_mut().unwrap().push(parser.clause()?),
+                Token::Or => clauses.push(vec![parser.clause()?]),
+                _ => return Err(format!("Unexpected token {:?}", token)),
+            }
+        }
+        Ok(Condition { clauses })
+    }
+
+    // The lookup function returns the numeric value of the variable or None for na, the bool
+    // value is 1 for true and 0 for false. The comparisons with na are false except `==`
+    // and `!=` which check whether the value is na.
+    pub fn eval<F>(&self, lookup: F) -> Result<bool, String>
+    where
+        F: Fn(&VarRef) -> Result<Option<f64>, String>,
+    {
+        let value = |operand: &Operand| match operand {
+            Operand::Num(n) => Ok(Some(*n)),
+            Operand::Na => Ok(None),
+            Operand::Var(var) => lookup(var),
+        };
+        for clauses in self.clauses.iter() {
+            let mut res = true;
+            for clause in clauses.iter() {
+                let lhs = value(&clause.lhs)?;
+                let is_true = match &clause.cmp {
+                    None => lhs.is_some_and(|v| v != 0f64),
+                    Some((op, rhs)) => match (lhs, value(rhs)?) {
+                        (Some(l), Some(r)) => op.cmp(l, r),
+                        (l, r) if *op == CmpOp::Eq => l.is_none() && r.is_none(),
+                        (l, r) if *op == CmpOp::Ne => l.is_some() || r.is_some(),
+                        _ => false,
+                    },
+                };
+                if !is_true {
+                    res = false;
+                    break;
+                }
+            }
+            if res {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+// The hit condition of breakpoint such as `5`, `>= 5` or `% 5`. The breakpoint stops when
+// the hit count satisfies the condition, the single number means `>=`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HitCondition {
+    Cmp(CmpOp, u64),
+    Mod(u64),
+}
+
+impl HitCondition {
+    pub fn parse(src: &str) -> Result<HitCondition, String> {
+        let src = src.trim();
+        let (op, num) = match src.find(|c: char| c.is_ascii_digit()) {
+            Some(pos) => src.split_at(pos),
+            None => return Err(format!("Invalid hit condition {}", src)),
+        };
+        let num: u64 = match num.trim().parse() {
+            Ok(n) => n,
+            Err(_) => return Err(format!("Invalid hit condition {}", src)),
+        };
+        match op.trim() {
+            "" | ">=" => Ok(HitCondition::Cmp(CmpOp::Ge, num)),
+            "==" | "=" => Ok(HitCondition::Cmp(CmpOp::Eq, num)),
+            ">" => Ok(HitCondition::Cmp(CmpOp::Gt, num)),
+            "<" => Ok(HitCondition::Cmp(CmpOp::Lt, num)),
+            "<=" => Ok(HitCondition::Cmp(CmpOp::Le, num)),
+            "%" if num > 0 => Ok(HitCondition::Mod(num)),
+            _ => Err(format!("Invalid hit condition {}", src)),
+        }
+    }
+
+    pub fn is_hit(&self, count: u64) -> bool {
+        match self {
+            HitCondition::Cmp(op, n) => op.cmp(count as f64, *n as f64),
+            HitCondition::Mod(n) => count.is_multiple_of(*n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(var: &VarRef) -> Result<Option<f64>, String> {
+        match (var.name.as_str(), var.offset) {
+            ("a", 0) => Ok(Some(2f64)),
+            ("a", 1) => Ok(Some(1f64)),
+            ("b", _) => Ok(None),
+            ("c", _) => Ok(Some(0f64)),
+            _ => Err(format!("Unknown variable {}", var.name)),
+        }
+    }
+
+    fn eval(src: &str) -> Result<bool, String> {
+        Condition::parse(src)?.eval(lookup)
+    }
+
+    #[test]
+    fn condition_test() {
+        assert_eq!(eval("a > 1"), Ok(true));
+        assert_eq!(eval("a[1]>=2"), Ok(false));
+        assert_eq!(eval("a == 2 and c"), Ok(false));
+        assert_eq!(eval("a == 2 && c || a[1] == 1"), Ok(true));
+        assert_eq!(eval("b == na and a != na"), Ok(true));
+        assert_eq!(eval("b < 1 or b"), Ok(false));
+        assert_eq!(eval("a > -1.5"), Ok(true));
+        assert_eq!(eval("d > 1"), Err(String::from("Unknown variable d")));
+        assert!(Condition::parse("a >").is_err());
+        assert!(Condition::parse("a > 1 b").is_err());
+        assert!(Condition::parse("a[x] > 1").is_err());
+        assert!(Condition::parse("a + 1").is_err());
+
+        assert_eq!(
+            parse_operand(" close[2] "),
+            Some(Operand::Var(VarRef {
+                name: String::from("close"),
+                offset: 2
+            }))
+        );
+        assert_eq!(parse_operand("a > 1"), None);
+    }
+
+    #[test]
+    fn hit_condition_test() {
+        assert_eq!(
+            HitCondition::parse("5"),
+            Ok(HitCondition::Cmp(CmpOp::Ge, 5))
+        );
+        assert_eq!(HitCondition::parse("% 3"), Ok(HitCondition::Mod(3)));
+        assert!(HitCondition::parse("%0").is_err());
+        assert!(HitCondition::parse("abc").is_err());
+        assert!(HitCondition::parse("== 2").unwrap().is_hit(2));
+        assert!(!HitCondition::parse("== 2").unwrap().is_hit(3));
+        assert!(HitCondition::parse("%2").unwrap().is_hit(4));
+        assert!(!HitCondition::parse(">= 5").unwrap().is_hit(4));
+    }
+}
@@ -0,0 +1,119 @@
+use pine::runtime::AnySeries;
+use std::fs;
+
+// The data columns that can be read from the csv file. The time column is the
+// timestamp in milliseconds and is provided to the script as `_time`.
+const FLOAT_COLUMNS: [&str; 4] = ["open", "high", "low", "close"];
+
+enum Column {
+    Float(&'static str, Vec<Option<f64>>),
+    Int(&'static str, Vec<Option<i64>>),
+    Ignore,
+}
+
+fn parse_float(val: &str) -> Result<Option<f64>, ()> {
+    match val {
+        "" | "na" | "NaN" => Ok(None),
+        _ => val.parse().map(Some).map_err(|_| ()),
+    }
+}
+
+fn parse_int(val: &str) -> Result<Option<i64>, ()> {
+    match val.parse::<i64>() {
+        Ok(v) => Ok(Some(v)),
+        Err(_) => parse_float(val).map(|v| v.map(|v| v as i64)),
+    }
+}
+
+fn new_column(name: &str) -> Column {
+    let name = name.trim().to_lowercase();
+    if let Some(name) = FLOAT_COLUMNS.iter().find(|n| **n == name) {
+        Column::Float(name, vec![])
+    } else if name == "volume" {
+        Column::Int("volume", vec![])
+    } else if name == "time" {
+        Column::Int("_time", vec![])
+    } else {
+        Column::Ignore
+    }
+}
+
+// Parse the bars from the csv content. The first line is the header which contains
+// the columns of time, open, high, low, close and volume, the other columns are ignored.
+pub fn parse_csv(content: &str) -> Result<Vec<(&'static str, AnySeries)>, String> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+    let mut columns: Vec<Column> = match lines.next() {
+        Some((_, header)) => header.split(',').map(new_column).collect(),
+        None => return Err(String::from("The data file is empty")),
+    };
+    if columns.iter().all(|c| matches!(c, Column::Ignore)) {
+        return Err(String::from(
+            "The data file must contain the time, open, high, low, close or volume column",
+        ));
+    }
+
+    for (i, line) in lines {
+        let vals: Vec<_> = line.split(',').map(|v| v.trim()).collect();
+        if vals.len() != columns.len() {
+            return Err(format!("Line {}: expect {} values", i + 1, columns.len()));
+        }
+        for (col, val) in columns.iter_mut().zip(vals) {
+            let res = match col {
+                Column::Float(_, v) => parse_float(val).map(|f| v.push(f)),
+                Column::Int(_, v) => parse_int(val).map(|n| v.push(n)),
+                Column::Ignore => Ok(()),
+            };
+            if res.is_err() {
+                return Err(format!("Line {}: invalid number {}", i + 1, val));
+            }
+        }
+    }
+
+    Ok(columns
+        .into_iter()
+        .filter_map(|col| match col {
+            Column::Float(name, v) => Some((name, AnySeries::from_float_vec(v))),
+            Column::Int(name, v) => Some((name, AnySeries::from_int_vec(v))),
+            Column::Ignore => None,
+        })
+        .collect())
+}
+
+pub fn load_csv(path: &str) -> Result<Vec<(&'static str, AnySeries)>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_csv(&content),
+        Err(err) => Err(format!("Cannot read the data file {}: {}", path, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_csv_test() {
+        let content = "Time,Close,Volume,Symbol\n100,1.5,10,A\n\n200,na,2.0,A\n";
+        let data = parse_csv(content).unwrap();
+        let names: Vec<_> = data.iter().map(|d| d.0).collect();
+        assert_eq!(names, vec!["_time", "close", "volume"]);
+        assert_eq!(
+            *data[0].1.as_vec::<Option<i64>>(),
+            vec![Some(100), Some(200)]
+        );
+        assert_eq!(*data[1].1.as_vec::<Option<f64>>(), vec![Some(1.5), None]);
+        assert_eq!(*data[2].1.as_vec::<Option<i64>>(), vec![Some(10), Some(2)]);
+        assert_eq!(
+            parse_csv("close\n1\nx").err(),
+            Some(String::from("Line 3: invalid number x"))
+        );
+        assert_eq!(
+            parse_csv("close,open\n1").err(),
+            Some(String::from("Line 2: expect 2 values"))
+        );
+        assert!(parse_csv("a,b\n1,2").is_err());
+        assert!(parse_csv("").is_err());
+    }
+}
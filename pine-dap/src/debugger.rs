@@ -0,0 +1,495 @@
+use crate::condition::{parse_operand, Condition, Operand, VarRef};
+use crate::protocol::Request;
+use crate::session::{Session, THREAD_ID};
+use pine::ast::input::StrRange;
+use pine::ast::stat_expr_types::{Block, VarIndex};
+use pine::runtime::context::Ctx;
+use pine::runtime::debug::{inspect_var, Debugger, ScopeMap, VarInspect, VarValue};
+use pine::types::PineRef;
+use serde_json::{json, Value};
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::ops::Range;
+use std::path::Path;
+
+// The maximum count of the history values listed for the series variable.
+const MAX_HISTORY: usize = 100;
+
+// The builtin series listed in the Bar scope, the time series is declared as `_time`.
+const BAR_VARS: [(&str, &str); 7] = [
+    ("bar_index", "bar_index"),
+    ("time", "_time"),
+    ("open", "open"),
+    ("high", "high"),
+    ("low", "low"),
+    ("close", "close"),
+    ("volume", "volume"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StepMode {
+    Continue,
+    // Stop at the next statement.
+    In,
+    // Stop at the next statement of the frame with the depth or the outer frames.
+    Over(usize),
+    // Stop at the next statement of the frames outer than the depth.
+    Out(usize),
+    // Stop at the first statement of the bar after this bar.
+    NextBar(i32),
+}
+
+struct BlockFrame<'a> {
+    blk: &'a Block<'a>,
+    // The context of the running block, it's only read when the script is paused
+    // in the hooks where all the contexts of the running blocks are alive.
+    ctx: *const (dyn Ctx<'a> + 'a),
+    // The range of the running statement.
+    range: StrRange,
+}
+
+// The stack frame shown to the client. It's the main block or the body of user function
+// with the nested blocks of if and for statements.
+struct StackFrame {
+    name: String,
+    range: StrRange,
+    blocks: Range<usize>,
+}
+
+pub struct DapDebugger<'a, 'c> {
+    session: RefCell<&'c mut Session>,
+    scopes: ScopeMap<'a>,
+    path: String,
+    blocks: RefCell<Vec<BlockFrame<'a>>>,
+    step: Cell<StepMode>,
+    // Whether to pause at the first statement.
+    entry: Cell<bool>,
+    bar_index: Cell<i32>,
+    // The variables of the variable references created after the script paused.
+    vars: RefCell<Vec<Vec<Value>>>,
+}
+
+fn num_value(val: Option<VarValue>) -> Result<Option<f64>, String> {
+    match val {
+        None | Some(VarValue::Float(None)) | Some(VarValue::Int(None)) => Ok(None),
+        Some(VarValue::Float(Some(v))) => Ok(Some(v)),
+        Some(VarValue::Int(Some(v))) => Ok(Some(v as f64)),
+        Some(VarValue::Bool(v)) => Ok(Some(if v { 1f64 } else { 0f64 })),
+        Some(v) => Err(format!("The value {} is not a number", v)),
+    }
+}
+
+fn type_name(inspect: &VarInspect) -> String {
+    let t = match inspect.current {
+        VarValue::Float(_) => "float",
+        VarValue::Int(_) => "int",
+        VarValue::Bool(_) => "bool",
+        VarValue::String(_) => "string",
+        VarValue::Color(_) => "color",
+    };
+    if inspect.series {
+        format!("series {}", t)
+    } else {
+        String::from(t)
+    }
+}
+
+// The value of the history offset, the history values are ordered from the oldest bar.
+fn value_at(inspect: VarInspect, offset: usize) -> Option<VarValue> {
+    if offset == 0 {
+        Some(inspect.current)
+    } else if offset <= inspect.history.len() {
+        inspect.history.into_iter().rev().nth(offset - 1)
+    } else {
+        None
+    }
+}
+
+impl<'a, 'c> DapDebugger<'a, 'c> {
+    pub fn new(
+        session: &'c mut Session,
+        scopes: ScopeMap<'a>,
+        path: &str,
+        stop_on_entry: bool,
+    ) -> DapDebugger<'a, 'c> {
+        DapDebugger {
+            session: RefCell::new(session),
+            scopes,
+            path: String::from(path),
+            blocks: RefCell::new(vec![]),
+            step: Cell::new(StepMode::Continue),
+            entry: Cell::new(stop_on_entry),
+            bar_index: Cell::new(0),
+            vars: RefCell::new(vec![]),
+        }
+    }
+
+    fn is_func_block(&self, blk: &'a Block<'a>) -> Option<&'a str> {
+        self.scopes
+            .get(&(blk as *const Block))
+            .and_then(|s| s.func_name)
+    }
+
+    // The stack frames from the innermost frame.
+    fn stack_frames(&self) -> Vec<StackFrame> {
+        let blocks = self.blocks.borrow();
+        let mut frames = vec![];
+        let mut end = blocks.len();
+        for i in (0..blocks.len()).rev() {
+            let func_name = self.is_func_block(blocks[i].blk);
+            if i == 0 || func_name.is_some() {
+                let name = match func_name {
+                    Some(name) => String::from(name),
+                    None => format!("main (bar {})", self.bar_index.get()),
+                };
+                frames.push(StackFrame {
+                    name,
+                    range: blocks[end - 1].range,
+                    blocks: i..end,
+                });
+                end = i;
+            }
+        }
+        frames
+    }
+
+    fn frame_depth(&self) -> usize {
+        let blocks = self.blocks.borrow();
+        1 + blocks
+            .iter()
+            .skip(1)
+            .filter(|b| self.is_func_block(b.blk).is_some())
+            .count()
+    }
+
+    fn read_block_vars<F>(&self, index: usize, mut f: F)
+    where
+        F: FnMut(&'a str, &PineRef<'a>) -> bool,
+    {
+        let blocks = self.blocks.borrow();
+        let frame = &blocks[index];
+        let ctx = unsafe { &*frame.ctx };
+        if let Some(scope) = self.scopes.get(&(frame.blk as *const Block)) {
+            for (name, varid) in scope.vars.iter() {
+                if let Some(val) = ctx.get_var(VarIndex::new(*varid, 0)) {
+                    if !f(name, val) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_builtin_var(&self, name: &str) -> Option<VarInspect> {
+        let blocks = self.blocks.borrow();
+        let ctx = unsafe { &*blocks.last()?.ctx };
+        let name = BAR_VARS.iter().find(|v| v.0 == name).map_or(name, |v| v.1);
+        let index = ctx.get_top_varname_index(name)?;
+        ctx.get_var(index).as_ref().and_then(inspect_var)
+    }
+
+    // Find the variable from the innermost block of the frame to the main block.
+    fn lookup(&self, frame: &StackFrame, var: &VarRef) -> Result<Option<VarValue>, String> {
+        let mut indexes: Vec<_> = frame.blocks.clone().rev().collect();
+        if frame.blocks.start != 0 {
+            indexes.push(0);
+        }
+        for index in indexes {
+            let mut found = None;
+            self.read_block_vars(index, |name, val| {
+                if name == var.name {
+                    found = Some(inspect_var(val));
+                    false
+                } else {
+                    true
+                }
+            });
+            match found {
+                Some(Some(inspect)) => return Ok(value_at(inspect, var.offset)),
+                Some(None) => return Err(format!("The variable {} can't be read", var.name)),
+                None => {}
+            }
+        }
+        match self.read_builtin_var(&var.name) {
+            Some(inspect) => Ok(value_at(inspect, var.offset)),
+            None => Err(format!("Unknown variable {}", var.name)),
+        }
+    }
+
+    fn add_vars(&self, vars: Vec<Value>) -> usize {
+        let mut all_vars = self.vars.borrow_mut();
+        all_vars.push(vars);
+        all_vars.len()
+    }
+
+    fn var_json(&self, name: &str, val: &PineRef<'a>) -> Value {
+        match inspect_var(val) {
+            Some(inspect) => {
+                let mut reference = 0;
+                if inspect.series && !inspect.history.is_empty() {
+                    let history = inspect
+                        .history
+                        .iter()
+                        .rev()
+                        .take(MAX_HISTORY)
+                        .enumerate()
+                        .map(|(i, v)| {
+                            json!({
+                                "name": format!("[{}]", i + 1),
+                                "value": v.to_string(),
+                                "variablesReference": 0,
+                            })
+                        })
+                        .collect();
+                    reference = self.add_vars(history);
+                }
+                json!({
+                    "name": name,
+                    "value": inspect.current.to_string(),
+                    "type": type_name(&inspect),
+                    "variablesReference": reference,
+                })
+            }
+            None => json!({
+                "name": name,
+                "value": format!("{:?}", val.get_type()),
+                "variablesReference": 0,
+            }),
+        }
+    }
+
+    fn scope_json(&self, name: &str, indexes: Vec<usize>) -> Value {
+        let mut vars = vec![];
+        for index in indexes {
+            self.read_block_vars(index, |name, val| {
+                vars.push(self.var_json(name, val));
+                true
+            });
+        }
+        json!({
+            "name": name,
+            "variablesReference": self.add_vars(vars),
+            "expensive": false,
+        })
+    }
+
+    fn scopes(&self, frame: &StackFrame) -> Value {
+        let mut scopes = vec![self.scope_json("Locals", frame.blocks.clone().rev().collect())];
+        if frame.blocks.start != 0 {
+            scopes.push(self.scope_json("Globals", vec![0]));
+        }
+
+        let blocks = self.blocks.borrow();
+        let ctx = unsafe { &*blocks[0].ctx };
+        let bar_vars = BAR_VARS
+            .iter()
+            .filter_map(|(name, var_name)| {
+                let index = ctx.get_top_varname_index(var_name)?;
+                Some(self.var_json(name, ctx.get_var(index).as_ref()?))
+            })
+            .collect();
+        scopes.push(json!({
+            "name": "Bar",
+            "variablesReference": self.add_vars(bar_vars),
+            "expensive": false,
+        }));
+        json!({ "scopes": scopes })
+    }
+
+    fn stack_trace(&self, frames: &[StackFrame]) -> Value {
+        let source_name = Path::new(&self.path)
+            .file_name()
+            .map_or(self.path.clone(), |n| n.to_string_lossy().into_owned());
+        let frames: Vec<_> = frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                json!({
+                    "id": i,
+                    "name": frame.name,
+                    "line": frame.range.start.get_line() + 1,
+                    "column": frame.range.start.get_character() + 1,
+                    "endLine": frame.range.end.get_line() + 1,
+                    "endColumn": frame.range.end.get_character() + 1,
+                    "source": { "name": source_name, "path": self.path },
+                })
+            })
+            .collect();
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    // Evaluate the variable such as `close[1]` or the condition such as `a > 1`.
+    fn evaluate(&self, frame: &StackFrame, expr: &str) -> Result<String, String> {
+        if let Some(Operand::Var(var)) = parse_operand(expr) {
+            return match self.lookup(frame, &var)? {
+                Some(val) => Ok(val.to_string()),
+                None => Ok(String::from("na")),
+            };
+        }
+        let condition = Condition::parse(expr)?;
+        let res = condition.eval(|var| num_value(self.lookup(frame, var)?))?;
+        Ok(res.to_string())
+    }
+
+    fn handle_paused_request(&self, request: &Request) {
+        let frames = self.stack_frames();
+        let frame_id = request.arguments["frameId"].as_u64().unwrap_or(0) as usize;
+        let body = match request.command.as_str() {
+            "stackTrace" => Ok(self.stack_trace(&frames)),
+            "scopes" => match frames.get(frame_id) {
+                Some(frame) => Ok(self.scopes(frame)),
+                None => Err(String::from("Invalid frame id")),
+            },
+            "variables" => {
+                let reference = request.arguments["variablesReference"].as_u64();
+                let vars = self.vars.borrow();
+                match reference.and_then(|r| vars.get((r as usize).wrapping_sub(1))) {
+                    Some(vars) => Ok(json!({ "variables": vars })),
+                    None => Err(String::from("Invalid variables reference")),
+                }
+            }
+            "evaluate" => {
+                let expr = request.arguments["expression"].as_str().unwrap_or("");
+                match frames.get(frame_id) {
+                    Some(frame) => self
+                        .evaluate(frame, expr)
+                        .map(|res| json!({ "result": res, "variablesReference": 0 })),
+                    None => Err(String::from("Invalid frame id")),
+                }
+            }
+            _ => {
+                self.session.borrow_mut().handle_request(request);
+                return;
+            }
+        };
+        let mut session = self.session.borrow_mut();
+        match body {
+            Ok(body) => session.conn.send_response(request, body),
+            Err(err) => session.conn.send_error(request, &err),
+        }
+    }
+
+    // Send the stopped event and handle the requests until the client resumes the script.
+    fn pause(&self, reason: &str) {
+        self.vars.borrow_mut().clear();
+        self.session.borrow_mut().conn.send_event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+        loop {
+            let request = self.session.borrow_mut().conn.read_request();
+            let request = match request {
+                Some(request) => request,
+                None => {
+                    self.session.borrow_mut().disconnected = true;
+                    break;
+                }
+            };
+            let depth = self.frame_depth();
+            let step = match request.command.as_str() {
+                "continue" => Some(StepMode::Continue),
+                "next" => Some(StepMode::Over(depth)),
+                "stepIn" => Some(StepMode::In),
+                "stepOut" if depth > 1 => Some(StepMode::Out(depth)),
+                "stepOut" | "nextBar" => Some(StepMode::NextBar(self.bar_index.get())),
+                _ => None,
+            };
+            if let Some(step) = step {
+                self.step.set(step);
+                let mut session = self.session.borrow_mut();
+                session
+                    .conn
+                    .send_response(&request, json!({ "allThreadsContinued": true }));
+                return;
+            }
+            self.handle_paused_request(&request);
+            if self.session.borrow().disconnected {
+                break;
+            }
+        }
+        // Run the rest of the script without pausing after the client disconnected.
+        self.step.set(StepMode::Continue);
+    }
+
+    fn should_step(&self, bar_index: i32) -> bool {
+        match self.step.get() {
+            StepMode::Continue => false,
+            StepMode::In => true,
+            StepMode::Over(depth) => self.frame_depth() <= depth,
+            StepMode::Out(depth) => self.frame_depth() < depth,
+            StepMode::NextBar(bar) => bar_index != bar,
+        }
+    }
+
+    fn hit_breakpoint(&self, line: u32) -> bool {
+        let frames = self.stack_frames();
+        let mut session = self.session.borrow_mut();
+        let breakpoint = match session.breakpoints.get_mut(&line) {
+            Some(breakpoint) => breakpoint,
+            None => return false,
+        };
+        let mut error = None;
+        if let Some(condition) = &breakpoint.condition {
+            match condition.eval(|var| num_value(self.lookup(&frames[0], var)?)) {
+                Ok(false) => return false,
+                Ok(true) => {}
+                // Pause the script to let the user fix the condition.
+                Err(err) => error = Some(err),
+            }
+        }
+        breakpoint.hits += 1;
+        if let Some(hit_condition) = &breakpoint.hit_condition {
+            if !hit_condition.is_hit(breakpoint.hits) {
+                return false;
+            }
+        }
+        if let Some(err) = error {
+            session.conn.send_output(
+                "stderr",
+                format!("Breakpoint condition error at line {}: {}\n", line + 1, err),
+            );
+        }
+        true
+    }
+}
+
+impl<'a, 'c> Debugger<'a> for DapDebugger<'a, 'c> {
+    fn enter_block(&self, context: &mut dyn Ctx<'a>, blk: &'a Block<'a>) {
+        let ctx: *const (dyn Ctx<'a> + '_) = context;
+        self.blocks.borrow_mut().push(BlockFrame {
+            blk,
+            ctx: unsafe {
+                mem::transmute::<*const (dyn Ctx<'a> + '_), *const (dyn Ctx<'a> + 'a)>(ctx)
+            },
+            range: StrRange::new_empty(),
+        });
+    }
+
+    fn on_stmt(&self, context: &mut dyn Ctx<'a>, _blk: &'a Block<'a>, range: StrRange) {
+        if let Some(frame) = self.blocks.borrow_mut().last_mut() {
+            frame.range = range;
+        }
+        if self.session.borrow().disconnected {
+            return;
+        }
+
+        let bar_index = context.get_iterindex();
+        let reason = if self.entry.replace(false) {
+            Some("entry")
+        } else if self.should_step(bar_index) {
+            Some("step")
+        } else if self.hit_breakpoint(range.start.get_line()) {
+            Some("breakpoint")
+        } else {
+            None
+        };
+        self.bar_index.set(bar_index);
+        if let Some(reason) = reason {
+            self.pause(reason);
+        }
+    }
+
+    fn exit_block(&self, _context: &mut dyn Ctx<'a>, _blk: &'a Block<'a>) {
+        self.blocks.borrow_mut().pop();
+    }
+}
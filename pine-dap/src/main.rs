@@ -0,0 +1,17 @@
+mod condition;
+mod data;
+mod debugger;
+mod protocol;
+mod session;
+
+use protocol::Connection;
+use session::Session;
+use std::io::{self, BufReader};
+
+fn main() {
+    let conn = Connection::new(
+        Box::new(BufReader::new(io::stdin())),
+        Box::new(io::stdout()),
+    );
+    Session::new(conn).start();
+}
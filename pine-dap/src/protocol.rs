@@ -0,0 +1,156 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, Read, Write};
+
+// The request sent by the client, the arguments are kept as the raw json value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub seq: i64,
+    pub command: String,
+    pub arguments: Value,
+}
+
+impl Request {
+    pub fn from_json(msg: &str) -> Option<Request> {
+        let val: Value = serde_json::from_str(msg).ok()?;
+        if val["type"] != "request" {
+            return None;
+        }
+        Some(Request {
+            seq: val["seq"].as_i64()?,
+            command: String::from(val["command"].as_str()?),
+            arguments: val["arguments"].clone(),
+        })
+    }
+}
+
+// The connection to the client that sends and receives the messages of the debug adapter protocol.
+pub struct Connection {
+    reader: Box<dyn BufRead>,
+    writer: Box<dyn Write>,
+    seq: i64,
+}
+
+impl Connection {
+    pub fn new(reader: Box<dyn BufRead>, writer: Box<dyn Write>) -> Connection {
+        Connection {
+            reader,
+            writer,
+            seq: 0,
+        }
+    }
+
+    // Read the next request, the other messages are ignored. None is returned if the
+    // client has closed the input.
+    pub fn read_request(&mut self) -> Option<Request> {
+        loop {
+            let msg = read_message(self.reader.as_mut())?;
+            if let Some(request) = Request::from_json(&msg) {
+                return Some(request);
+            }
+        }
+    }
+
+    fn send(&mut self, mut msg: Value) {
+        self.seq += 1;
+        msg["seq"] = json!(self.seq);
+        send_message(self.writer.as_mut(), &msg.to_string());
+    }
+
+    pub fn send_response(&mut self, request: &Request, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "success": true,
+            "command": request.command,
+            "body": body,
+        }));
+    }
+
+    pub fn send_error(&mut self, request: &Request, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "success": false,
+            "command": request.command,
+            "message": message,
+        }));
+    }
+
+    pub fn send_event(&mut self, event: &str, body: Value) {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }));
+    }
+
+    pub fn send_output(&mut self, category: &str, output: String) {
+        self.send_event(
+            "output",
+            json!({
+                "category": category,
+                "output": output,
+            }),
+        );
+    }
+}
+
+// Read the content of the message, the headers are ended with an empty line and
+// only the Content-Length header is used.
+pub fn read_message(reader: &mut dyn BufRead) -> Option<String> {
+    let mut content_length = None;
+    loop {
+        let mut buffer = String::new();
+        if reader.read_line(&mut buffer).ok()? == 0 {
+            return None;
+        }
+        let line = buffer.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(len) = line.strip_prefix("Content-Length:") {
+            content_length = len.trim().parse::<u64>().ok();
+        }
+    }
+
+    let mut msg = String::new();
+    reader.take(content_length?).read_to_string(&mut msg).ok()?;
+    Some(msg)
+}
+
+pub fn send_message(writer: &mut dyn Write, msg: &str) {
+    write!(writer, "Content-Length: {}\r\n\r\n{}", msg.len(), msg).unwrap();
+    writer.flush().expect("Could not flush stdout");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    #[test]
+    fn message_test() {
+        let mut buf = vec![];
+        send_message(
+            &mut buf,
+            r#"{"seq":1,"type":"request","command":"threads"}"#,
+        );
+        buf.extend_from_slice(b"Content-Length: 22\r\nContent-Type: json\r\n\r\n");
+        buf.extend_from_slice(br#"{"type":"event","a":1}"#);
+
+        let mut reader = BufReader::new(buf.as_slice());
+        let msg = read_message(&mut reader).unwrap();
+        assert_eq!(
+            Request::from_json(&msg),
+            Some(Request {
+                seq: 1,
+                command: String::from("threads"),
+                arguments: Value::Null
+            })
+        );
+        let msg = read_message(&mut reader).unwrap();
+        assert_eq!(msg, r#"{"type":"event","a":1}"#);
+        assert_eq!(Request::from_json(&msg), None);
+        assert_eq!(read_message(&mut reader), None);
+    }
+}
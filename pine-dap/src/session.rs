@@ -0,0 +1,425 @@
+use crate::condition::{Condition, HitCondition};
+use crate::data::load_csv;
+use crate::debugger::DapDebugger;
+use crate::protocol::{Connection, Request};
+use pine::library::{FsLibraryResolver, LibraryArena};
+use pine::runtime::debug::collect_scopes;
+use pine::runtime::error_format::{ErrorFormater, PineFormatError};
+use pine::runtime::{AnySeries, NoneCallback};
+use pine::version::script_version;
+use pine::{lib_info_for_version, PineParser, PineRunner};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+
+// The script only runs in one thread.
+pub const THREAD_ID: i64 = 1;
+
+pub struct Breakpoint {
+    pub condition: Option<Condition>,
+    pub hit_condition: Option<HitCondition>,
+    pub hits: u64,
+}
+
+pub struct LaunchConfig {
+    pub program: String,
+    pub source: String,
+    pub data: Vec<(&'static str, AnySeries)>,
+    pub stop_on_entry: bool,
+    pub library_root: Option<String>,
+}
+
+pub struct Session {
+    pub conn: Connection,
+    // The breakpoints of the program, the key is the line number starting from 0.
+    pub breakpoints: HashMap<u32, Breakpoint>,
+    pub disconnected: bool,
+    launch: Option<LaunchConfig>,
+    configured: bool,
+    running: bool,
+}
+
+impl Session {
+    pub fn new(conn: Connection) -> Session {
+        Session {
+            conn,
+            breakpoints: HashMap::new(),
+            disconnected: false,
+            launch: None,
+            configured: false,
+            running: false,
+        }
+    }
+
+    pub fn start(&mut self) {
+        while !self.disconnected {
+            match self.conn.read_request() {
+                Some(request) => self.handle_request(&request),
+                None => break,
+            }
+        }
+    }
+
+    // Handle the requests that don't depend on the paused script.
+    pub fn handle_request(&mut self, request: &Request) {
+        match request.command.as_str() {
+            "initialize" => {
+                self.conn.send_response(
+                    request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsConditionalBreakpoints": true,
+                        "supportsHitConditionalBreakpoints": true,
+                        "supportsEvaluateForHovers": true,
+                        "supportsTerminateRequest": true,
+                    }),
+                );
+                self.conn.send_event("initialized", json!({}));
+            }
+            "launch" => match read_launch_config(&request.arguments) {
+                Ok(config) => {
+                    self.launch = Some(config);
+                    self.conn.send_response(request, Value::Null);
+                    self.try_run();
+                }
+                Err(err) => self.conn.send_error(request, &err),
+            },
+            "configurationDone" => {
+                self.configured = true;
+                self.conn.send_response(request, Value::Null);
+                self.try_run();
+            }
+            "setBreakpoints" => {
+                let body = self.set_breakpoints(&request.arguments);
+                self.conn.send_response(request, body);
+            }
+            "setExceptionBreakpoints" => {
+                self.conn
+                    .send_response(request, json!({ "breakpoints": [] }));
+            }
+            "threads" => {
+                self.conn.send_response(
+                    request,
+                    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+                );
+            }
+            "disconnect" | "terminate" => {
+                self.disconnected = true;
+                self.conn.send_response(request, Value::Null);
+            }
+            _ => self.conn.send_error(
+                request,
+                &format!("The request {} is not supported now", request.command),
+            ),
+        }
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Value {
+        self.breakpoints.clear();
+        let empty = vec![];
+        let breakpoints = args["breakpoints"].as_array().unwrap_or(&empty);
+        let results: Vec<_> = breakpoints
+            .iter()
+            .filter_map(|bp| {
+                let line = bp["line"].as_u64()? as u32;
+                let condition = bp["condition"].as_str().map(Condition::parse);
+                let hit_condition = bp["hitCondition"].as_str().map(HitCondition::parse);
+                match (condition.transpose(), hit_condition.transpose()) {
+                    (Ok(condition), Ok(hit_condition)) => {
+                        self.breakpoints.insert(
+                            line.saturating_sub(1),
+                            Breakpoint {
+                                condition,
+                                hit_condition,
+                                hits: 0,
+                            },
+                        );
+                        Some(json!({ "verified": true, "line": line }))
+                    }
+                    (Err(err), _) | (_, Err(err)) => {
+                        Some(json!({ "verified": false, "line": line, "message": err }))
+                    }
+                }
+            })
+            .collect();
+        json!({ "breakpoints": results })
+    }
+
+    fn try_run(&mut self) {
+        if !self.configured || self.running {
+            return;
+        }
+        if let Some(launch) = self.launch.take() {
+            self.running = true;
+            let result = self.run(&launch);
+            self.running = false;
+            let exit_code = match result {
+                Ok(_) => 0,
+                Err(errs) => {
+                    for err in errs {
+                        let output = match err.bar_index {
+                            Some(bar) => format!(
+                                "{}:{}: {} at bar {}\n",
+                                err.range.start.get_line() + 1,
+                                err.range.start.get_character() + 1,
+                                err.message,
+                                bar
+                            ),
+                            None => format!(
+                                "{}:{}: {}\n",
+                                err.range.start.get_line() + 1,
+                                err.range.start.get_character() + 1,
+                                err.message
+                            ),
+                        };
+                        self.conn.send_output("stderr", output);
+                    }
+                    1
+                }
+            };
+            self.conn
+                .send_event("exited", json!({ "exitCode": exit_code }));
+            self.conn.send_event("terminated", json!({}));
+        }
+    }
+
+    fn run(&mut self, launch: &LaunchConfig) -> Result<(), Vec<PineFormatError>> {
+        let formater = ErrorFormater::new();
+        let version = script_version(&launch.source);
+        let resolver = launch.library_root.as_ref().map(FsLibraryResolver::new);
+        let arena = LibraryArena::new();
+
+        let blk = {
+            let lib_info = lib_info_for_version(version);
+            let mut parser = PineParser::new(&launch.source, &lib_info);
+            if let Some(resolver) = &resolver {
+                parser.set_library_resolver(resolver, &arena);
+            }
+            parser.parse_blk()
+        };
+        let blk = match blk {
+            Ok(blk) => blk,
+            Err(errs) => {
+                return Err(errs
+                    .into_iter()
+                    .map(|err| PineFormatError::from_input_error(&formater, err))
+                    .collect())
+            }
+        };
+
+        let debugger = DapDebugger::new(
+            self,
+            collect_scopes(&blk),
+            &launch.program,
+            launch.stop_on_entry,
+        );
+        // The debugger borrows the block, so the runner must be created after it.
+        let lib_info = lib_info_for_version(version);
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.set_debugger(Some(&debugger));
+        match runner.run(&launch.data, None) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(vec![PineFormatError::from_runtime_error(&formater, err)]),
+        }
+    }
+}
+
+fn read_launch_config(args: &Value) -> Result<LaunchConfig, String> {
+    let program = match args["program"].as_str() {
+        Some(program) => String::from(program),
+        None => return Err(String::from("The program to debug is required")),
+    };
+    let source = match fs::read_to_string(&program) {
+        Ok(source) => source,
+        Err(err) => return Err(format!("Cannot read the program {}: {}", program, err)),
+    };
+    let data = match args["data"].as_str() {
+        Some(data) => load_csv(data)?,
+        None => return Err(String::from("The data file of bars is required")),
+    };
+    Ok(LaunchConfig {
+        program,
+        source,
+        data,
+        stop_on_entry: args["stopOnEntry"].as_bool().unwrap_or(false),
+        library_root: args["libraryRoot"].as_str().map(String::from),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{read_message, send_message};
+    use std::cell::RefCell;
+    use std::env;
+    use std::io::{self, BufReader, Write};
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run_session(requests: Vec<Value>) -> Vec<Value> {
+        let mut input = vec![];
+        for (i, mut request) in requests.into_iter().enumerate() {
+            request["seq"] = json!(i + 1);
+            request["type"] = json!("request");
+            send_message(&mut input, &request.to_string());
+        }
+        let output = SharedBuf(Rc::new(RefCell::new(vec![])));
+        let conn = Connection::new(
+            Box::new(BufReader::new(io::Cursor::new(input))),
+            Box::new(output.clone()),
+        );
+        Session::new(conn).start();
+
+        let buf = output.0.borrow();
+        let mut reader = BufReader::new(buf.as_slice());
+        let mut msgs = vec![];
+        while let Some(msg) = read_message(&mut reader) {
+            msgs.push(serde_json::from_str(&msg).unwrap());
+        }
+        msgs
+    }
+
+    fn msg_name(msg: &Value) -> String {
+        match msg["type"].as_str() {
+            Some("event") => format!("event {}", msg["event"].as_str().unwrap()),
+            _ => msg["command"].as_str().unwrap().to_string(),
+        }
+    }
+
+    fn find_response(msgs: &[Value], request_seq: i64) -> &Value {
+        msgs.iter()
+            .find(|m| m["type"] == "response" && m["request_seq"] == request_seq)
+            .unwrap()
+    }
+
+    #[test]
+    fn debug_session_test() {
+        let dir = env::temp_dir().join(format!("pine-dap-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("test.pine");
+        let data = dir.join("test.csv");
+        fs::write(
+            &program,
+            "a = close + 1\nf(x) =>\n    y = x * 2\n    y\nb = f(a)\n",
+        )
+        .unwrap();
+        fs::write(&data, "time,close\n1,1\n2,2\n3,3\n").unwrap();
+
+        let msgs = run_session(vec![
+            json!({ "command": "initialize", "arguments": {} }),
+            json!({
+                "command": "launch",
+                "arguments": {
+                    "program": program.to_str().unwrap(),
+                    "data": data.to_str().unwrap(),
+                }
+            }),
+            json!({
+                "command": "setBreakpoints",
+                "arguments": {
+                    "source": { "path": program.to_str().unwrap() },
+                    "breakpoints": [
+                        { "line": 3, "condition": "bar_index >= 1" },
+                        { "line": 1, "condition": "a >" }
+                    ]
+                }
+            }),
+            json!({ "command": "configurationDone" }),
+            // Pause at the line 3 of bar 1.
+            json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+            json!({ "command": "scopes", "arguments": { "frameId": 0 } }),
+            json!({ "command": "variables", "arguments": { "variablesReference": 1 } }),
+            json!({ "command": "evaluate", "arguments": { "expression": "y[1]", "frameId": 0 } }),
+            json!({
+                "command": "evaluate",
+                "arguments": { "expression": "a > 2 and bar_index == 1", "frameId": 1 }
+            }),
+            json!({ "command": "next" }),
+            // Pause at the line 4 of bar 1.
+            json!({ "command": "evaluate", "arguments": { "expression": "y", "frameId": 0 } }),
+            json!({ "command": "stepOut" }),
+            // Pause at the line 1 of bar 2.
+            json!({ "command": "evaluate", "arguments": { "expression": "close", "frameId": 0 } }),
+            json!({ "command": "continue" }),
+            // Pause at the line 3 of bar 2.
+            json!({ "command": "disconnect" }),
+        ]);
+
+        let names: Vec<_> = msgs.iter().map(msg_name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "initialize",
+                "event initialized",
+                "launch",
+                "setBreakpoints",
+                "configurationDone",
+                "event stopped",
+                "stackTrace",
+                "scopes",
+                "variables",
+                "evaluate",
+                "evaluate",
+                "next",
+                "event stopped",
+                "evaluate",
+                "stepOut",
+                "event stopped",
+                "evaluate",
+                "continue",
+                "event stopped",
+                "disconnect",
+                "event exited",
+                "event terminated",
+            ]
+        );
+
+        let breakpoints = &find_response(&msgs, 3)["body"]["breakpoints"];
+        assert_eq!(breakpoints[0]["verified"], true);
+        assert_eq!(breakpoints[1]["verified"], false);
+
+        let stopped: Vec<_> = msgs
+            .iter()
+            .filter(|m| m["event"] == "stopped")
+            .map(|m| m["body"]["reason"].as_str().unwrap())
+            .collect();
+        assert_eq!(stopped, vec!["breakpoint", "step", "step", "breakpoint"]);
+
+        let frames = &find_response(&msgs, 5)["body"]["stackFrames"];
+        assert_eq!(frames[0]["name"], "f");
+        assert_eq!(frames[0]["line"], 3);
+        assert_eq!(frames[1]["name"], "main (bar 1)");
+        assert_eq!(frames[1]["line"], 5);
+
+        let scopes: Vec<_> = find_response(&msgs, 6)["body"]["scopes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(scopes, vec!["Locals", "Globals", "Bar"]);
+        // The history values of the parameter x before this bar.
+        assert_eq!(
+            find_response(&msgs, 7)["body"]["variables"],
+            json!([{ "name": "[1]", "value": "2", "variablesReference": 0 }])
+        );
+        assert_eq!(find_response(&msgs, 8)["body"]["result"], "4");
+        assert_eq!(find_response(&msgs, 9)["body"]["result"], "true");
+        assert_eq!(find_response(&msgs, 11)["body"]["result"], "6");
+        assert_eq!(find_response(&msgs, 13)["body"]["result"], "3");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
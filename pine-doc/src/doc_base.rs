@@ -4,19 +4,23 @@ pub enum VarType {
     Function,
 }
 
+// The documentation of the variable or the function, the builtin documentations are static
+// and the documentations of the user scripts borrow the annotations.
 #[derive(Debug, PartialEq)]
-pub struct DocBase {
+pub struct Doc<'a> {
     pub var_type: VarType,
-    pub name: &'static str,
+    pub name: &'a str,
     pub signatures: Vec<String>,
-    pub description: &'static str,
-    pub example: &'static str,
-    pub returns: &'static str,
-    pub arguments: &'static str,
-    pub remarks: &'static str,
-    pub links: &'static str,
+    pub description: &'a str,
+    pub example: &'a str,
+    pub returns: &'a str,
+    pub arguments: &'a str,
+    pub remarks: &'a str,
+    pub links: &'a str,
 }
 
+pub type DocBase = Doc<'static>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,9 +4,10 @@ use super::vardoc_gen::*;
 
 use pine::ast::syntax_type::*;
 use pine::libs::{declare_vars, VarResult};
+use pine::script_doc::parse_script_doc;
 use pine::types::PineRef;
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, PartialEq)]
 struct NameInfo {
@@ -67,20 +68,20 @@ fn format_func_type<'a>(name: String, t: FunctionType<'a>) -> String {
     format!("{}({}) -> {}", name, func_sig, ret)
 }
 
-fn format_func_types<'a>(name: String, t: Rc<FunctionTypes<'a>>) -> Vec<String> {
+fn format_func_types<'a>(name: String, t: Arc<FunctionTypes<'a>>) -> Vec<String> {
     t.0.iter()
         .map(|t| format_func_type(name.clone(), t.clone()))
         .collect()
 }
 
-fn format_obj_type<'a>(name: String, obj: Rc<BTreeMap<&'a str, SyntaxType<'a>>>) -> Vec<NameInfo> {
+fn format_obj_type<'a>(name: String, obj: Arc<BTreeMap<&'a str, SyntaxType<'a>>>) -> Vec<NameInfo> {
     obj.iter()
         .map(|(n, t)| format_var_type([name.clone(), String::from(*n)].join("."), t.clone()))
         .flatten()
         .collect()
 }
 
-fn format_func<'a>(name: String, func: Rc<FunctionTypes<'a>>) -> Vec<NameInfo> {
+fn format_func<'a>(name: String, func: Arc<FunctionTypes<'a>>) -> Vec<NameInfo> {
     vec![NameInfo::func_vars(
         name.clone(),
         format_func_types(name.clone(), func),
@@ -172,6 +173,9 @@ pub struct LibVarParser {
 
     pub brief_vars: BTreeMap<String, String>,
     pub brief_funcs: BTreeMap<String, String>,
+
+    // The documentation of the library or the script that the functions belong to.
+    pub script: Option<String>,
 }
 
 fn inner_vars<'a>() -> Vec<VarResult<'a>> {
@@ -194,6 +198,7 @@ impl LibVarParser {
             functions: BTreeMap::new(),
             brief_vars: BTreeMap::new(),
             brief_funcs: BTreeMap::new(),
+            script: None,
         }
     }
 
@@ -244,12 +249,61 @@ impl LibVarParser {
                 // match s.syntax_type {}
             });
     }
+
+    // Generate the documentations of the user script from the annotations, only the exported
+    // functions are documented if the script is a library.
+    pub fn parse_script(&mut self, src: &str) {
+        let script_doc = parse_script_doc(src);
+        let is_library = script_doc.library.is_some();
+        for func in script_doc
+            .functions
+            .iter()
+            .filter(|f| !is_library || f.export)
+        {
+            let arguments = func
+                .params
+                .iter()
+                .map(|p| format!("**{}** {}", p.signature, p.description))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let doc = Doc {
+                var_type: VarType::Function,
+                name: &func.name,
+                signatures: vec![func.signature.clone()],
+                description: &func.description,
+                example: "",
+                returns: &func.returns,
+                arguments: &arguments,
+                remarks: "",
+                links: "",
+            };
+            self.functions.insert(
+                func.name.clone(),
+                gen_var_doc(
+                    func.name.clone(),
+                    Some(&doc),
+                    &doc.signatures,
+                    String::from("fun"),
+                ),
+            );
+            self.brief_funcs.insert(
+                func.name.clone(),
+                gen_brief_var_doc(func.name.clone(), Some(&doc), &doc.signatures),
+            );
+        }
+        if is_library || !script_doc.description.is_empty() {
+            self.script = Some(gen_script_doc(
+                script_doc.library.as_deref(),
+                &script_doc.description,
+            ));
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::rc::Rc;
+    use std::sync::Arc;
     #[test]
     fn format_type_test() {
         assert_eq!(
@@ -273,7 +327,7 @@ mod tests {
         assert_eq!(
             format_var_type(
                 String::from("hello"),
-                SyntaxType::Tuple(Rc::new(vec![SyntaxType::int(), SyntaxType::float()]))
+                SyntaxType::Tuple(Arc::new(vec![SyntaxType::int(), SyntaxType::float()]))
             ),
             vec![NameInfo::simple_var(String::from("hello"), "[int, float]")]
         );
@@ -282,10 +336,10 @@ mod tests {
         let mut obj2 = BTreeMap::new();
         obj.insert("e1", SyntaxType::int());
         obj2.insert("se", SyntaxType::float());
-        obj.insert("e2", SyntaxType::Object(Rc::new(obj2)));
+        obj.insert("e2", SyntaxType::Object(Arc::new(obj2)));
 
         assert_eq!(
-            format_var_type(String::from("hello"), SyntaxType::Object(Rc::new(obj))),
+            format_var_type(String::from("hello"), SyntaxType::Object(Arc::new(obj))),
             vec![
                 NameInfo::simple_var(String::from("hello.e1"), "int"),
                 NameInfo::simple_var(String::from("hello.e2.se"), "float")
@@ -294,7 +348,7 @@ mod tests {
         assert_eq!(
             format_var_type(
                 String::from("hello"),
-                SyntaxType::Function(Rc::new(FunctionTypes(vec![
+                SyntaxType::Function(Arc::new(FunctionTypes(vec![
                     FunctionType::new((vec![("e1", SyntaxType::bool())], SyntaxType::int())),
                     FunctionType::new((vec![("e1", SyntaxType::int())], SyntaxType::int()))
                 ])))
@@ -308,4 +362,24 @@ mod tests {
             ),]
         );
     }
+
+    #[test]
+    fn parse_script_test() {
+        let mut parser = LibVarParser::new();
+        parser.parse_script(
+            "//@description The averages.\nlibrary(\"Avg\")\n\
+             //@function The average.\n//@param x The source.\n//@returns The value.\n\
+             export avg(x) => x\nhelper(x) => x",
+        );
+        assert_eq!(parser.functions.keys().collect::<Vec<_>>(), vec!["avg"]);
+        assert!(parser.functions["avg"].contains("<p>The average.</p>"));
+        assert!(parser.functions["avg"].contains("<strong>x</strong> The source."));
+        assert!(parser.brief_funcs["avg"].contains("The value."));
+        assert_eq!(
+            parser.script,
+            Some(String::from(
+                "<h1 id=\"library\">Avg</h1>\n<p>The averages.</p>\n"
+            ))
+        );
+    }
 }
@@ -15,7 +15,9 @@ use regex::Captures;
 use regex::Regex;
 
 use std::collections::HashMap;
+use std::env;
 use std::error::Error;
+use std::fs;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
@@ -51,7 +53,7 @@ fn process_name(name: String) -> String {
 }
 
 fn gen_doc(doc_parser: &LibVarParser) -> String {
-    [
+    let doc = [
         doc_parser
             .variables
             .iter()
@@ -65,7 +67,11 @@ fn gen_doc(doc_parser: &LibVarParser) -> String {
             .collect::<Vec<_>>()
             .join("\n"),
     ]
-    .join("\n")
+    .join("\n");
+    match doc_parser.script.as_ref() {
+        Some(script) => [script.clone(), doc].join("\n"),
+        None => doc,
+    }
 }
 
 fn gen_detail_doc(doc_parser: &LibVarParser) -> String {
@@ -87,6 +93,9 @@ fn gen_json_doc(doc_parser: &LibVarParser) -> String {
         .map(|s| (process_name(format!("{}-{}", "fun", s.0)), s.1))
         .collect::<HashMap<_, _>>();
     vars.extend(funs.into_iter());
+    if let Some(script) = doc_parser.script.as_ref() {
+        vars.insert(String::from("library"), script);
+    }
     serde_json::to_string(&vars).unwrap()
 }
 
@@ -157,7 +166,38 @@ fn write_vars(doc_parser: &LibVarParser) {
     }
 }
 
+// Write the documentation of the user script `name.pine` to `name.html` and `name.json`
+// next to the script.
+fn write_script_doc(script_path: &Path) {
+    let src = match fs::read_to_string(script_path) {
+        Err(why) => panic!("couldn't read {}: {}", script_path.display(), why),
+        Ok(src) => src,
+    };
+    let mut parser = LibVarParser::new();
+    parser.parse_script(&src);
+
+    let outputs = vec![
+        (script_path.with_extension("html"), gen_detail_doc(&parser)),
+        (script_path.with_extension("json"), gen_json_doc(&parser)),
+    ];
+    for (path, content) in outputs {
+        match fs::write(&path, content) {
+            Err(why) => panic!("couldn't write to {}: {}", path.display(), why),
+            Ok(_) => println!("successfully wrote to {}", path.display()),
+        }
+    }
+}
+
+// Generate the documentation of the builtin variables, or the documentation of the user
+// scripts if the paths of the scripts are given.
 fn main() {
+    let scripts: Vec<String> = env::args().skip(1).collect();
+    if !scripts.is_empty() {
+        scripts
+            .iter()
+            .for_each(|script| write_script_doc(Path::new(script)));
+        return;
+    }
     let mut parser = LibVarParser::new();
     parser.parse_lib_vars();
     write_doc(&parser);
@@ -23,12 +23,15 @@ plot(sin(f) > 0 ? close : open)
 const PINE_FN_ARGUMENTS: &'static str = "
 **defval (Depends on 'type' argument)** Default value of the input variable. Note, that input value that will be ACTUALLY USED by the script is set by user on the Chart Setting dialog.
 **title (string)** Title of the input
-**type (string)** Input type. Possible values are [input.bool](#var-input-bool), [input.integer](#var-input-integer), [input.float](#var-input-float), [input.string](#var-input-string), [input.symbol](#var_input-symbol), [input.resolution](#var-input-resolution), [input.session](#var-input-session), [input.source](#var-input-source).
+**type (string)** Input type. Possible values are [input.bool](#var-input-bool), [input.integer](#var-input-integer), [input.float](#var-input-float), [input.string](#var-input-string), [input.symbol](#var_input-symbol), [input.resolution](#var-input-resolution), [input.session](#var-input-session), [input.source](#var-input-source), [input.time](#var-input-time), [input.price](#var-input-price), [input.color](#var-input-color).
 **minval (integer, float)** Minimal possible value of the input variable. This argument is used only when input type is [input.integer](#var-input-integer) or [input.float](#var-input-float).
 **maxval (integer, float)** Maximum possible value of the input variable. This argument is used only when input type is [input.integer](#var-input-integer) or [input.float](#var-input-float).
 **confirm (bool)** If true, then user will be asked to confirm input value before indicator is added to chart. Default value is false. This argument not used when input type is [input.source](#var-input-source).
 **step (integer, float)** Step value to use for incrementing/decrementing input from format dialog. Default value is 1. This argument is used only for input types [input.integer](#var-input-integer) and [input.float](#var-input-float).
 **options (List of constants: [<type>...])** A list of options to choose from. This argument is used only for input types [input.integer](#var-input-integer), [input.float](#var-input-float) and [input.string](#var-input-string).
+**tooltip (string)** The tooltip shown next to the input on the chart setting dialog.
+**inline (string)** The inputs with the same inline argument are shown on the same line.
+**group (string)** The inputs with the same group argument are shown under the same header.
 ";
 
 pub fn gen_doc() -> Vec<DocBase> {
@@ -1,4 +1,4 @@
-use super::DocBase;
+use super::Doc;
 use comrak::markdown_to_html;
 use comrak::ComrakOptions;
 
@@ -28,7 +28,7 @@ fn process_name(name: String) -> String {
 
 pub fn gen_var_doc(
     name: String,
-    doc_base: Option<&DocBase>,
+    doc_base: Option<&Doc>,
     sigs: &Vec<String>,
     fmt_type: String,
 ) -> String {
@@ -99,7 +99,7 @@ pub fn gen_var_doc(
     // doc_str
 }
 
-pub fn gen_brief_var_doc(name: String, doc_base: Option<&DocBase>, sigs: &Vec<String>) -> String {
+pub fn gen_brief_var_doc(name: String, doc_base: Option<&Doc>, sigs: &Vec<String>) -> String {
     let desc = match doc_base {
         None => None,
         Some(doc_base) => match doc_base.description {
@@ -131,9 +131,18 @@ pub fn gen_brief_var_doc(name: String, doc_base: Option<&DocBase>, sigs: &Vec<St
     markdown_to_html(&doc_str, &MD_OPTIONS)
 }
 
+// The title and the description of the library or the script.
+pub fn gen_script_doc(library: Option<&str>, description: &str) -> String {
+    let title = match library {
+        Some(title) => format!("<h1 id=\"library\">{}</h1>\n", title),
+        None => String::new(),
+    };
+    title + &markdown_to_html(description, &MD_OPTIONS)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::super::VarType;
+    use super::super::{DocBase, VarType};
     use super::*;
 
     #[test]
@@ -0,0 +1,196 @@
+use super::pine_server::{from_str_range, make_notification};
+use lsp_types::*;
+use pine::PineScript;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::spawn;
+use std::time::Duration;
+
+// Wait for the following changes before analyzing the document.
+const DEBOUNCE_MS: u64 = 200;
+
+pub struct AnalysisTask {
+    uri: Url,
+    version: i32,
+    text: String,
+}
+
+// The latest versions of the opened documents, the analyses of other versions are stale.
+type LatestVersions = Arc<Mutex<HashMap<Url, i32>>>;
+
+fn is_latest(versions: &LatestVersions, uri: &Url, version: i32) -> bool {
+    versions.lock().unwrap().get(uri) == Some(&version)
+}
+
+// Parse and type check the documents on the worker thread, then publish the diagnostics.
+pub struct Analyzer {
+    sender: Sender<AnalysisTask>,
+    versions: LatestVersions,
+}
+
+impl Analyzer {
+    pub fn new(output: Sender<String>) -> Analyzer {
+        let (sender, receiver) = channel();
+        let versions: LatestVersions = Arc::new(Mutex::new(HashMap::new()));
+        let worker_versions = Arc::clone(&versions);
+        spawn(move || run_worker(receiver, worker_versions, output));
+        Analyzer { sender, versions }
+    }
+
+    pub fn schedule(&self, uri: Url, version: i32, text: String) {
+        self.versions.lock().unwrap().insert(uri.clone(), version);
+        self.sender
+            .send(AnalysisTask { uri, version, text })
+            .unwrap();
+    }
+
+    // Cancel the pending analysis of the closed document.
+    pub fn cancel(&self, uri: &Url) {
+        self.versions.lock().unwrap().remove(uri);
+    }
+}
+
+fn run_worker(receiver: Receiver<AnalysisTask>, versions: LatestVersions, output: Sender<String>) {
+    // The scripts keep the parse results of the documents for the incremental parsing.
+    let mut scripts: HashMap<Url, PineScript> = HashMap::new();
+    while let Ok(task) = receiver.recv() {
+        // Only the last change of each document in the debounce period is analyzed.
+        let mut pending = vec![task];
+        loop {
+            match receiver.recv_timeout(Duration::from_millis(DEBOUNCE_MS)) {
+                Ok(task) => {
+                    pending.retain(|t| t.uri != task.uri);
+                    pending.push(task);
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        // Release the scripts of the closed documents.
+        scripts.retain(|uri, _| versions.lock().unwrap().contains_key(uri));
+        for task in pending {
+            if !is_latest(&versions, &task.uri, task.version) {
+                continue;
+            }
+            let script = scripts.entry(task.uri.clone()).or_insert_with(|| {
+                let mut script = PineScript::new(None);
+                script.set_ast_cache(true);
+                script
+            });
+            let diagnostics = match script.parse_src(task.text) {
+                Ok(_) => vec![],
+                Err(errs) => errs
+                    .into_iter()
+                    .map(|err| {
+                        Diagnostic::new(
+                            from_str_range(err.range),
+                            Some(DiagnosticSeverity::Error),
+                            None,
+                            Some(String::from("pine ls")),
+                            err.message,
+                            None,
+                            None,
+                        )
+                    })
+                    .collect(),
+            };
+
+            let msg = match publish_msg(&versions, task.uri, task.version, diagnostics) {
+                Some(msg) => msg,
+                None => continue,
+            };
+            if output.send(msg).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// The notification to publish the diagnostics, it is None if the document is changed during
+// the analysis so the diagnostics are stale.
+fn publish_msg(
+    versions: &LatestVersions,
+    uri: Url,
+    version: i32,
+    diagnostics: Vec<Diagnostic>,
+) -> Option<String> {
+    if !is_latest(versions, &uri, version) {
+        return None;
+    }
+    let publish_diagnostics = PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version: Some(version),
+    };
+    Some(make_notification(
+        "textDocument/publishDiagnostics",
+        publish_diagnostics,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recv_published(receiver: &Receiver<String>) -> Option<serde_json::Value> {
+        receiver
+            .recv_timeout(Duration::from_millis(DEBOUNCE_MS * 5))
+            .ok()
+            .map(|msg| serde_json::from_str::<serde_json::Value>(&msg).unwrap()["params"].clone())
+    }
+
+    #[test]
+    fn debounce_test() {
+        let (sender, receiver) = channel();
+        let analyzer = Analyzer::new(sender);
+        let uri = Url::parse("http://a.b").unwrap();
+        // Only the last change in the debounce period is analyzed.
+        analyzer.schedule(uri.clone(), 1, String::from("m = 1 +"));
+        analyzer.schedule(uri.clone(), 2, String::from("m = 1 1"));
+        analyzer.schedule(uri.clone(), 3, String::from("m = 1"));
+        let params = recv_published(&receiver).unwrap();
+        assert_eq!(params["version"], 3);
+        assert_eq!(params["diagnostics"].as_array().unwrap().len(), 0);
+        assert_eq!(recv_published(&receiver), None);
+
+        analyzer.schedule(uri.clone(), 4, String::from("m = 1 +"));
+        let params = recv_published(&receiver).unwrap();
+        assert_eq!(params["version"], 4);
+        assert_eq!(params["diagnostics"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn version_test() {
+        let (sender, receiver) = channel();
+        let analyzer = Analyzer::new(sender);
+        let uri = Url::parse("http://a.b").unwrap();
+        // The analysis of the closed document is canceled.
+        analyzer.schedule(uri.clone(), 1, String::from("m = 1 +"));
+        analyzer.cancel(&uri);
+        assert_eq!(recv_published(&receiver), None);
+
+        // The task of the old version is skipped.
+        analyzer.schedule(uri.clone(), 2, String::from("m = 1 +"));
+        analyzer.versions.lock().unwrap().insert(uri.clone(), 3);
+        assert_eq!(recv_published(&receiver), None);
+    }
+
+    #[test]
+    fn stale_result_test() {
+        let versions: LatestVersions = Arc::new(Mutex::new(HashMap::new()));
+        let uri = Url::parse("http://a.b").unwrap();
+        versions.lock().unwrap().insert(uri.clone(), 2);
+        // The document is changed to the version 2 during the analysis of the version 1.
+        assert_eq!(publish_msg(&versions, uri.clone(), 1, vec![]), None);
+        let msg = publish_msg(&versions, uri.clone(), 2, vec![]).unwrap();
+        let msg: serde_json::Value = serde_json::from_str(&msg).unwrap();
+        assert_eq!(msg["method"], "textDocument/publishDiagnostics");
+        assert_eq!(msg["params"]["version"], 2);
+
+        // The document is closed during the analysis.
+        versions.lock().unwrap().remove(&uri);
+        assert_eq!(publish_msg(&versions, uri, 2, vec![]), None);
+    }
+}
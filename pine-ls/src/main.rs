@@ -1,3 +1,4 @@
+mod analyzer;
 mod pine_server;
 mod stdio_server;
 mod text_doc;
@@ -1,27 +1,24 @@
+use super::analyzer::Analyzer;
 use super::text_doc::TextDoc;
 use jsonrpc_core::request::Notification;
 use jsonrpc_core::Params;
 use lsp_types::*;
 use pine::ast::input::StrRange;
+use pine::script_doc::{parse_script_doc, FunctionDoc};
 use std::collections::HashMap;
 use std::sync::mpsc::Sender;
 
-pub struct PineServer<'a> {
+pub struct PineServer {
     init_params: Option<InitializeParams>,
-    text_docs: HashMap<Url, TextDoc<'a>>,
+    text_docs: HashMap<Url, TextDoc>,
     sender: Sender<String>,
+    analyzer: Analyzer,
 }
 
-fn from_str_range(range: StrRange) -> Range {
+pub fn from_str_range(range: StrRange) -> Range {
     Range::new(
-        Position::new(
-            range.start.get_line() as u64,
-            range.start.get_character() as u64,
-        ),
-        Position::new(
-            range.end.get_line() as u64,
-            range.end.get_character() as u64,
-        ),
+        Position::new(range.start.get_line(), range.start.get_character()),
+        Position::new(range.end.get_line(), range.end.get_character()),
     )
 }
 
@@ -32,9 +29,10 @@ fn from_str_range(range: StrRange) -> Range {
 //     )
 // }
 
-impl<'a> PineServer<'a> {
-    pub fn new(sender: Sender<String>) -> PineServer<'a> {
+impl PineServer {
+    pub fn new(sender: Sender<String>) -> PineServer {
         PineServer {
+            analyzer: Analyzer::new(sender.clone()),
             sender,
             init_params: None,
             text_docs: HashMap::new(),
@@ -48,8 +46,8 @@ impl<'a> PineServer<'a> {
     pub fn add_doc(&mut self, params: DidOpenTextDocumentParams) {
         let text = params.text_document.text;
         let uri = params.text_document.uri;
-        let mut new_doc = TextDoc::new(text, uri.clone());
-        self.parse_doc(&mut new_doc);
+        let new_doc = TextDoc::new(text, uri.clone(), params.text_document.version);
+        self.analyze_doc(&new_doc);
         self.text_docs.insert(uri, new_doc);
 
         self.send_notification(
@@ -62,27 +60,31 @@ impl<'a> PineServer<'a> {
     }
 
     pub fn change_doc(&mut self, params: DidChangeTextDocumentParams) {
-        match self.text_docs.get_mut(&params.text_document.uri) {
-            Some(doc) => {
-                params.content_changes.into_iter().for_each(|item| {
-                    if let Some(range) = item.range {
-                        // let StrRange { start, end } = to_str_range(range);
-                        doc.change(
-                            range.start,
-                            range.end,
-                            item.range_length.unwrap() as usize,
-                            item.text,
-                        );
-                    } else {
-                        doc.reset(item.text);
-                    }
-                });
-            }
+        let doc = match self.text_docs.get_mut(&params.text_document.uri) {
+            Some(doc) => doc,
             None => return,
         };
-        let mut text_doc = self.text_docs.remove(&params.text_document.uri).unwrap();
-        self.parse_doc(&mut text_doc);
-        self.text_docs.insert(params.text_document.uri, text_doc);
+        // The changes are stale if the version is not newer than the document.
+        let version = params.text_document.version;
+        if version <= doc.get_version() {
+            return;
+        }
+        params.content_changes.into_iter().for_each(|item| {
+            if let Some(range) = item.range {
+                // let StrRange { start, end } = to_str_range(range);
+                doc.change(
+                    range.start,
+                    range.end,
+                    item.range_length.unwrap() as usize,
+                    item.text,
+                );
+            } else {
+                doc.reset(item.text);
+            }
+        });
+        doc.set_version(version);
+        let doc = &self.text_docs[&params.text_document.uri];
+        self.analyze_doc(doc);
 
         self.send_notification(
             "window/showMessage",
@@ -93,69 +95,47 @@ impl<'a> PineServer<'a> {
         );
     }
 
-    pub fn parse_doc(&mut self, doc: &mut TextDoc) {
-        // The below is the test code for publishing diagnostics.
-        // let range = Range {
-        //     start: lsp_types::Position {
-        //         line: 3,
-        //         character: 0,
-        //     },
-        //     end: lsp_types::Position {
-        //         line: 3,
-        //         character: 2,
-        //     },
-        // };
-        // let diagnostics = vec![Diagnostic {
-        //     range: range.clone(),
-        //     code: None,
-        //     severity: Some(DiagnosticSeverity::Error),
-        //     source: Some("pine ls".to_owned()),
-        //     message: "No entity \'ent2\' within library \'lib\'".to_owned(),
-        //     related_information: Some(vec![DiagnosticRelatedInformation {
-        //         location: Location::new(doc.get_uri().clone(), range.clone()),
-        //         message: String::from("Spelling matters"),
-        //     }]),
-        //     tags: None,
-        // }];
-
-        // let publish_diagnostics = PublishDiagnosticsParams {
-        //     uri: doc.get_uri().clone(),
-        //     diagnostics: diagnostics,
-        //     version: None,
-        // };
-        // info!("publish errors {:?}", publish_diagnostics);
-        // self.send_notification("textDocument/publishDiagnostics", publish_diagnostics);
-        if let Err(errs) = doc.parse_src() {
-            let diagnostics: Vec<_> = errs
-                .into_iter()
-                .map(|err| {
-                    Diagnostic::new(
-                        from_str_range(err.range),
-                        Some(DiagnosticSeverity::Error),
-                        None,
-                        Some(String::from("pine ls")),
-                        err.message,
-                        None,
-                        None,
-                    )
-                })
-                .collect();
-
-            let publish_diagnostics = PublishDiagnosticsParams {
-                uri: doc.get_uri().clone(),
-                diagnostics: diagnostics,
-                version: None,
-            };
-            // info!("publish errors {:?}", publish_diagnostics);
-            self.send_notification("textDocument/publishDiagnostics", publish_diagnostics);
-        } else {
-            let publish_diagnostics = PublishDiagnosticsParams {
-                uri: doc.get_uri().clone(),
+    pub fn close_doc(&mut self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.text_docs.remove(&uri);
+        self.analyzer.cancel(&uri);
+        self.send_notification(
+            "textDocument/publishDiagnostics",
+            PublishDiagnosticsParams {
+                uri,
                 diagnostics: vec![],
                 version: None,
-            };
-            self.send_notification("textDocument/publishDiagnostics", publish_diagnostics);
-        }
+            },
+        );
+    }
+
+    // Analyze the document on the worker thread, the diagnostics are published by the worker.
+    fn analyze_doc(&self, doc: &TextDoc) {
+        self.analyzer.schedule(
+            doc.get_uri().clone(),
+            doc.get_version(),
+            String::from(doc.get_text()),
+        );
+    }
+
+    // Show the documentation from the annotations of the user function under the cursor.
+    pub fn hover(&self, params: TextDocumentPositionParams) -> Option<Hover> {
+        let doc = self.text_docs.get(&params.text_document.uri)?;
+        let line = doc.get_text().lines().nth(params.position.line as usize)?;
+        let (name, range) = word_at(line, params.position.character as usize)?;
+        let script_doc = parse_script_doc(doc.get_text());
+        let func = script_doc.find_function(name)?;
+        let line_num = params.position.line;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: function_markdown(func),
+            }),
+            range: Some(Range::new(
+                Position::new(line_num, range.0 as u32),
+                Position::new(line_num, range.1 as u32),
+            )),
+        })
     }
 
     pub fn send_notification(
@@ -163,23 +143,72 @@ impl<'a> PineServer<'a> {
         method: impl Into<String>,
         notification: impl serde::ser::Serialize,
     ) {
-        let params_json = match serde_json::to_value(notification).unwrap() {
-            serde_json::Value::Object(map) => map,
-            map => panic!("{:?}", map),
-        };
-
-        let notification_json = Notification {
-            jsonrpc: Some(jsonrpc_core::Version::V2),
-            method: method.into(),
-            params: Params::Map(params_json),
-        };
-
         self.sender
-            .send(serde_json::to_string(&notification_json).unwrap())
+            .send(make_notification(method, notification))
             .unwrap();
     }
 }
 
+// The identifier that contains the character and the character range of it.
+fn word_at(line: &str, character: usize) -> Option<(&str, (usize, usize))> {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let is_ident = |i: usize| {
+        chars
+            .get(i)
+            .is_some_and(|(_, c)| c.is_alphanumeric() || *c == '_')
+    };
+    if !is_ident(character) {
+        return None;
+    }
+    let mut start = character;
+    while start > 0 && is_ident(start - 1) {
+        start -= 1;
+    }
+    let mut end = character + 1;
+    while is_ident(end) {
+        end += 1;
+    }
+    let end_byte = chars.get(end).map_or(line.len(), |(i, _)| *i);
+    Some((&line[chars[start].0..end_byte], (start, end)))
+}
+
+fn function_markdown(func: &FunctionDoc) -> String {
+    let mut sections = vec![format!("```pine\n{}\n```", func.signature)];
+    if !func.description.is_empty() {
+        sections.push(func.description.clone());
+    }
+    let params: Vec<String> = func
+        .params
+        .iter()
+        .filter(|p| !p.description.is_empty())
+        .map(|p| format!("- `{}` {}", p.signature, p.description))
+        .collect();
+    if !params.is_empty() {
+        sections.push(format!("**Parameters**\n{}", params.join("\n")));
+    }
+    if !func.returns.is_empty() {
+        sections.push(format!("**Returns** {}", func.returns));
+    }
+    sections.join("\n\n")
+}
+
+pub fn make_notification(
+    method: impl Into<String>,
+    notification: impl serde::ser::Serialize,
+) -> String {
+    let params_json = match serde_json::to_value(notification).unwrap() {
+        serde_json::Value::Object(map) => map,
+        map => panic!("{:?}", map),
+    };
+
+    let notification_json = Notification {
+        jsonrpc: Some(jsonrpc_core::Version::V2),
+        method: method.into(),
+        params: Params::Map(params_json),
+    };
+    serde_json::to_string(&notification_json).unwrap()
+}
+
 // fn client_supports_related_information(init_params: &InitializeParams) -> bool {
 //     let try_fun = || {
 //         init_params
@@ -202,16 +231,79 @@ mod tests {
     fn pine_server_test() {
         let (sender, receiver) = channel::<String>();
         let mut server = PineServer::new(sender);
+        let uri = Url::parse("http://a.b").unwrap();
         server.add_doc(DidOpenTextDocumentParams {
             text_document: TextDocumentItem::new(
-                Url::parse("http://a.b").unwrap(),
+                uri.clone(),
                 String::from("pine"),
                 2,
                 String::from("a = "),
             ),
         });
-        // println!("receive msg {:?}", receiver.recv().unwrap());
+        let gen_change = |version: i32, text: &str| DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier {
+                uri: uri.clone(),
+                version,
+            },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: String::from(text),
+            }],
+        };
+        server.change_doc(gen_change(3, "a = 1\nplot(b)"));
+        // The stale change is ignored.
+        server.change_doc(gen_change(3, "a = 1"));
+
+        // Only the diagnostics of the latest version are published after the debounce.
+        let diagnostics = loop {
+            let msg: serde_json::Value = serde_json::from_str(&receiver.recv().unwrap()).unwrap();
+            if msg["method"] == "textDocument/publishDiagnostics" {
+                break msg["params"].clone();
+            }
+        };
+        assert_eq!(diagnostics["version"], 3);
+        assert_eq!(diagnostics["diagnostics"].as_array().unwrap().len(), 1);
+        assert_eq!(diagnostics["diagnostics"][0]["range"]["start"]["line"], 1);
+    }
 
-        // assert_eq!(1, 2);
+    #[test]
+    fn hover_test() {
+        let (sender, _receiver) = channel::<String>();
+        let mut server = PineServer::new(sender);
+        let uri = Url::parse("http://a.b").unwrap();
+        server.add_doc(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                String::from("pine"),
+                1,
+                String::from(
+                    "//@function The double value.\n//@param x The source.\n\
+                     double(x) => x * 2\nplot(double(close))",
+                ),
+            ),
+        });
+        let hover = |line: u32, character: u32| {
+            server.hover(TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri.clone()),
+                Position::new(line, character),
+            ))
+        };
+        let result = hover(3, 7).unwrap();
+        assert_eq!(
+            result.contents,
+            HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: String::from(
+                    "```pine\ndouble(x)\n```\n\nThe double value.\n\n\
+                     **Parameters**\n- `x` The source."
+                ),
+            })
+        );
+        assert_eq!(
+            result.range,
+            Some(Range::new(Position::new(3, 5), Position::new(3, 11)))
+        );
+        assert_eq!(hover(3, 13), None);
     }
 }
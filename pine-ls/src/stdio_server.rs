@@ -4,7 +4,6 @@ use jsonrpc_core;
 use lsp_types::*;
 use serde;
 use serde_json;
-use serde_json::json;
 use std::io::{self, BufRead, Read, Write};
 use std::sync::mpsc::channel;
 use std::sync::{Arc, Mutex};
@@ -30,7 +29,7 @@ pub fn start() {
         // capabilities.declaration_provider = Some(true);
         // capabilities.definition_provider = Some(true);
         // capabilities.references_provider = Some(true);
-        capabilities.hover_provider = Some(true);
+        capabilities.hover_provider = Some(HoverProviderCapability::Simple(true));
         capabilities.signature_help_provider = Some(SignatureHelpOptions {
             trigger_characters: Some(vec![String::from("("), String::from(".")]),
             retrigger_characters: None,
@@ -87,13 +86,17 @@ pub fn start() {
         server.lock().unwrap().change_doc(params.parse().unwrap());
     });
 
+    let server = Arc::clone(&pine_server);
     io.add_notification("textDocument/didClose", move |params: Params| {
         info!("Close text document {:?}", params);
+        server.lock().unwrap().close_doc(params.parse().unwrap());
     });
 
+    let server = Arc::clone(&pine_server);
     io.add_method("textDocument/hover", move |params: Params| {
         info!("hover text document {:?}", params);
-        Ok(json!(null))
+        let hover = server.lock().unwrap().hover(params.parse()?);
+        serde_json::to_value(hover).map_err(|_| jsonrpc_core::Error::internal_error())
     });
 
     // Spawn thread to read requests from stdin
@@ -1,16 +1,13 @@
 use lsp_types::Url;
 use lsp_types::*;
 // use pine::ast::input::{Position as StrPos, StrRange};
-use pine::runtime::error_format::PineFormatError;
-use pine::syntax::{SyntaxContext, SyntaxParser};
-use pine::PineScript;
-use std::mem;
 
-pub struct TextDoc<'a> {
+pub struct TextDoc {
     text: String,
     uri: Url,
     line_lens: Vec<usize>,
-    syntax_ctx: Option<Box<SyntaxContext<'a>>>,
+    // The version of the document given by the client, it increases after each change.
+    version: i32,
 }
 
 fn get_line_lens(text: &str) -> Vec<usize> {
@@ -30,14 +27,14 @@ fn get_line_lens(text: &str) -> Vec<usize> {
     line_lens
 }
 
-impl<'a> TextDoc<'a> {
-    pub fn new(text: String, uri: Url) -> TextDoc<'a> {
+impl TextDoc {
+    pub fn new(text: String, uri: Url, version: i32) -> TextDoc {
         let line_lens = get_line_lens(&text);
         TextDoc {
             text,
             uri,
             line_lens,
-            syntax_ctx: None,
+            version,
         }
     }
 
@@ -57,7 +54,7 @@ impl<'a> TextDoc<'a> {
         // split the new text to lines
         let mut new_lines: Vec<_> = get_line_lens(&text);
         new_lines[0] += start.character as usize;
-        if end.line < self.line_lens.len() as u64 {
+        if end.line < self.line_lens.len() as u32 {
             let last_len = self.line_lens[end.line as usize] - end.character as usize;
             *new_lines.last_mut().unwrap() += last_len;
             self.line_lens
@@ -71,6 +68,18 @@ impl<'a> TextDoc<'a> {
         &self.uri
     }
 
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_version(&self) -> i32 {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: i32) {
+        self.version = version;
+    }
+
     // pub fn transfer_range(&self, range: StrRange) -> StrRange {
     //     if range.end == StrPos::max() {
     //         StrRange::new(
@@ -81,21 +90,6 @@ impl<'a> TextDoc<'a> {
     //         range
     //     }
     // }
-
-    pub fn parse_src(&mut self) -> Result<(), Vec<PineFormatError>> {
-        let mut pine_script = PineScript::new(None);
-        let result = pine_script.parse_src(&self.text);
-        let parser = pine_script.move_parser();
-        if parser.is_some() {
-            unsafe {
-                let mut syntax_parser =
-                    mem::transmute::<Option<SyntaxParser>, Option<SyntaxParser<'a>>>(parser);
-                let context = syntax_parser.unwrap().move_context();
-                self.syntax_ctx = Some(context);
-            }
-        }
-        result
-    }
 }
 
 #[cfg(test)]
@@ -121,6 +115,7 @@ mod tests {
         let mut text_doc = TextDoc::new(
             String::from("hello\nworld\nwode\n"),
             Url::parse("https://example.net").unwrap(),
+            1,
         );
         text_doc.change(
             Position::new(1, 1),
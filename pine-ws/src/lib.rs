@@ -42,10 +42,12 @@ macro_rules! log {
 // }
 
 use pine::runtime::{
-    AnySeries, InputVal, NoneCallback, OutputData, OutputDataCollect, OutputInfo, PineFormatError,
-    PlotInfo, StrOptionsData, SymbolInfo,
+    AnySeries, ErrorFormater, InputVal, NoneCallback, OutputData, OutputDataCollect,
+    PineFormatError, PineRuntimeError, SymbolInfo,
 };
+use pine::types::RuntimeErr;
 use pine::PineScript;
+use serde::Serialize;
 use std::convert::TryInto;
 use std::f64;
 use std::mem::transmute;
@@ -69,27 +71,47 @@ pub fn new_runner() -> ExportPineRunner {
     }
 }
 
+fn get_script(runner: &mut ExportPineRunner) -> &mut PineScript<'static, 'static, 'static> {
+    unsafe { &mut *transmute::<*mut (), *mut PineScript>(runner.script) }
+}
+
+// Convert the value to JsValue, the values passed to JS are always serializable. The maps
+// are converted to the plain objects and None to null like JSON.
+fn to_js<T: Serialize>(val: &T) -> JsValue {
+    val.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap_or(JsValue::NULL)
+}
+
+fn runtime_error(err: RuntimeErr) -> PineFormatError {
+    PineFormatError::from_runtime_error(&ErrorFormater::new(), PineRuntimeError::new_no_range(err))
+}
+
+// Deserialize the argument passed from JS, the invalid argument is returned as the error.
+fn from_js<T>(val: &JsValue, name: &str) -> Result<T, JsValue>
+where
+    T: for<'de> serde::Deserialize<'de>,
+{
+    serde_wasm_bindgen::from_value(val.clone()).map_err(|err| {
+        let msg = format!("The argument {} is invalid, {}.", name, err);
+        to_js(&runtime_error(RuntimeErr::InvalidParameters(msg)))
+    })
+}
+
 #[wasm_bindgen]
 pub fn parse_src(runner: &mut ExportPineRunner, src: String) -> Result<(), JsValue> {
-    let runner_ins = unsafe {
-        let script = transmute::<*mut (), *mut PineScript>(runner.script);
-        script.as_mut().unwrap()
-    };
+    let runner_ins = get_script(runner);
     match runner_ins.parse_src(src) {
         Ok(_) => Ok(()),
-        Err(errs) => Err(JsValue::from_serde(&errs).unwrap()),
+        Err(errs) => Err(to_js(&errs)),
     }
 }
 
 #[wasm_bindgen]
 pub fn gen_io_info(runner: &mut ExportPineRunner) -> Result<JsValue, JsValue> {
-    let runner_ins = unsafe {
-        let script = transmute::<*mut (), *mut PineScript>(runner.script);
-        script.as_mut().unwrap()
-    };
+    let runner_ins = get_script(runner);
     match runner_ins.gen_io_info() {
-        Ok(io_info) => Ok(JsValue::from_serde(&io_info).unwrap()),
-        Err(errs) => Err(JsValue::from_serde(&errs).unwrap()),
+        Ok(io_info) => Ok(to_js(&io_info)),
+        Err(errs) => Err(to_js(&errs)),
     }
 }
 
@@ -131,7 +153,7 @@ pub fn output_options(output: &mut ExportOutputData) -> JsValue {
     match output_d {
         None => {
             let opts: Vec<String> = vec![];
-            JsValue::from_serde(&opts).unwrap()
+            to_js(&opts)
         }
         Some(output_d) => {
             let opts: Vec<_> = output_d
@@ -139,7 +161,7 @@ pub fn output_options(output: &mut ExportOutputData) -> JsValue {
                 .iter()
                 .map(|d| d.options.join("|"))
                 .collect();
-            JsValue::from_serde(&opts).unwrap()
+            to_js(&opts)
         }
     }
 }
@@ -187,15 +209,18 @@ impl Drop for ExportOutputArray {
 #[wasm_bindgen]
 pub fn output_array(array: &ExportOutputArray) -> JsValue {
     let output = unsafe { transmute::<_, &mut OutputDataCollect>(array.outputs) };
-    JsValue::from_serde(&vec![output.from, output.to, output.data_list.len() as i32]).unwrap()
+    to_js(&vec![output.from, output.to, output.data_list.len() as i32])
 }
 
 #[wasm_bindgen]
-pub fn output_array_get(array: &ExportOutputArray, i: usize) -> ExportOutputData {
+pub fn output_array_get(array: &ExportOutputArray, i: usize) -> Result<ExportOutputData, JsValue> {
     let output = unsafe { transmute::<_, &mut OutputDataCollect>(array.outputs) };
-
-    ExportOutputData {
-        output_data: &mut output.data_list[i],
+    match output.data_list.get_mut(i) {
+        Some(output_data) => Ok(ExportOutputData { output_data }),
+        None => {
+            let msg = format!("The output index {} is out of range.", i);
+            Err(to_js(&runtime_error(RuntimeErr::InvalidParameters(msg))))
+        }
     }
 }
 
@@ -210,14 +235,11 @@ pub fn run_with_input(
     runner: &mut ExportPineRunner,
     input_val: JsValue,
 ) -> Result<ExportOutputArray, JsValue> {
-    let runner_ins = unsafe {
-        let script = transmute::<*mut (), *mut PineScript>(runner.script);
-        script.as_mut().unwrap()
-    };
-    let input: Vec<Option<InputVal>> = input_val.into_serde().unwrap();
+    let runner_ins = get_script(runner);
+    let input: Vec<Option<InputVal>> = from_js(&input_val, "input_val")?;
     match runner_ins.run_with_input(input) {
         Ok(output) => Ok(output_data_to_slice(output)),
-        Err(err) => Err(JsValue::from_serde(&err).unwrap()),
+        Err(err) => Err(to_js(&err)),
     }
 }
 
@@ -235,40 +257,45 @@ fn slice_input_data_i64(origin_data: &[f64], index: usize, count: usize) -> Vec<
         .collect()
 }
 
+// Split the data of all the sources to the series, the data of every source has count items.
 fn transfer_input_data(
     src_strs: Vec<String>,
     count: usize,
     data: &[f64],
-) -> Vec<(&'static str, AnySeries)> {
+) -> Result<Vec<(&'static str, AnySeries)>, PineFormatError> {
+    match src_strs.len().checked_mul(count) {
+        Some(len) if len <= data.len() => {}
+        _ => return Err(runtime_error(RuntimeErr::InvalidDataLen)),
+    }
     src_strs
         .into_iter()
         .enumerate()
         .map(|(i, s)| match s.as_str() {
-            "close" => (
+            "close" => Ok((
                 "close",
                 AnySeries::from_float_vec(slice_input_data(data, i, count)),
-            ),
-            "open" => (
+            )),
+            "open" => Ok((
                 "open",
                 AnySeries::from_float_vec(slice_input_data(data, i, count)),
-            ),
-            "high" => (
+            )),
+            "high" => Ok((
                 "high",
                 AnySeries::from_float_vec(slice_input_data(data, i, count)),
-            ),
-            "low" => (
+            )),
+            "low" => Ok((
                 "low",
                 AnySeries::from_float_vec(slice_input_data(data, i, count)),
-            ),
-            "time" => (
+            )),
+            "time" => Ok((
                 "_time",
                 AnySeries::from_int_vec(slice_input_data_i64(data, i, count)),
-            ),
-            "volume" => (
+            )),
+            "volume" => Ok((
                 "volume",
                 AnySeries::from_int_vec(slice_input_data_i64(data, i, count)),
-            ),
-            _ => unreachable!(),
+            )),
+            _ => Err(runtime_error(RuntimeErr::UnknownDataSource(s))),
         })
         .collect()
 }
@@ -281,22 +308,18 @@ pub fn run_with_data(
     data: &[f64],
     syminfo: JsValue,
 ) -> Result<ExportOutputArray, JsValue> {
-    let runner_ins = unsafe {
-        let script = transmute::<*mut (), *mut PineScript>(runner.script);
-        script.as_mut().unwrap()
-    };
-    let src_strs: Vec<String> = srcs.into_serde().unwrap();
-    debug_assert_eq!(data.len(), src_strs.len() * count);
-    let input_data = transfer_input_data(src_strs, count, data);
+    let runner_ins = get_script(runner);
+    let src_strs: Vec<String> = from_js(&srcs, "srcs")?;
+    let input_data = transfer_input_data(src_strs, count, data).map_err(|err| to_js(&err))?;
 
-    let info: Option<Rc<SymbolInfo>> = match syminfo.into_serde() {
+    let info: Option<Rc<SymbolInfo>> = match serde_wasm_bindgen::from_value(syminfo) {
         Ok(info) => Some(Rc::new(info)),
         Err(_) => None,
     };
     log!("Get sym info {:?}", info);
     match runner_ins.run_with_datal(input_data, count, info) {
         Ok(output) => Ok(output_data_to_slice(output)),
-        Err(err) => Err(JsValue::from_serde(&err).unwrap()),
+        Err(err) => Err(to_js(&err)),
     }
 }
 
@@ -309,23 +332,18 @@ pub fn run(
     data: &[f64],
     syminfo: JsValue,
 ) -> Result<ExportOutputArray, JsValue> {
-    let runner_ins = unsafe {
-        let script = transmute::<*mut (), *mut PineScript>(runner.script);
-        script.as_mut().unwrap()
-    };
-    let src_strs: Vec<String> = srcs.into_serde().unwrap();
-    debug_assert_eq!(data.len(), src_strs.len() * count);
-
-    let input: Vec<Option<InputVal>> = input_val.into_serde().unwrap();
-    let input_data = transfer_input_data(src_strs, count, data);
+    let runner_ins = get_script(runner);
+    let src_strs: Vec<String> = from_js(&srcs, "srcs")?;
+    let input: Vec<Option<InputVal>> = from_js(&input_val, "input_val")?;
+    let input_data = transfer_input_data(src_strs, count, data).map_err(|err| to_js(&err))?;
 
-    let info: Option<Rc<SymbolInfo>> = match syminfo.into_serde() {
+    let info: Option<Rc<SymbolInfo>> = match serde_wasm_bindgen::from_value(syminfo) {
         Ok(info) => Some(Rc::new(info)),
         Err(_) => None,
     };
     match runner_ins.runl(input, input_data, count, info) {
         Ok(output) => Ok(output_data_to_slice(output)),
-        Err(err) => Err(JsValue::from_serde(&err).unwrap()),
+        Err(err) => Err(to_js(&err)),
     }
 }
 
@@ -336,16 +354,12 @@ pub fn update(
     count: usize,
     data: &[f64],
 ) -> Result<ExportOutputArray, JsValue> {
-    let runner_ins = unsafe {
-        let script = transmute::<*mut (), *mut PineScript>(runner.script);
-        script.as_mut().unwrap()
-    };
-    let src_strs: Vec<String> = srcs.into_serde().unwrap();
-    debug_assert_eq!(data.len(), src_strs.len() * count);
-    let input_data = transfer_input_data(src_strs, count, data);
+    let runner_ins = get_script(runner);
+    let src_strs: Vec<String> = from_js(&srcs, "srcs")?;
+    let input_data = transfer_input_data(src_strs, count, data).map_err(|err| to_js(&err))?;
     match runner_ins.updatel(input_data, count) {
         Ok(output) => Ok(output_data_to_slice(output)),
-        Err(err) => Err(JsValue::from_serde(&err).unwrap()),
+        Err(err) => Err(to_js(&err)),
     }
 }
 
@@ -357,16 +371,12 @@ pub fn update_from(
     count: usize,
     data: &[f64],
 ) -> Result<ExportOutputArray, JsValue> {
-    let runner_ins = unsafe {
-        let script = transmute::<*mut (), *mut PineScript>(runner.script);
-        script.as_mut().unwrap()
-    };
-    let src_strs: Vec<String> = srcs.into_serde().unwrap();
-    debug_assert_eq!(data.len(), src_strs.len() * count);
-    let input_data = transfer_input_data(src_strs, count, data);
+    let runner_ins = get_script(runner);
+    let src_strs: Vec<String> = from_js(&srcs, "srcs")?;
+    let input_data = transfer_input_data(src_strs, count, data).map_err(|err| to_js(&err))?;
     match runner_ins.update_froml(input_data, from, count) {
         Ok(output) => Ok(output_data_to_slice(output)),
-        Err(err) => Err(JsValue::from_serde(&err).unwrap()),
+        Err(err) => Err(to_js(&err)),
     }
 }
 
@@ -93,7 +93,7 @@ fn volume_test() {
     );
     assert!(result.is_ok());
     if let Ok(output) = result {
-        let mut out_data = output_array_get(&output, 0);
+        let mut out_data = output_array_get(&output, 0).unwrap();
         let vec = unsafe { Vec::from_raw_parts(output_series(&mut out_data), 3, 3) };
         assert_eq!(vec, vec![1f64, 1f64, 10f64]);
     }
@@ -110,3 +110,39 @@ fn timenow_test() {
     );
     assert!(gen_io_info(&mut runner).is_ok());
 }
+
+#[derive(serde_derive::Deserialize)]
+struct RuntimeErrorShape {
+    bar_index: Option<i32>,
+    call_stack: Vec<CallFrameShape>,
+}
+
+#[derive(serde_derive::Deserialize)]
+struct CallFrameShape {
+    name: String,
+}
+
+#[wasm_bindgen_test]
+fn runtime_error_test() {
+    init_panic_hook();
+    let mut runner = new_runner();
+    assert_eq!(
+        parse_src(
+            &mut runner,
+            String::from("f(i) => close[i]\nidx = bar_index >= 1 ? na : 1\nplot(f(idx))")
+        ),
+        Ok(())
+    );
+    let input_data: Vec<f64> = vec![1f64, 2f64];
+    let result = run_with_data(
+        &mut runner,
+        serde_wasm_bindgen::to_value(&vec!["close"]).unwrap(),
+        2,
+        input_data.into_boxed_slice().as_mut(),
+        JsValue::NULL,
+    );
+    let err: RuntimeErrorShape = serde_wasm_bindgen::from_value(result.err().unwrap()).unwrap();
+    assert_eq!(err.bar_index, Some(1));
+    assert_eq!(err.call_stack.len(), 1);
+    assert_eq!(err.call_stack[0].name, "f");
+}
@@ -22,6 +22,15 @@ plot(VIP, title="VI +", color=#3BB3E4)
 plot(VIM, title="VI -", color=#FF006E)
 "#;
 
+const ARITH_SCRIPTS: &str = r#"
+mid = (high + low) / 2
+range = high - low
+up = close > open and range > 0 ? (close - open) / range : 0.0
+down = close < open and range > 0 ? (open - close) / range : 0.0
+score = (up - down) * 100 + (close - close[1]) / (mid + 1) - (high[2] - low[2]) * 0.5
+plot(score)
+"#;
+
 fn run_data() {
     let lib_info = pine::LibInfo::new(
         declare_vars(),
@@ -51,8 +60,33 @@ fn run_data() {
     assert!(parser.run_with_data(data, None).is_ok());
 }
 
+fn run_arith_data(vm: bool) {
+    let mut parser = pine::PineScript::new(Some(&NoneCallback()));
+    parser.set_vm(vm);
+    parser.parse_src(String::from(ARITH_SCRIPTS)).unwrap();
+
+    let gen_data = |v: f64| {
+        let mut data: Vec<Option<f64>> = vec![];
+        data.resize(10000, Some(v));
+        AnySeries::from_float_vec(data)
+    };
+    let data = vec![
+        ("close", gen_data(5f64)),
+        ("open", gen_data(4f64)),
+        ("high", gen_data(10f64)),
+        ("low", gen_data(1f64)),
+    ];
+    assert!(parser.run_with_data(data, None).is_ok());
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("pine runner", |b| b.iter(|| run_data()));
+    c.bench_function("pine runner arith ast", |b| {
+        b.iter(|| run_arith_data(black_box(false)))
+    });
+    c.bench_function("pine runner arith vm", |b| {
+        b.iter(|| run_arith_data(black_box(true)))
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
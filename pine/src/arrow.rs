@@ -0,0 +1,261 @@
+// Convert the Arrow record batches to the input data of the script and the output data of
+// the script to the record batches, so the columnar data is converted in one pass.
+use crate::runtime::error_format::PineFormatError;
+use crate::runtime::output::{OutputDataCollect, SymbolInfo};
+use crate::runtime::{AnySeries, AnySeriesType};
+use crate::types::RuntimeErr;
+use crate::{runtime_error, PineScript};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    ArrowPrimitiveType, Float32Type, Float64Type, Int32Type, Int64Type, TimestampMillisecondType,
+};
+use arrow_array::{Array, ArrayRef, Float64Array, Int64Array, PrimitiveArray, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, TimeUnit};
+use std::borrow::Cow;
+use std::iter;
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub const BAR_INDEX_COLUMN: &str = "bar_index";
+
+// The name of the column for the series of the output, like "output_0_0" for the first
+// series of the first output.
+pub fn output_column_name(output: usize, series: usize) -> String {
+    format!("output_{}_{}", output, series)
+}
+
+// The values are copied from the value buffer directly if the array has no null values,
+// otherwise the null values are mapped to na.
+fn to_options<T: ArrowPrimitiveType, U>(
+    array: &PrimitiveArray<T>,
+    f: impl Fn(T::Native) -> U,
+) -> Vec<Option<U>> {
+    match array.nulls() {
+        Some(nulls) if nulls.null_count() > 0 => array
+            .values()
+            .iter()
+            .zip(nulls.iter())
+            .map(|(v, valid)| if valid { Some(f(*v)) } else { None })
+            .collect(),
+        _ => array.values().iter().map(|v| Some(f(*v))).collect(),
+    }
+}
+
+fn to_series(array: &ArrayRef, series_type: AnySeriesType) -> Option<AnySeries> {
+    match (series_type, array.data_type()) {
+        (AnySeriesType::Float, DataType::Float64) => Some(AnySeries::from_float_vec(to_options(
+            array.as_primitive::<Float64Type>(),
+            |v| v,
+        ))),
+        (AnySeriesType::Float, DataType::Float32) => Some(AnySeries::from_float_vec(to_options(
+            array.as_primitive::<Float32Type>(),
+            |v| v as f64,
+        ))),
+        (AnySeriesType::Int, DataType::Int64) => Some(AnySeries::from_int_vec(to_options(
+            array.as_primitive::<Int64Type>(),
+            |v| v,
+        ))),
+        (AnySeriesType::Int, DataType::Int32) => Some(AnySeries::from_int_vec(to_options(
+            array.as_primitive::<Int32Type>(),
+            |v| v as i64,
+        ))),
+        (AnySeriesType::Int, DataType::Timestamp(TimeUnit::Millisecond, _)) => {
+            Some(AnySeries::from_int_vec(to_options(
+                array.as_primitive::<TimestampMillisecondType>(),
+                |v| v,
+            )))
+        }
+        _ => None,
+    }
+}
+
+// Convert the columns named by the input sources like "close" and "time" to the input data,
+// the other columns are ignored. The float sources accept the Float64 and Float32 columns,
+// the int sources accept the Int64, Int32 and millisecond Timestamp columns.
+pub fn batch_to_data(
+    batch: &RecordBatch,
+    input_names: &[(&str, AnySeriesType)],
+) -> Result<Vec<(Cow<'static, str>, AnySeries)>, RuntimeErr> {
+    let schema = batch.schema();
+    let mut data = vec![];
+    for (field, array) in schema.fields().iter().zip(batch.columns()) {
+        let name = field.name().as_str();
+        let input = input_names
+            .iter()
+            .find(|(n, _)| *n == name || name == "time" && *n == "_time");
+        if let Some((name, series_type)) = input {
+            let series = to_series(array, *series_type)
+                .ok_or_else(|| RuntimeErr::DataTypeMismatch(field.name().clone()))?;
+            data.push((Cow::Owned(name.to_string()), series));
+        }
+    }
+    Ok(data)
+}
+
+// Convert the output data to the record batch with the bar index column and one Float64
+// column for every series of the outputs, the rows start at the `from` bar. The outputs
+// that have no data and the empty series like the series of fill are skipped.
+pub fn output_to_batch(output: &OutputDataCollect) -> Result<RecordBatch, ArrowError> {
+    let series: Vec<_> = output
+        .data_list
+        .iter()
+        .enumerate()
+        .filter_map(|(i, data)| data.as_ref().map(|data| (i, data)))
+        .flat_map(|(i, data)| {
+            data.series
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| !s.is_empty())
+                .map(move |(j, s)| (output_column_name(i, j), s))
+        })
+        .collect();
+    // The rendered series with the positive offset may be longer than the bars.
+    let rows = series
+        .iter()
+        .map(|(_, s)| s.len())
+        .fold((output.to - output.from).max(0) as usize, usize::max);
+
+    let mut fields = vec![Field::new(BAR_INDEX_COLUMN, DataType::Int64, false)];
+    let mut columns: Vec<ArrayRef> = vec![Arc::new(Int64Array::from_iter_values(
+        (0..rows as i64).map(|i| output.from as i64 + i),
+    ))];
+    for (name, s) in series {
+        fields.push(Field::new(name, DataType::Float64, true));
+        let values = s.iter().cloned().chain(iter::repeat(None)).take(rows);
+        columns.push(Arc::new(values.collect::<Float64Array>()));
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+}
+
+impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
+    fn batch_to_data(
+        &self,
+        batch: &RecordBatch,
+    ) -> Result<Vec<(Cow<'static, str>, AnySeries)>, PineFormatError> {
+        batch_to_data(batch, &self.lib_info.input_names)
+            .map_err(|err| runtime_error(&self.error_format, err))
+    }
+
+    // Run the script with the data of the record batch.
+    pub fn run_with_batch(
+        &mut self,
+        batch: &RecordBatch,
+        syminfo: Option<Rc<SymbolInfo>>,
+    ) -> Result<OutputDataCollect, PineFormatError>
+    where
+        'li: 'ra,
+        'pa: 'ra,
+    {
+        let data = self.batch_to_data(batch)?;
+        self.run_with_datal_named(data, batch.num_rows(), syminfo)
+    }
+
+    // Run the script with the updated data of the record batch(The last data included).
+    pub fn update_with_batch(
+        &mut self,
+        batch: &RecordBatch,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        let data = self.batch_to_data(batch)?;
+        self.updatel_named(data, batch.num_rows())
+    }
+
+    // Run the script with the data of the record batch since the `from` bar.
+    pub fn update_from_batch(
+        &mut self,
+        batch: &RecordBatch,
+        from: i32,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        let data = self.batch_to_data(batch)?;
+        self.update_froml_named(data, from, batch.num_rows())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::output::OutputData;
+    use crate::runtime::NoneCallback;
+    use arrow_array::TimestampMillisecondArray;
+
+    fn batch(close: Vec<Option<f64>>) -> RecordBatch {
+        let times: Vec<_> = (0..close.len() as i64).map(|i| i * 1000).collect();
+        RecordBatch::try_from_iter(vec![
+            (
+                "time",
+                Arc::new(TimestampMillisecondArray::from(times)) as ArrayRef,
+            ),
+            ("close", Arc::new(Float64Array::from(close)) as ArrayRef),
+            (
+                "unknown",
+                Arc::new(Int64Array::from(vec![0; 3])) as ArrayRef,
+            ),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn batch_test() {
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script
+            .parse_src(String::from("plot(close * 2)\nplot(time)"))
+            .unwrap();
+        let output = script
+            .run_with_batch(&batch(vec![Some(1f64), None, Some(3f64)]), None)
+            .unwrap();
+        assert_eq!(
+            output.data_list[0],
+            Some(OutputData::new(vec![vec![Some(2f64), None, Some(6f64)]]))
+        );
+        assert_eq!(
+            output.data_list[1],
+            Some(OutputData::new(vec![vec![
+                Some(0f64),
+                Some(1000f64),
+                Some(2000f64)
+            ]]))
+        );
+
+        let batch = output_to_batch(&output).unwrap();
+        let schema = batch.schema();
+        let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, vec!["bar_index", "output_0_0", "output_1_0"]);
+        assert_eq!(
+            batch.column(0).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![0, 1, 2])
+        );
+        assert_eq!(
+            batch.column(1).as_primitive::<Float64Type>(),
+            &Float64Array::from(vec![Some(2f64), None, Some(6f64)])
+        );
+
+        // The updated rows start at the last bar.
+        let update = RecordBatch::try_from_iter(vec![
+            (
+                "time",
+                Arc::new(TimestampMillisecondArray::from(vec![2000, 3000])) as ArrayRef,
+            ),
+            (
+                "close",
+                Arc::new(Float64Array::from(vec![4f64, 5f64])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let output = output_to_batch(&script.update_with_batch(&update).unwrap()).unwrap();
+        assert_eq!(
+            output.column(0).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![2, 3])
+        );
+        assert_eq!(
+            output.column(1).as_primitive::<Float64Type>(),
+            &Float64Array::from(vec![8f64, 10f64])
+        );
+
+        // The source column must have the numeric type of the source.
+        let invalid = RecordBatch::try_from_iter(vec![(
+            "close",
+            Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef,
+        )])
+        .unwrap();
+        assert!(script.run_with_batch(&invalid, None).is_err());
+    }
+}
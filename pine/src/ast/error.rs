@@ -48,7 +48,16 @@ pub enum PineErrorKind {
     VarHasDeclare,                // The variable in assignment has declared before.
     BreakNotInForStmt,            // Use break in non for-range statement.
     ContinueNotInForStmt,         // Use break in non for-range statement.
+    LibraryNotResolved,           // The imported library cannot be found by the resolver.
+    LibraryInvalid,               // The imported library is not a valid library script.
+    LibFuncNotExported,           // The function is not exported by the imported library.
+    ImportNotInGlobal,            // The import statement is not in the global scope.
+    TypeNotInGlobal,              // The type declaration is not in the global scope.
     NonRecongnizeStmt,            // This statement is not recongnized.
+    VersionNotMatch {
+        script: i32,
+        builtins: i32,
+    }, // The script version doesn't match the version of the builtin variables.
     UnknownErr,                   // Unknown error.
 }
 
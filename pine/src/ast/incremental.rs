@@ -0,0 +1,499 @@
+use super::error::PineErrorKind;
+use super::input::{Input, Position, StrRange};
+use super::num::Numeral;
+use super::stat_expr::block;
+use super::stat_expr_types::{Block, Exp, FunctionCall, FunctionDef, Statement};
+use super::state::{AstState, PineInputError};
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+
+// The parsed top-level statement together with the following indented lines.
+pub struct CachedChunk {
+    src: Rc<str>,
+    start_line: u32,
+    // The statements borrow the source of this chunk.
+    stmts: Vec<Statement<'static>>,
+    errors: Vec<PineInputError>,
+}
+
+// Cache the parse results of the top-level statements by their sources, so that parsing the
+// edited script only parses the changed statements again. The statements moved by the
+// inserted or removed lines are reused with the positions moved.
+#[derive(Default)]
+pub struct AstCache {
+    chunks: HashMap<Rc<str>, Vec<Rc<CachedChunk>>>,
+    reused: usize,
+}
+
+// The chunks of the parsed source. The block of the source borrows the statements of the
+// chunks, the chunks are shared with the cache and live until both of them are dropped.
+pub struct ParsedChunks {
+    chunks: Vec<Rc<CachedChunk>>,
+}
+
+fn is_else_line(line: &str) -> bool {
+    line.starts_with("else")
+        && !line[4..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+// Whether the line starts a new top-level statement.
+fn is_stmt_line(line: &str) -> bool {
+    match line.chars().next() {
+        None | Some(' ') | Some('\t') | Some('\r') | Some('\n') => false,
+        _ => !line.starts_with("//") && !is_else_line(line),
+    }
+}
+
+// Remove the comment at the end of the line.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q && prev != '\\' => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '/' && prev == '/' => return &line[..i - 1],
+            None => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+// Whether the expression in the line is wrapped to the next line, e.g. the line ends with
+// an operator or an open bracket.
+fn is_wrapped_line(line: &str) -> bool {
+    let line = strip_comment(line).trim_end();
+    match line.chars().last() {
+        Some(c) if "+-*/%<>=!?:,([".contains(c) => true,
+        _ => {
+            let is_word_end = |word: &str| {
+                line.ends_with(word)
+                    && !line[..line.len() - word.len()]
+                        .chars()
+                        .last()
+                        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+            };
+            is_word_end("and") || is_word_end("or") || is_word_end("not")
+        }
+    }
+}
+
+// Split the source to the chunks each contains one top-level statement. The indented lines,
+// else branches, wrapped lines, comments and empty lines belong to the previous statement.
+fn split_chunks(src: &str) -> Vec<(u32, &str)> {
+    let mut chunks = vec![];
+    let mut start = 0;
+    let mut start_line = 0;
+    let mut offset = 0;
+    let mut has_stmt = false;
+    let mut wrapped = false;
+    for (line_no, line) in src.split_inclusive('\n').enumerate() {
+        if is_stmt_line(line) {
+            if has_stmt && !wrapped {
+                chunks.push((start_line, &src[start..offset]));
+                start = offset;
+                start_line = line_no as u32;
+            }
+            has_stmt = true;
+        }
+        if !strip_comment(line).trim().is_empty() {
+            wrapped = is_wrapped_line(line);
+        }
+        offset += line.len();
+    }
+    chunks.push((start_line, &src[start..]));
+    chunks
+}
+
+fn parse_chunk(src: Rc<str>, start_line: u32) -> CachedChunk {
+    let chunk_src = unsafe { mem::transmute::<&str, &'static str>(&src) };
+    let input = Input::new(chunk_src, Position::new(start_line, 0), Position::max());
+    let state = AstState::new();
+    let mut stmts = vec![];
+    match block(input, &state) {
+        Ok((input, blk)) => {
+            stmts = blk.stmts;
+            if let Some(exp) = blk.ret_stmt {
+                stmts.push(Statement::Exp(exp));
+            }
+            if input.len() != 0 {
+                state.catch(PineInputError::new(
+                    PineErrorKind::NonRecongnizeStmt,
+                    StrRange::new(input.start, Position::new(input.start.get_line(), u32::MAX)),
+                ));
+            }
+        }
+        Err(nom::Err::Error(pine_error)) => state.merge_pine_error(pine_error),
+        _ => state.catch(PineInputError::new(
+            PineErrorKind::UnknownErr,
+            StrRange::new(input.start, Position::max()),
+        )),
+    }
+    CachedChunk {
+        src,
+        start_line,
+        stmts,
+        errors: state.into_inner(),
+    }
+}
+
+fn shift_block(blk: &mut Block, delta: i64) {
+    blk.stmts
+        .iter_mut()
+        .for_each(|stmt| shift_stmt(stmt, delta));
+    if let Some(exp) = blk.ret_stmt.as_mut() {
+        shift_exp(exp, delta);
+    }
+    blk.range = blk.range.shift_lines(delta);
+}
+
+fn shift_func_call(func_call: &mut FunctionCall, delta: i64) {
+    shift_exp(&mut func_call.method, delta);
+    func_call
+        .pos_args
+        .iter_mut()
+        .for_each(|exp| shift_exp(exp, delta));
+    for (name, exp) in func_call.dict_args.iter_mut() {
+        name.range = name.range.shift_lines(delta);
+        shift_exp(exp, delta);
+    }
+    func_call.range = func_call.range.shift_lines(delta);
+}
+
+fn shift_func_def(def: &mut FunctionDef, delta: i64) {
+    def.name.range = def.name.range.shift_lines(delta);
+    for name in def.params.iter_mut() {
+        name.range = name.range.shift_lines(delta);
+    }
+    for spec in def.param_specs.iter_mut() {
+        if let Some(exp) = spec.default.as_mut() {
+            shift_exp(exp, delta);
+        }
+    }
+    shift_block(&mut def.body, delta);
+    if let Some(defs) = def.spec_defs.as_mut() {
+        defs.iter_mut().for_each(|def| shift_func_def(def, delta));
+    }
+    def.range = def.range.shift_lines(delta);
+}
+
+fn shift_exp(exp: &mut Exp, delta: i64) {
+    match exp {
+        Exp::Na(node) => node.range = node.range.shift_lines(delta),
+        Exp::Bool(node) => node.range = node.range.shift_lines(delta),
+        Exp::Num(Numeral::Float(node)) => node.range = node.range.shift_lines(delta),
+        Exp::Num(Numeral::Int(node)) => node.range = node.range.shift_lines(delta),
+        Exp::Str(node) => node.range = node.range.shift_lines(delta),
+        Exp::Color(node) => node.range = node.range.shift_lines(delta),
+        Exp::VarName(node) => node.name.range = node.name.range.shift_lines(delta),
+        Exp::Tuple(node) => {
+            node.exps.iter_mut().for_each(|exp| shift_exp(exp, delta));
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::TypeCast(node) => {
+            shift_exp(&mut node.exp, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::FuncCall(node) => shift_func_call(node, delta),
+        Exp::RefCall(node) => {
+            shift_exp(&mut node.name, delta);
+            shift_exp(&mut node.arg, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::PrefixExp(node) => {
+            shift_exp(&mut node.left_exp, delta);
+            node.right_name.range = node.right_name.range.shift_lines(delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::Condition(node) => {
+            shift_exp(&mut node.cond, delta);
+            shift_exp(&mut node.exp1, delta);
+            shift_exp(&mut node.exp2, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::Ite(node) => {
+            shift_exp(&mut node.cond, delta);
+            shift_block(&mut node.then_blk, delta);
+            if let Some(blk) = node.else_blk.as_mut() {
+                shift_block(blk, delta);
+            }
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::ForRange(node) => {
+            node.var.range = node.var.range.shift_lines(delta);
+            shift_exp(&mut node.start, delta);
+            shift_exp(&mut node.end, delta);
+            if let Some(exp) = node.step.as_mut() {
+                shift_exp(exp, delta);
+            }
+            shift_block(&mut node.do_blk, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::Assignment(node) => {
+            for name in node.names.iter_mut() {
+                name.range = name.range.shift_lines(delta);
+            }
+            shift_exp(&mut node.val, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::VarAssignment(node) => {
+            node.name.range = node.name.range.shift_lines(delta);
+            for name in node.fields.iter_mut() {
+                name.range = name.range.shift_lines(delta);
+            }
+            shift_exp(&mut node.val, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::UnaryExp(node) => {
+            shift_exp(&mut node.exp, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Exp::BinaryExp(node) => {
+            shift_exp(&mut node.exp1, delta);
+            shift_exp(&mut node.exp2, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+    }
+}
+
+// Move the positions of the statement and all the nodes in it by the count of lines.
+fn shift_stmt(stmt: &mut Statement, delta: i64) {
+    match stmt {
+        Statement::Break(range)
+        | Statement::Continue(range)
+        | Statement::None(range)
+        | Statement::Error(range) => *range = range.shift_lines(delta),
+        Statement::Assignment(node) => {
+            for name in node.names.iter_mut() {
+                name.range = name.range.shift_lines(delta);
+            }
+            shift_exp(&mut node.val, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Statement::VarAssignment(node) => {
+            node.name.range = node.name.range.shift_lines(delta);
+            for name in node.fields.iter_mut() {
+                name.range = name.range.shift_lines(delta);
+            }
+            shift_exp(&mut node.val, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Statement::Ite(node) => {
+            shift_exp(&mut node.cond, delta);
+            shift_block(&mut node.then_blk, delta);
+            if let Some(blk) = node.else_blk.as_mut() {
+                shift_block(blk, delta);
+            }
+            node.range = node.range.shift_lines(delta);
+        }
+        Statement::ForRange(node) => {
+            node.var.range = node.var.range.shift_lines(delta);
+            shift_exp(&mut node.start, delta);
+            shift_exp(&mut node.end, delta);
+            if let Some(exp) = node.step.as_mut() {
+                shift_exp(exp, delta);
+            }
+            shift_block(&mut node.do_blk, delta);
+            node.range = node.range.shift_lines(delta);
+        }
+        Statement::FuncCall(node) => shift_func_call(node, delta),
+        Statement::FuncDef(node) => shift_func_def(node, delta),
+        Statement::Import(node) => {
+            node.alias.range = node.alias.range.shift_lines(delta);
+            node.defs
+                .iter_mut()
+                .for_each(|def| shift_func_def(def, delta));
+            node.range = node.range.shift_lines(delta);
+        }
+        Statement::TypeDef(node) => {
+            node.name.range = node.name.range.shift_lines(delta);
+            for field in node.fields.iter_mut() {
+                field.name.range = field.name.range.shift_lines(delta);
+                if let Some(exp) = field.default.as_mut() {
+                    shift_exp(exp, delta);
+                }
+                field.range = field.range.shift_lines(delta);
+            }
+            node.range = node.range.shift_lines(delta);
+        }
+        Statement::Exp(exp) => shift_exp(exp, delta),
+    }
+}
+
+impl CachedChunk {
+    // The chunk that has the same source and the statements moved to the start line.
+    fn move_to(&self, start_line: u32) -> CachedChunk {
+        let delta = start_line as i64 - self.start_line as i64;
+        let mut stmts = self.stmts.clone();
+        stmts.iter_mut().for_each(|stmt| shift_stmt(stmt, delta));
+        CachedChunk {
+            src: self.src.clone(),
+            start_line,
+            stmts,
+            errors: self
+                .errors
+                .iter()
+                .map(|err| PineInputError::new(err.code.clone(), err.range.shift_lines(delta)))
+                .collect(),
+        }
+    }
+}
+
+impl AstCache {
+    pub fn new() -> AstCache {
+        AstCache::default()
+    }
+
+    // The count of the top-level statements reused by the last parse.
+    pub fn reused_count(&self) -> usize {
+        self.reused
+    }
+
+    // Parse the source to the chunks of the top-level statements, the statements whose
+    // sources are not changed since the last parse are reused from the cache. The replaced
+    // chunks are only dropped by the cache, the blocks that borrow them keep them alive by
+    // the previous parsed chunks.
+    pub fn parse(&mut self, src: &str) -> ParsedChunks {
+        let mut old_chunks = mem::take(&mut self.chunks);
+        self.reused = 0;
+
+        let mut chunks = vec![];
+        for (start_line, chunk_src) in split_chunks(src) {
+            // Prefer the chunk that is not moved if the same source occurs more than once.
+            let old_chunk = old_chunks.get_mut(chunk_src).and_then(|chunks| {
+                let i = chunks
+                    .iter()
+                    .position(|c| c.start_line == start_line)
+                    .unwrap_or(0);
+                if i < chunks.len() {
+                    Some(chunks.swap_remove(i))
+                } else {
+                    None
+                }
+            });
+            let chunk = match old_chunk {
+                Some(chunk) => {
+                    self.reused += 1;
+                    if chunk.start_line == start_line {
+                        chunk
+                    } else {
+                        Rc::new(chunk.move_to(start_line))
+                    }
+                }
+                None => Rc::new(parse_chunk(Rc::from(chunk_src), start_line)),
+            };
+            self.chunks
+                .entry(chunk.src.clone())
+                .or_default()
+                .push(chunk.clone());
+            chunks.push(chunk);
+        }
+        ParsedChunks { chunks }
+    }
+}
+
+impl ParsedChunks {
+    // The block of the source with the syntax errors.
+    pub fn block(&self) -> (Block<'_>, Vec<PineInputError>) {
+        let mut stmts: Vec<Statement<'_>> = vec![];
+        let mut errors = vec![];
+        for chunk in self.chunks.iter() {
+            stmts.extend(chunk.stmts.iter().cloned());
+            errors.extend(chunk.errors.iter().cloned());
+        }
+        let range = match (stmts.first(), stmts.last()) {
+            (Some(first), Some(last)) => StrRange::new(first.range().start, last.range().end),
+            _ => StrRange::new(Position::new(0, 0), Position::new(0, 0)),
+        };
+        (Block::new(stmts, None, range), errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    #[test]
+    fn split_chunks_test() {
+        assert_eq!(
+            split_chunks("//@version=4\na = 1\n\nif a\n    b = 1\nelse\n    b = 2\n// c\nc = 1"),
+            vec![
+                (0, "//@version=4\na = 1\n\n"),
+                (3, "if a\n    b = 1\nelse\n    b = 2\n// c\n"),
+                (8, "c = 1")
+            ]
+        );
+        assert_eq!(split_chunks(""), vec![(0, "")]);
+        assert_eq!(
+            split_chunks("elsea = 1\nb = 2"),
+            vec![(0, "elsea = 1\n"), (1, "b = 2")]
+        );
+        assert_eq!(
+            split_chunks("a = b or\nc\nd = e(1,\n\nf)\ng = h\nor = 1"),
+            vec![
+                (0, "a = b or\nc\n"),
+                (2, "d = e(1,\n\nf)\n"),
+                (5, "g = h\n"),
+                (6, "or = 1")
+            ]
+        );
+    }
+
+    #[test]
+    fn ast_cache_test() {
+        let srcs = [
+            "a = 1\nf(x) =>\n    y = x + 1\n    y\nif a > 1\n    b = 1\nelse\n    b = 2\nplot(f(a))",
+            "a = 2\nf(x) =>\n    y = x + 1\n    y\nif a > 1\n    b = 1\nelse\n    b = 2\nplot(f(a))",
+            "a = 2\nf(x) =>\n    y = x + 1\n    y\nif a > 1\n    b = 1 +\nelse\n    b = 2\nplot(f(a))",
+            "a = 2\n\nf(x) =>\n    y = x + 1\n    y\nm = 1 1\nplot(f(a))",
+            "a = 1 +\n\nb = 1\nc = (a and\nb)\nd = a ? 1 : // 'c\n2\ne = \"//\" + \n\"a\"",
+            "// empty\n",
+        ];
+        let mut cache = AstCache::new();
+        for src in srcs.iter() {
+            let chunks = cache.parse(src);
+            let result = match chunks.block() {
+                (blk, errs) if errs.is_empty() => Ok(blk),
+                (blk, errs) => Err((Some(blk), errs)),
+            };
+            assert_eq!(result, parse_ast(src));
+        }
+
+        let mut cache = AstCache::new();
+        assert!(cache.parse(srcs[0]).block().1.is_empty());
+        assert_eq!(cache.reused_count(), 0);
+        // Only the first statement is changed.
+        assert!(cache.parse(srcs[1]).block().1.is_empty());
+        assert_eq!(cache.reused_count(), 3);
+        // The if statement is changed.
+        assert!(!cache.parse(srcs[2]).block().1.is_empty());
+        assert_eq!(cache.reused_count(), 3);
+        // The statements after the inserted line are moved.
+        assert!(!cache.parse(srcs[3]).block().1.is_empty());
+        assert_eq!(cache.reused_count(), 2);
+
+        // The moved statements and errors have the same positions as the parsed ones.
+        let src = "a = 1\nf(x) =>\n    y = x + 1\n    y\nm = 1 1\nplot(f(a))";
+        let moved = "// comment\n\n".to_owned() + src;
+        let mut cache = AstCache::new();
+        cache.parse(src);
+        let chunks = cache.parse(&moved);
+        assert_eq!(cache.reused_count(), 3);
+        let (blk, errs) = chunks.block();
+        assert_eq!(Err((Some(blk), errs)), parse_ast(&moved));
+
+        // The block keeps the replaced chunks alive after the cache drops them.
+        let chunks = cache.parse(src);
+        cache.parse("b = 2");
+        drop(cache);
+        let (blk, errs) = chunks.block();
+        assert_eq!(Err((Some(blk), errs)), parse_ast(src));
+    }
+}
@@ -37,6 +37,15 @@ impl Position {
         self.line
     }
 
+    // Move the position by the count of lines, the max line is kept as the end of the input.
+    pub fn shift_lines(&self, delta: i64) -> Position {
+        if self.line == u32::MAX {
+            *self
+        } else {
+            Position::new((self.line as i64 + delta) as u32, self.character)
+        }
+    }
+
     pub fn get_character(&self) -> u32 {
         self.character
     }
@@ -74,6 +83,10 @@ impl StrRange {
         StrRange::new(start, end)
     }
 
+    pub fn shift_lines(&self, delta: i64) -> StrRange {
+        StrRange::new(self.start.shift_lines(delta), self.end.shift_lines(delta))
+    }
+
     pub fn contain(&self, pos: Position) -> bool {
         self.start <= pos && pos <= self.end
     }
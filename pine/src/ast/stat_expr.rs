@@ -3,19 +3,20 @@ use super::color::color_lit;
 use super::error::{PineError, PineErrorKind, PineResult};
 use super::func_call::{func_call, func_call_args, func_call_ws};
 use super::input::{Input, StrRange};
-use super::name::{varname, varname_only, varname_ws};
+use super::name::{is_alphanum_or_underscore, varname, varname_only, varname_ws, VarName};
+use super::num::decimal;
 use super::num::num_lit_ws;
 use super::op::*;
 use super::stat_expr_types::*;
 use super::state::{AstState, PineInputError};
 use super::string::string_lit_ws;
 use super::trans::flatexp_from_components;
-use super::utils::{eat_sep, eat_space, eat_statement, statement_end, statement_indent};
+use super::utils::{eat_sep, eat_space, eat_statement, is_space, statement_end, statement_indent};
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    combinator::{map, opt},
-    multi::{many0, separated_list},
+    bytes::complete::{tag, take_while1},
+    combinator::{map, opt, peek},
+    multi::{many0, many1, separated_list},
     sequence::{delimited, preceded, terminated, tuple},
     Err,
 };
@@ -423,15 +424,66 @@ fn for_range_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'
     })(input)
 }
 
+fn type_qualifier<'a>(input: Input<'a>) -> PineResult<'a, TypeQualifier> {
+    let (input, label) = atom_vals(&["simple", "series"])(input)?;
+    match label.src {
+        "simple" => Ok((input, TypeQualifier::Simple)),
+        _ => Ok((input, TypeQualifier::Series)),
+    }
+}
+
+// The parameter of the function definition like `src`, `length = 14` or
+// `simple int mult = 2`.
+fn function_param<'a>(
+    input: Input<'a>,
+    state: &AstState,
+) -> PineResult<'a, (VarName<'a>, FunctionParam<'a>)> {
+    let (input, ((qualifier, data_type, name), default)) = tuple((
+        alt((
+            map(
+                tuple((
+                    opt(eat_sep(type_qualifier)),
+                    eat_sep(|s| decl_datatype(s, state)),
+                    |s| varname_ws(s, state),
+                )),
+                |(qualifier, data_type, name)| (qualifier, Some(data_type.value), name),
+            ),
+            map(|s| varname_ws(s, state), |name| (None, None, name)),
+        )),
+        opt(preceded(eat_sep(tag("=")), |s| all_exp(s, state))),
+    ))(input)?;
+    Ok((
+        input,
+        (
+            name,
+            FunctionParam {
+                qualifier,
+                data_type,
+                default,
+            },
+        ),
+    ))
+}
+
 pub fn function_def_with_indent<'a>(
     input: Input<'a>,
     state: &AstState,
 ) -> PineResult<'a, FunctionDef<'a>> {
-    let (input, (_, name, _, params, _, _, body)) = tuple((
+    let (input, (_, (export, method, name), _, params, _, _, body)) = tuple((
         statement_indent(state.get_indent()),
-        |s| varname(s, state),
+        alt((
+            map(
+                tuple((atom_val("export"), |s| varname_ws(s, state))),
+                |(_, name)| (true, false, name),
+            ),
+            map(
+                tuple((atom_val("method"), |s| varname_ws(s, state))),
+                |(_, name)| (false, true, name),
+            ),
+            map(|s| varname(s, state), |name| (false, false, name)),
+        )),
         eat_sep(tag("(")),
-        separated_list(eat_sep(tag(",")), |s| varname_ws(s, state)),
+        separated_list(eat_sep(tag(",")), |s| function_param(s, state)),
         eat_sep(tag(")")),
         eat_sep(tag("=>")),
         alt((
@@ -447,7 +499,73 @@ pub fn function_def_with_indent<'a>(
     ))(input)?;
 
     let range = StrRange::new(name.range.start, body.range.end);
-    Ok((input, FunctionDef::new(name, params, body, range)))
+    let (params, param_specs) = params.into_iter().unzip();
+    let mut func_def = FunctionDef::new(name, params, body, range);
+    func_def.param_specs = param_specs;
+    func_def.export = export;
+    func_def.method = method;
+    Ok((input, func_def))
+}
+
+// The path segment of the library like the user name and the library name.
+fn library_segment<'a>(input: Input<'a>) -> PineResult<'a> {
+    take_while1(is_alphanum_or_underscore)(input)
+}
+
+// Parse the import statement like `import user/name/1 as alias`.
+fn import_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, ImportStmt<'a>> {
+    let (input, (_, start, user, _, name, _, version, alias)) = tuple((
+        statement_indent(state.get_indent()),
+        atom_val("import"),
+        eat_sep(library_segment),
+        tag("/"),
+        library_segment,
+        tag("/"),
+        decimal,
+        opt(preceded(eat_sep(atom_val("as")), |s| varname_ws(s, state))),
+    ))(input)?;
+    let range = StrRange::new(start.start, input.start);
+    let (input, _) = statement_end(input)?;
+
+    let alias = alias.unwrap_or(VarName::new(name.src, StrRange::from_input(&name)));
+    Ok((
+        input,
+        ImportStmt::new(user.src, name.src, version, alias, range),
+    ))
+}
+
+// The field of the user-defined type like `float price = 0.0`.
+fn type_field<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, TypeField<'a>> {
+    let (input, (_, data_type, name, default, _)) = tuple((
+        statement_indent(state.get_indent()),
+        |s| decl_datatype(s, state),
+        |s| varname_ws(s, state),
+        opt(preceded(eat_sep(tag("=")), |s| all_exp(s, state))),
+        statement_end,
+    ))(input)?;
+    let end = match &default {
+        Some(exp) => exp.range().end,
+        None => name.range.end,
+    };
+    let range = StrRange::new(data_type.range.start, end);
+    Ok((input, TypeField::new(data_type.value, name, default, range)))
+}
+
+// Parse the user-defined type like `type Pivot` followed by the indented fields.
+fn type_def_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, TypeDef<'a>> {
+    let (input, (_, start, name, _)) = tuple((
+        statement_indent(state.get_indent()),
+        atom_val("type"),
+        |s| varname_ws(s, state),
+        statement_end,
+    ))(input)?;
+    state.enter_scope();
+    let result = many1(|s| type_field(s, state))(input);
+    state.exit_scope();
+    let (input, fields) = result?;
+
+    let range = StrRange::new(start.start, fields.last().unwrap().range.end);
+    Ok((input, TypeDef::new(name, fields, range)))
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -481,6 +599,21 @@ fn datatype<'a>(input: Input<'a>, _state: &AstState) -> PineResult<'a, DataTypeN
     ))
 }
 
+// The data type in the declarations that can also be a user-defined type like `Pivot`.
+fn decl_datatype<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, DataTypeNode<'a>> {
+    alt((
+        |s| datatype(s, state),
+        // The type name must be followed by the variable name in the same line.
+        map(
+            terminated(
+                |s| varname(s, state),
+                peek(preceded(take_while1(is_space), |s| varname(s, state))),
+            ),
+            |name| DataTypeNode::new(DataType::Custom(name.value), name.range),
+        ),
+    ))(input)
+}
+
 fn assign_lv_names<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, LVTupleNode<'a>> {
     alt((
         map(
@@ -503,7 +636,7 @@ where
         map(
             tuple((
                 atom_val("var"),
-                eat_sep(|s| datatype(s, state)),
+                eat_sep(|s| decl_datatype(s, state)),
                 |s| assign_lv_names(s, state),
                 eat_sep(tag("=")),
                 |s| assign_fn(s, state),
@@ -527,7 +660,7 @@ where
         ),
         map(
             tuple((
-                |s| datatype(s, state),
+                |s| decl_datatype(s, state),
                 |s| assign_lv_names(s, state),
                 eat_sep(tag("=")),
                 |s| assign_fn(s, state),
@@ -572,12 +705,15 @@ where
     map(
         tuple((
             |s| varname(s, state),
+            many0(preceded(tag("."), |s| varname(s, state))),
             eat_sep(tag(":=")),
             |input| assign_fn(input, state),
         )),
         |s| {
-            let range = StrRange::new(s.0.range.start, s.2.range().end);
-            VarAssignment::new(s.0, s.2, range)
+            let range = StrRange::new(s.0.range.start, s.3.range().end);
+            let mut assign = VarAssignment::new(s.0, s.3, range);
+            assign.fields = s.1;
+            assign
         },
     )(input)
 }
@@ -610,13 +746,10 @@ fn block_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, B
                 _ => stmts.push(stas),
             }
             cur_input = next_input;
-        } else {
-            break;
+            continue;
         }
-    }
-    if cur_input.len() > 0 {
         if let Ok((next_input, ret_stmt)) =
-            eat_statement(gen_indent, |input| exp_with_stmt_end(input, state))(cur_input)
+            eat_statement(&gen_indent, |input| exp_with_stmt_end(input, state))(cur_input)
         {
             let range = if stmts.is_empty() {
                 ret_stmt.range()
@@ -625,6 +758,15 @@ fn block_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, B
             };
             return Ok((next_input, Block::new(stmts, Some(ret_stmt), range)));
         }
+        // Record the invalid statement and resume from the next statement of this block.
+        match recover_statement(cur_input, state) {
+            Some((next_input, range)) => {
+                state.catch(PineInputError::new(PineErrorKind::NonRecongnizeStmt, range));
+                stmts.push(Statement::Error(range));
+                cur_input = next_input;
+            }
+            None => break,
+        }
     }
     if stmts.is_empty() {
         state.catch(PineInputError::new(
@@ -641,6 +783,45 @@ fn block_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, B
     }
 }
 
+// Skip the invalid statement that starts at the indentation of the current block,
+// together with the deeper indented lines following it(e.g. the body of a broken if statement).
+// Return the remaining input and the range of the invalid statement line.
+fn recover_statement<'a>(input: Input<'a>, state: &AstState) -> Option<(Input<'a>, StrRange)> {
+    let (stmt_input, _) = statement_indent(state.get_indent())(input).ok()?;
+    let src = stmt_input.src;
+    let line_len = src.find('\n').unwrap_or(src.len());
+    let line = src[..line_len].trim_end();
+    let content = line.trim_start_matches(is_space);
+    if content.is_empty() {
+        return None;
+    }
+    let start = stmt_input
+        .start
+        .translate(0, (line.len() - content.len()) as u32);
+    let range = StrRange::from_start(content, start);
+
+    let indent_width = state.get_indent() * 4;
+    let mut offset = line_len;
+    while offset < src.len() {
+        let rest = &src[offset + 1..];
+        let next_len = rest.find('\n').unwrap_or(rest.len());
+        let next_line = &rest[..next_len];
+        let next_content = next_line.trim_start_matches(is_space);
+        let width: usize = next_line[..next_line.len() - next_content.len()]
+            .chars()
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+        if !next_content.trim().is_empty() && width <= indent_width {
+            break;
+        }
+        offset += 1 + next_len;
+    }
+    if offset < src.len() {
+        offset += 1;
+    }
+    Some((stmt_input.forward(offset), range))
+}
+
 fn transfer_block_ret<'a>(mut blk: Block<'a>) -> Block<'a> {
     if blk.ret_stmt.is_some() {
         return blk;
@@ -713,6 +894,14 @@ fn statement_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'
             |input| function_def_with_indent(input, state),
             |s| Statement::FuncDef(Box::new(s)),
         ),
+        map(
+            |input| import_with_indent(input, state),
+            |s| Statement::Import(Box::new(s)),
+        ),
+        map(
+            |input| type_def_with_indent(input, state),
+            |s| Statement::TypeDef(Box::new(s)),
+        ),
         // map(eat_statement(gen_indent(), |s| func_call(s, state)), |s| {
         //     Statement::FuncCall(Box::new(s))
         // }),
@@ -1271,6 +1460,133 @@ mod tests {
         );
     }
 
+    #[test]
+    fn func_def_params_test() {
+        let (_, func_def) = function_def_with_indent(
+            Input::new_with_str("f(src, length = 14, simple int mult = 2, float x) => src"),
+            &AstState::new(),
+        )
+        .unwrap();
+        let names: Vec<_> = func_def.params.iter().map(|p| p.value).collect();
+        assert_eq!(names, vec!["src", "length", "mult", "x"]);
+        assert_eq!(func_def.param_specs[0], FunctionParam::default());
+        assert_eq!(
+            func_def.param_specs[1],
+            FunctionParam {
+                qualifier: None,
+                data_type: None,
+                default: Some(Exp::Num(Numeral::Int(IntNode::new(
+                    14,
+                    StrRange::from_start("14", Position::new(0, 16))
+                )))),
+            }
+        );
+        assert_eq!(
+            func_def.param_specs[2],
+            FunctionParam {
+                qualifier: Some(TypeQualifier::Simple),
+                data_type: Some(DataType::Int),
+                default: Some(Exp::Num(Numeral::Int(IntNode::new(
+                    2,
+                    StrRange::from_start("2", Position::new(0, 38))
+                )))),
+            }
+        );
+        assert_eq!(
+            func_def.param_specs[3],
+            FunctionParam {
+                qualifier: None,
+                data_type: Some(DataType::Float),
+                default: None,
+            }
+        );
+    }
+
+    #[test]
+    fn type_def_test() {
+        let (_, type_def) = type_def_with_indent(
+            Input::new_with_str("type Pivot\n    float price = 1.0\n    Pivot prev\n"),
+            &AstState::new(),
+        )
+        .unwrap();
+        assert_eq!(type_def.name.value, "Pivot");
+        let fields: Vec<_> = type_def
+            .fields
+            .iter()
+            .map(|f| (f.data_type.clone(), f.name.value, f.default.is_some()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (DataType::Float, "price", true),
+                (DataType::Custom("Pivot"), "prev", false)
+            ]
+        );
+        assert_eq!(type_def.field_index("prev"), Some(1));
+
+        let (_, func_def) = function_def_with_indent(
+            Input::new_with_str("method double(Pivot this) => this.price * 2"),
+            &AstState::new(),
+        )
+        .unwrap();
+        assert!(func_def.method);
+        assert_eq!(
+            func_def.param_specs[0].data_type,
+            Some(DataType::Custom("Pivot"))
+        );
+
+        let (_, assign) = parse_var_assign(
+            Input::new_with_str("p.prev.price := 1"),
+            &AstState::new(),
+            all_exp,
+        )
+        .unwrap();
+        let fields: Vec<_> = assign.fields.iter().map(|f| f.value).collect();
+        assert_eq!(fields, vec!["prev", "price"]);
+    }
+
+    #[test]
+    fn import_export_test() {
+        let mut func_def = FunctionDef::new(
+            VarName::new_with_start("a", Position::new(0, 7)),
+            vec![VarName::new_with_start("arg1", Position::new(0, 9))],
+            Block::new(
+                vec![],
+                Some(Exp::VarName(RVVarName::new_with_start(
+                    "arg1",
+                    Position::new(0, 18),
+                ))),
+                StrRange::from_start("arg1", Position::new(0, 18)),
+            ),
+            StrRange::new(Position::new(0, 7), Position::new(0, 22)),
+        );
+        func_def.export = true;
+        check_res("export a(arg1) => arg1", function_def_with_indent, func_def);
+
+        check_res(
+            "import user/lib/2 as m\n",
+            statement,
+            Statement::Import(Box::new(ImportStmt::new(
+                "user",
+                "lib",
+                2,
+                VarName::new_with_start("m", Position::new(0, 21)),
+                StrRange::new(Position::new(0, 0), Position::new(0, 22)),
+            ))),
+        );
+        check_res(
+            "import user/lib/1",
+            statement,
+            Statement::Import(Box::new(ImportStmt::new(
+                "user",
+                "lib",
+                1,
+                VarName::new_with_start("lib", Position::new(0, 12)),
+                StrRange::new(Position::new(0, 0), Position::new(0, 17)),
+            ))),
+        );
+    }
+
     #[test]
     fn if_then_else_exp_test() {
         check_res(
@@ -1439,8 +1755,67 @@ mod tests {
     #[test]
     fn exp_error_test() {
         let test_input = Input::new_with_str("a1 = 1\na = 1;\nb = 1");
-        let result = block(test_input, &AstState::new());
-        let (input, _output) = result.unwrap();
-        assert_eq!(input.start.get_line(), 1);
+        let state = AstState::new();
+        let (input, output) = block(test_input, &state).unwrap();
+        assert_eq!(input.src, "");
+        assert_eq!(output.stmts.len(), 3);
+        assert_eq!(
+            output.stmts[1],
+            Statement::Error(StrRange::from_start("a = 1;", Position::new(1, 0)))
+        );
+        assert_eq!(
+            state.into_inner(),
+            vec![PineInputError::new(
+                PineErrorKind::NonRecongnizeStmt,
+                StrRange::from_start("a = 1;", Position::new(1, 0))
+            )]
+        );
+    }
+
+    #[test]
+    fn error_recovery_test() {
+        // The body of the invalid if statement is skipped with it.
+        let test_input = Input::new_with_str(
+            "a = 1 +\nif a ==\n    b = 1\n    c = 2\nm = 1\nif a\n    d = ]\n    e = 2\nf = 3",
+        );
+        let state = AstState::new();
+        let (input, output) = block(test_input, &state).unwrap();
+        assert_eq!(input.src, "");
+        assert_eq!(output.stmts.len(), 5);
+        assert_eq!(
+            output.stmts[0],
+            Statement::Error(StrRange::from_start("a = 1 +", Position::new(0, 0)))
+        );
+        assert_eq!(
+            output.stmts[1],
+            Statement::Error(StrRange::from_start("if a ==", Position::new(1, 0)))
+        );
+        match &output.stmts[3] {
+            Statement::Ite(ite) => {
+                assert_eq!(ite.then_blk.stmts.len(), 2);
+                assert_eq!(
+                    ite.then_blk.stmts[0],
+                    Statement::Error(StrRange::from_start("d = ]", Position::new(6, 4)))
+                );
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            state.into_inner(),
+            vec![
+                PineInputError::new(
+                    PineErrorKind::NonRecongnizeStmt,
+                    StrRange::from_start("a = 1 +", Position::new(0, 0))
+                ),
+                PineInputError::new(
+                    PineErrorKind::NonRecongnizeStmt,
+                    StrRange::from_start("if a ==", Position::new(1, 0))
+                ),
+                PineInputError::new(
+                    PineErrorKind::NonRecongnizeStmt,
+                    StrRange::from_start("d = ]", Position::new(6, 4))
+                ),
+            ]
+        );
     }
 }
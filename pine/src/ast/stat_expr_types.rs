@@ -5,6 +5,7 @@ use super::num::Numeral;
 use super::op::{BinaryOp, BinaryOpNode, UnaryOp, UnaryOpNode};
 use super::string::StringNode;
 use super::syntax_type::{FunctionType, SyntaxType};
+use crate::vm::{BlockProgram, Program};
 
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub struct VarIndex {
@@ -174,6 +175,8 @@ pub struct BinaryExp<'a> {
     pub range: StrRange,
     pub ref_type: SyntaxType<'a>,
     pub result_type: SyntaxType<'a>,
+    // The bytecode program compiled after the syntax check, see the vm module.
+    pub program: Option<Box<Program>>,
 }
 
 impl<'a> BinaryExp<'a> {
@@ -185,6 +188,7 @@ impl<'a> BinaryExp<'a> {
             range,
             ref_type: SyntaxType::Any,
             result_type: SyntaxType::Any,
+            program: None,
         }
     }
 }
@@ -441,6 +445,10 @@ pub struct PrefixExp<'a> {
     pub left_exp: Exp<'a>,
     pub right_name: VarName<'a>,
     pub range: StrRange,
+    // If the field is evaluate var such as ta.tr, then the eval_id save the eval index
+    pub eval_id: i32,
+    // If the left value is the user-defined object, the field type gives the type of na field.
+    pub field_type: Option<DataType<'a>>,
     // pub var_index: VarIndex,
 }
 
@@ -450,6 +458,8 @@ impl<'a> PrefixExp<'a> {
             left_exp,
             right_name,
             range,
+            eval_id: 0,
+            field_type: None,
             // var_index: VarIndex::new(0, 0),
         }
     }
@@ -459,6 +469,8 @@ impl<'a> PrefixExp<'a> {
             left_exp,
             right_name,
             range: StrRange::new_empty(),
+            eval_id: 0,
+            field_type: None,
             // var_index: VarIndex::new(0, 0),
         }
     }
@@ -474,6 +486,21 @@ pub enum DataType<'a> {
     Custom(&'a str),
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeQualifier {
+    Simple,
+    Series,
+}
+
+// The optional type and default value of the parameter of the user-defined function,
+// e.g. `simple int length = 14`.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct FunctionParam<'a> {
+    pub qualifier: Option<TypeQualifier>,
+    pub data_type: Option<DataType<'a>>,
+    pub default: Option<Exp<'a>>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Assignment<'a> {
     pub names: Vec<VarName<'a>>,
@@ -550,6 +577,8 @@ impl<'a> Assignment<'a> {
 #[derive(Clone, Debug, PartialEq)]
 pub struct VarAssignment<'a> {
     pub name: VarName<'a>,
+    // The field names of the object assignment like `p.price := 1`.
+    pub fields: Vec<VarName<'a>>,
     pub val: Exp<'a>,
     pub range: StrRange,
     pub var_index: VarIndex,
@@ -559,6 +588,7 @@ impl<'a> VarAssignment<'a> {
     pub fn new(name: VarName<'a>, val: Exp<'a>, range: StrRange) -> VarAssignment<'a> {
         VarAssignment {
             name,
+            fields: vec![],
             val,
             range,
             var_index: VarIndex::new(0, 0),
@@ -568,6 +598,7 @@ impl<'a> VarAssignment<'a> {
     pub fn new_no_input(name: VarName<'a>, val: Exp<'a>) -> VarAssignment<'a> {
         VarAssignment {
             name,
+            fields: vec![],
             val,
             range: StrRange::new_empty(),
             var_index: VarIndex::new(0, 0),
@@ -581,6 +612,7 @@ impl<'a> VarAssignment<'a> {
     ) -> VarAssignment<'a> {
         VarAssignment {
             name,
+            fields: vec![],
             val,
             range: StrRange::new_empty(),
             var_index,
@@ -596,6 +628,8 @@ pub struct Block<'a> {
     pub var_count: i32,
     pub libfun_count: i32,
     pub subctx_count: i32,
+    // The bytecode program compiled after the syntax check, see the vm module.
+    pub program: Option<Box<BlockProgram>>,
 }
 
 impl<'a> Block<'a> {
@@ -607,6 +641,7 @@ impl<'a> Block<'a> {
             var_count: 0,
             libfun_count: 0,
             subctx_count: 0,
+            program: None,
         }
     }
 
@@ -618,6 +653,7 @@ impl<'a> Block<'a> {
             var_count: 0,
             libfun_count: 0,
             subctx_count: 0,
+            program: None,
         }
     }
 
@@ -635,6 +671,7 @@ impl<'a> Block<'a> {
             var_count,
             libfun_count,
             subctx_count,
+            program: None,
         }
     }
 }
@@ -811,6 +848,8 @@ pub struct FunctionDef<'a> {
     pub name: VarName<'a>,
     pub gen_name: Option<String>, // The method name generated by the system.
     pub params: Vec<VarName<'a>>,
+    // The types and default values of the parameters.
+    pub param_specs: Vec<FunctionParam<'a>>,
     pub body: Block<'a>,
     pub range: StrRange,
     // The index in global name context
@@ -819,6 +858,10 @@ pub struct FunctionDef<'a> {
     pub varids: Option<Vec<i32>>,
     // The function definition with specific types.
     pub spec_defs: Option<Box<Vec<FunctionDef<'a>>>>,
+    // Whether the function is exported by the library.
+    pub export: bool,
+    // Whether the function is a method that can be called like `obj.func()`.
+    pub method: bool,
 }
 
 impl<'a> FunctionDef<'a> {
@@ -831,12 +874,15 @@ impl<'a> FunctionDef<'a> {
         FunctionDef {
             name,
             gen_name: None,
+            param_specs: vec![FunctionParam::default(); params.len()],
             params,
             body,
             range,
             name_varid: 0,
             varids: None,
             spec_defs: Some(Box::new(vec![])),
+            export: false,
+            method: false,
         }
     }
 
@@ -849,12 +895,15 @@ impl<'a> FunctionDef<'a> {
         FunctionDef {
             name: VarName::new_with_start("", Position::new(0, 0)),
             gen_name: Some(name),
+            param_specs: vec![FunctionParam::default(); params.len()],
             params,
             body,
             range,
             name_varid: 0,
             varids: None,
             spec_defs: Some(Box::new(vec![])),
+            export: false,
+            method: false,
         }
     }
 
@@ -863,11 +912,14 @@ impl<'a> FunctionDef<'a> {
             name: self.name.clone(),
             gen_name: None,
             params: self.params.clone(),
+            param_specs: self.param_specs.clone(),
             body: self.body.clone(),
             range: self.range.clone(),
             name_varid: 0,
             varids: None,
             spec_defs: None,
+            export: self.export,
+            method: self.method,
         }
     }
 
@@ -884,17 +936,104 @@ impl<'a> FunctionDef<'a> {
     }
 }
 
+// The field of the user-defined type like `float price = 0.0`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeField<'a> {
+    pub data_type: DataType<'a>,
+    pub name: VarName<'a>,
+    pub default: Option<Exp<'a>>,
+    pub range: StrRange,
+}
+
+impl<'a> TypeField<'a> {
+    pub fn new(
+        data_type: DataType<'a>,
+        name: VarName<'a>,
+        default: Option<Exp<'a>>,
+        range: StrRange,
+    ) -> TypeField<'a> {
+        TypeField {
+            data_type,
+            name,
+            default,
+            range,
+        }
+    }
+}
+
+// The user-defined type declaration like `type Pivot` followed by the indented fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeDef<'a> {
+    pub name: VarName<'a>,
+    pub fields: Vec<TypeField<'a>>,
+    pub range: StrRange,
+    // The index in global name context
+    pub name_varid: i32,
+}
+
+impl<'a> TypeDef<'a> {
+    pub fn new(name: VarName<'a>, fields: Vec<TypeField<'a>>, range: StrRange) -> TypeDef<'a> {
+        TypeDef {
+            name,
+            fields,
+            range,
+            name_varid: 0,
+        }
+    }
+
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.name.value == name)
+    }
+}
+
+// The import statement like `import user/name/1 as alias`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportStmt<'a> {
+    pub user: &'a str,
+    pub name: &'a str,
+    pub version: i64,
+    pub alias: VarName<'a>,
+    pub range: StrRange,
+    // The function definitions linked from the library, the names are prefixed by alias.
+    pub defs: Vec<FunctionDef<'a>>,
+    // The exported function names and the linked names of them.
+    pub exports: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> ImportStmt<'a> {
+    pub fn new(
+        user: &'a str,
+        name: &'a str,
+        version: i64,
+        alias: VarName<'a>,
+        range: StrRange,
+    ) -> ImportStmt<'a> {
+        ImportStmt {
+            user,
+            name,
+            version,
+            alias,
+            range,
+            defs: vec![],
+            exports: vec![],
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Statement<'a> {
     Break(StrRange),
     Continue(StrRange),
     None(StrRange),
+    Error(StrRange), // The unparseable statement skipped by the error recovery.
     Assignment(Box<Assignment<'a>>),
     VarAssignment(Box<VarAssignment<'a>>),
     Ite(Box<IfThenElse<'a>>),
     ForRange(Box<ForRange<'a>>),
     FuncCall(Box<FunctionCall<'a>>),
     FuncDef(Box<FunctionDef<'a>>),
+    Import(Box<ImportStmt<'a>>),
+    TypeDef(Box<TypeDef<'a>>),
     Exp(Exp<'a>),
 }
 
@@ -904,12 +1043,15 @@ impl<'a> Statement<'a> {
             &Statement::Break(range) => range,
             &Statement::Continue(range) => range,
             &Statement::None(range) => range,
+            &Statement::Error(range) => range,
             Statement::Assignment(assign) => assign.range,
             Statement::VarAssignment(assign) => assign.range,
             Statement::Ite(ite) => ite.range,
             Statement::ForRange(for_range) => for_range.range,
             Statement::FuncCall(func_call) => func_call.range,
             Statement::FuncDef(func_def) => func_def.range,
+            Statement::Import(import) => import.range,
+            Statement::TypeDef(type_def) => type_def.range,
             Statement::Exp(exp) => exp.range(),
         }
     }
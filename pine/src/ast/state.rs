@@ -58,7 +58,11 @@ impl AstState {
     }
 
     pub fn catch(&self, err: PineInputError) {
-        self.errors.borrow_mut().push(err);
+        // The same block may be parsed again by another alternative, so skip the caught errors.
+        let mut errors = self.errors.borrow_mut();
+        if !errors.contains(&err) {
+            errors.push(err);
+        }
     }
 
     pub fn is_ok(&self) -> bool {
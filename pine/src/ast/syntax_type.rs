@@ -1,8 +1,8 @@
 use super::stat_expr_types::DataType;
 use std::collections::BTreeMap;
 use std::convert::From;
-use std::rc::Rc;
 use std::string::ToString;
+use std::sync::Arc;
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct FunctionType<'a> {
@@ -64,7 +64,8 @@ impl<'a> From<SyntaxType<'a>> for SimpleSyntaxType {
         match syntax_type {
             SyntaxType::Simple(simple_type) => simple_type,
             SyntaxType::Series(simple_type) => simple_type,
-            _ => unreachable!(),
+            // The other types like functions are not simple types, e.g. `na := na` in the error.
+            _ => SimpleSyntaxType::Na,
         }
     }
 }
@@ -88,19 +89,22 @@ pub enum SyntaxType<'a> {
     Simple(SimpleSyntaxType),
     Series(SimpleSyntaxType),
     List(SimpleSyntaxType), // tuple list like [1, 2, 3]
-    Tuple(Rc<Vec<SyntaxType<'a>>>),
+    Tuple(Arc<Vec<SyntaxType<'a>>>),
     ObjectClass(&'a str),
     Val(Box<SyntaxType<'a>>), // evaluate value
-    Object(Rc<BTreeMap<&'a str, SyntaxType<'a>>>),
-    Function(Rc<FunctionTypes<'a>>), // function
-    ObjectFunction(Rc<BTreeMap<&'a str, SyntaxType<'a>>>, Rc<FunctionTypes<'a>>), // object + function
-    ValFunction(Box<SyntaxType<'a>>, Rc<FunctionTypes<'a>>), // value + function
+    Object(Arc<BTreeMap<&'a str, SyntaxType<'a>>>),
+    Function(Arc<FunctionTypes<'a>>), // function
+    ObjectFunction(
+        Arc<BTreeMap<&'a str, SyntaxType<'a>>>,
+        Arc<FunctionTypes<'a>>,
+    ), // object + function
+    ValFunction(Box<SyntaxType<'a>>, Arc<FunctionTypes<'a>>), // value + function
     ValObjectFunction(
         Box<SyntaxType<'a>>,
-        Rc<BTreeMap<&'a str, SyntaxType<'a>>>,
-        Rc<FunctionTypes<'a>>,
+        Arc<BTreeMap<&'a str, SyntaxType<'a>>>,
+        Arc<FunctionTypes<'a>>,
     ), // value + object + function
-    UserFunction(Rc<(Vec<&'a str>, SyntaxType<'a>)>),
+    UserFunction(Arc<(Vec<&'a str>, SyntaxType<'a>)>),
     DynamicExpr(Box<SyntaxType<'a>>), // dynamic expression that can be invoked as function
     Any,
 }
@@ -0,0 +1,437 @@
+// Aggregate the lower timeframe bars into the higher timeframe bars, e.g. build the daily
+// bars from the 5 minutes bars. The time of the aggregated bar is the start time of it.
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{Resolution, ResolutionType, Session};
+use crate::runtime::{AnySeries, AnySeriesType};
+use crate::types::{Float, Int, RuntimeErr};
+use chrono::{TimeZone, Timelike};
+use chrono_tz::Tz;
+use std::mem;
+
+const MINUTE_MS: i64 = 60 * 1000;
+const DAY_MS: i64 = 24 * 60 * MINUTE_MS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateKind {
+    First,
+    Last,
+    Max,
+    Min,
+    Sum,
+}
+
+impl AggregateKind {
+    // The open, high, low, close and volume are aggregated as the OHLCV bar,
+    // other sources take the last value.
+    pub fn from_name(name: &str) -> AggregateKind {
+        match name {
+            "open" => AggregateKind::First,
+            "high" => AggregateKind::Max,
+            "low" => AggregateKind::Min,
+            "volume" => AggregateKind::Sum,
+            _ => AggregateKind::Last,
+        }
+    }
+
+    pub fn merge(&self, prev: Float, val: Float) -> Float {
+        match (prev, val) {
+            (None, val) => val,
+            (prev, None) => prev,
+            (Some(p), Some(v)) => Some(match self {
+                AggregateKind::First => p,
+                AggregateKind::Last => v,
+                AggregateKind::Max => p.max(v),
+                AggregateKind::Min => p.min(v),
+                AggregateKind::Sum => p + v,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarAggregator {
+    resolution: Resolution,
+    session: Option<Session>,
+    tz: Tz,
+}
+
+impl BarAggregator {
+    pub fn new(resolution: Resolution, session: Option<Session>, tz: Tz) -> BarAggregator {
+        BarAggregator {
+            resolution,
+            session,
+            tz,
+        }
+    }
+
+    // Create the aggregator from the resolution like "60" or "D", the session like
+    // "0930-1600" and the timezone like "America/New_York".
+    pub fn parse(
+        resolution: &str,
+        session: Option<&str>,
+        timezone: &str,
+    ) -> Result<BarAggregator, RuntimeErr> {
+        let resolution = Resolution::parse(resolution)?;
+        let session = match session {
+            Some(session) => Some(Session::parse(session)?),
+            None => None,
+        };
+        let tz = timezone.parse::<Tz>().map_err(|_| {
+            RuntimeErr::InvalidParameters(str_replace(
+                UNRECONGNIZED_TZ,
+                vec![String::from(timezone)],
+            ))
+        })?;
+        Ok(BarAggregator::new(resolution, session, tz))
+    }
+
+    // The start time of the higher timeframe bar that contains the time. It is None if the
+    // time is out of the session or out of the range of the date time.
+    pub fn bar_time(&self, time: i64) -> Option<i64> {
+        let dt = self.tz.timestamp_millis_opt(time).single()?;
+        let session = match &self.session {
+            Some(session) => session,
+            None => return Some(self.resolution.get_restime(time, &self.tz)),
+        };
+        if !session.is_in(time, &self.tz) {
+            return None;
+        }
+        let minute = (dt.hour() * 60 + dt.minute()) as i32;
+        // The minutes from the start of the session, the night part of the session
+        // crossing midnight belongs to the next trading day.
+        let (offset, next_day) = session.spans.iter().find_map(|span| {
+            let (start, end) = (span.start.to_m(), span.end.to_m());
+            if minute >= start && minute < end {
+                Some((minute - start, false))
+            } else if start < 0 && minute - 1440 >= start && minute - 1440 < end {
+                Some((minute - 1440 - start, true))
+            } else {
+                None
+            }
+        })?;
+        match self.resolution.get_restype() {
+            ResolutionType::Minute => {
+                let count = self.resolution.get_count();
+                let skip_minutes = (offset % count) as i64;
+                Some(time - time.rem_euclid(MINUTE_MS) - skip_minutes * MINUTE_MS)
+            }
+            _ => {
+                let day_time = if next_day { time + DAY_MS } else { time };
+                Some(self.resolution.get_restime(day_time, &self.tz))
+            }
+        }
+    }
+
+    // Aggregate the sources of the lower timeframe bars. The sources must contain the time
+    // named `time` or `_time`, the names and types of the sources are kept in the result.
+    pub fn aggregate(
+        &self,
+        data: &[(&str, AnySeries)],
+    ) -> Result<Vec<(String, AnySeries)>, RuntimeErr> {
+        let time_index = data
+            .iter()
+            .position(|(name, series)| {
+                (*name == "time" || *name == "_time") && series.get_type() == AnySeriesType::Int
+            })
+            .ok_or_else(|| {
+                RuntimeErr::InvalidParameters(str_replace(
+                    REQUIRED_PARAMETERS,
+                    vec![String::from("time")],
+                ))
+            })?;
+        let srcs: Vec<_> = data
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != time_index)
+            .map(|(_, src)| src)
+            .collect();
+        let kinds = srcs
+            .iter()
+            .map(|(name, _)| AggregateKind::from_name(name))
+            .collect();
+        let mut builder = BarBuilder::new(self.clone(), kinds);
+
+        let mut times: Vec<Int> = vec![];
+        let mut columns: Vec<Vec<Float>> = vec![vec![]; srcs.len()];
+        let mut add_bar = |bar: Option<(i64, Vec<Float>)>| {
+            if let Some((time, values)) = bar {
+                times.push(Some(time));
+                for (column, val) in columns.iter_mut().zip(values) {
+                    column.push(val);
+                }
+            }
+        };
+        let time_series = &data[time_index].1;
+        for i in 0..time_series.len() {
+            if let Some(time) = time_series.index::<Int>(i as isize) {
+                let values: Vec<Float> = srcs
+                    .iter()
+                    .map(|(_, series)| series_float_at(series, i))
+                    .collect();
+                add_bar(builder.push(time, &values));
+            }
+        }
+        add_bar(builder.finish());
+
+        let mut columns = columns.into_iter();
+        let result = data
+            .iter()
+            .enumerate()
+            .map(|(i, (name, series))| {
+                let series = if i == time_index {
+                    AnySeries::from_int_vec(times.clone())
+                } else {
+                    float_vec_to_series(columns.next().unwrap(), series.get_type())
+                };
+                (String::from(*name), series)
+            })
+            .collect();
+        Ok(result)
+    }
+}
+
+// Read the value of the series as float, it is na if the index exceeds the series.
+pub fn series_float_at(series: &AnySeries, i: usize) -> Float {
+    if i >= series.len() {
+        return None;
+    }
+    match series.get_type() {
+        AnySeriesType::Float => series.index::<Float>(i as isize),
+        AnySeriesType::Int => series.index::<Int>(i as isize).map(|v| v as f64),
+    }
+}
+
+pub fn float_vec_to_series(values: Vec<Float>, series_type: AnySeriesType) -> AnySeries {
+    match series_type {
+        AnySeriesType::Float => AnySeries::from_float_vec(values),
+        AnySeriesType::Int => {
+            AnySeries::from_int_vec(values.into_iter().map(|v| v.map(|v| v as i64)).collect())
+        }
+    }
+}
+
+// Build the higher timeframe bars by adding the lower timeframe bars one by one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarBuilder {
+    aggregator: BarAggregator,
+    kinds: Vec<AggregateKind>,
+    time: Option<i64>,
+    values: Vec<Float>,
+}
+
+impl BarBuilder {
+    pub fn new(aggregator: BarAggregator, kinds: Vec<AggregateKind>) -> BarBuilder {
+        BarBuilder {
+            aggregator,
+            kinds,
+            time: None,
+            values: vec![],
+        }
+    }
+
+    // Add the lower timeframe bar, the completed higher timeframe bar is returned if this
+    // bar starts a new one. The bars out of the session and the late bars that belong to
+    // the completed higher timeframe bars are ignored.
+    pub fn push(&mut self, time: i64, values: &[Float]) -> Option<(i64, Vec<Float>)> {
+        let bar_time = self.aggregator.bar_time(time)?;
+        match self.time {
+            Some(cur_time) if bar_time < cur_time => None,
+            Some(cur_time) if bar_time == cur_time => {
+                for (i, val) in values.iter().enumerate() {
+                    self.values[i] = self.kinds[i].merge(self.values[i], *val);
+                }
+                None
+            }
+            _ => {
+                let completed = self.finish();
+                self.time = Some(bar_time);
+                self.values = values.to_vec();
+                completed
+            }
+        }
+    }
+
+    pub fn get_aggregator(&self) -> &BarAggregator {
+        &self.aggregator
+    }
+
+    // The higher timeframe bar being built.
+    pub fn current(&self) -> Option<(i64, &[Float])> {
+        self.time.map(|time| (time, self.values.as_slice()))
+    }
+
+    // Complete the higher timeframe bar being built.
+    pub fn finish(&mut self) -> Option<(i64, Vec<Float>)> {
+        let time = self.time.take()?;
+        Some((time, mem::take(&mut self.values)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(tz: &Tz, d: u32, h: u32, m: u32) -> i64 {
+        tz.with_ymd_and_hms(2020, 2, d, h, m, 0)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    #[test]
+    fn bar_time_test() {
+        let tz = Tz::UTC;
+        let aggregator = BarAggregator::parse("60", None, "UTC").unwrap();
+        assert_eq!(
+            aggregator.bar_time(ts(&tz, 17, 9, 45)),
+            Some(ts(&tz, 17, 9, 0))
+        );
+        assert_eq!(aggregator.bar_time(i64::MIN), None);
+
+        // The bars are aligned with the start of the session.
+        let aggregator = BarAggregator::parse("60", Some("0930-1600"), "UTC").unwrap();
+        assert_eq!(
+            aggregator.bar_time(ts(&tz, 17, 9, 45)),
+            Some(ts(&tz, 17, 9, 30))
+        );
+        assert_eq!(
+            aggregator.bar_time(ts(&tz, 17, 10, 30)),
+            Some(ts(&tz, 17, 10, 30))
+        );
+        assert_eq!(aggregator.bar_time(ts(&tz, 17, 9, 0)), None);
+        // 2020-02-16 is Sunday.
+        assert_eq!(aggregator.bar_time(ts(&tz, 16, 10, 0)), None);
+        // The time out of the range of the date time has no bar.
+        assert_eq!(aggregator.bar_time(i64::MAX), None);
+
+        // The night part of the session belongs to the next day.
+        let aggregator = BarAggregator::parse("D", Some("1800-1600:1234567"), "UTC").unwrap();
+        assert_eq!(
+            aggregator.bar_time(ts(&tz, 17, 19, 0)),
+            Some(ts(&tz, 18, 0, 0))
+        );
+        assert_eq!(
+            aggregator.bar_time(ts(&tz, 18, 10, 0)),
+            Some(ts(&tz, 18, 0, 0))
+        );
+        assert_eq!(aggregator.bar_time(ts(&tz, 18, 17, 0)), None);
+
+        let aggregator = BarAggregator::parse("120", Some("1800-1600:1234567"), "UTC").unwrap();
+        assert_eq!(
+            aggregator.bar_time(ts(&tz, 18, 1, 30)),
+            Some(ts(&tz, 18, 0, 0))
+        );
+
+        assert!(BarAggregator::parse("D", None, "Nowhere").is_err());
+    }
+
+    #[test]
+    fn aggregate_test() {
+        let tz: Tz = "Asia/Shanghai".parse().unwrap();
+        let aggregator = BarAggregator::parse("D", None, "Asia/Shanghai").unwrap();
+        let data = vec![
+            (
+                "_time",
+                AnySeries::from_int_vec(vec![
+                    Some(ts(&tz, 17, 9, 30)),
+                    Some(ts(&tz, 17, 10, 30)),
+                    None,
+                    Some(ts(&tz, 18, 9, 30)),
+                    Some(ts(&tz, 18, 10, 30)),
+                ]),
+            ),
+            (
+                "open",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), None, None, Some(5f64)]),
+            ),
+            (
+                "high",
+                AnySeries::from_float_vec(vec![
+                    Some(3f64),
+                    Some(4f64),
+                    None,
+                    Some(6f64),
+                    Some(5f64),
+                ]),
+            ),
+            (
+                "low",
+                AnySeries::from_float_vec(vec![
+                    Some(1f64),
+                    Some(0f64),
+                    None,
+                    Some(4f64),
+                    Some(5f64),
+                ]),
+            ),
+            (
+                "close",
+                AnySeries::from_float_vec(vec![Some(2f64), Some(3f64), None, Some(5f64), None]),
+            ),
+            (
+                "volume",
+                AnySeries::from_int_vec(vec![Some(10), Some(20), None, Some(30), Some(40)]),
+            ),
+        ];
+        let result = aggregator.aggregate(&data).unwrap();
+        assert_eq!(
+            result
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["_time", "open", "high", "low", "close", "volume"]
+        );
+        assert_eq!(
+            *result[0].1.as_vec::<Int>(),
+            vec![Some(ts(&tz, 17, 0, 0)), Some(ts(&tz, 18, 0, 0))]
+        );
+        assert_eq!(*result[1].1.as_vec::<Float>(), vec![Some(1f64), Some(5f64)]);
+        assert_eq!(*result[2].1.as_vec::<Float>(), vec![Some(4f64), Some(6f64)]);
+        assert_eq!(*result[3].1.as_vec::<Float>(), vec![Some(0f64), Some(4f64)]);
+        assert_eq!(*result[4].1.as_vec::<Float>(), vec![Some(3f64), Some(5f64)]);
+        assert_eq!(*result[5].1.as_vec::<Int>(), vec![Some(30), Some(70)]);
+
+        assert!(aggregator
+            .aggregate(&[("close", AnySeries::from_float_vec(vec![]))])
+            .is_err());
+    }
+
+    #[test]
+    fn bar_builder_test() {
+        let tz = Tz::UTC;
+        let aggregator = BarAggregator::parse("30", None, "UTC").unwrap();
+        let mut builder = BarBuilder::new(
+            aggregator,
+            vec![
+                AggregateKind::from_name("high"),
+                AggregateKind::from_name("close"),
+            ],
+        );
+        assert_eq!(
+            builder.push(ts(&tz, 17, 9, 0), &[Some(1f64), Some(1f64)]),
+            None
+        );
+        assert_eq!(
+            builder.push(ts(&tz, 17, 9, 15), &[Some(3f64), Some(2f64)]),
+            None
+        );
+        assert_eq!(
+            builder.current(),
+            Some((ts(&tz, 17, 9, 0), &[Some(3f64), Some(2f64)][..]))
+        );
+        assert_eq!(
+            builder.push(ts(&tz, 17, 9, 30), &[Some(2f64), Some(2f64)]),
+            Some((ts(&tz, 17, 9, 0), vec![Some(3f64), Some(2f64)]))
+        );
+        // The late bar of the completed bar is ignored.
+        assert_eq!(
+            builder.push(ts(&tz, 17, 9, 20), &[Some(9f64), Some(9f64)]),
+            None
+        );
+        assert_eq!(
+            builder.finish(),
+            Some((ts(&tz, 17, 9, 30), vec![Some(2f64), Some(2f64)]))
+        );
+        assert_eq!(builder.current(), None);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::ast::stat_expr_types::Block;
+use crate::runtime::context::PineRuntimeError;
+use crate::runtime::data_src::{Callback, NoneCallback};
+use crate::runtime::error_format::{ErrorFormater, PineFormatError};
+use crate::runtime::output::{IOInfo, InputVal, OutputDataCollect, SymbolInfo};
+use crate::runtime::snapshot::RuntimeSnapshot;
+use crate::runtime::AnySeries;
+use crate::types::RuntimeErr;
+use crate::version::script_version;
+use crate::{default_lib_info, lib_info_for_version, LibInfo, ParsedNames, PineScript};
+use std::mem;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError};
+
+// The script that has been parsed and checked. The parsed block is kept so the runners are
+// created without parsing the source again. The block only shares the syntax types by Arc
+// and every runner clones it under the lock, so the script can be shared between threads by Arc.
+#[derive(Debug)]
+pub struct CompiledScript {
+    // The block borrows the source, so it must be dropped before the source.
+    parsed: Mutex<(Block<'static>, ParsedNames<'static>)>,
+    source: String,
+    lib_info: fn() -> LibInfo<'static>,
+    io_info: IOInfo,
+}
+
+impl CompiledScript {
+    // The builtin variables are selected by the //@version directive of the source.
+    pub fn compile(source: String) -> Result<Arc<CompiledScript>, Vec<PineFormatError>> {
+        let lib_info: fn() -> LibInfo<'static> = if script_version(&source) >= 5 {
+            || lib_info_for_version(5)
+        } else {
+            default_lib_info
+        };
+        CompiledScript::compile_with_libinfo(source, lib_info)
+    }
+
+    // The lib_info factory is invoked for every runner because the library values
+    // can't be shared between threads.
+    pub fn compile_with_libinfo(
+        source: String,
+        lib_info: fn() -> LibInfo<'static>,
+    ) -> Result<Arc<CompiledScript>, Vec<PineFormatError>> {
+        let callback = NoneCallback();
+        let mut script = PineScript::new_with_libinfo(lib_info(), Some(&callback));
+        script.parse_src(source)?;
+        let io_info = match script.gen_io_info() {
+            Ok(io_info) => io_info,
+            Err(err) => return Err(vec![err]),
+        };
+        let (source, blk, names) = match script.into_parsed_block() {
+            Some(parsed) => parsed,
+            None => {
+                let err = PineRuntimeError::new_no_range(RuntimeErr::ScriptNotParsed);
+                return Err(vec![PineFormatError::from_runtime_error(
+                    &ErrorFormater::new(),
+                    err,
+                )]);
+            }
+        };
+        // The heap data of the source is not moved with the string, so the block is valid
+        // as long as the compiled script.
+        let parsed = unsafe {
+            (
+                mem::transmute::<Block<'_>, Block<'static>>(blk),
+                mem::transmute::<ParsedNames<'_>, ParsedNames<'static>>(names),
+            )
+        };
+        Ok(Arc::new(CompiledScript {
+            parsed: Mutex::new(parsed),
+            source,
+            lib_info,
+            io_info,
+        }))
+    }
+
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn get_io_info(&self) -> &IOInfo {
+        &self.io_info
+    }
+
+    // Create a runner that owns all the runtime state of this script.
+    pub fn runner(self: &Arc<Self>) -> ScriptRunner {
+        ScriptRunner::new_with_callback(self.clone(), Box::new(NoneCallback()))
+    }
+
+    // Clone the parsed block for a new runner. The clone is consistent even if another
+    // thread panicked while holding the lock, because the lock is only held to clone.
+    fn clone_parsed(&self) -> (Block<'static>, ParsedNames<'static>) {
+        self.parsed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+// The owned runner of the compiled script. Every runtime value created by the runner
+// is only reachable from the runner itself, so the runner can be moved to another
+// thread as a whole although the runtime uses Rc internally.
+pub struct ScriptRunner {
+    // The script borrows the callback, so it must be dropped before the callback.
+    script: Box<PineScript<'static, 'static, 'static>>,
+    _callback: Box<dyn Callback + Send>,
+    compiled: Arc<CompiledScript>,
+}
+
+// The runner is Send because of these invariants:
+// - The Rc values and the raw pointers of the runtime are created by the runner from its own
+//   library information and its own clone of the block, and they are never handed out of
+//   the runner, so no other thread refers to them.
+// - The block clone only shares the syntax types with the compiled script by Arc, and it
+//   borrows the source of the compiled script that is immutable and kept alive by `compiled`.
+// - The callback is Send and only the script borrows it.
+unsafe impl Send for ScriptRunner {}
+
+impl ScriptRunner {
+    pub fn new_with_callback(
+        compiled: Arc<CompiledScript>,
+        callback: Box<dyn Callback + Send>,
+    ) -> ScriptRunner {
+        let callback_ref = unsafe {
+            mem::transmute::<&(dyn Callback + Send), &'static (dyn Callback + Send)>(
+                callback.as_ref(),
+            )
+        };
+        let mut script = Box::new(PineScript::new_with_libinfo(
+            (compiled.lib_info)(),
+            Some(callback_ref),
+        ));
+        let (blk, names) = compiled.clone_parsed();
+        script.set_parsed_block(compiled.source.clone(), blk, names);
+        ScriptRunner {
+            script,
+            _callback: callback,
+            compiled,
+        }
+    }
+
+    pub fn get_compiled(&self) -> &Arc<CompiledScript> {
+        &self.compiled
+    }
+
+    pub fn get_io_info(&self) -> &IOInfo {
+        self.compiled.get_io_info()
+    }
+
+    pub fn run(
+        &mut self,
+        input: Vec<Option<InputVal>>,
+        data: Vec<(&'static str, AnySeries)>,
+        syminfo: Option<SymbolInfo>,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        self.script.run(input, data, syminfo.map(Rc::new))
+    }
+
+    pub fn runl(
+        &mut self,
+        input: Vec<Option<InputVal>>,
+        data: Vec<(&'static str, AnySeries)>,
+        datalen: usize,
+        syminfo: Option<SymbolInfo>,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        self.script.runl(input, data, datalen, syminfo.map(Rc::new))
+    }
+
+    pub fn run_with_data(
+        &mut self,
+        data: Vec<(&'static str, AnySeries)>,
+        syminfo: Option<SymbolInfo>,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        self.script.run_with_data(data, syminfo.map(Rc::new))
+    }
+
+    pub fn run_with_input(
+        &mut self,
+        input: Vec<Option<InputVal>>,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        self.script.run_with_input(input)
+    }
+
+    pub fn update(
+        &mut self,
+        data: Vec<(&'static str, AnySeries)>,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        self.script.update(data)
+    }
+
+    pub fn update_from(
+        &mut self,
+        data: Vec<(&'static str, AnySeries)>,
+        from: i32,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        self.script.update_from(data, from)
+    }
+
+    pub fn trace_vars(&mut self, names: &[&str]) -> Result<(), PineFormatError> {
+        self.script.trace_vars(names)
+    }
+
+    pub fn snapshot(&mut self) -> Result<RuntimeSnapshot, PineFormatError> {
+        self.script.snapshot()
+    }
+
+    pub fn restore(&mut self, snapshot: RuntimeSnapshot) -> Result<(), PineFormatError> {
+        self.script.restore(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn send_sync_test() {
+        assert_send::<ScriptRunner>();
+        assert_send::<Arc<CompiledScript>>();
+        assert_sync::<CompiledScript>();
+        assert_send::<Block<'static>>();
+    }
+
+    #[test]
+    fn parsed_block_test() {
+        let compiled =
+            CompiledScript::compile(String::from("m = input(1)\ns = close + m\nplot(s)")).unwrap();
+        let data = || {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+            )]
+        };
+
+        // The runners share the parsed block but run with their own state.
+        let mut runner = compiled.runner();
+        runner.trace_vars(&["s"]).unwrap();
+        assert!(runner.trace_vars(&["nothing"]).is_err());
+        let output = runner
+            .run(vec![Some(InputVal::Int(2))], data(), None)
+            .unwrap();
+        assert_eq!(output.traces.len(), 1);
+
+        let mut other = compiled.runner();
+        assert_eq!(
+            other.run(vec![Some(InputVal::Int(1))], data(), None),
+            Ok(OutputDataCollect::new_with_one(
+                0,
+                2,
+                vec![Some(2f64), Some(3f64)]
+            ))
+        );
+
+        let snapshot = runner.snapshot().unwrap();
+        let mut restored = compiled.runner();
+        assert_eq!(restored.restore(snapshot), Ok(()));
+        assert_eq!(restored.snapshot(), runner.snapshot());
+    }
+
+    #[test]
+    fn compile_err_test() {
+        assert!(CompiledScript::compile(String::from("m = ")).is_err());
+        assert!(CompiledScript::compile(String::from("plot(nothing)")).is_err());
+    }
+
+    #[test]
+    fn parallel_run_test() {
+        let compiled =
+            CompiledScript::compile(String::from("m = input(1)\nplot(sma(close, 2) + m)")).unwrap();
+        assert_eq!(compiled.get_io_info().get_inputs().len(), 1);
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let mut runner = compiled.runner();
+                thread::spawn(move || {
+                    let close = vec![Some(i as f64), Some(i as f64 + 2f64)];
+                    runner.run(
+                        vec![Some(InputVal::Int(i))],
+                        vec![("close", AnySeries::from_float_vec(close))],
+                        None,
+                    )
+                })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(
+                handle.join().unwrap(),
+                Ok(OutputDataCollect::new_with_one(
+                    0,
+                    2,
+                    vec![None, Some(i as f64 + 1f64 + i as f64)]
+                ))
+            );
+        }
+    }
+}
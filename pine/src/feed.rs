@@ -0,0 +1,398 @@
+// Build the bars of the chart resolution from the realtime trades and quotes, and run the
+// script on the forming bar for every tick.
+use crate::bars::{float_vec_to_series, series_float_at, AggregateKind, BarAggregator, BarBuilder};
+use crate::runtime::error_format::PineFormatError;
+use crate::runtime::output::{OutputDataCollect, SymbolInfo};
+use crate::runtime::{AnySeries, AnySeriesType};
+use crate::types::{Float, Int, RuntimeErr};
+use crate::PineScript;
+use std::borrow::Cow;
+use std::mem;
+use std::rc::Rc;
+
+// The sources of the bar built from the ticks.
+const BAR_SRCS: [&str; 5] = ["open", "high", "low", "close", "volume"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tick {
+    pub time: i64,
+    pub price: f64,
+    // The size of the trade, it is zero for the quote.
+    pub size: f64,
+}
+
+impl Tick {
+    pub fn new(time: i64, price: f64, size: f64) -> Tick {
+        Tick { time, price, size }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarStatus {
+    // The bar is still forming and will be updated by the following ticks.
+    Unconfirmed,
+    // The bar is closed by the tick of the next bar.
+    Confirmed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedBar {
+    pub time: i64,
+    pub open: Float,
+    pub high: Float,
+    pub low: Float,
+    pub close: Float,
+    pub volume: Float,
+    pub status: BarStatus,
+}
+
+impl FeedBar {
+    fn new(time: i64, values: &[Float], status: BarStatus) -> FeedBar {
+        FeedBar {
+            time,
+            open: values[0],
+            high: values[1],
+            low: values[2],
+            close: values[3],
+            volume: values[4],
+            status,
+        }
+    }
+}
+
+pub struct TickFeeder {
+    builder: BarBuilder,
+    syminfo: Option<Rc<SymbolInfo>>,
+    // Whether the last bar of the script is the forming bar, otherwise the forming bar is
+    // appended to the script.
+    is_last_forming: bool,
+    confirmed_bar: Option<FeedBar>,
+}
+
+impl TickFeeder {
+    pub fn new(aggregator: BarAggregator) -> TickFeeder {
+        let kinds = BAR_SRCS
+            .iter()
+            .map(|name| AggregateKind::from_name(name))
+            .collect();
+        TickFeeder {
+            builder: BarBuilder::new(aggregator, kinds),
+            syminfo: None,
+            is_last_forming: false,
+            confirmed_bar: None,
+        }
+    }
+
+    // Create the feeder for the chart resolution like "5" or "D", the session like
+    // "0930-1600" and the timezone like "America/New_York".
+    pub fn parse(
+        resolution: &str,
+        session: Option<&str>,
+        timezone: &str,
+    ) -> Result<TickFeeder, RuntimeErr> {
+        Ok(TickFeeder::new(BarAggregator::parse(
+            resolution, session, timezone,
+        )?))
+    }
+
+    // The symbol information to run the script that has not been run with the history data.
+    pub fn set_syminfo(&mut self, syminfo: Option<Rc<SymbolInfo>>) {
+        self.syminfo = syminfo;
+    }
+
+    // The bar being built by the ticks, it is the last bar of the script.
+    pub fn get_forming_bar(&self) -> Option<FeedBar> {
+        self.builder
+            .current()
+            .map(|(time, values)| FeedBar::new(time, values, BarStatus::Unconfirmed))
+    }
+
+    // The last bar closed by the ticks.
+    pub fn get_confirmed_bar(&self) -> Option<&FeedBar> {
+        self.confirmed_bar.as_ref()
+    }
+
+    // Add the tick to the forming bar and run the script on it. The forming bar is updated in
+    // place, and it is confirmed and followed by a new bar when the tick rolls over to the next
+    // bar. The ticks out of the session and the late ticks of the confirmed bars are ignored
+    // and None is returned.
+    pub fn push<'pa, 'li, 'ra>(
+        &mut self,
+        script: &mut PineScript<'pa, 'li, 'ra>,
+        tick: Tick,
+    ) -> Result<Option<OutputDataCollect>, PineFormatError>
+    where
+        'li: 'ra,
+        'pa: 'ra,
+    {
+        let bar_time = match self.builder.get_aggregator().bar_time(tick.time) {
+            None => return Ok(None),
+            Some(time) => time,
+        };
+        let last_time = match self.builder.current() {
+            Some((time, _)) => Some(time),
+            None => self.seed_from_script(script, bar_time),
+        };
+        // The late tick of a confirmed bar can't change the bar, so it is ignored.
+        if last_time.is_some_and(|time| bar_time < time) {
+            return Ok(None);
+        }
+
+        let price = Some(tick.price);
+        let values = [price, price, price, price, Some(tick.size)];
+        let mut bars = vec![];
+        if let Some((time, values)) = self.builder.push(tick.time, &values) {
+            self.confirmed_bar = Some(FeedBar::new(time, &values, BarStatus::Confirmed));
+            bars.push((time, values));
+        }
+        let (time, values) = self.builder.current().unwrap();
+        bars.push((time, values.to_vec()));
+
+        let data = gen_bar_data(script, &bars);
+        let is_last_forming = mem::replace(&mut self.is_last_forming, true);
+        let output = if is_last_forming {
+            // The first bar is the last bar of the script.
+            script.updatel_named(data, bars.len())?
+        } else if script.datalen == 0 {
+            script.run_with_datal_named(data, bars.len(), self.syminfo.clone())?
+        } else {
+            let from = script.datalen as i32;
+            script.update_froml_named(data, from, bars.len())?
+        };
+        Ok(Some(output))
+    }
+
+    // Continue building the last bar of the script if the tick belongs to it. The bar time of
+    // the last bar of the script is returned.
+    fn seed_from_script(&mut self, script: &PineScript, bar_time: i64) -> Option<i64> {
+        if script.datalen == 0 {
+            return None;
+        }
+        let index = script.datalen - 1;
+        let last_time = script
+            .data
+            .iter()
+            .find(|(name, series)| *name == "_time" && series.get_type() == AnySeriesType::Int)
+            .and_then(|(_, series)| {
+                if index < series.len() {
+                    series.index::<Int>(index as isize)
+                } else {
+                    None
+                }
+            })?;
+        let last_bar_time = self.builder.get_aggregator().bar_time(last_time);
+        if last_bar_time == Some(bar_time) {
+            let values: Vec<Float> = BAR_SRCS
+                .iter()
+                .map(|src| {
+                    script
+                        .data
+                        .iter()
+                        .find(|(name, _)| name == src)
+                        .and_then(|(_, series)| series_float_at(series, index))
+                })
+                .collect();
+            self.builder.push(last_time, &values);
+            self.is_last_forming = true;
+        }
+        last_bar_time
+    }
+}
+
+// Generate the data of the bars with the same sources of the data that the script has been
+// run with, or the input sources of the script if it has not been run.
+fn gen_bar_data(
+    script: &PineScript,
+    bars: &[(i64, Vec<Float>)],
+) -> Vec<(Cow<'static, str>, AnySeries)> {
+    let srcs: Vec<(Cow<'static, str>, AnySeriesType)> = if script.data.is_empty() {
+        script
+            .lib_info
+            .input_names
+            .iter()
+            .filter(|(name, _)| *name != "bar_index")
+            .map(|(name, series_type)| (Cow::Owned(String::from(*name)), *series_type))
+            .collect()
+    } else {
+        script
+            .data
+            .iter()
+            .map(|(name, series)| (name.clone(), series.get_type()))
+            .collect()
+    };
+    srcs.into_iter()
+        .map(|(name, series_type)| {
+            let series = match BAR_SRCS.iter().position(|src| *src == name) {
+                _ if name == "_time" => {
+                    AnySeries::from_int_vec(bars.iter().map(|(time, _)| Some(*time)).collect())
+                }
+                Some(i) => float_vec_to_series(
+                    bars.iter().map(|(_, values)| values[i]).collect(),
+                    series_type,
+                ),
+                None => float_vec_to_series(vec![None; bars.len()], series_type),
+            };
+            (name, series)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::NoneCallback;
+
+    const MINUTE_MS: i64 = 60 * 1000;
+
+    #[test]
+    fn tick_feeder_test() {
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script
+            .parse_src(String::from("plot(close)\nplot(volume)"))
+            .unwrap();
+        let history = vec![
+            (
+                "open",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+            ),
+            (
+                "high",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2.5f64)]),
+            ),
+            (
+                "low",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(1.5f64)]),
+            ),
+            (
+                "close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+            ),
+            ("volume", AnySeries::from_int_vec(vec![Some(10), Some(20)])),
+            (
+                "time",
+                AnySeries::from_int_vec(vec![Some(0), Some(MINUTE_MS)]),
+            ),
+        ];
+        script.run_with_data(history, None).unwrap();
+
+        let mut feeder = TickFeeder::parse("1", None, "UTC").unwrap();
+        let outputs = |output: Option<OutputDataCollect>| {
+            let output = output.unwrap();
+            let values: Vec<_> = output
+                .data_list
+                .into_iter()
+                .map(|data| data.unwrap().series[0].clone())
+                .collect();
+            (output.from, output.to, values)
+        };
+
+        // The tick of the last history bar updates it.
+        let output = feeder
+            .push(&mut script, Tick::new(MINUTE_MS + 10, 3f64, 5f64))
+            .unwrap();
+        assert_eq!(
+            outputs(output),
+            (1, 2, vec![vec![Some(3f64)], vec![Some(25f64)]])
+        );
+        assert_eq!(
+            feeder.get_forming_bar(),
+            Some(FeedBar {
+                time: MINUTE_MS,
+                open: Some(2f64),
+                high: Some(3f64),
+                low: Some(1.5f64),
+                close: Some(3f64),
+                volume: Some(25f64),
+                status: BarStatus::Unconfirmed
+            })
+        );
+
+        // The tick of the next bar confirms the forming bar and appends the new bar.
+        let output = feeder
+            .push(&mut script, Tick::new(2 * MINUTE_MS, 4f64, 1f64))
+            .unwrap();
+        assert_eq!(
+            outputs(output),
+            (
+                1,
+                3,
+                vec![vec![Some(3f64), Some(4f64)], vec![Some(25f64), Some(1f64)]]
+            )
+        );
+        assert_eq!(
+            feeder.get_confirmed_bar().map(|bar| (bar.time, bar.status)),
+            Some((MINUTE_MS, BarStatus::Confirmed))
+        );
+        let output = feeder
+            .push(&mut script, Tick::new(2 * MINUTE_MS + 30, 2f64, 2f64))
+            .unwrap();
+        assert_eq!(
+            outputs(output),
+            (2, 3, vec![vec![Some(2f64)], vec![Some(3f64)]])
+        );
+        assert_eq!(
+            feeder
+                .get_forming_bar()
+                .map(|bar| (bar.open, bar.high, bar.low)),
+            Some((Some(4f64), Some(4f64), Some(2f64)))
+        );
+
+        // The late tick of the confirmed bar neither changes the forming bar nor appends a bar.
+        assert_eq!(
+            feeder.push(&mut script, Tick::new(MINUTE_MS + 50, 9f64, 1f64)),
+            Ok(None)
+        );
+        assert_eq!(
+            feeder
+                .get_forming_bar()
+                .map(|bar| (bar.time, bar.high, bar.volume)),
+            Some((2 * MINUTE_MS, Some(4f64), Some(3f64)))
+        );
+        assert_eq!(script.datalen, 3);
+    }
+
+    #[test]
+    fn tick_feeder_without_history_test() {
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script.parse_src(String::from("plot(close)")).unwrap();
+
+        // The ticks out of the session are ignored.
+        let mut feeder = TickFeeder::parse("60", Some("0930-1600"), "UTC").unwrap();
+        // 2020-02-17 is Monday.
+        let day = 1581897600000;
+        assert_eq!(
+            feeder.push(&mut script, Tick::new(day + 9 * 60 * MINUTE_MS, 1f64, 1f64)),
+            Ok(None)
+        );
+        assert_eq!(feeder.get_forming_bar(), None);
+
+        let output = feeder
+            .push(
+                &mut script,
+                Tick::new(day + 10 * 60 * MINUTE_MS, 1f64, 1f64),
+            )
+            .unwrap();
+        assert_eq!(
+            output,
+            Some(OutputDataCollect::new_with_one(0, 1, vec![Some(1f64)]))
+        );
+        assert_eq!(
+            feeder.get_forming_bar().map(|bar| bar.time),
+            Some(day + (9 * 60 + 30) * MINUTE_MS)
+        );
+        let output = feeder
+            .push(
+                &mut script,
+                Tick::new(day + (10 * 60 + 30) * MINUTE_MS, 2f64, 1f64),
+            )
+            .unwrap();
+        assert_eq!(
+            output,
+            Some(OutputDataCollect::new_with_one(
+                0,
+                2,
+                vec![Some(1f64), Some(2f64)]
+            ))
+        );
+    }
+}
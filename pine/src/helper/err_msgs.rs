@@ -1,8 +1,12 @@
-pub const EXP_VAL_BUT_GET_VAL: &'static str = "{} is expected, not a {}.";
-pub const NO_FIELD_IN_OBJECT: &'static str = "No property named {} found in {}.";
-pub const REQUIRED_PARAMETERS: &'static str = "Parameters {} is required.";
-pub const UNRECONGNIZED_TZ: &'static str = "Unrecognized timezone {}.";
-pub const UNRECONGNIZED_RES: &'static str = "Unrecognized resolution {}.";
-pub const GE_1: &'static str = "The value of {} should be greater than or equal to 1.";
-pub const INPUT_SRCS: &'static str = "The input source should be one of {}.";
-pub const INVALID_VALS: &'static str = "The input value is invalid for property named {}.";
+pub const EXP_VAL_BUT_GET_VAL: &str = "{} is expected, not a {}.";
+pub const NO_FIELD_IN_OBJECT: &str = "No property named {} found in {}.";
+pub const REQUIRED_PARAMETERS: &str = "Parameters {} is required.";
+pub const UNRECONGNIZED_TZ: &str = "Unrecognized timezone {}.";
+pub const UNRECONGNIZED_RES: &str = "Unrecognized resolution {}.";
+pub const GE_1: &str = "The value of {} should be greater than or equal to 1.";
+pub const INPUT_SRCS: &str = "The input source should be one of {}.";
+pub const INVALID_VALS: &str = "The input value is invalid for property named {}.";
+pub const INPUT_LT_MIN: &str = "The value {} is less than the minimum value {}.";
+pub const INPUT_GT_MAX: &str = "The value {} is greater than the maximum value {}.";
+pub const INPUT_NOT_IN_OPTIONS: &str = "The value {} should be one of {}.";
+pub const INVALID_COLOR: &str = "The color {} should be like #RRGGBB or #RRGGBBAA.";
@@ -8,6 +8,8 @@ pub mod resolution;
 pub mod session;
 pub mod str_replace;
 
+#[macro_use]
+pub mod state;
 #[macro_use]
 pub mod vec;
 
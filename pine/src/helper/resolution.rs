@@ -29,6 +29,14 @@ impl Resolution {
         Resolution { count, restype }
     }
 
+    pub fn get_count(&self) -> i32 {
+        self.count
+    }
+
+    pub fn get_restype(&self) -> ResolutionType {
+        self.restype
+    }
+
     pub fn parse(resstr: &str) -> Result<Resolution, RuntimeErr> {
         let re = Regex::new(r"(\d*)([DWM]?)").unwrap();
         match re.captures(resstr) {
@@ -174,6 +174,14 @@ impl Session {
         ]
     }
 
+    // The session with one trading time span for all the weekdays.
+    pub fn from_span(span: TradeTimeSpan) -> Session {
+        Session {
+            spans: vec![span],
+            weekdays: Session::all_weekdays(),
+        }
+    }
+
     pub fn parse(sstr: &str) -> Result<Session, RuntimeErr> {
         if sstr == "24x7" {
             let session = Session {
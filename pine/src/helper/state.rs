@@ -0,0 +1,16 @@
+// Implement the save_state and load_state of the SeriesCall or EvaluateVal for the builtin
+// function whose fields don't change with the bars, so the runtime snapshot can keep it.
+macro_rules! impl_no_state {
+    () => {
+        fn save_state(&self) -> Option<Vec<crate::runtime::snapshot::StateValue>> {
+            Some(vec![])
+        }
+
+        fn load_state(
+            &mut self,
+            _state: Vec<crate::runtime::snapshot::StateValue>,
+        ) -> Result<(), crate::types::RuntimeErr> {
+            Ok(())
+        }
+    };
+}
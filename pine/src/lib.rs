@@ -18,12 +18,25 @@ pub mod ast;
 #[macro_use]
 pub mod helper;
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod bars;
+pub mod compiled;
+pub mod feed;
+
+pub mod library;
 pub mod libs;
+pub mod optimize;
 pub mod runtime;
+pub mod script_doc;
 pub mod syntax;
+pub mod synthetic;
 pub mod types;
+pub mod version;
+pub mod vm;
 
 use ast::error::PineErrorKind;
+use ast::incremental::{AstCache, ParsedChunks};
 use ast::input::{Input, Position, StrRange};
 use ast::stat_expr::block;
 use ast::stat_expr_types::{Block, VarIndex};
@@ -32,16 +45,23 @@ use ast::syntax_type::{SimpleSyntaxType, SyntaxType};
 
 use syntax::SyntaxParser;
 
-use libs::{declare_vars, VarResult};
+use library::{link_imports, LibraryArena, LibraryLinker, LibraryResolver};
+use libs::{declare_vars_for_version, VarResult};
 use runtime::context::{downcast_ctx, Ctx, PineRuntimeError, VarOperate};
-use runtime::data_src::{parse_datalen, Callback, DataSrc};
+use runtime::data_src::{check_data, parse_datalen, Callback, DataSrc, NoneCallback};
+use runtime::debug::{Debugger, VarValue};
+use runtime::diff::{collect_drawings, DiffTracker, OutputDiff};
 use runtime::error_format::{ErrorFormater, PineFormatError};
-use runtime::output::{IOInfo, InputVal, OutputData, OutputDataCollect, SymbolInfo};
+use runtime::output::{IOInfo, InputVal, OutputData, OutputDataCollect, SymbolInfo, TraceData};
+use runtime::render::OutputHistory;
+use runtime::snapshot::{RuntimeSnapshot, ScriptState, SNAPSHOT_VERSION};
 use runtime::{AnySeries, AnySeriesType};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::mem;
 use std::rc::Rc;
 use syntax::InputSrcDetector;
-use types::{Float, Int, PineRef};
+use types::{Float, Int, PineRef, RuntimeErr};
 
 #[derive(Debug, Clone)]
 pub struct LibInfo<'a> {
@@ -49,6 +69,7 @@ pub struct LibInfo<'a> {
     var_values: Vec<(&'a str, PineRef<'a>)>,
     input_names: Vec<(&'a str, AnySeriesType)>, // The input varnames include bar_index
     client_input_names: Vec<&'a str>,           // The input varnames user client should pass in
+    version: Option<i32>,                       // The Pine version of the builtin variables
 }
 
 const BAR_INDEX: &'static str = "bar_index";
@@ -65,8 +86,14 @@ impl<'a> LibInfo<'a> {
             var_values: values,
             input_names,
             client_input_names,
+            version: None,
         }
     }
+
+    // Get the Pine version of the builtin variables, None for the custom builtins.
+    pub fn version(&self) -> Option<i32> {
+        self.version
+    }
 }
 
 impl<'a> InputSrcDetector<'a> for LibInfo<'a> {
@@ -110,6 +137,9 @@ pub struct PineParser<'a, 'b> {
     var_types: &'b Vec<(&'a str, SyntaxType<'a>)>,
     // client_input_names: &'b Vec<&'a str>,
     lib_info: &'b LibInfo<'a>,
+    vm: bool,
+    linker: Option<LibraryLinker<'a, 'b>>,
+    parsed_chunks: Option<&'a ParsedChunks>,
 }
 
 impl<'a, 'b> PineParser<'a, 'b> {
@@ -119,21 +149,67 @@ impl<'a, 'b> PineParser<'a, 'b> {
             var_types: &lib_info.var_types,
             // client_input_names: &lib_info.client_input_names,
             lib_info,
+            vm: true,
+            linker: None,
+            parsed_chunks: None,
         }
     }
 
+    // Set the resolver to find the imported libraries, the library sources are kept in the arena.
+    pub fn set_library_resolver(
+        &mut self,
+        resolver: &'b dyn LibraryResolver,
+        arena: &'a LibraryArena,
+    ) {
+        self.linker = Some(LibraryLinker::new(resolver, arena));
+    }
+
+    // Whether compile the expressions to the bytecode programs after the syntax check.
+    pub fn set_vm(&mut self, vm: bool) {
+        self.vm = vm;
+    }
+
+    // Use the top-level statements parsed by the AstCache instead of parsing the source.
+    pub fn set_parsed_chunks(&mut self, chunks: &'a ParsedChunks) {
+        self.parsed_chunks = Some(chunks);
+    }
+
     pub fn parse(
         &mut self,
     ) -> Result<(Block<'a>, SyntaxParser<'a>, Vec<PineInputError>), Vec<PineInputError>> {
+        // The builtin variables must match the version declared by the script.
+        if let Some(builtins) = self.lib_info.version {
+            if let Some((line, script)) = version::find_version(self.src) {
+                if version::builtins_version(script) != builtins {
+                    let line = line as u32;
+                    return Err(vec![PineInputError::new(
+                        PineErrorKind::VersionNotMatch { script, builtins },
+                        StrRange::new(Position::new(line, 0), Position::new(line + 1, 0)),
+                    )]);
+                }
+            }
+        }
         let mut all_errs = vec![];
-        let mut blk = match parse_ast(self.src) {
-            Ok(blk) => blk,
-            Err((Some(blk), errs)) => {
+        let mut blk = match self.parsed_chunks {
+            Some(chunks) => {
+                let (blk, errs) = chunks.block();
                 all_errs = errs;
                 blk
             }
-            Err((None, errs)) => return Err(errs),
+            None => match parse_ast(self.src) {
+                Ok(blk) => blk,
+                Err((Some(blk), errs)) => {
+                    all_errs = errs;
+                    blk
+                }
+                Err((None, errs)) => return Err(errs),
+            },
         };
+        let link_errs = link_imports(&mut blk, self.linker.as_ref());
+        if !link_errs.is_empty() {
+            all_errs.extend(link_errs);
+            return Err(all_errs);
+        }
         let syntax_parser;
 
         match parse_syntax(&mut blk, &self.var_types, unsafe {
@@ -149,6 +225,9 @@ impl<'a, 'b> PineParser<'a, 'b> {
                 syntax_parser = parser.unwrap();
             }
         }
+        if self.vm && all_errs.is_empty() {
+            vm::lower_block(&mut blk);
+        }
         Ok((blk, syntax_parser, all_errs))
     }
 
@@ -180,7 +259,18 @@ impl<'a> PineRunner<'a> {
         let input_names = lib_info.input_names.clone();
 
         let blk_ref = unsafe { mem::transmute::<&Block<'a>, &'a Block<'a>>(blk) };
-        let datasrc = DataSrc::new(blk_ref, var_values, input_names, callback);
+        let mut datasrc = DataSrc::new(blk_ref, var_values, input_names, callback);
+        // If the inputs can be found from the syntax tree, every input call takes the input of
+        // its call site, so the inputs in the branches keep their slots whichever branch runs.
+        let mut extractor = DataSrc::new(
+            blk_ref,
+            lib_info.var_values.clone(),
+            lib_info.input_names.clone(),
+            callback,
+        );
+        if let Ok((inputs, sites)) = extractor.extract_input_sites() {
+            datasrc.set_input_sites(inputs, sites);
+        }
         PineRunner { datasrc }
     }
 
@@ -201,6 +291,15 @@ impl<'a> PineRunner<'a> {
         self.datasrc.runl(data, len, syminfo)
     }
 
+    pub(crate) fn runl_named<N: AsRef<str>>(
+        &mut self,
+        data: &[(N, AnySeries)],
+        len: usize,
+        syminfo: Option<Rc<SymbolInfo>>,
+    ) -> Result<(), PineRuntimeError> {
+        self.datasrc.runl_named(data, len, syminfo)
+    }
+
     pub fn update(
         &mut self,
         data: &Vec<(&'static str, AnySeries)>,
@@ -216,6 +315,14 @@ impl<'a> PineRunner<'a> {
         self.datasrc.updatel(data, len)
     }
 
+    pub(crate) fn updatel_named<N: AsRef<str>>(
+        &mut self,
+        data: &[(N, AnySeries)],
+        len: usize,
+    ) -> Result<(), PineRuntimeError> {
+        self.datasrc.updatel_named(data, len)
+    }
+
     pub fn update_from(
         &mut self,
         data: &Vec<(&'static str, AnySeries)>,
@@ -233,6 +340,15 @@ impl<'a> PineRunner<'a> {
         self.datasrc.update_froml(data, from, len)
     }
 
+    pub(crate) fn update_froml_named<N: AsRef<str>>(
+        &mut self,
+        data: &[(N, AnySeries)],
+        from: i32,
+        len: usize,
+    ) -> Result<(), PineRuntimeError> {
+        self.datasrc.update_froml_named(data, from, len)
+    }
+
     pub fn set_input_srcs(&mut self, srcs: Vec<String>) {
         self.datasrc.set_input_srcs(srcs);
     }
@@ -245,56 +361,154 @@ impl<'a> PineRunner<'a> {
         self.datasrc.get_context()
     }
 
+    // Save the runtime state of the script that has run, it's None if the state can't be saved.
+    pub fn save_state(&self) -> Option<ScriptState> {
+        self.datasrc.save_state()
+    }
+
+    // Restore the runtime state saved after running the bars of the data without running
+    // these bars again.
+    pub fn restore<N: AsRef<str>>(
+        &mut self,
+        data: &[(N, AnySeries)],
+        syminfo: Option<Rc<SymbolInfo>>,
+        state: ScriptState,
+        data_range: (Option<i32>, Option<i32>),
+    ) -> Result<(), PineRuntimeError> {
+        self.datasrc.restore(data, syminfo, state, data_range)
+    }
+
+    // Set the debugger that is notified before every statement of the script runs.
+    pub fn set_debugger(&mut self, debugger: Option<&'a dyn Debugger<'a>>) {
+        self.datasrc.set_debugger(debugger);
+    }
+
+    // Trace the values of the variables of the main context for every bar.
+    pub fn set_trace_vars(&mut self, vars: Vec<VarIndex>) {
+        self.datasrc.set_trace_vars(vars);
+    }
+
+    // Move the values of the traced variables recorded since the last move.
+    pub fn move_traces(&mut self) -> Vec<Vec<Option<VarValue>>> {
+        self.datasrc.move_traces()
+    }
+
     pub fn get_io_info(&mut self) -> &IOInfo {
         downcast_ctx(self.get_context()).get_io_info()
     }
 
+    // Extract the IOInfo from the syntax tree without running the script. The IOInfo is pushed
+    // into the main context, so the runner should not be used to run the script after that.
+    pub fn extract_io_info(&mut self) -> Result<IOInfo, RuntimeErr> {
+        self.datasrc.extract_io_info()
+    }
+
     pub fn move_output_data(&mut self) -> Vec<Option<OutputData>> {
         downcast_ctx(self.get_context()).move_output_data()
     }
 }
 
+// The names of the parsed script that the runner needs. They are kept when the syntax parser
+// is moved out, and they are cloned with the block of the compiled script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedNames<'a> {
+    input_names: Vec<&'a str>,
+    global_vars: HashMap<String, VarIndex>,
+}
+
+impl<'a> ParsedNames<'a> {
+    pub fn new(parser: &SyntaxParser<'a>) -> ParsedNames<'a> {
+        ParsedNames {
+            input_names: parser.get_inputnames(),
+            global_vars: parser.get_global_var_indexes(),
+        }
+    }
+}
+
 pub struct PineScript<'pa, 'li, 'ra> {
     source: String,
-    lib_info: LibInfo<'li>,
+    // The block and the runner refer to the boxed library information, so it can be
+    // replaced after a new block is parsed.
+    lib_info: Box<LibInfo<'li>>,
     blk: Block<'pa>,
     syntax_parser: Option<SyntaxParser<'pa>>,
+    parsed_names: Option<ParsedNames<'pa>>,
     callback: Option<&'ra dyn Callback>,
     runner: Option<PineRunner<'ra>>,
-    data: Vec<(&'static str, AnySeries)>,
+    // The data that the script has run with, the names of the restored data are owned.
+    data: Vec<(Cow<'static, str>, AnySeries)>,
     datalen: usize,
     syminfo: Option<Rc<SymbolInfo>>,
     error_format: ErrorFormater,
+    vm: bool,
+    // The version of the builtin variables. It is None if the library information is
+    // given by the user, otherwise the builtin variables follow the script version.
+    lib_version: Option<i32>,
+    library_resolver: Option<Box<dyn LibraryResolver>>,
+    // The sources of the imported libraries that the block refers to.
+    library_arena: LibraryArena,
+    // The names and indexes of the traced variables.
+    traces: Vec<(String, VarIndex)>,
+    // The cache of the parsed top-level statements.
+    ast_cache: Option<AstCache>,
+    // The parsed top-level statements that the block refers to.
+    parsed_chunks: Option<ParsedChunks>,
+    // The output data of all the bars to render the outputs if the rendered outputs are enabled.
+    output_history: Option<OutputHistory>,
+    // The revisions of the outputs if the output diff is enabled.
+    diff_tracker: Option<DiffTracker>,
 }
 
 const SERIES_FLOAT: SyntaxType = SyntaxType::Series(SimpleSyntaxType::Float);
 const SERIES_INT: SyntaxType = SyntaxType::Series(SimpleSyntaxType::Int);
 
+// The library information with all the builtin variables and the default input sources.
+pub fn default_lib_info<'a>() -> LibInfo<'a> {
+    lib_info_for_version(version::DEFAULT_VERSION)
+}
+
+// The library information with the builtin variables of the specific Pine version.
+pub fn lib_info_for_version<'a>(version: i32) -> LibInfo<'a> {
+    let mut lib_info = LibInfo::new(
+        declare_vars_for_version(version),
+        vec![
+            ("close", SERIES_FLOAT.clone()),
+            ("open", SERIES_FLOAT.clone()),
+            ("high", SERIES_FLOAT.clone()),
+            ("low", SERIES_FLOAT.clone()),
+            ("volume", SERIES_INT.clone()),
+            ("_time", SERIES_INT.clone()),
+            (BAR_INDEX, SERIES_INT.clone()),
+        ],
+    );
+    lib_info.version = Some(version::builtins_version(version));
+    lib_info
+}
+
 impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
     pub fn new(callback: Option<&'ra dyn Callback>) -> PineScript<'pa, 'li, 'ra> {
-        let lib_info = LibInfo::new(
-            declare_vars(),
-            vec![
-                ("close", SERIES_FLOAT.clone()),
-                ("open", SERIES_FLOAT.clone()),
-                ("high", SERIES_FLOAT.clone()),
-                ("low", SERIES_FLOAT.clone()),
-                ("volume", SERIES_INT.clone()),
-                ("_time", SERIES_INT.clone()),
-                (BAR_INDEX, SERIES_INT.clone()),
-            ],
-        );
+        let lib_info = default_lib_info();
         PineScript {
             source: String::from(""),
-            lib_info,
+            lib_info: Box::new(lib_info),
             blk: Block::new_no_input(vec![], None),
             syntax_parser: None,
+            parsed_names: None,
             callback,
             runner: None,
             data: vec![],
             datalen: 0,
             syminfo: None,
             error_format: ErrorFormater::new(),
+            vm: true,
+            lib_version: Some(version::DEFAULT_VERSION),
+            library_resolver: None,
+            library_arena: LibraryArena::new(),
+            traces: vec![],
+            ast_cache: None,
+            parsed_chunks: None,
+            output_history: None,
+            diff_tracker: None,
         }
     }
 
@@ -304,38 +518,138 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
     ) -> PineScript<'pa, 'li, 'ra> {
         PineScript {
             source: String::from(""),
-            lib_info,
+            lib_info: Box::new(lib_info),
             blk: Block::new_no_input(vec![], None),
             syntax_parser: None,
+            parsed_names: None,
             callback,
             runner: None,
             data: vec![],
             datalen: 0,
             syminfo: None,
             error_format: ErrorFormater::new(),
+            vm: true,
+            lib_version: None,
+            library_resolver: None,
+            library_arena: LibraryArena::new(),
+            traces: vec![],
+            ast_cache: None,
+            parsed_chunks: None,
+            output_history: None,
+            diff_tracker: None,
         }
     }
 
-    pub fn parse_src<'s, 'a, 'pb>(&'s mut self, src: String) -> Result<(), Vec<PineFormatError>>
+    // Whether run the script with the bytecode programs, it takes effect on the next parse.
+    pub fn set_vm(&mut self, vm: bool) {
+        self.vm = vm;
+    }
+
+    // Set the resolver to find the imported libraries, it takes effect on the next parse.
+    pub fn set_library_resolver(&mut self, resolver: Box<dyn LibraryResolver>) {
+        self.library_resolver = Some(resolver);
+    }
+
+    // Whether reuse the parse results of the unchanged top-level statements when parsing
+    // the edited source, it takes effect on the next parse.
+    pub fn set_ast_cache(&mut self, enable: bool) {
+        self.ast_cache = if enable { Some(AstCache::new()) } else { None };
+    }
+
+    // Whether produce the rendered outputs that the offsets are applied, the bars older than
+    // the last show_last bars are masked and the hidden outputs are omitted. The rendered data
+    // of the update starts at the first bar that may change and the series with the positive
+    // offset extend past the `to` bar. It takes effect on the next run.
+    pub fn set_render_outputs(&mut self, enable: bool) {
+        self.output_history = if enable {
+            Some(OutputHistory::new())
+        } else {
+            None
+        };
+    }
+
+    // Whether track the outputs of every run as a revision, so the diff from the last
+    // acknowledged revision can be generated by output_diff. It takes effect on the next run.
+    pub fn set_output_diff(&mut self, enable: bool) {
+        self.diff_tracker = if enable {
+            Some(DiffTracker::new())
+        } else {
+            None
+        };
+    }
+
+    // The changed bar ranges of the outputs, the changed drawings and the new alert events
+    // from the last acknowledged revision to the latest revision.
+    pub fn output_diff(&self) -> Result<OutputDiff, PineFormatError> {
+        match self.diff_tracker.as_ref() {
+            Some(tracker) => Ok(tracker.diff()),
+            None => Err(runtime_error(&self.error_format, RuntimeErr::OutputDiffNotEnabled)),
+        }
+    }
+
+    // Acknowledge the revision that the client has applied, the next diff starts at it.
+    pub fn ack_revision(&mut self, revision: u64) -> Result<(), PineFormatError> {
+        let result = match self.diff_tracker.as_mut() {
+            Some(tracker) => tracker.ack(revision),
+            None => Err(RuntimeErr::OutputDiffNotEnabled),
+        };
+        result.map_err(|err| runtime_error(&self.error_format, err))
+    }
+
+    pub fn parse_src<'s, 'pb>(&'s mut self, src: String) -> Result<(), Vec<PineFormatError>>
     where
         's: 'pb,
         'li: 'pa,
-        'a: 'pa,
     {
+        // The current block refers to the library information, the library arena, the
+        // source and the parsed chunks, so they are only replaced after the new block is
+        // parsed. Moving the boxes, the string and the chunks doesn't move the heap data
+        // that the new block borrows.
+        let new_version = match self.lib_version {
+            Some(lib_version) => {
+                let version = version::builtins_version(version::script_version(&src));
+                if version != lib_version {
+                    Some(version)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+        let new_lib_info = new_version.map(|version| Box::new(lib_info_for_version(version)));
+        let library_arena = LibraryArena::new();
+        let parsed_chunks = self.ast_cache.as_mut().map(|cache| cache.parse(&src));
         let mut parser: PineParser<'pa, 'pb>;
         unsafe {
-            self.source = src;
-            let src: &'a str = mem::transmute::<_, &'a str>(self.source.as_str());
-            let lib_ref = mem::transmute::<&LibInfo<'li>, &'pb LibInfo<'pa>>(&self.lib_info);
-            let src_ref = mem::transmute::<&'a str, &'pa str>(src);
+            let src_ref = mem::transmute::<&str, &'pa str>(src.as_str());
+            let lib_info: &LibInfo<'li> = new_lib_info.as_deref().unwrap_or(&self.lib_info);
+            let lib_ref = mem::transmute::<&LibInfo<'li>, &'pb LibInfo<'pa>>(lib_info);
             parser = PineParser::new(src_ref, lib_ref);
+            if let Some(resolver) = &self.library_resolver {
+                let arena_ref = mem::transmute::<&LibraryArena, &'pa LibraryArena>(&library_arena);
+                parser.set_library_resolver(resolver.as_ref(), arena_ref);
+            }
+            if let Some(chunks) = parsed_chunks.as_ref() {
+                parser.set_parsed_chunks(mem::transmute::<&ParsedChunks, &'pa ParsedChunks>(
+                    chunks,
+                ));
+            }
         }
-        // parser = PineParser::new(src, &self.lib_info);
+        parser.set_vm(self.vm);
         match parser.parse() {
             Ok((blk, parser, errs)) => {
-                self.blk = blk;
-                self.syntax_parser = Some(parser);
                 self.runner = None;
+                self.traces.clear();
+                self.parsed_names = Some(ParsedNames::new(&parser));
+                self.syntax_parser = Some(parser);
+                self.blk = blk;
+                self.source = src;
+                self.parsed_chunks = parsed_chunks;
+                self.library_arena = library_arena;
+                if let Some(lib_info) = new_lib_info {
+                    self.lib_info = lib_info;
+                    self.lib_version = new_version;
+                }
                 if errs.is_empty() {
                     Ok(())
                 } else {
@@ -352,25 +666,84 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         }
     }
 
-    pub fn get_runner(&mut self) -> &mut PineRunner<'ra> {
-        if self.runner.is_none() {
-            let mut runner: PineRunner<'ra>;
-            unsafe {
-                let blk_ref: &'ra Block<'ra> =
-                    mem::transmute::<&Block<'pa>, &'ra Block<'ra>>(&self.blk);
-                let lib_ref = mem::transmute::<&LibInfo<'li>, &LibInfo<'ra>>(&self.lib_info);
-                runner = PineRunner::new(lib_ref, blk_ref, self.callback.unwrap());
-                let names = self.syntax_parser.as_ref().unwrap().get_inputnames();
-                runner.set_input_srcs(names.into_iter().map(|s| String::from(s)).collect());
+    fn new_runner(&self) -> Result<PineRunner<'ra>, PineFormatError> {
+        let names = match self.parsed_names.as_ref() {
+            Some(names) => names.input_names.clone(),
+            None => {
+                return Err(runtime_error(
+                    &self.error_format,
+                    RuntimeErr::ScriptNotParsed,
+                ))
             }
+        };
+        let callback: &'ra dyn Callback = self.callback.unwrap_or(&NoneCallback());
+        let mut runner: PineRunner<'ra>;
+        unsafe {
+            let blk_ref: &'ra Block<'ra> =
+                mem::transmute::<&Block<'pa>, &'ra Block<'ra>>(&self.blk);
+            let lib_ref = mem::transmute::<&LibInfo<'li>, &LibInfo<'ra>>(&self.lib_info);
+            runner = PineRunner::new(lib_ref, blk_ref, callback);
+        }
+        runner.set_input_srcs(names.into_iter().map(String::from).collect());
+        Ok(runner)
+    }
+
+    fn init_runner(&mut self) -> Result<(), PineFormatError> {
+        if self.runner.is_none() {
+            let mut runner = self.new_runner()?;
+            runner.set_trace_vars(self.traces.iter().map(|t| t.1).collect());
             self.runner = Some(runner);
         }
-        self.runner.as_mut().unwrap()
+        Ok(())
     }
 
-    // Run the script with the experimental data to generate IOInfo data
+    // Get the runner of the parsed script, the runner is created on the first call.
+    pub fn get_runner(&mut self) -> Result<&mut PineRunner<'ra>, PineFormatError> {
+        self.init_runner()?;
+        let error_format = &self.error_format;
+        self.runner
+            .as_mut()
+            .ok_or_else(|| runtime_error(error_format, RuntimeErr::ScriptNotParsed))
+    }
+
+    // Run the script with the saved data and inputs.
+    fn run_saved_data(
+        &mut self,
+        syminfo: Option<Rc<SymbolInfo>>,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        self.init_runner()?;
+        let result = match self.runner.as_mut() {
+            Some(runner) => runner.runl_named(&self.data, self.datalen, syminfo),
+            None => return Err(runtime_error(&self.error_format, RuntimeErr::ScriptNotParsed)),
+        };
+        match result {
+            Ok(_) => self.move_output_data(),
+            Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
+        }
+    }
+
+    fn check_data<N: AsRef<str>>(
+        &self,
+        data: &[(N, AnySeries)],
+        datalen: usize,
+    ) -> Result<(), PineFormatError> {
+        check_data(data, &self.lib_info.input_names, datalen)
+            .map_err(|err| PineFormatError::from_runtime_error(&self.error_format, err))
+    }
+
+    fn parse_datalen<N: AsRef<str>>(&self, data: &[(N, AnySeries)]) -> Result<usize, PineFormatError> {
+        parse_datalen(data, &self.lib_info.input_names)
+            .map_err(|err| PineFormatError::from_runtime_error(&self.error_format, err))
+    }
+
+    // Generate the IOInfo from the syntax tree, so the inputs and outputs in the branches not
+    // taken are also found. If some arguments can't be evaluated without running, the script
+    // is run with the experimental data to generate IOInfo data instead.
     pub fn gen_io_info(&mut self) -> Result<IOInfo, PineFormatError> {
-        match self.get_runner().run(
+        if let Ok(io_info) = self.new_runner()?.extract_io_info() {
+            return Ok(io_info);
+        }
+        match self.get_runner()?.run(
             &vec![
                 ("close", AnySeries::from_float_vec(vec![Some(0f64)])),
                 ("open", AnySeries::from_float_vec(vec![Some(0f64)])),
@@ -383,22 +756,114 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         ) {
             Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
             Ok(_) => {
-                self.move_output_data();
-                Ok(downcast_ctx(self.get_runner().get_context())
+                self.move_output_data()?;
+                Ok(downcast_ctx(self.get_runner()?.get_context())
                     .get_io_info()
                     .clone())
             }
         }
     }
 
-    pub fn move_output_data(&mut self) -> OutputDataCollect {
-        let context = downcast_ctx(self.get_runner().get_context());
-        let (start, end) = context.get_data_range();
-        OutputDataCollect::new(start.unwrap(), end.unwrap(), context.move_output_data())
+    pub fn move_output_data(&mut self) -> Result<OutputDataCollect, PineFormatError> {
+        let render = self.output_history.is_some();
+        let track = self.diff_tracker.is_some();
+        let runner = self.get_runner()?;
+        let context = downcast_ctx(runner.get_context());
+        // The output data can only be moved once after running.
+        let (start, end) = match context.get_data_range() {
+            (Some(start), Some(end)) if context.has_output_data() => (start, end),
+            _ => return Err(runtime_error(&self.error_format, RuntimeErr::ScriptNotRun)),
+        };
+        let mut data_list = context.move_output_data();
+        let outputs = if render {
+            context.get_io_info().get_outputs().clone()
+        } else {
+            vec![]
+        };
+        let drawings = if track {
+            collect_drawings(context, end)
+        } else {
+            BTreeMap::new()
+        };
+        let alerts = context.move_alerts();
+        let values = runner.move_traces();
+        let mut from = start;
+        if let Some(history) = self.output_history.as_mut() {
+            let old_len = history.bar_count();
+            history.merge(start as usize, end as usize, data_list);
+            let (rendered_from, rendered) = history.render(&outputs, start as usize, old_len);
+            from = rendered_from as i32;
+            data_list = rendered;
+        }
+        let traces = self
+            .traces
+            .iter()
+            .zip(values)
+            .map(|((name, _), mut values)| {
+                // The bars before the start are not run again, so their values are unknown.
+                values.splice(0..0, (from..start).map(|_| None));
+                TraceData::new(name.clone(), values)
+            })
+            .collect();
+        let mut output = OutputDataCollect::new_with_traces(from, end, data_list, traces);
+        output.alerts = alerts;
+        if let Some(tracker) = self.diff_tracker.as_mut() {
+            tracker.commit(&output, start, drawings);
+        }
+        Ok(output)
     }
 
-    pub fn change_inputs(&mut self, inputs: Vec<Option<InputVal>>) {
-        self.get_runner().change_inputs(inputs);
+    // Trace the values of the global variables for every bar, the values are returned in
+    // the traces of OutputDataCollect. The traces are cleared when the script is parsed again.
+    pub fn trace_vars(&mut self, names: &[&str]) -> Result<(), PineFormatError> {
+        let mut traces = vec![];
+        for name in names {
+            let index = self
+                .parsed_names
+                .as_ref()
+                .and_then(|p| p.global_vars.get(*name).cloned());
+            match index {
+                Some(index) => traces.push((String::from(*name), index)),
+                None => {
+                    let err = RuntimeErr::TraceVarNotFound(String::from(*name));
+                    return Err(PineFormatError::from_runtime_error(
+                        &self.error_format,
+                        PineRuntimeError::new_no_range(err),
+                    ));
+                }
+            }
+        }
+        self.trace_var_indexes(traces);
+        Ok(())
+    }
+
+    // Trace the variables by the indexes resolved from the SyntaxParser, the names are the
+    // names of the generated TraceData.
+    pub fn trace_var_indexes(&mut self, traces: Vec<(String, VarIndex)>) {
+        let indexes = traces.iter().map(|t| t.1).collect();
+        self.traces = traces;
+        if let Some(runner) = self.runner.as_mut() {
+            runner.set_trace_vars(indexes);
+        }
+    }
+
+    // Change the input values, the count of the values must be the same as the inputs of
+    // the script if the script has been run. The empty values use the default inputs.
+    pub fn change_inputs(&mut self, inputs: Vec<Option<InputVal>>) -> Result<(), PineFormatError> {
+        let runner = self.get_runner()?;
+        let count = runner.get_io_info().get_inputs().len();
+        if count != 0 && !inputs.is_empty() && count != inputs.len() {
+            let msg = format!("The count of the inputs should be {}.", count);
+            return Err(runtime_error(
+                &self.error_format,
+                RuntimeErr::InvalidParameters(msg),
+            ));
+        }
+        if let Err(err) = runner.get_io_info().check_inputs(&inputs) {
+            return Err(runtime_error(&self.error_format, err));
+        }
+        runner.change_inputs(inputs);
+        Ok(())
     }
 
     // Run the script with new input settings and old data
@@ -406,23 +871,14 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         &mut self,
         input: Vec<Option<InputVal>>,
     ) -> Result<OutputDataCollect, PineFormatError> {
-        let runner = self.get_runner();
-        runner.change_inputs(input);
-        match self
-            .runner
-            .as_mut()
-            .unwrap()
-            .runl(&self.data, self.datalen, None)
-        {
-            Ok(_) => Ok(self.move_output_data()),
-            Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
-        }
+        self.change_inputs(input)?;
+        self.run_saved_data(None)
     }
 
-    fn transform_data(data: &mut Vec<(&'static str, AnySeries)>) {
+    fn transform_data(data: &mut Vec<(Cow<'static, str>, AnySeries)>) {
         for item in data.iter_mut() {
             if item.0 == "time" {
-                item.0 = "_time";
+                item.0 = Cow::Borrowed("_time");
             }
         }
     }
@@ -437,13 +893,29 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         'li: 'ra,
         'pa: 'ra,
     {
-        let len = parse_datalen(&data, &self.lib_info.input_names).unwrap();
-        self.run_with_datal(data, len, syminfo)
+        let data = named_data(data);
+        let len = self.parse_datalen(&data)?;
+        self.run_with_datal_named(data, len, syminfo)
     }
 
     pub fn run_with_datal(
         &mut self,
-        mut data: Vec<(&'static str, AnySeries)>,
+        data: Vec<(&'static str, AnySeries)>,
+        datalen: usize,
+        syminfo: Option<Rc<SymbolInfo>>,
+    ) -> Result<OutputDataCollect, PineFormatError>
+    where
+        'li: 'ra,
+        'pa: 'ra,
+    {
+        self.run_with_datal_named(named_data(data), datalen, syminfo)
+    }
+
+    // Run the script with the data whose names may be owned, like the names restored from
+    // the snapshot or generated from the library info.
+    pub(crate) fn run_with_datal_named(
+        &mut self,
+        data: Vec<(Cow<'static, str>, AnySeries)>,
         datalen: usize,
         syminfo: Option<Rc<SymbolInfo>>,
     ) -> Result<OutputDataCollect, PineFormatError>
@@ -451,27 +923,21 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         'li: 'ra,
         'pa: 'ra,
     {
+        let mut data = data;
         PineScript::transform_data(&mut data);
+        self.check_data(&data, datalen)?;
+        self.init_runner()?;
         self.datalen = datalen;
         self.data = data;
         self.syminfo = syminfo.clone();
-        self.get_runner();
-        match self
-            .runner
-            .as_mut()
-            .unwrap()
-            .runl(&self.data, self.datalen, syminfo)
-        {
-            Ok(_) => Ok(self.move_output_data()),
-            Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
-        }
+        self.run_saved_data(syminfo)
     }
 
     // Run the script with new data
     pub fn runl(
         &mut self,
         input: Vec<Option<InputVal>>,
-        mut data: Vec<(&'static str, AnySeries)>,
+        data: Vec<(&'static str, AnySeries)>,
         datalen: usize,
         syminfo: Option<Rc<SymbolInfo>>,
     ) -> Result<OutputDataCollect, PineFormatError>
@@ -479,20 +945,14 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         'li: 'ra,
         'pa: 'ra,
     {
+        let mut data = named_data(data);
         PineScript::transform_data(&mut data);
+        self.check_data(&data, datalen)?;
+        self.change_inputs(input)?;
         self.datalen = datalen;
         self.data = data;
         self.syminfo = syminfo.clone();
-        self.get_runner().change_inputs(input);
-        match self
-            .runner
-            .as_mut()
-            .unwrap()
-            .runl(&self.data, self.datalen, syminfo)
-        {
-            Ok(_) => Ok(self.move_output_data()),
-            Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
-        }
+        self.run_saved_data(syminfo)
     }
 
     pub fn run(
@@ -505,7 +965,7 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         'li: 'ra,
         'pa: 'ra,
     {
-        let len = parse_datalen(&data, &self.lib_info.input_names).unwrap();
+        let len = self.parse_datalen(&data)?;
         self.runl(input, data, len, syminfo)
     }
 
@@ -516,25 +976,41 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         'pa: 'ra,
     {
         let syminfo = self.syminfo.clone();
-        match self
-            .runner
-            .as_mut()
-            .unwrap()
-            .runl(&self.data, self.datalen, syminfo)
-        {
-            Ok(_) => Ok(self.move_output_data()),
-            Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
+        self.run_saved_data(syminfo)
+    }
+
+    // Check the update data has the same sources as the run data, so they can be merged.
+    fn check_update_data(
+        &self,
+        new_data: &[(Cow<'static, str>, AnySeries)],
+        from: usize,
+        dlen: usize,
+    ) -> Result<(), PineFormatError> {
+        if self.runner.is_none() || self.datalen == 0 {
+            return Err(runtime_error(&self.error_format, RuntimeErr::ScriptNotRun));
+        }
+        if from > self.datalen {
+            return Err(runtime_error(&self.error_format, RuntimeErr::InvalidUpdateFrom(from as i32)));
         }
+        let is_same = self.data.len() == new_data.len()
+            && self.data.iter().zip(new_data).all(|(v1, v2)| {
+                (v1.0 == v2.0 || v1.0 == "_time" && v2.0 == "time")
+                    && v1.1.get_type() == v2.1.get_type()
+                    && v1.1.len() >= from
+            });
+        if !is_same {
+            return Err(runtime_error(&self.error_format, RuntimeErr::UpdateDataMismatch));
+        }
+        self.check_data(new_data, dlen)
     }
 
-    fn merge_data(&mut self, new_data: &Vec<(&'static str, AnySeries)>, from: usize) {
+    fn merge_data(&mut self, new_data: &[(Cow<'static, str>, AnySeries)], from: usize) {
         let origin_data = mem::replace(&mut self.data, vec![]);
 
         self.data = origin_data
             .into_iter()
             .zip(new_data)
             .map(|(v1, v2)| {
-                debug_assert!(v1.0 == v2.0);
                 let series = match v1.1.get_type() {
                     AnySeriesType::Int => {
                         let mut vec = v1.1.into_vec::<Int>();
@@ -557,8 +1033,9 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         &mut self,
         data: Vec<(&'static str, AnySeries)>,
     ) -> Result<OutputDataCollect, PineFormatError> {
-        let len = parse_datalen(&data, &self.lib_info.input_names).unwrap();
-        self.updatel(data, len)
+        let data = named_data(data);
+        let len = self.parse_datalen(&data)?;
+        self.updatel_named(data, len)
     }
 
     pub fn updatel(
@@ -566,10 +1043,23 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         data: Vec<(&'static str, AnySeries)>,
         dlen: usize,
     ) -> Result<OutputDataCollect, PineFormatError> {
-        self.merge_data(&data, self.datalen - 1);
-        self.datalen += dlen - 1;
-        match self.runner.as_mut().unwrap().updatel(&data, dlen) {
-            Ok(_) => Ok(self.move_output_data()),
+        self.updatel_named(named_data(data), dlen)
+    }
+
+    pub(crate) fn updatel_named(
+        &mut self,
+        data: Vec<(Cow<'static, str>, AnySeries)>,
+        dlen: usize,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        if dlen == 0 {
+            return Err(runtime_error(&self.error_format, RuntimeErr::InvalidDataLen));
+        }
+        let from = self.datalen.saturating_sub(1);
+        self.check_update_data(&data, from, dlen)?;
+        self.merge_data(&data, from);
+        self.datalen = from + dlen;
+        match self.get_runner()?.updatel_named(&data, dlen) {
+            Ok(_) => self.move_output_data(),
             Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
         }
     }
@@ -579,8 +1069,9 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         data: Vec<(&'static str, AnySeries)>,
         from: i32,
     ) -> Result<OutputDataCollect, PineFormatError> {
-        let len = parse_datalen(&data, &self.lib_info.input_names).unwrap();
-        self.update_froml(data, from, len)
+        let data = named_data(data);
+        let len = self.parse_datalen(&data)?;
+        self.update_froml_named(data, from, len)
     }
 
     pub fn update_froml(
@@ -589,31 +1080,161 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         from: i32,
         dlen: usize,
     ) -> Result<OutputDataCollect, PineFormatError> {
+        self.update_froml_named(named_data(data), from, dlen)
+    }
+
+    pub(crate) fn update_froml_named(
+        &mut self,
+        data: Vec<(Cow<'static, str>, AnySeries)>,
+        from: i32,
+        dlen: usize,
+    ) -> Result<OutputDataCollect, PineFormatError> {
+        if from < 0 {
+            return Err(runtime_error(&self.error_format, RuntimeErr::InvalidUpdateFrom(from)));
+        }
+        self.check_update_data(&data, from as usize, dlen)?;
         self.merge_data(&data, from as usize);
         self.datalen = from as usize + dlen;
-        match self
-            .runner
-            .as_mut()
-            .unwrap()
-            .update_froml(&data, from, dlen)
-        {
-            Ok(_) => Ok(self.move_output_data()),
+        match self.get_runner()?.update_froml_named(&data, from, dlen) {
+            Ok(_) => self.move_output_data(),
             Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
         }
     }
 
+    // Capture the runtime state of the script that has been run so that it can be
+    // restored later and continue with update.
+    pub fn snapshot(&mut self) -> Result<RuntimeSnapshot, PineFormatError> {
+        let datalen = self.datalen;
+        let runner = self.get_runner()?;
+        let state = if datalen > 0 {
+            runner.save_state()
+        } else {
+            None
+        };
+        let context = downcast_ctx(runner.get_context());
+        let inputs = context.get_inputs().clone();
+        let data_range = context.get_data_range();
+        Ok(RuntimeSnapshot::new(
+            self.source.clone(),
+            inputs,
+            self.syminfo.as_ref().map(|s| (**s).clone()),
+            data_range,
+            self.datalen,
+            &self.data,
+            state,
+        ))
+    }
+
+    // Restore the runtime state from the snapshot. The script must be parsed from
+    // the same source code that generated the snapshot. The restored bars are not run
+    // again, so their outputs are not generated, unless the snapshot has no state and
+    // the bars are run again to rebuild the runtime.
+    pub fn restore(&mut self, snapshot: RuntimeSnapshot) -> Result<(), PineFormatError>
+    where
+        'li: 'ra,
+        'pa: 'ra,
+    {
+        let snapshot_err = |msg: &str| {
+            PineFormatError::from_runtime_error(
+                &self.error_format,
+                PineRuntimeError::new_no_range(RuntimeErr::InvalidSnapshot(String::from(msg))),
+            )
+        };
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(snapshot_err("The snapshot version is not supported."));
+        }
+        if snapshot.source != self.source || self.parsed_names.is_none() {
+            return Err(snapshot_err(
+                "The snapshot is not generated from the current script.",
+            ));
+        }
+
+        let RuntimeSnapshot {
+            inputs,
+            syminfo,
+            data_range,
+            datalen,
+            data,
+            state,
+            ..
+        } = snapshot;
+        let syminfo = syminfo.map(Rc::new);
+        self.data = data
+            .into_iter()
+            .map(|(name, series)| (Cow::Owned(name), series.into_any_series()))
+            .collect();
+        self.datalen = datalen;
+        self.syminfo = syminfo.clone();
+        self.change_inputs(inputs)?;
+        let runner = match self.runner.as_mut() {
+            Some(runner) => runner,
+            None => return Err(runtime_error(&self.error_format, RuntimeErr::ScriptNotParsed)),
+        };
+        let result = match state {
+            Some(state) if datalen > 0 => runner.restore(&self.data, syminfo, state, data_range),
+            _ => match runner.runl_named(&self.data, datalen, syminfo) {
+                Ok(_) => {
+                    // The rendered outputs and the output diff keep the outputs of the restored bars.
+                    self.move_output_data()?;
+                    let context = downcast_ctx(self.get_runner()?.get_context());
+                    context.update_data_range(data_range);
+                    Ok(())
+                }
+                Err(err) => Err(err),
+            },
+        };
+        result.map_err(|err| PineFormatError::from_runtime_error(&self.error_format, err))
+    }
+
     pub fn move_parser(&mut self) -> Option<SyntaxParser<'pa>> {
         mem::replace(&mut self.syntax_parser, None)
     }
 
+    // Move out the source and the parsed block, so other scripts with the same library
+    // information can run the block without parsing the source again. The block borrows the
+    // heap data of the source, so the source must outlive the block. It's None if the script
+    // is not parsed or the block borrows the imported libraries or the cached statements.
+    pub(crate) fn into_parsed_block(mut self) -> Option<(String, Block<'pa>, ParsedNames<'pa>)> {
+        if self.library_resolver.is_some() || self.parsed_chunks.is_some() {
+            return None;
+        }
+        let names = self.parsed_names.take()?;
+        self.runner = None;
+        let blk = mem::replace(&mut self.blk, Block::new_no_input(vec![], None));
+        Some((mem::take(&mut self.source), blk, names))
+    }
+
+    // Set the block parsed by another script with the same library information. The block
+    // borrows the source that the caller owns, the script keeps a copy to check the snapshots.
+    pub(crate) fn set_parsed_block(&mut self, source: String, blk: Block<'pa>, names: ParsedNames<'pa>) {
+        self.runner = None;
+        self.traces.clear();
+        self.syntax_parser = None;
+        self.parsed_names = Some(names);
+        self.blk = blk;
+        self.source = source;
+        self.parsed_chunks = None;
+    }
+
     pub fn move_var(&mut self, var_index: VarIndex) -> Option<PineRef<'pa>> {
-        let runner: &mut PineRunner<'ra> = self.get_runner();
+        let runner: &mut PineRunner<'ra> = self.get_runner().ok()?;
 
         let context = runner.get_context();
         unsafe { mem::transmute::<_, Option<PineRef<'pa>>>(context.move_var(var_index)) }
     }
 }
 
+// The data names are borrowed from the static names or owned by the data.
+fn named_data(data: Vec<(&'static str, AnySeries)>) -> Vec<(Cow<'static, str>, AnySeries)> {
+    data.into_iter()
+        .map(|(name, series)| (Cow::Borrowed(name), series))
+        .collect()
+}
+
+fn runtime_error(error_format: &ErrorFormater, err: RuntimeErr) -> PineFormatError {
+    PineFormatError::from_runtime_error(error_format, PineRuntimeError::new_no_range(err))
+}
+
 pub fn parse_ast(in_str: &str) -> Result<Block, (Option<Block>, Vec<PineInputError>)> {
     let input = Input::new(in_str, Position::new(0, 0), Position::max());
     let state = AstState::new();
@@ -715,10 +1336,15 @@ pub fn extract_vars<'a>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::libs::input;
     use crate::libs::plot;
+    use crate::libs::{alert, input, label};
+    use crate::runtime::context::CallFrame;
+    use crate::runtime::diff::{DrawingChange, DrawingId, OutputRange};
     use crate::runtime::data_src::NoneCallback;
-    use crate::runtime::output::{InputInfo, InputSrc, IntInputInfo, OutputInfo, PlotInfo};
+    use crate::runtime::snapshot::SnapshotSeries;
+    use crate::runtime::output::{
+        AlertEvent, InputInfo, InputSrc, IntInputInfo, OutputInfo, PlotInfo,
+    };
 
     #[test]
     fn lib_info_test() {
@@ -791,6 +1417,9 @@ mod tests {
                     maxval: None,
                     step: None,
                     options: None,
+                    tooltip: None,
+                    inline: None,
+                    group: None,
                 })],
                 vec![OutputInfo::Plot(PlotInfo {
                     title: None,
@@ -876,6 +1505,322 @@ mod tests {
         );
     }
 
+    #[test]
+    fn snapshot_test() {
+        let gen_script = || {
+            let lib_info = LibInfo::new(
+                libs::declare_vars(),
+                vec![
+                    ("close", SERIES_FLOAT.clone()),
+                    (BAR_INDEX, SERIES_INT.clone()),
+                ],
+            );
+            let mut script = PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+            script
+                .parse_src(String::from(
+                    "m = input(2, 'len', 'int')\nvar s = 0.0\ns := s + close\nplot(ema(close, m) + s)",
+                ))
+                .unwrap();
+            script
+        };
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+        )];
+        let mut script = gen_script();
+        script.change_inputs(vec![Some(InputVal::Int(3))]).unwrap();
+        script.run_with_data(data, None).unwrap();
+
+        let snapshot = script.snapshot().unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: RuntimeSnapshot = serde_json::from_str(&json).unwrap();
+
+        let mut restored = gen_script();
+        assert_eq!(restored.restore(snapshot.clone()), Ok(()));
+
+        let new_data = || {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(4f64), Some(5f64)]),
+            )]
+        };
+        let output = script.update(new_data());
+        assert_eq!(restored.update(new_data()), output);
+        assert_eq!(restored.snapshot(), script.snapshot());
+
+        let mut other = gen_script();
+        other.parse_src(String::from("plot(close)")).unwrap();
+        assert!(other.restore(snapshot.clone()).is_err());
+
+        let mut old_version = snapshot;
+        old_version.version = SNAPSHOT_VERSION + 1;
+        assert!(gen_script().restore(old_version).is_err());
+    }
+
+    #[test]
+    fn snapshot_state_test() {
+        let gen_script = |src: &str| {
+            let mut vars = libs::declare_vars();
+            vars.push(label::declare_var());
+            let lib_info = LibInfo::new(
+                vars,
+                vec![
+                    ("close", SERIES_FLOAT.clone()),
+                    (BAR_INDEX, SERIES_INT.clone()),
+                ],
+            );
+            let mut script = PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+            script.parse_src(String::from(src)).unwrap();
+            script
+        };
+        let src = "f(x) =>\n    var c = 0\n    c := c + 1\n    x + c\nr = rsi(close, 2)\nv = 0.0\nif close > 2\n    v := ema(close, 2)\nplot(r + f(close) + close[2] + v)";
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(3f64), Some(2f64), Some(4f64)]),
+        )];
+        let mut script = gen_script(src);
+        script.run_with_data(data, None).unwrap();
+
+        let mut snapshot = script.snapshot().unwrap();
+        assert!(snapshot.state.is_some());
+        // The bars are not run again, so the restored runtime doesn't depend on the old bars.
+        snapshot.data = vec![(
+            String::from("close"),
+            SnapshotSeries::Float(vec![Some(0f64); 4]),
+        )];
+        let mut restored = gen_script(src);
+        assert_eq!(restored.restore(snapshot), Ok(()));
+
+        let new_data = || {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(5f64), Some(3f64)]),
+            )]
+        };
+        assert_eq!(restored.update(new_data()), script.update(new_data()));
+
+        // The script with the drawings runs the bars again to restore the runtime.
+        let src = "l = label.new(bar_index, close)\nplot(close)";
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(3f64)]),
+        )];
+        let mut script = gen_script(src);
+        script.run_with_data(data, None).unwrap();
+        let snapshot = script.snapshot().unwrap();
+        assert!(snapshot.state.is_none());
+        let mut restored = gen_script(src);
+        assert_eq!(restored.restore(snapshot), Ok(()));
+        assert_eq!(restored.update(new_data()), script.update(new_data()));
+    }
+
+    #[test]
+    fn trace_test() {
+        let lib_info = LibInfo::new(
+            libs::declare_vars(),
+            vec![
+                ("close", SERIES_FLOAT.clone()),
+                (BAR_INDEX, SERIES_INT.clone()),
+            ],
+        );
+        let mut script = PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+        script
+            .parse_src(String::from(
+                "m = close * 2\nn = bar_index\nb = close > 1\nplot(m)",
+            ))
+            .unwrap();
+        assert_eq!(
+            script.trace_vars(&["m", "nothing"]).unwrap_err().message,
+            "The traced variable nothing is not declared in the global scope."
+        );
+        script.trace_vars(&["m", "n", "b"]).unwrap();
+
+        let floats = |v: Vec<f64>| v.into_iter().map(|v| Some(VarValue::Float(Some(v))));
+        let ints = |v: Vec<i64>| v.into_iter().map(|v| Some(VarValue::Int(Some(v))));
+        let bools = |v: Vec<bool>| v.into_iter().map(|v| Some(VarValue::Bool(v)));
+        let traces = |m: Vec<f64>, n: Vec<i64>, b: Vec<bool>| {
+            vec![
+                TraceData::new(String::from("m"), floats(m).collect()),
+                TraceData::new(String::from("n"), ints(n).collect()),
+                TraceData::new(String::from("b"), bools(b).collect()),
+            ]
+        };
+
+        let output = script
+            .run_with_data(
+                vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            output.traces,
+            traces(vec![2f64, 4f64], vec![0, 1], vec![false, true])
+        );
+
+        let output = script
+            .update(vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(3f64), Some(4f64)]),
+            )])
+            .unwrap();
+        assert_eq!((output.from, output.to), (1, 3));
+        assert_eq!(
+            output.traces,
+            traces(vec![6f64, 8f64], vec![1, 2], vec![true, true])
+        );
+
+        let output = script
+            .update_from(
+                vec![("close", AnySeries::from_float_vec(vec![Some(0f64)]))],
+                2,
+            )
+            .unwrap();
+        assert_eq!(output.traces, traces(vec![0f64], vec![2], vec![false]));
+
+        // The traces are cleared after the script is parsed again.
+        script.parse_src(String::from("plot(close)")).unwrap();
+        let output = script.run_with_input(vec![]).unwrap();
+        assert_eq!(output.traces, vec![]);
+    }
+
+    #[test]
+    fn render_outputs_test() {
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script.set_render_outputs(true);
+        script
+            .parse_src(String::from(
+                "p1 = plot(close, offset=1)\np2 = plot(close, offset=-1, show_last=2)\n\
+                 plot(close, display=display.none)\nfill(p1, p2)\nhline(1)",
+            ))
+            .unwrap();
+        let close = |v: Vec<f64>| {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(v.into_iter().map(Some).collect()),
+            )]
+        };
+        let plot_data = |v: Vec<Option<f64>>| Some(OutputData::new(vec![v]));
+
+        let output = script
+            .run_with_data(close(vec![1f64, 2f64, 3f64]), None)
+            .unwrap();
+        assert_eq!((output.from, output.to), (0, 3));
+        assert_eq!(
+            output.data_list,
+            vec![
+                plot_data(vec![None, Some(1f64), Some(2f64), Some(3f64)]),
+                plot_data(vec![Some(2f64), Some(3f64), None]),
+                None,
+                None,
+                None
+            ]
+        );
+
+        // The bars shifted by the negative offset and masked by show_last are rendered again.
+        let output = script.update(close(vec![4f64, 5f64])).unwrap();
+        assert_eq!((output.from, output.to), (0, 4));
+        assert_eq!(
+            output.data_list[..2],
+            [
+                plot_data(vec![None, Some(1f64), Some(2f64), Some(4f64), Some(5f64)]),
+                plot_data(vec![None, Some(4f64), Some(5f64), None]),
+            ]
+        );
+
+        script.set_render_outputs(false);
+        let output = script.run_with_odi().unwrap();
+        assert_eq!(output.data_list.len(), 5);
+        assert_eq!(
+            output.data_list[2],
+            plot_data(vec![Some(1f64), Some(2f64), Some(4f64), Some(5f64)])
+        );
+    }
+
+    #[test]
+    fn output_diff_test() {
+        let lib_info = LibInfo::new(
+            vec![
+                plot::declare_var(),
+                label::declare_var(),
+                alert::declare_var(),
+            ],
+            vec![("close", SERIES_FLOAT.clone())],
+        );
+        let mut script = PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+        assert!(script.output_diff().is_err());
+        script.set_output_diff(true);
+        script
+            .parse_src(String::from(
+                "plot(close)\nif close > 2\n    label.new(1, close)\n    alert('up')",
+            ))
+            .unwrap();
+        let close = |v: Vec<f64>| {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(v.into_iter().map(Some).collect()),
+            )]
+        };
+        let id = DrawingId { shape: 0, bar: 2 };
+        let label_y = |change: &DrawingChange| match change {
+            DrawingChange::Added { data, .. } | DrawingChange::Modified { data, .. } => {
+                serde_json::to_value(data).unwrap()["y"].as_f64()
+            }
+            DrawingChange::Deleted { .. } => None,
+        };
+
+        let output = script
+            .run_with_data(close(vec![1f64, 2f64, 3f64]), None)
+            .unwrap();
+        assert_eq!(
+            output.alerts,
+            vec![AlertEvent::new(2, String::from("up"), String::from("once_per_bar"))]
+        );
+        let diff = script.output_diff().unwrap();
+        assert_eq!((diff.base_revision, diff.revision, diff.to), (0, 1, 3));
+        assert_eq!(diff.outputs[0].from, 0);
+        assert_eq!(diff.drawings.len(), 1);
+        assert!(matches!(diff.drawings[0], DrawingChange::Added { id: i, .. } if i == id));
+        assert_eq!(label_y(&diff.drawings[0]), Some(3f64));
+        assert_eq!(diff.alerts, output.alerts);
+        script.ack_revision(1).unwrap();
+
+        // The alert triggered again by the updated bar is not new.
+        script.update(close(vec![4f64])).unwrap();
+        script.update(close(vec![5f64, 1f64])).unwrap();
+        let diff = script.output_diff().unwrap();
+        assert_eq!((diff.base_revision, diff.revision, diff.to), (1, 3, 4));
+        assert_eq!(
+            diff.outputs,
+            vec![OutputRange {
+                index: 0,
+                from: 2,
+                data: OutputData::new(vec![vec![Some(5f64), Some(1f64)]]),
+            }]
+        );
+        assert!(matches!(diff.drawings[0], DrawingChange::Modified { id: i, .. } if i == id));
+        assert_eq!(label_y(&diff.drawings[0]), Some(5f64));
+        assert_eq!(diff.alerts, vec![]);
+
+        // The label created by the bar that is run again is deleted.
+        script.update_from(close(vec![0f64, 3f64]), 1).unwrap();
+        script.update_from(close(vec![0f64]), 1).unwrap();
+        let diff = script.output_diff().unwrap();
+        assert_eq!((diff.revision, diff.to), (5, 2));
+        assert_eq!(diff.outputs[0].from, 1);
+        assert_eq!(diff.drawings, vec![DrawingChange::Deleted { id }]);
+
+        script.ack_revision(5).unwrap();
+        let diff = script.output_diff().unwrap();
+        assert_eq!((diff.base_revision, diff.outputs.len()), (5, 0));
+        assert_eq!(
+            script.ack_revision(6).unwrap_err().message,
+            "The revision 6 of the outputs is not generated."
+        );
+    }
+
     #[test]
     fn datalen_test() {
         let lib_info = LibInfo::new(vec![input::declare_var(), plot::declare_var()], vec![]);
@@ -916,4 +1861,280 @@ mod tests {
         );
         assert_eq!(parser.datalen, 3);
     }
+
+    #[test]
+    fn runtime_error_test() {
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script
+            .parse_src(String::from(
+                "f(i) => close[i]\ng(i) => f(i) * 2\nidx = bar_index >= 2 ? na : 1\nplot(g(idx))",
+            ))
+            .unwrap();
+        let data = vec![
+            (
+                "close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+            ),
+            (
+                "_time",
+                AnySeries::from_int_vec(vec![Some(100i64), Some(200i64), Some(300i64)]),
+            ),
+        ];
+        let err = script.run_with_data(data, None).unwrap_err();
+        assert_eq!(
+            err.range,
+            StrRange::from_start("close[i]", Position::new(0, 8))
+        );
+        assert_eq!(err.bar_index, Some(2));
+        assert_eq!(err.time, Some(300));
+        assert_eq!(
+            err.call_stack,
+            vec![
+                CallFrame::new(
+                    String::from("g"),
+                    StrRange::from_start("g(idx)", Position::new(3, 5))
+                ),
+                CallFrame::new(
+                    String::from("f"),
+                    StrRange::from_start("f(i)", Position::new(1, 8))
+                ),
+            ]
+        );
+
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["bar_index"], serde_json::json!(2));
+        assert_eq!(json["call_stack"][1]["name"], serde_json::json!("f"));
+    }
+
+    #[test]
+    fn parse_recovery_test() {
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        let errs = script
+            .parse_src(String::from(
+                "m = 1 +\nn = close\nfor i = 0 1\n    plot(n)\nplot(k)\nplot(n)",
+            ))
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| e.range).collect::<Vec<_>>(),
+            vec![
+                StrRange::from_start("m = 1 +", Position::new(0, 0)),
+                StrRange::from_start("for i = 0 1", Position::new(2, 0)),
+                StrRange::from_start("k", Position::new(4, 5)),
+            ]
+        );
+        assert_eq!(errs[0].message, "This statement is invalid.");
+    }
+
+    #[test]
+    fn ast_cache_test() {
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script.set_ast_cache(true);
+        let mut run_src = |src: &str| {
+            script.parse_src(String::from(src)).unwrap();
+            script.trace_vars(&["m"]).unwrap();
+            let data = vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+            )];
+            script.run_with_data(data, None).unwrap().traces
+        };
+        let src = "f(x) => x + 1\nm = f(close) * 2\nplot(m)";
+        assert_eq!(
+            run_src(src),
+            vec![TraceData::new(
+                String::from("m"),
+                vec![
+                    Some(VarValue::Float(Some(4f64))),
+                    Some(VarValue::Float(Some(6f64)))
+                ]
+            )]
+        );
+        assert_eq!(
+            run_src("f(x) => x + 2\nm = f(close) * 2\nplot(m)"),
+            vec![TraceData::new(
+                String::from("m"),
+                vec![
+                    Some(VarValue::Float(Some(6f64))),
+                    Some(VarValue::Float(Some(8f64)))
+                ]
+            )]
+        );
+
+        // The failed parse keeps the last parsed script and the statements it borrows.
+        assert!(script
+            .parse_src(String::from("import user/lib/1 as l\nm = close\nplot(m)"))
+            .is_err());
+        script.set_ast_cache(false);
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+        )];
+        assert_eq!(
+            script.run_with_data(data, None).unwrap().traces[0].values,
+            vec![
+                Some(VarValue::Float(Some(6f64))),
+                Some(VarValue::Float(Some(8f64)))
+            ]
+        );
+        script.set_ast_cache(true);
+
+        let errs = script
+            .parse_src(String::from("f(x) => x + 2\nm = f(close) * k\nplot(m)"))
+            .unwrap_err();
+        assert_eq!(
+            errs[0].range,
+            StrRange::from_start("k", Position::new(1, 15))
+        );
+    }
+
+    #[test]
+    fn version_libs_test() {
+        let gen_data = || {
+            vec![
+                (
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(3f64), Some(5f64)]),
+                ),
+                (
+                    "high",
+                    AnySeries::from_float_vec(vec![Some(2f64), Some(6f64), Some(7f64)]),
+                ),
+                (
+                    "low",
+                    AnySeries::from_float_vec(vec![Some(0f64), Some(2f64), Some(4f64)]),
+                ),
+            ]
+        };
+        let run_src = |src: &str| {
+            let mut script = PineScript::new(Some(&NoneCallback()));
+            script.parse_src(String::from(src))?;
+            let io_info = script.gen_io_info().unwrap();
+            let output = script.run_with_data(gen_data(), None).unwrap();
+            Ok((io_info.get_inputs().len(), output))
+        };
+
+        let v4_res: Result<_, Vec<PineFormatError>> = run_src(
+            "//@version=4\nstudy('hello')\nlen = input(2, 'len', input.integer)\n
+            plot(sma(close, len) + abs(-1) + tr(true) + tr)",
+        );
+        let v5_res = run_src(
+            "//@version=5\nindicator('hello')\nlen = input.int(2, 'len', minval=1)\n
+            plot(ta.sma(close, len) + math.abs(-1) + ta.tr(true) + ta.tr)",
+        );
+        assert!(v4_res.is_ok());
+        assert_eq!(v4_res, v5_res);
+
+        // The v5 builtins are only available for v5 scripts and vice versa.
+        assert!(run_src("//@version=5\nplot(sma(close, 2))").is_err());
+        assert!(run_src("//@version=4\nplot(ta.sma(close, 2))").is_err());
+        assert!(run_src("plot(ta.sma(close, 2))").is_err());
+        assert!(PineScript::new(Some(&NoneCallback()))
+            .parse_src(String::from(
+                "//@version=5\nm = request.security('AAPL', 'D', close)"
+            ))
+            .is_ok());
+
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        assert!(script
+            .parse_src(String::from("plot(sma(close, 2))"))
+            .is_ok());
+        assert!(script
+            .parse_src(String::from("//@version=5\nplot(ta.sma(close, 2))"))
+            .is_ok());
+        assert_eq!(
+            script.run_with_data(gen_data(), None),
+            Ok(OutputDataCollect::new_with_one(
+                0,
+                3,
+                vec![None, Some(2f64), Some(4f64)]
+            ))
+        );
+
+        // A failed parse keeps the builtins and the block of the last parsed script.
+        assert!(script
+            .parse_src(String::from("//@version=4\nplot(ta.sma(close, 2))"))
+            .is_err());
+        assert_eq!(script.lib_info.version(), Some(5));
+        assert_eq!(
+            script.run_with_data(gen_data(), None),
+            Ok(OutputDataCollect::new_with_one(
+                0,
+                3,
+                vec![None, Some(2f64), Some(4f64)]
+            ))
+        );
+
+        // The builtins given by the user must match the script version.
+        let mut script =
+            PineScript::new_with_libinfo(lib_info_for_version(4), Some(&NoneCallback()));
+        let errs = script
+            .parse_src(String::from("// v5\n//@version=5\nplot(ta.sma(close, 2))"))
+            .unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(
+            errs[0].message,
+            "The script of version 5 can't be parsed with the builtins of version 4."
+        );
+        assert_eq!(
+            errs[0].range,
+            StrRange::new(Position::new(1, 0), Position::new(2, 0))
+        );
+        assert!(script
+            .parse_src(String::from("//@version=3\nplot(sma(close, 2))"))
+            .is_ok());
+    }
+
+    #[test]
+    fn security_synthetic_ticker_test() {
+        let syminfo = Rc::new(SymbolInfo {
+            symbol_type: String::from("stock"),
+            timezone: String::from("UTC"),
+            ticker: String::from("MSFT"),
+            session: String::from("regular"),
+            trade_start: String::from(""),
+            trade_end: String::from(""),
+            root: None,
+            currency: String::from("USD"),
+            description: String::from(""),
+            mintick: 0.01,
+        });
+        let gen_data = || {
+            vec![
+                (
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                ),
+                (
+                    "time",
+                    AnySeries::from_int_vec(vec![Some(10i64), Some(20i64)]),
+                ),
+                (
+                    "MSFT-D-_time",
+                    AnySeries::from_int_vec(vec![Some(5i64), Some(15i64)]),
+                ),
+                (
+                    "MSFT-D-close",
+                    AnySeries::from_float_vec(vec![Some(2f64), Some(4f64)]),
+                ),
+            ]
+        };
+        let run_src = |src: &str| {
+            let mut script = PineScript::new(Some(&NoneCallback()));
+            script.parse_src(String::from(src)).unwrap();
+            script
+                .run_with_data(gen_data(), Some(syminfo.clone()))
+                .unwrap()
+        };
+
+        // The synthetic ticker is built by the builtin function and passed to security.
+        let v4_output = run_src("m = security(heikinashi(syminfo.tickerid), 'D', close)\nplot(m)");
+        assert_eq!(
+            v4_output,
+            OutputDataCollect::new_with_one(0, 2, vec![Some(2f64), Some(4f64)])
+        );
+        let v5_output = run_src(
+            "//@version=5\nm = request.security(ticker.heikinashi(syminfo.tickerid), 'D', close)\nplot(m)",
+        );
+        assert_eq!(v4_output, v5_output);
+    }
 }
@@ -0,0 +1,435 @@
+use crate::ast::error::PineErrorKind;
+use crate::ast::stat_expr_types::{
+    Block, Exp, ForRange, FunctionCall, IfThenElse, ImportStmt, Statement,
+};
+use crate::ast::state::PineInputError;
+use crate::parse_ast;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+// The builtin function that declares the script as a library.
+pub const LIBRARY_NAME: &str = "library";
+
+// The path of the library in the import statement `import user/name/version`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LibraryPath<'a> {
+    pub user: &'a str,
+    pub name: &'a str,
+    pub version: i64,
+}
+
+impl<'a> LibraryPath<'a> {
+    pub fn new(user: &'a str, name: &'a str, version: i64) -> LibraryPath<'a> {
+        LibraryPath {
+            user,
+            name,
+            version,
+        }
+    }
+}
+
+// Find the source code of the imported library.
+pub trait LibraryResolver {
+    fn resolve(&self, path: &LibraryPath) -> Option<String>;
+}
+
+// The resolver that reads the library from the file `root/user/name/version.pine`.
+#[derive(Debug, Clone)]
+pub struct FsLibraryResolver {
+    root: PathBuf,
+}
+
+impl FsLibraryResolver {
+    pub fn new<P: Into<PathBuf>>(root: P) -> FsLibraryResolver {
+        FsLibraryResolver { root: root.into() }
+    }
+
+    pub fn library_file(&self, path: &LibraryPath) -> PathBuf {
+        self.root
+            .join(path.user)
+            .join(path.name)
+            .join(format!("{}.pine", path.version))
+    }
+}
+
+impl LibraryResolver for FsLibraryResolver {
+    fn resolve(&self, path: &LibraryPath) -> Option<String> {
+        fs::read_to_string(self.library_file(path)).ok()
+    }
+}
+
+// The resolver that keeps the library sources in memory, the key is `user/name/version`.
+impl LibraryResolver for HashMap<String, String> {
+    fn resolve(&self, path: &LibraryPath) -> Option<String> {
+        let key = format!("{}/{}/{}", path.user, path.name, path.version);
+        self.get(&key).cloned()
+    }
+}
+
+// The storage of the library sources and the linked names. The linked function
+// definitions borrow the strings, so the arena must live as long as the block.
+#[derive(Debug, Default)]
+pub struct LibraryArena {
+    strs: RefCell<Vec<Box<str>>>,
+}
+
+impl LibraryArena {
+    pub fn new() -> LibraryArena {
+        LibraryArena {
+            strs: RefCell::new(vec![]),
+        }
+    }
+
+    pub fn alloc(&self, s: String) -> &str {
+        let s = s.into_boxed_str();
+        // The boxed string is never moved or dropped until the arena is dropped.
+        let s_ref = unsafe { &*(s.as_ref() as *const str) };
+        self.strs.borrow_mut().push(s);
+        s_ref
+    }
+}
+
+// The resolver and the arena used to link the imported libraries.
+#[derive(Clone, Copy)]
+pub struct LibraryLinker<'a, 'b> {
+    resolver: &'b dyn LibraryResolver,
+    arena: &'a LibraryArena,
+}
+
+impl<'a, 'b> LibraryLinker<'a, 'b> {
+    pub fn new(
+        resolver: &'b dyn LibraryResolver,
+        arena: &'a LibraryArena,
+    ) -> LibraryLinker<'a, 'b> {
+        LibraryLinker { resolver, arena }
+    }
+
+    // Link all the function definitions of the library to the import statement.
+    // The functions are renamed to `alias.name` so they can't conflict with the
+    // variables of the importing script.
+    fn link(&self, import: &mut ImportStmt<'a>) -> Result<(), PineInputError> {
+        let path = LibraryPath::new(import.user, import.name, import.version);
+        let src = match self.resolver.resolve(&path) {
+            Some(src) => self.arena.alloc(src),
+            None => {
+                return Err(PineInputError::new(
+                    PineErrorKind::LibraryNotResolved,
+                    import.range,
+                ))
+            }
+        };
+        let invalid_err = PineInputError::new(PineErrorKind::LibraryInvalid, import.range);
+        let blk = parse_ast(src).map_err(|_| invalid_err.clone())?;
+        if !is_library(&blk) {
+            return Err(invalid_err);
+        }
+
+        let mut defs = vec![];
+        for stmt in blk.stmts {
+            match stmt {
+                Statement::FuncDef(def) => defs.push(*def),
+                // The library can't import other libraries.
+                Statement::Import(_) => return Err(invalid_err),
+                _ => {}
+            }
+        }
+
+        let alias = import.alias.value;
+        let names: HashMap<&'a str, &'a str> = defs
+            .iter()
+            .map(|d| {
+                let linked_name = self.arena.alloc(format!("{}.{}", alias, d.name.value));
+                (d.name.value, linked_name)
+            })
+            .collect();
+        for def in defs.iter_mut() {
+            rename_block(&mut def.body, &names);
+            if def.export {
+                import.exports.push((def.name.value, names[def.name.value]));
+            }
+            def.name.value = names[def.name.value];
+        }
+        import.defs = defs;
+        Ok(())
+    }
+}
+
+// Check if the script declares itself as a library by the `library` function call.
+fn is_library(blk: &Block) -> bool {
+    let is_library_call = |func_call: &FunctionCall| match &func_call.method {
+        Exp::VarName(name) => name.name.value == LIBRARY_NAME,
+        _ => false,
+    };
+    blk.stmts.iter().any(|stmt| match stmt {
+        Statement::FuncCall(func_call) => is_library_call(func_call),
+        Statement::Exp(Exp::FuncCall(func_call)) => is_library_call(func_call),
+        _ => false,
+    })
+}
+
+// Link the import statements of the global scope. All the import statements are
+// unresolved if there is no linker.
+pub fn link_imports<'a>(
+    blk: &mut Block<'a>,
+    linker: Option<&LibraryLinker<'a, '_>>,
+) -> Vec<PineInputError> {
+    let mut errs = vec![];
+    for stmt in blk.stmts.iter_mut() {
+        if let Statement::Import(import) = stmt {
+            let res = match linker {
+                Some(linker) => linker.link(import),
+                None => Err(PineInputError::new(
+                    PineErrorKind::LibraryNotResolved,
+                    import.range,
+                )),
+            };
+            if let Err(err) = res {
+                errs.push(err);
+            }
+        }
+    }
+    errs
+}
+
+// Rename the calls of the sibling functions in the library function bodies.
+fn rename_block<'a>(blk: &mut Block<'a>, names: &HashMap<&'a str, &'a str>) {
+    for stmt in blk.stmts.iter_mut() {
+        match stmt {
+            Statement::Assignment(assign) => rename_exp(&mut assign.val, names),
+            Statement::VarAssignment(assign) => rename_exp(&mut assign.val, names),
+            Statement::Ite(ite) => rename_ite(ite, names),
+            Statement::ForRange(for_range) => rename_for_range(for_range, names),
+            Statement::FuncCall(func_call) => rename_func_call(func_call, names),
+            Statement::FuncDef(func_def) => rename_block(&mut func_def.body, names),
+            Statement::Exp(exp) => rename_exp(exp, names),
+            Statement::Import(_)
+            | Statement::TypeDef(_)
+            | Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::None(_)
+            | Statement::Error(_) => {}
+        }
+    }
+    if let Some(exp) = &mut blk.ret_stmt {
+        rename_exp(exp, names);
+    }
+}
+
+fn rename_exp<'a>(exp: &mut Exp<'a>, names: &HashMap<&'a str, &'a str>) {
+    match exp {
+        Exp::Tuple(tuple) => tuple.exps.iter_mut().for_each(|e| rename_exp(e, names)),
+        Exp::TypeCast(type_cast) => rename_exp(&mut type_cast.exp, names),
+        Exp::FuncCall(func_call) => rename_func_call(func_call, names),
+        Exp::RefCall(ref_call) => {
+            rename_exp(&mut ref_call.name, names);
+            rename_exp(&mut ref_call.arg, names);
+        }
+        Exp::PrefixExp(prefix_exp) => rename_exp(&mut prefix_exp.left_exp, names),
+        Exp::Condition(cond) => {
+            rename_exp(&mut cond.cond, names);
+            rename_exp(&mut cond.exp1, names);
+            rename_exp(&mut cond.exp2, names);
+        }
+        Exp::Ite(ite) => rename_ite(ite, names),
+        Exp::ForRange(for_range) => rename_for_range(for_range, names),
+        Exp::Assignment(assign) => rename_exp(&mut assign.val, names),
+        Exp::VarAssignment(assign) => rename_exp(&mut assign.val, names),
+        Exp::UnaryExp(unary_exp) => rename_exp(&mut unary_exp.exp, names),
+        Exp::BinaryExp(binary_exp) => {
+            rename_exp(&mut binary_exp.exp1, names);
+            rename_exp(&mut binary_exp.exp2, names);
+        }
+        _ => {}
+    }
+}
+
+// Only the function call methods are renamed because the function can't be used as value.
+fn rename_func_call<'a>(func_call: &mut FunctionCall<'a>, names: &HashMap<&'a str, &'a str>) {
+    match &mut func_call.method {
+        Exp::VarName(name) => {
+            if let Some(linked_name) = names.get(name.name.value) {
+                name.name.value = linked_name;
+            }
+        }
+        method => rename_exp(method, names),
+    }
+    func_call
+        .pos_args
+        .iter_mut()
+        .for_each(|e| rename_exp(e, names));
+    func_call
+        .dict_args
+        .iter_mut()
+        .for_each(|(_, e)| rename_exp(e, names));
+}
+
+fn rename_ite<'a>(ite: &mut IfThenElse<'a>, names: &HashMap<&'a str, &'a str>) {
+    rename_exp(&mut ite.cond, names);
+    rename_block(&mut ite.then_blk, names);
+    if let Some(blk) = &mut ite.else_blk {
+        rename_block(blk, names);
+    }
+}
+
+fn rename_for_range<'a>(for_range: &mut ForRange<'a>, names: &HashMap<&'a str, &'a str>) {
+    rename_exp(&mut for_range.start, names);
+    rename_exp(&mut for_range.end, names);
+    if let Some(step) = &mut for_range.step {
+        rename_exp(step, names);
+    }
+    rename_block(&mut for_range.do_blk, names);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback, OutputData};
+    use crate::types::{PineRef, Series};
+    use crate::{lib_info_for_version, PineParser, PineRunner, PineScript};
+
+    const LIB_SRC: &str = "//@version=5
+library('mylib')
+square(x) => x * x
+export double(x) => square(x) * 2
+";
+
+    fn gen_resolver() -> HashMap<String, String> {
+        let mut resolver = HashMap::new();
+        resolver.insert(String::from("user/mylib/1"), String::from(LIB_SRC));
+        resolver
+    }
+
+    fn parse_errs(src: &str, resolver: Option<&dyn LibraryResolver>) -> Vec<PineErrorKind> {
+        let arena = LibraryArena::new();
+        let lib_info = lib_info_for_version(5);
+        let mut parser = PineParser::new(src, &lib_info);
+        if let Some(resolver) = resolver {
+            parser.set_library_resolver(resolver, &arena);
+        }
+        match parser.parse_blk() {
+            Ok(_) => vec![],
+            Err(errs) => errs.into_iter().map(|e| e.code).collect(),
+        }
+    }
+
+    #[test]
+    fn import_library_test() {
+        let arena = LibraryArena::new();
+        let lib_info = lib_info_for_version(5);
+        let resolver = gen_resolver();
+        // The private function of library can't conflict with the function of script.
+        let src = "//@version=5
+import user/mylib/1 as m
+square(x) => x + 1
+m1 = m.double(close)
+m2 = square(close)
+";
+        let mut parser = PineParser::new(src, &lib_info);
+        parser.set_library_resolver(&resolver, &arena);
+        let blk = parser.parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                Some(2f64),
+                Some(8f64),
+                Some(18f64)
+            ])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(4, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                Some(2f64),
+                Some(3f64),
+                Some(4f64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn import_error_test() {
+        let resolver = gen_resolver();
+        assert_eq!(
+            parse_errs(
+                "import user/mylib/1 as m\nm1 = m.square(close)",
+                Some(&resolver)
+            ),
+            vec![PineErrorKind::LibFuncNotExported]
+        );
+        assert_eq!(
+            parse_errs(
+                "import user/mylib/2 as m\nm1 = m.double(close)",
+                Some(&resolver)
+            ),
+            vec![PineErrorKind::LibraryNotResolved]
+        );
+        assert_eq!(
+            parse_errs("import user/mylib/1 as m\nm1 = m.double(close)", None),
+            vec![PineErrorKind::LibraryNotResolved]
+        );
+        assert_eq!(
+            parse_errs("if true\n    import user/mylib/1 as m\n", Some(&resolver)),
+            vec![PineErrorKind::ImportNotInGlobal]
+        );
+
+        let mut resolver = HashMap::new();
+        resolver.insert(
+            String::from("user/mylib/1"),
+            String::from("export double(x) => x * 2"),
+        );
+        assert_eq!(
+            parse_errs(
+                "import user/mylib/1 as m\nm1 = m.double(close)",
+                Some(&resolver)
+            ),
+            vec![PineErrorKind::LibraryInvalid]
+        );
+    }
+
+    #[test]
+    fn fs_resolver_test() {
+        let root = std::env::temp_dir().join(format!("pine-libs-{}", std::process::id()));
+        let resolver = FsLibraryResolver::new(&root);
+        let path = LibraryPath::new("user", "mylib", 1);
+        assert_eq!(resolver.resolve(&path), None);
+
+        let file = resolver.library_file(&path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, LIB_SRC).unwrap();
+        assert_eq!(resolver.resolve(&path), Some(String::from(LIB_SRC)));
+
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script.set_library_resolver(Box::new(resolver));
+        script
+            .parse_src(String::from(
+                "//@version=5\nimport user/mylib/1\nplot(mylib.double(close))",
+            ))
+            .unwrap();
+        let output = script
+            .run_with_data(
+                vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            output.data_list,
+            vec![Some(OutputData::new(vec![vec![Some(2f64), Some(8f64)]]))]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -4,7 +4,7 @@ use crate::helper::{pine_ref_to_f64, pine_ref_to_i64};
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::types::{Callable, Float, Int, PineFrom, PineRef, RuntimeErr, Series, NA};
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn int_abs<'a>(xval: Option<PineRef<'a>>) -> Int {
     match pine_ref_to_i64(xval) {
@@ -65,7 +65,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             SyntaxType::float_series(),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
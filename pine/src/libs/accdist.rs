@@ -3,6 +3,7 @@ use crate::ast::stat_expr_types::VarIndex;
 use crate::ast::syntax_type::SyntaxType;
 use crate::helper::{ensure_srcs, float_add};
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_float, load_floats, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Evaluate, EvaluateFactory, EvaluateVal, Float, Int,
@@ -92,6 +93,20 @@ impl<'a> EvaluateVal<'a> for AccDistVal {
     //     Ok(())
     // }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            StateValue::Floats(self.ad_history.clone()),
+            StateValue::Float(self.prev_cmfv),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.ad_history = load_floats(state.next())?;
+        self.prev_cmfv = load_float(state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
         Box::new(self.clone())
     }
@@ -0,0 +1,187 @@
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::{move_element, pine_ref_to_string, str_replace};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::output::AlertEvent;
+use crate::runtime::snapshot::{load_int, state_err, StateValue};
+use crate::types::{Callable, CallableObject, PineClass, PineRef, RuntimeErr, SeriesCall, NA};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+const FREQ_ALL: &str = "all";
+const FREQ_ONCE_PER_BAR: &str = "once_per_bar";
+const FREQ_ONCE_PER_BAR_CLOSE: &str = "once_per_bar_close";
+const FREQS: [&str; 3] = [FREQ_ALL, FREQ_ONCE_PER_BAR, FREQ_ONCE_PER_BAR_CLOSE];
+
+struct AlertProps;
+
+impl<'a> PineClass<'a> for AlertProps {
+    fn custom_type(&self) -> &str {
+        "alert"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "freq_all" => Ok(PineRef::new(String::from(FREQ_ALL))),
+            "freq_once_per_bar" => Ok(PineRef::new(String::from(FREQ_ONCE_PER_BAR))),
+            "freq_once_per_bar_close" => Ok(PineRef::new(String::from(FREQ_ONCE_PER_BAR_CLOSE))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("alert")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(AlertProps)
+    }
+}
+
+// Every alert call records the events, the calls except freq_all only trigger once on
+// every bar. The bar that is run again after rolling back can trigger the alert again.
+#[derive(Debug, Clone)]
+struct AlertCall {
+    // The bar and the roll back count when the alert is triggered.
+    triggered: Option<(i32, u32)>,
+}
+
+impl<'a> SeriesCall<'a> for AlertCall {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        move_tuplet!((message, freq) = param);
+        let freq = match pine_ref_to_string(freq) {
+            None => String::from(FREQ_ONCE_PER_BAR),
+            Some(s) if FREQS.contains(&s.as_str()) => s,
+            Some(_) => {
+                return Err(RuntimeErr::InvalidParameters(str_replace(
+                    INVALID_VALS,
+                    vec![String::from("freq")],
+                )))
+            }
+        };
+        let bar_index = context.get_iterindex();
+        let triggered = Some((bar_index, downcast_ctx(context).get_rollback_count()));
+        if freq != FREQ_ALL && self.triggered == triggered {
+            return Ok(PineRef::new(NA));
+        }
+        if let Some(message) = pine_ref_to_string(message) {
+            self.triggered = triggered;
+            downcast_ctx(context).push_alert(AlertEvent::new(bar_index, message, freq));
+        }
+        Ok(PineRef::new(NA))
+    }
+
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        let state = match self.triggered {
+            Some((bar_index, rollback_count)) => vec![
+                StateValue::Int(Some(bar_index as i64)),
+                StateValue::Int(Some(rollback_count as i64)),
+            ],
+            None => vec![],
+        };
+        Some(state)
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.triggered = match state.next() {
+            None => None,
+            bar_index => {
+                let bar_index = load_int(bar_index)?.ok_or_else(state_err)?;
+                let rollback_count = load_int(state.next())?.ok_or_else(state_err)?;
+                Some((bar_index as i32, rollback_count as u32))
+            }
+        };
+        Ok(())
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+pub const VAR_NAME: &str = "alert";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableObject::new(Box::new(AlertProps), || {
+        Callable::new(None, Some(Box::new(AlertCall { triggered: None })))
+    }));
+
+    let func_type = FunctionTypes(vec![FunctionType::new((
+        vec![
+            ("message", SyntaxType::string_series()),
+            ("freq", SyntaxType::string()),
+        ],
+        SyntaxType::Void,
+    ))]);
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("freq_all", SyntaxType::string());
+    obj_type.insert("freq_once_per_bar", SyntaxType::string());
+    obj_type.insert("freq_once_per_bar_close", SyntaxType::string());
+    let syntax_type = SyntaxType::ObjectFunction(Arc::new(obj_type), Arc::new(func_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{downcast_ctx, AnySeries, NoneCallback};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn alert_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "if close > 1\n    alert('up')\n\
+                   for i = 1 to 2\n    alert('loop')\n    alert('all', alert.freq_all)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = |v: Vec<f64>| {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(v.into_iter().map(Some).collect()),
+            )]
+        };
+        runner.run(&data(vec![1f64, 2f64]), None).unwrap();
+
+        let event = |bar: i32, msg: &str, freq: &str| {
+            AlertEvent::new(bar, String::from(msg), String::from(freq))
+        };
+        assert_eq!(
+            downcast_ctx(runner.get_context()).move_alerts(),
+            vec![
+                event(0, "loop", FREQ_ONCE_PER_BAR),
+                event(0, "all", FREQ_ALL),
+                event(0, "all", FREQ_ALL),
+                event(1, "up", FREQ_ONCE_PER_BAR),
+                event(1, "loop", FREQ_ONCE_PER_BAR),
+                event(1, "all", FREQ_ALL),
+                event(1, "all", FREQ_ALL),
+            ]
+        );
+
+        // The last bar triggers the alerts again when it is updated.
+        runner.update(&data(vec![3f64])).unwrap();
+        assert_eq!(
+            downcast_ctx(runner.get_context()).move_alerts(),
+            vec![
+                event(1, "up", FREQ_ONCE_PER_BAR),
+                event(1, "loop", FREQ_ONCE_PER_BAR),
+                event(1, "all", FREQ_ALL),
+                event(1, "all", FREQ_ALL),
+            ]
+        );
+
+        let src = "alert('a', 'never')";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert!(runner.run(&data(vec![1f64]), None).is_err());
+    }
+}
@@ -13,7 +13,7 @@ use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Evaluate, EvaluateVal,
     Float, Int, ParamCollectCall, PineRef, RuntimeErr, Series, SeriesCall, NA,
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct AlmaVal;
@@ -68,6 +68,8 @@ impl<'a> SeriesCall<'a> for AlmaVal {
         Ok(PineRef::new(Series::from(Some(sum / norm))))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -94,7 +96,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
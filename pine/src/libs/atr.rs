@@ -9,13 +9,14 @@ use crate::helper::{
     pine_ref_to_f64_series, pine_ref_to_i64, require_param, series_index,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_float, load_floats, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Float, Int, PineRef,
     RefData, RuntimeErr, Series, SeriesCall,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn true_range(
     close: &Option<RefData<Series<Float>>>,
@@ -88,6 +89,20 @@ impl<'a> SeriesCall<'a> for AtrVal {
         Ok(())
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            StateValue::Floats(self.val_history.clone()),
+            StateValue::Float(self.prev_val),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.val_history = load_floats(state.next())?;
+        self.prev_val = load_float(state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -103,7 +118,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         vec![("length", SyntaxType::int())],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -8,7 +8,7 @@ use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::types::{Callable, Float, Int, PineFrom, PineRef, RuntimeErr, Series, SeriesCall, NA};
 use std::cmp;
 use std::mem::transmute;
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn float_avg<'a>(vals: Vec<Option<PineRef<'a>>>) -> Float {
     let iter = vals.into_iter().filter_map(|v| pine_ref_to_f64(v));
@@ -47,6 +47,8 @@ impl<'a> SeriesCall<'a> for AvgCallVal {
         }
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -87,7 +89,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             SyntaxType::float_series(),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "avg")
 }
 
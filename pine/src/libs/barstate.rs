@@ -17,7 +17,7 @@ use crate::types::{
 use chrono_tz::Tz;
 use std::cell::{Cell, RefCell};
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, PartialEq, Clone)]
 struct BarStateProps {
@@ -35,15 +35,16 @@ impl BarStateProps {
         }
     }
 
-    fn get_varindex<'a>(&self, ctx: &mut dyn Ctx<'a>) -> i64 {
+    // The bar index is na if the fields are got before running, like extracting the IOInfo.
+    fn get_varindex<'a>(&self, ctx: &mut dyn Ctx<'a>) -> Option<i64> {
         let barindex = ctx.get_var(self.barindex_index.get()).clone();
-        pine_ref_to_i64(barindex).unwrap()
+        pine_ref_to_i64(barindex)
     }
 
     fn is_last<'a>(&self, ctx: &mut dyn Ctx<'a>) -> bool {
         let index = self.get_varindex(ctx);
         let (_, end) = downcast_ctx(ctx.get_main_ctx()).get_data_range();
-        index == (end.unwrap() - 1) as i64
+        index == Some((end.unwrap() - 1) as i64)
     }
 
     fn is_in_trade<'a>(&self, ctx: &mut dyn Ctx<'a>) -> bool {
@@ -86,7 +87,7 @@ impl<'a> PineClass<'a> for BarStateProps {
         match name {
             "isfirst" => {
                 let index = self.get_varindex(_ctx);
-                if index == 0 {
+                if index == Some(0) {
                     Ok(PineRef::new_rc(Series::from(true)))
                 } else {
                     Ok(PineRef::new_rc(Series::from(false)))
@@ -116,7 +117,7 @@ impl<'a> PineClass<'a> for BarStateProps {
                 }
             }
             "isnew" => {
-                let index = self.get_varindex(_ctx) as i32;
+                let index = self.get_varindex(_ctx).unwrap_or(-1) as i32;
                 let appear_count = self
                     .data_ranges
                     .borrow()
@@ -130,7 +131,7 @@ impl<'a> PineClass<'a> for BarStateProps {
                 }
             }
             "isconfirmed" => {
-                let index = self.get_varindex(_ctx) as i32;
+                let index = self.get_varindex(_ctx).unwrap_or(-1) as i32;
                 let appear_count = self
                     .data_ranges
                     .borrow()
@@ -169,7 +170,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("isrealtime", SyntaxType::bool());
     obj_type.insert("isnew", SyntaxType::bool());
     obj_type.insert("isconfirmed", SyntaxType::bool());
-    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    let syntax_type = SyntaxType::Object(Arc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -181,6 +182,7 @@ mod tests {
     use crate::{LibInfo, PineParser, PineRunner};
     use chrono::offset::TimeZone;
     use std::mem;
+    use std::rc::Rc;
 
     fn gen_ts(h: u32, m: u32) -> i64 {
         Tz::Asia__Shanghai
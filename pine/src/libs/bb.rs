@@ -14,7 +14,7 @@ use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Evaluate, EvaluateVal,
     Float, Int, ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall, Tuple, NA,
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct BbVal;
@@ -51,6 +51,8 @@ impl<'a> SeriesCall<'a> for BbVal {
         ])))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -70,13 +72,13 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             ("length", SyntaxType::int()),
             ("mult", SyntaxType::float()),
         ],
-        SyntaxType::Tuple(Rc::new(vec![
+        SyntaxType::Tuple(Arc::new(vec![
             SyntaxType::float_series(),
             SyntaxType::float_series(),
             SyntaxType::float_series(),
         ])),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "bb")
 }
 
@@ -10,6 +10,7 @@ use crate::helper::{
     pine_ref_to_i64, require_param, series_index,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_floats, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Float, Int,
@@ -17,7 +18,7 @@ use crate::types::{
 };
 use std::cmp;
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct CciVal {
@@ -69,6 +70,20 @@ impl<'a> SeriesCall<'a> for CciVal {
         Ok(())
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            StateValue::Floats(self.ma_history.clone()),
+            StateValue::Floats(self.tp_history.clone()),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.ma_history = load_floats(state.next())?;
+        self.tp_history = load_floats(state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -92,7 +107,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -12,7 +12,7 @@ use crate::types::{
     RuntimeErr, Series, NA,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub fn series_change(series: &Series<Option<f64>>, length: usize) -> Float {
     series_index2(series, 0).minus(series_index2(series, length))
@@ -49,7 +49,7 @@ fn declare_var<'a>(name: &'static str) -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, name)
 }
 
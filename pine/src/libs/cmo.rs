@@ -16,7 +16,6 @@ use crate::types::{
 };
 use std::f64;
 use std::mem;
-use std::rc::Rc;
 
 fn cmo_func<'a>(source: RefData<Series<Float>>, length: i64) -> Result<Float, RuntimeErr> {
     let mut sm1 = 0f64;
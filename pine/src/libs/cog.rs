@@ -10,13 +10,14 @@ use crate::types::{
     RefData, RuntimeErr, Series, SeriesCall, NA,
 };
 
+// -sum(source[i] * (i + 1)) / sum(source) for i = 0 to length - 1
 fn cog_func<'a>(source: RefData<Series<Float>>, length: i64) -> Result<Float, RuntimeErr> {
     let mut sum_val1 = Some(0f64);
     let mut sum_val2 = Some(0f64);
     for i in 0..length {
         let val = source.index_value(i as usize).unwrap();
         sum_val1 = sum_val1.add(val);
-        sum_val2 = sum_val2.add(val.mul(Some((length - i) as f64)))
+        sum_val2 = sum_val2.add(val.mul(Some((i + 1) as f64)))
     }
     match sum_val2.div(sum_val1) {
         Some(val) => Ok(Some(-val)),
@@ -53,7 +54,7 @@ mod tests {
             .run(
                 &vec![(
                     "close",
-                    AnySeries::from_float_vec(vec![Some(12f64), Some(12f64)]),
+                    AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
                 )],
                 None,
             )
@@ -61,7 +62,10 @@ mod tests {
 
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(0, 0)),
-            Some(PineRef::new(Series::from_vec(vec![None, Some(-1.5f64),])))
+            Some(PineRef::new(Series::from_vec(vec![
+                None,
+                Some(-40f64 / 30f64)
+            ])))
         );
     }
 }
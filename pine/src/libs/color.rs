@@ -12,8 +12,9 @@ use crate::types::{
     Bool, Callable, Color, DataType, Float, Int, Object, ParamCollectCall, PineClass, PineFrom,
     PineRef, PineType, RefData, RuntimeErr, SecondType, Series, NA,
 };
+use std::borrow::Cow;
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 struct ColorProps;
 
@@ -24,23 +25,23 @@ impl<'a> PineClass<'a> for ColorProps {
 
     fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
         match name {
-            "aqua" => Ok(PineRef::new_box(Color("#00BCD4"))),
-            "black" => Ok(PineRef::new_box(Color("#363A45"))),
-            "blue" => Ok(PineRef::new_box(Color("#2196F3"))),
-            "fuchsia" => Ok(PineRef::new_box(Color("#E040FB"))),
-            "gray" => Ok(PineRef::new_box(Color("#787B86"))),
-            "green" => Ok(PineRef::new_box(Color("#4CAF50"))),
-            "lime" => Ok(PineRef::new_box(Color("#00E676"))),
-            "maroon" => Ok(PineRef::new_box(Color("#880E4F"))),
-            "navy" => Ok(PineRef::new_box(Color("#311B92"))),
-            "olive" => Ok(PineRef::new_box(Color("#808000"))),
-            "orange" => Ok(PineRef::new_box(Color("#FF9800"))),
-            "purple" => Ok(PineRef::new_box(Color("#9C27B0"))),
-            "red" => Ok(PineRef::new_box(Color("#FF5252"))),
-            "silver" => Ok(PineRef::new_box(Color("#B2B5BE"))),
-            "teal" => Ok(PineRef::new_box(Color("#00897B"))),
-            "white" => Ok(PineRef::new_box(Color("#FFFFFF"))),
-            "yellow" => Ok(PineRef::new_box(Color("#FFEB3B"))),
+            "aqua" => Ok(PineRef::new_box(Color(Cow::Borrowed("#00BCD4")))),
+            "black" => Ok(PineRef::new_box(Color(Cow::Borrowed("#363A45")))),
+            "blue" => Ok(PineRef::new_box(Color(Cow::Borrowed("#2196F3")))),
+            "fuchsia" => Ok(PineRef::new_box(Color(Cow::Borrowed("#E040FB")))),
+            "gray" => Ok(PineRef::new_box(Color(Cow::Borrowed("#787B86")))),
+            "green" => Ok(PineRef::new_box(Color(Cow::Borrowed("#4CAF50")))),
+            "lime" => Ok(PineRef::new_box(Color(Cow::Borrowed("#00E676")))),
+            "maroon" => Ok(PineRef::new_box(Color(Cow::Borrowed("#880E4F")))),
+            "navy" => Ok(PineRef::new_box(Color(Cow::Borrowed("#311B92")))),
+            "olive" => Ok(PineRef::new_box(Color(Cow::Borrowed("#808000")))),
+            "orange" => Ok(PineRef::new_box(Color(Cow::Borrowed("#FF9800")))),
+            "purple" => Ok(PineRef::new_box(Color(Cow::Borrowed("#9C27B0")))),
+            "red" => Ok(PineRef::new_box(Color(Cow::Borrowed("#FF5252")))),
+            "silver" => Ok(PineRef::new_box(Color(Cow::Borrowed("#B2B5BE")))),
+            "teal" => Ok(PineRef::new_box(Color(Cow::Borrowed("#00897B")))),
+            "white" => Ok(PineRef::new_box(Color(Cow::Borrowed("#FFFFFF")))),
+            "yellow" => Ok(PineRef::new_box(Color(Cow::Borrowed("#FFEB3B")))),
             _ => Err(RuntimeErr::NotImplement(str_replace(
                 NO_FIELD_IN_OBJECT,
                 vec![String::from(name), String::from("color")],
@@ -76,7 +77,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("teal", SyntaxType::color());
     obj_type.insert("white", SyntaxType::color());
     obj_type.insert("yellow", SyntaxType::color());
-    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    let syntax_type = SyntaxType::Object(Arc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -117,23 +118,23 @@ mod tests {
         assert_eq!(
             tuple_vec,
             vec![
-                PineRef::new_box(Color("#00BCD4")),
-                PineRef::new_box(Color("#363A45")),
-                PineRef::new_box(Color("#2196F3")),
-                PineRef::new_box(Color("#E040FB")),
-                PineRef::new_box(Color("#787B86")),
-                PineRef::new_box(Color("#4CAF50")),
-                PineRef::new_box(Color("#00E676")),
-                PineRef::new_box(Color("#880E4F")),
-                PineRef::new_box(Color("#311B92")),
-                PineRef::new_box(Color("#808000")),
-                PineRef::new_box(Color("#FF9800")),
-                PineRef::new_box(Color("#9C27B0")),
-                PineRef::new_box(Color("#FF5252")),
-                PineRef::new_box(Color("#B2B5BE")),
-                PineRef::new_box(Color("#00897B")),
-                PineRef::new_box(Color("#FFFFFF")),
-                PineRef::new_box(Color("#FFEB3B")),
+                PineRef::new_box(Color(Cow::Borrowed("#00BCD4"))),
+                PineRef::new_box(Color(Cow::Borrowed("#363A45"))),
+                PineRef::new_box(Color(Cow::Borrowed("#2196F3"))),
+                PineRef::new_box(Color(Cow::Borrowed("#E040FB"))),
+                PineRef::new_box(Color(Cow::Borrowed("#787B86"))),
+                PineRef::new_box(Color(Cow::Borrowed("#4CAF50"))),
+                PineRef::new_box(Color(Cow::Borrowed("#00E676"))),
+                PineRef::new_box(Color(Cow::Borrowed("#880E4F"))),
+                PineRef::new_box(Color(Cow::Borrowed("#311B92"))),
+                PineRef::new_box(Color(Cow::Borrowed("#808000"))),
+                PineRef::new_box(Color(Cow::Borrowed("#FF9800"))),
+                PineRef::new_box(Color(Cow::Borrowed("#9C27B0"))),
+                PineRef::new_box(Color(Cow::Borrowed("#FF5252"))),
+                PineRef::new_box(Color(Cow::Borrowed("#B2B5BE"))),
+                PineRef::new_box(Color(Cow::Borrowed("#00897B"))),
+                PineRef::new_box(Color(Cow::Borrowed("#FFFFFF"))),
+                PineRef::new_box(Color(Cow::Borrowed("#FFEB3B"))),
             ]
         );
     }
@@ -9,6 +9,7 @@ use crate::helper::{
     pine_ref_to_i64, require_param, series_mul,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_series, save_series, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableCreator, CallableFactory, Evaluate,
@@ -16,7 +17,7 @@ use crate::types::{
     NA,
 };
 use std::f64;
-use std::rc::Rc;
+use std::sync::Arc;
 
 // conv = sma(close * open, 2) - sma(close, 2) * sma(open, 2)
 // cor = conv / (stdev(close, 2) * stdev(open, 2))
@@ -64,6 +65,16 @@ impl<'a> SeriesCall<'a> for CorrelationVal<'a> {
         Ok(PineRef::new(Series::from(cor_val)))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![save_series(&self.abmul)])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        load_series(&mut self.abmul, state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -87,7 +98,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "correlation")
 }
 
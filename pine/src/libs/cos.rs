@@ -5,7 +5,7 @@ use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::types::{Callable, Float, Int, PineFrom, PineRef, RuntimeErr, Series, SeriesCall, NA};
 use std::mem;
 use std::mem::transmute;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct MathCallVal {
@@ -41,6 +41,8 @@ impl<'a> SeriesCall<'a> for MathCallVal {
         }
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -63,7 +65,7 @@ pub fn declare_math_var<'a>(
             SyntaxType::float_series(),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, varname)
 }
 
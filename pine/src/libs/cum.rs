@@ -6,12 +6,13 @@ use crate::helper::{
     require_param, series_index,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_float, StateValue};
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Evaluate, EvaluateVal,
     Float, Int, PineRef, RefData, RuntimeErr, Series, SeriesCall, NA,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct CumVal {
@@ -42,6 +43,16 @@ impl<'a> SeriesCall<'a> for CumVal {
         Ok(PineRef::new(Series::from(self.prev_sum)))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![StateValue::Float(self.prev_sum)])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.prev_sum = load_float(state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -56,7 +67,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         vec![("x", SyntaxType::float_series())],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "cum")
 }
 
@@ -10,13 +10,14 @@ use crate::libs::change::series_change;
 use crate::libs::ema::series_rma;
 use crate::libs::tr::series_tr;
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_series, save_series, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Comparator, Float, Int,
     Negative, PineRef, RefData, RuntimeErr, Series, SeriesCall, Tuple,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct DirmovProps<'a> {
@@ -165,6 +166,24 @@ impl<'a> SeriesCall<'a> for DmiVal<'a> {
         Ok(())
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            save_series(&self.dirmov_props.trs),
+            save_series(&self.dirmov_props.dm1s),
+            save_series(&self.dirmov_props.dm2s),
+            save_series(&self.adxs),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        load_series(&mut self.dirmov_props.trs, state.next())?;
+        load_series(&mut self.dirmov_props.dm1s, state.next())?;
+        load_series(&mut self.dirmov_props.dm2s, state.next())?;
+        load_series(&mut self.adxs, state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -181,13 +200,13 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             ("diLength", SyntaxType::int()),
             ("adxSmoothing", SyntaxType::int()),
         ],
-        SyntaxType::Tuple(Rc::new(vec![
+        SyntaxType::Tuple(Arc::new(vec![
             SyntaxType::float_series(),
             SyntaxType::float_series(),
             SyntaxType::float_series(),
         ])),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
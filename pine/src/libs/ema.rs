@@ -8,13 +8,14 @@ use crate::helper::{
     pine_ref_to_i64,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_float, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Evaluate, EvaluateVal,
     Float, Int, PineRef, RefData, RuntimeErr, Series, SeriesCall, NA,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub fn series_rma<'a>(
     src: Float,
@@ -101,6 +102,16 @@ impl<'a> SeriesCall<'a> for EmaVal {
         Ok(PineRef::new(Series::from(val)))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![StateValue::Float(self.prev_val)])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.prev_val = load_float(state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -118,7 +129,7 @@ fn declare_ma_var<'a>(name: &'static str, factory: fn() -> Callable<'a>) -> VarR
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, name)
 }
 
@@ -2,15 +2,10 @@ use super::VarResult;
 use crate::ast::syntax_type::SyntaxType;
 use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
-use crate::runtime::context::{downcast_ctx, Ctx};
-use crate::runtime::output::{OutputData, OutputInfo, PlotInfo, StrOptionsData};
-use crate::types::{
-    Bool, Callable, CallableObject, Color, DataType, Float, Int, Object, ParamCollectCall,
-    PineClass, PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall,
-    NA,
-};
+use crate::runtime::context::Ctx;
+use crate::types::{Object, PineClass, PineRef, RuntimeErr};
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 struct PlotProps;
 
@@ -37,7 +32,7 @@ impl<'a> PineClass<'a> for PlotProps {
     }
 }
 
-pub const VAR_NAME: &'static str = "extend";
+pub const VAR_NAME: &str = "extend";
 
 pub fn declare_var<'a>() -> VarResult<'a> {
     let value = PineRef::new(Object::new(Box::new(PlotProps)));
@@ -47,7 +42,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("left", SyntaxType::string());
     obj_type.insert("none", SyntaxType::string());
     obj_type.insert("right", SyntaxType::string());
-    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    let syntax_type = SyntaxType::Object(Arc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
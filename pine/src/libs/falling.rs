@@ -13,7 +13,7 @@ use crate::types::{
     ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 type CheckHandler<'a> = fn(RefData<Series<Float>>, i64) -> Result<PineRef<'a>, RuntimeErr>;
 fn check_less<'a>(source: RefData<Series<Float>>, length: i64) -> Result<PineRef<'a>, RuntimeErr> {
@@ -52,6 +52,8 @@ impl<'a> SeriesCall<'a> for AtrVal {
         check_func(source, length)
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -73,7 +75,7 @@ pub fn declare_s_var<'a>(name: &'static str, check_func: CheckHandler<'a>) -> Va
         ],
         SyntaxType::bool_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, name)
 }
 
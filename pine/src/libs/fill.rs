@@ -14,7 +14,7 @@ use crate::types::{
     PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall, NA,
 };
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn pine_plot<'a>(
     context: &mut dyn Ctx<'a>,
@@ -61,7 +61,7 @@ impl<'a> SeriesCall<'a> for PlotVal {
         _func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
         if self.output_id < 0 && !downcast_ctx(context).check_is_output_info_ready() {
-            move_tuplet!((plot1, plot2, color, opacity, title, editable, show_last) = p);
+            move_tuplet!((plot1, plot2, color, opacity, title, editable, show_last, display) = p);
             let names = match _func_type.get_type(0) {
                 Some(&SyntaxType::ObjectClass("plot")) => ("plot", "plot1", "plot2"),
                 Some(&SyntaxType::ObjectClass("hline")) => ("hline", "hline1", "hline2"),
@@ -76,6 +76,7 @@ impl<'a> SeriesCall<'a> for PlotVal {
                 opacity: pine_ref_to_i64(opacity),
                 editable: pine_ref_to_bool(editable),
                 show_last: pine_ref_to_i64(show_last),
+                display: pine_ref_to_i64(display),
             };
             self.output_id =
                 downcast_ctx(context).push_output_info_retindex(OutputInfo::Fill(plot_info));
@@ -93,6 +94,8 @@ impl<'a> SeriesCall<'a> for PlotVal {
         pine_plot(_context, params, func_type)
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -120,6 +123,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
                 ("title", SyntaxType::string()),
                 ("editable", SyntaxType::bool()),
                 ("show_last", SyntaxType::int()),
+                ("display", SyntaxType::int()),
             ],
             SyntaxType::Void,
         )),
@@ -132,6 +136,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
                 ("title", SyntaxType::string()),
                 ("editable", SyntaxType::bool()),
                 ("show_last", SyntaxType::int()),
+                ("display", SyntaxType::int()),
             ],
             SyntaxType::Void,
         )),
@@ -144,6 +149,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
                 ("title", SyntaxType::string()),
                 ("editable", SyntaxType::bool()),
                 ("show_last", SyntaxType::int()),
+                ("display", SyntaxType::int()),
             ],
             SyntaxType::Void,
         )),
@@ -156,11 +162,12 @@ pub fn declare_var<'a>() -> VarResult<'a> {
                 ("title", SyntaxType::string()),
                 ("editable", SyntaxType::bool()),
                 ("show_last", SyntaxType::int()),
+                ("display", SyntaxType::int()),
             ],
             SyntaxType::Void,
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -205,6 +212,7 @@ mod tests {
                 opacity: Some(1i64),
                 editable: Some(true),
                 show_last: Some(1i64),
+                display: None,
             })
         );
 
@@ -232,7 +240,7 @@ mod tests {
             vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
         );
         let src = "p1 = hline(1.0)\np2 = hline(2.0)\n
-        fill(p1, p2, #111111, 1, 'fill', true, 1)";
+        fill(p1, p2, #111111, 1, 'fill', true, 1, 0)";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
 
@@ -256,6 +264,7 @@ mod tests {
                 opacity: Some(1i64),
                 editable: Some(true),
                 show_last: Some(1i64),
+                display: Some(0),
             })
         );
     }
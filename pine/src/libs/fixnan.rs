@@ -7,13 +7,14 @@ use crate::helper::{
     pine_ref_to_i64, require_param, series_index,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{state_err, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Float, Int, PineRef,
     RefData, RuntimeErr, Series, SeriesCall,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, PartialEq, Clone)]
 enum NNVal {
@@ -87,6 +88,27 @@ impl<'a> SeriesCall<'a> for AtrVal {
         }
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        let state = match &self.prev_val {
+            None => StateValue::Na,
+            Some(NNVal::Int(v)) => StateValue::Int(Some(*v)),
+            Some(NNVal::Float(v)) => StateValue::Float(Some(*v)),
+            Some(NNVal::Color(v)) => StateValue::Color(v.clone()),
+        };
+        Some(vec![state])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        self.prev_val = match state.into_iter().next() {
+            Some(StateValue::Na) => None,
+            Some(StateValue::Int(Some(v))) => Some(NNVal::Int(v)),
+            Some(StateValue::Float(Some(v))) => Some(NNVal::Float(v)),
+            Some(StateValue::Color(v)) => Some(NNVal::Color(v)),
+            _ => return Err(state_err()),
+        };
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -117,7 +139,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         //     SyntaxType::Series(SimpleSyntaxType::Color),
         // )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
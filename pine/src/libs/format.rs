@@ -14,7 +14,7 @@ use crate::types::{
     NA,
 };
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 struct PlotProps;
 
@@ -49,7 +49,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("inherit", SyntaxType::string());
     obj_type.insert("price", SyntaxType::string());
     obj_type.insert("volume", SyntaxType::string());
-    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    let syntax_type = SyntaxType::Object(Arc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -13,7 +13,7 @@ use crate::types::{
     ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 type GenIndexFunc<'a> = fn(&mut dyn Ctx<'a>) -> VarIndex;
 type GetValFunc = fn(&Option<RefData<Series<Float>>>, i64) -> Float;
@@ -77,6 +77,8 @@ impl<'a> SeriesCall<'a> for AtrVal {
         Ok(PineRef::new_rc(Series::from(max_val)))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -131,7 +133,7 @@ pub fn declare_s_var<'a>(
             SyntaxType::float_series(),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, name)
 }
 
@@ -13,7 +13,7 @@ use crate::types::{
     PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall, NA,
 };
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 struct PlotVal {
@@ -38,7 +38,7 @@ impl<'a> SeriesCall<'a> for PlotVal {
             downcast_ctx(context).check_is_output_info_ready(),
         );
         if self.output_id < 0 && !downcast_ctx(context).check_is_output_info_ready() {
-            move_tuplet!((price, title, color, linestyle, linewidth, editable) = p);
+            move_tuplet!((price, title, color, linestyle, linewidth, editable, display) = p);
             let plot_info = HLineInfo {
                 price: pine_ref_to_f64(price),
                 title: pine_ref_to_string(title),
@@ -46,6 +46,7 @@ impl<'a> SeriesCall<'a> for PlotVal {
                 linestyle: pine_ref_to_string(linestyle),
                 linewidth: pine_ref_to_i64(linewidth),
                 editable: pine_ref_to_bool(editable),
+                display: pine_ref_to_i64(display),
             };
             self.output_id =
                 downcast_ctx(context).push_output_info_retindex(OutputInfo::HLine(plot_info));
@@ -60,6 +61,8 @@ impl<'a> SeriesCall<'a> for PlotVal {
         Ok(())
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -104,6 +107,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             ("linestyle", SyntaxType::string()),
             ("linewidth", SyntaxType::int()),
             ("editable", SyntaxType::bool()),
+            ("display", SyntaxType::int()),
         ],
         SyntaxType::ObjectClass("hline"),
     ))]);
@@ -111,7 +115,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("style_dashed", SyntaxType::string());
     obj_type.insert("style_dotted", SyntaxType::string());
     obj_type.insert("style_solid", SyntaxType::string());
-    let syntax_type = SyntaxType::ObjectFunction(Rc::new(obj_type), Rc::new(func_type));
+    let syntax_type = SyntaxType::ObjectFunction(Arc::new(obj_type), Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -164,6 +168,7 @@ mod tests {
                 linestyle: Some(String::from("dashed")),
                 linewidth: Some(1i64),
                 editable: Some(true),
+                display: None,
             })]
         );
         assert_eq!(
@@ -192,7 +197,7 @@ mod tests {
             vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
         );
         let src = "h1 = hline(1200)
-        h2 = hline(3.14, title='Pi', color=color.orange, linestyle=hline.style_dotted, linewidth=2)";
+        h2 = hline(3.14, title='Pi', color=color.orange, linestyle=hline.style_dotted, linewidth=2, display=0)";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
 
@@ -215,6 +220,7 @@ mod tests {
                     linestyle: None,
                     linewidth: None,
                     editable: None,
+                    display: None,
                 }),
                 OutputInfo::HLine(HLineInfo {
                     price: Some(3.14f64),
@@ -223,6 +229,7 @@ mod tests {
                     linestyle: Some(String::from("dotted")),
                     linewidth: Some(2i64),
                     editable: None,
+                    display: Some(0),
                 })
             ]
         );
@@ -9,13 +9,14 @@ use crate::helper::{
     pine_ref_to_i64, require_param,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_series, save_series, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableCreator, CallableFactory, Evaluate,
     EvaluateVal, Float, Int, ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall,
 };
 use std::f64;
-use std::rc::Rc;
+use std::sync::Arc;
 
 // X=2*WMA(C,ROUND(N/2))-WMA(C,N);
 // HULLMA=WMA(X,ROUND(SQRT(N)));
@@ -64,6 +65,16 @@ impl<'a> SeriesCall<'a> for HmaVal<'a> {
         Ok(PineRef::new(Series::from(hullma)))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![save_series(&self.val_history)])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        load_series(&mut self.val_history, state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -86,7 +97,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "hma")
 }
 
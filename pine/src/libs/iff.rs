@@ -13,8 +13,9 @@ use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Color, Evaluate,
     EvaluateVal, Float, Int, PineRef, RefData, RuntimeErr, Series, SeriesCall, NA,
 };
+use std::borrow::Cow;
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn iff_func<'a>(
     _ctx: &mut dyn Ctx<'a>,
@@ -32,7 +33,7 @@ fn iff_func<'a>(
             Ok(PineRef::new_box(pine_ref_to_bool(val).unwrap_or(false)))
         }
         SyntaxType::Simple(SimpleSyntaxType::Color) => Ok(PineRef::new_box(
-            pine_ref_to_color2(val).unwrap_or(Color("")),
+            pine_ref_to_color2(val).unwrap_or(Color(Cow::Borrowed(""))),
         )),
         SyntaxType::Simple(SimpleSyntaxType::String) => Ok(PineRef::new_rc(
             pine_ref_to_string(val).unwrap_or(String::from("")),
@@ -48,7 +49,7 @@ fn iff_func<'a>(
             pine_ref_to_bool(val).unwrap_or(false),
         ))),
         SyntaxType::Series(SimpleSyntaxType::Color) => Ok(PineRef::new_rc(Series::from(
-            pine_ref_to_color2(val).unwrap_or(Color("")),
+            pine_ref_to_color2(val).unwrap_or(Color(Cow::Borrowed(""))),
         ))),
         SyntaxType::Series(SimpleSyntaxType::String) => Ok(PineRef::new_rc(Series::from(
             pine_ref_to_string(val).unwrap_or(String::from("")),
@@ -142,7 +143,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             SyntaxType::Series(SimpleSyntaxType::String),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "iff")
 }
 
@@ -199,7 +200,7 @@ mod tests {
         );
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(starti + 4, 0)),
-            Some(PineRef::new(Color("#123456")))
+            Some(PineRef::new(Color(Cow::Borrowed("#123456"))))
         );
 
         assert_eq!(
@@ -224,8 +225,8 @@ mod tests {
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(starti + 9, 0)),
             Some(PineRef::new(Series::from_vec(vec![
-                Color("#123456"),
-                Color("#654321")
+                Color(Cow::Borrowed("#123456")),
+                Color(Cow::Borrowed("#654321"))
             ])))
         );
     }
@@ -4,27 +4,34 @@ use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, Syn
 use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
 use crate::helper::{
-    move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_i64, pine_ref_to_string,
+    move_element, pine_ref_to_bool, pine_ref_to_color, pine_ref_to_f64, pine_ref_to_i64,
+    pine_ref_to_string,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::output::{
-    BoolInputInfo, FloatInputInfo, InputInfo, InputVal, IntInputInfo, SourceInputInfo,
-    StringInputInfo,
+    BoolInputInfo, ColorInputInfo, FloatInputInfo, InputInfo, InputVal, IntInputInfo,
+    PriceInputInfo, SourceInputInfo, StringInputInfo, TimeInputInfo,
 };
 use crate::types::{
-    downcast_pf, Bool, Callable, CallableObject, DataType, Float, Int, ParamCollectCall, PineClass,
-    PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall,
-    SimpleCallableObject, Tuple, NA,
+    downcast_pf, Callable, Color, PineClass, PineRef, RuntimeErr, SeriesCall, SimpleCallableObject,
+    Tuple,
 };
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
-use std::rc::Rc;
-
-const BOOL_TYPE_STR: &'static str = "bool";
-const INT_TYPE_STR: &'static str = "int";
-const FLOAT_TYPE_STR: &'static str = "float";
-const STRING_TYPE_STR: &'static str = "string";
-const SOURCE_TYPE_STR: &'static str = "source";
+use std::sync::Arc;
+
+const BOOL_TYPE_STR: &str = "bool";
+const INT_TYPE_STR: &str = "int";
+const FLOAT_TYPE_STR: &str = "float";
+const STRING_TYPE_STR: &str = "string";
+const SOURCE_TYPE_STR: &str = "source";
+const TIME_TYPE_STR: &str = "time";
+const PRICE_TYPE_STR: &str = "price";
+const COLOR_TYPE_STR: &str = "color";
+const SESSION_TYPE_STR: &str = "session";
+const RESOLUTION_TYPE_STR: &str = "resolution";
+const SYMBOL_TYPE_STR: &str = "symbol";
 
 #[derive(Debug, PartialEq, Clone)]
 struct InputCall<'a> {
@@ -63,78 +70,128 @@ impl<'a> SeriesCall<'a> for InputCall<'a> {
         Ok(())
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
 }
 
+// The type argument of the input must be one of the types supported by the signature.
+fn check_type_str(type_str: &Option<String>, types: &[&str]) -> Result<(), RuntimeErr> {
+    match type_str {
+        Some(type_str) if !types.contains(&type_str.as_str()) => {
+            Err(RuntimeErr::FuncCallParamNotValid(str_replace(
+                EXP_VAL_BUT_GET_VAL,
+                vec![String::from(types[0]), type_str.clone()],
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
 fn input_for_bool<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((defval, title, input_type, confirm, tooltip, inline, group) = param);
     let ctx_ins = downcast_ctx(context);
     if !ctx_ins.check_is_input_info_ready() {
-        let type_str = pine_ref_to_string(move_element(&mut param, 2));
-
-        if type_str.is_some() && type_str.as_ref().unwrap() != BOOL_TYPE_STR {
-            // type must be BOOL_TYPE_STR
-            return Err(RuntimeErr::FuncCallParamNotValid(str_replace(
-                EXP_VAL_BUT_GET_VAL,
-                vec![
-                    String::from(BOOL_TYPE_STR),
-                    String::from(type_str.as_ref().unwrap()),
-                ],
-            )));
-        }
+        check_type_str(&pine_ref_to_string(input_type), &[BOOL_TYPE_STR])?;
         ctx_ins.push_input_info(InputInfo::Bool(BoolInputInfo {
-            defval: pine_ref_to_bool(param[0].clone()),
-            title: pine_ref_to_string(move_element(&mut param, 1)),
+            defval: pine_ref_to_bool(defval.clone()),
+            title: pine_ref_to_string(title),
             input_type: String::from(BOOL_TYPE_STR),
-            confirm: pine_ref_to_bool(move_element(&mut param, 3)),
+            confirm: pine_ref_to_bool(confirm),
+            tooltip: pine_ref_to_string(tooltip),
+            inline: pine_ref_to_string(inline),
+            group: pine_ref_to_string(group),
         }));
     }
 
-    let input_val = ctx_ins.copy_next_input();
+    let input_val = ctx_ins.copy_next_input()?;
     match input_val {
         Some(InputVal::Bool(val)) => Ok(PineRef::new_box(val)),
-        _ => match move_element(&mut param, 0) {
+        _ => match defval {
             Some(val) => Ok(val),
             _ => Err(RuntimeErr::NotValidParam),
         },
     }
 }
 
+// The session, resolution and symbol inputs are string inputs with different types.
 fn input_for_string<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((defval, title, input_type, confirm, options, tooltip, inline, group) = param);
     let ctx_ins = downcast_ctx(context);
     if !ctx_ins.check_is_input_info_ready() {
-        let type_str = pine_ref_to_string(move_element(&mut param, 2));
+        let type_str = pine_ref_to_string(input_type);
+        check_type_str(
+            &type_str,
+            &[
+                STRING_TYPE_STR,
+                SESSION_TYPE_STR,
+                RESOLUTION_TYPE_STR,
+                SYMBOL_TYPE_STR,
+            ],
+        )?;
+        let type_str = type_str.unwrap_or(String::from(STRING_TYPE_STR));
+        let info = StringInputInfo {
+            defval: pine_ref_to_string(defval.clone()),
+            title: pine_ref_to_string(title),
+            input_type: type_str.clone(),
+            confirm: pine_ref_to_bool(confirm),
+            options: pine_ref_to_str_list(options),
+            tooltip: pine_ref_to_string(tooltip),
+            inline: pine_ref_to_string(inline),
+            group: pine_ref_to_string(group),
+        };
+        ctx_ins.push_input_info(match type_str.as_str() {
+            SESSION_TYPE_STR => InputInfo::Session(info),
+            RESOLUTION_TYPE_STR => InputInfo::Resolution(info),
+            SYMBOL_TYPE_STR => InputInfo::Symbol(info),
+            _ => InputInfo::String(info),
+        });
+    }
 
-        if type_str.is_some() && type_str.as_ref().unwrap() != STRING_TYPE_STR {
-            // type must be BOOL_TYPE_STR
-            return Err(RuntimeErr::FuncCallParamNotValid(str_replace(
-                EXP_VAL_BUT_GET_VAL,
-                vec![
-                    String::from(STRING_TYPE_STR),
-                    String::from(type_str.as_ref().unwrap()),
-                ],
-            )));
-        }
-        ctx_ins.push_input_info(InputInfo::String(StringInputInfo {
-            defval: pine_ref_to_string(param[0].clone()),
-            title: pine_ref_to_string(move_element(&mut param, 1)),
-            input_type: String::from(STRING_TYPE_STR),
-            confirm: pine_ref_to_bool(move_element(&mut param, 3)),
-            options: pine_ref_to_str_list(move_element(&mut param, 4)),
+    let input_val = ctx_ins.copy_next_input()?;
+    match input_val {
+        Some(InputVal::String(val))
+        | Some(InputVal::Session(val))
+        | Some(InputVal::Resolution(val))
+        | Some(InputVal::Symbol(val)) => Ok(PineRef::new_rc(val)),
+        _ => match defval {
+            Some(val) => Ok(val),
+            _ => Err(RuntimeErr::NotValidParam),
+        },
+    }
+}
+
+fn input_for_color<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((defval, title, input_type, confirm, tooltip, inline, group) = param);
+    let ctx_ins = downcast_ctx(context);
+    if !ctx_ins.check_is_input_info_ready() {
+        check_type_str(&pine_ref_to_string(input_type), &[COLOR_TYPE_STR])?;
+        ctx_ins.push_input_info(InputInfo::Color(ColorInputInfo {
+            defval: pine_ref_to_color(defval.clone()),
+            title: pine_ref_to_string(title),
+            input_type: String::from(COLOR_TYPE_STR),
+            confirm: pine_ref_to_bool(confirm),
+            tooltip: pine_ref_to_string(tooltip),
+            inline: pine_ref_to_string(inline),
+            group: pine_ref_to_string(group),
         }));
     }
 
-    let input_val = ctx_ins.copy_next_input();
+    let input_val = ctx_ins.copy_next_input()?;
     match input_val {
-        Some(InputVal::String(val)) => Ok(PineRef::new_rc(val)),
-        _ => match move_element(&mut param, 0) {
+        Some(InputVal::Color(val)) => Ok(PineRef::new_box(Color(Cow::Owned(val)))),
+        _ => match defval {
             Some(val) => Ok(val),
             _ => Err(RuntimeErr::NotValidParam),
         },
@@ -184,26 +241,19 @@ fn input_for_source<'a>(
 ) -> Result<PineRef<'a>, RuntimeErr> {
     if !downcast_ctx(context).check_is_input_info_ready() {
         let type_str = pine_ref_to_string(move_element(&mut param, 2));
-
-        if type_str.is_some() && type_str.as_ref().unwrap() != SOURCE_TYPE_STR {
-            // type must be BOOL_TYPE_STR
-            return Err(RuntimeErr::FuncCallParamNotValid(str_replace(
-                EXP_VAL_BUT_GET_VAL,
-                vec![
-                    String::from(SOURCE_TYPE_STR),
-                    String::from(type_str.as_ref().unwrap()),
-                ],
-            )));
-        }
+        check_type_str(&type_str, &[SOURCE_TYPE_STR])?;
         let name = get_name_from_source(context.get_top_ctx(), &param[0])?;
         downcast_ctx(context).push_input_info(InputInfo::Source(SourceInputInfo {
             defval: name,
             title: pine_ref_to_string(move_element(&mut param, 1)),
             input_type: String::from(SOURCE_TYPE_STR),
+            tooltip: pine_ref_to_string(move_element(&mut param, 3)),
+            inline: pine_ref_to_string(move_element(&mut param, 4)),
+            group: pine_ref_to_string(move_element(&mut param, 5)),
         }));
     }
 
-    let input_val = downcast_ctx(context).copy_next_input();
+    let input_val = downcast_ctx(context).copy_next_input()?;
     println!("Get input val {:?}", input_val);
 
     match input_val {
@@ -252,23 +302,20 @@ fn input_for_int<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
-    if pine_ref_to_string(param[2].clone()) == Some(String::from("float")) {
-        return input_for_float(context, param);
+    match pine_ref_to_string(param[2].clone()).as_deref() {
+        Some(FLOAT_TYPE_STR) | Some(PRICE_TYPE_STR) => return input_for_float(context, param),
+        Some(TIME_TYPE_STR) => return input_for_time(context, param),
+        _ => {}
     }
-    move_tuplet!((defval, title, input_type, minval, maxval, confirm, step, options) = param);
+    move_tuplet!(
+        (
+            defval, title, input_type, minval, maxval, confirm, step, options, tooltip, inline,
+            group
+        ) = param
+    );
     let ctx_ins = downcast_ctx(context);
     if !ctx_ins.check_is_input_info_ready() {
-        let type_str = pine_ref_to_string(input_type);
-        if type_str.is_some() && type_str.as_ref().unwrap() != INT_TYPE_STR {
-            // type must be BOOL_TYPE_STR
-            return Err(RuntimeErr::FuncCallParamNotValid(str_replace(
-                EXP_VAL_BUT_GET_VAL,
-                vec![
-                    String::from(INT_TYPE_STR),
-                    String::from(type_str.as_ref().unwrap()),
-                ],
-            )));
-        }
+        check_type_str(&pine_ref_to_string(input_type), &[INT_TYPE_STR])?;
         ctx_ins.push_input_info(InputInfo::Int(IntInputInfo {
             defval: pine_ref_to_i64(defval.clone()),
             title: pine_ref_to_string(title),
@@ -278,10 +325,13 @@ fn input_for_int<'a>(
             confirm: pine_ref_to_bool(confirm),
             step: pine_ref_to_i64(step),
             options: pine_ref_to_i64_list(options),
+            tooltip: pine_ref_to_string(tooltip),
+            inline: pine_ref_to_string(inline),
+            group: pine_ref_to_string(group),
         }));
     }
 
-    let input_val = ctx_ins.copy_next_input();
+    let input_val = ctx_ins.copy_next_input()?;
     match input_val {
         Some(InputVal::Int(val)) => Ok(PineRef::new_box(Some(val))),
         _ => match defval {
@@ -291,24 +341,55 @@ fn input_for_int<'a>(
     }
 }
 
+// The time input is the int input of the timestamp, so it has the same arguments.
+fn input_for_time<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((defval, title) = param);
+    let confirm = move_element(&mut param, 5);
+    let tooltip = move_element(&mut param, 8);
+    let inline = move_element(&mut param, 9);
+    let group = move_element(&mut param, 10);
+    let ctx_ins = downcast_ctx(context);
+    if !ctx_ins.check_is_input_info_ready() {
+        ctx_ins.push_input_info(InputInfo::Time(TimeInputInfo {
+            defval: pine_ref_to_i64(defval.clone()),
+            title: pine_ref_to_string(title),
+            input_type: String::from(TIME_TYPE_STR),
+            confirm: pine_ref_to_bool(confirm),
+            tooltip: pine_ref_to_string(tooltip),
+            inline: pine_ref_to_string(inline),
+            group: pine_ref_to_string(group),
+        }));
+    }
+
+    let input_val = ctx_ins.copy_next_input()?;
+    match input_val {
+        Some(InputVal::Time(val)) | Some(InputVal::Int(val)) => Ok(PineRef::new_box(Some(val))),
+        _ => match defval {
+            Some(val) => Ok(val),
+            _ => Err(RuntimeErr::NotValidParam),
+        },
+    }
+}
+
 fn input_for_float<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
-    move_tuplet!((defval, title, input_type, minval, maxval, confirm, step, options) = param);
+    if pine_ref_to_string(param[2].clone()).as_deref() == Some(PRICE_TYPE_STR) {
+        return input_for_price(context, param);
+    }
+    move_tuplet!(
+        (
+            defval, title, input_type, minval, maxval, confirm, step, options, tooltip, inline,
+            group
+        ) = param
+    );
     let ctx_ins = downcast_ctx(context);
     if !ctx_ins.check_is_input_info_ready() {
-        let type_str = pine_ref_to_string(input_type);
-        if type_str.is_some() && type_str.as_ref().unwrap() != FLOAT_TYPE_STR {
-            // type must be BOOL_TYPE_STR
-            return Err(RuntimeErr::FuncCallParamNotValid(str_replace(
-                EXP_VAL_BUT_GET_VAL,
-                vec![
-                    String::from(FLOAT_TYPE_STR),
-                    String::from(type_str.as_ref().unwrap()),
-                ],
-            )));
-        }
+        check_type_str(&pine_ref_to_string(input_type), &[FLOAT_TYPE_STR])?;
         ctx_ins.push_input_info(InputInfo::Float(FloatInputInfo {
             defval: pine_ref_to_f64(defval.clone()),
             title: pine_ref_to_string(title),
@@ -318,12 +399,50 @@ fn input_for_float<'a>(
             confirm: pine_ref_to_bool(confirm),
             step: pine_ref_to_f64(step),
             options: pine_ref_to_f64_list(options),
+            tooltip: pine_ref_to_string(tooltip),
+            inline: pine_ref_to_string(inline),
+            group: pine_ref_to_string(group),
         }));
     }
 
-    let input_val = ctx_ins.copy_next_input();
+    let input_val = ctx_ins.copy_next_input()?;
     match input_val {
         Some(InputVal::Float(val)) => Ok(PineRef::new_box(Some(val))),
+        Some(InputVal::Int(val)) => Ok(PineRef::new_box(Some(val as f64))),
+        _ => match defval {
+            Some(val) => Ok(val),
+            _ => Err(RuntimeErr::NotValidParam),
+        },
+    }
+}
+
+// The price input is the float input without the range and the options.
+fn input_for_price<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((defval, title) = param);
+    let confirm = move_element(&mut param, 5);
+    let tooltip = move_element(&mut param, 8);
+    let inline = move_element(&mut param, 9);
+    let group = move_element(&mut param, 10);
+    let ctx_ins = downcast_ctx(context);
+    if !ctx_ins.check_is_input_info_ready() {
+        ctx_ins.push_input_info(InputInfo::Price(PriceInputInfo {
+            defval: pine_ref_to_f64(defval.clone()),
+            title: pine_ref_to_string(title),
+            input_type: String::from(PRICE_TYPE_STR),
+            confirm: pine_ref_to_bool(confirm),
+            tooltip: pine_ref_to_string(tooltip),
+            inline: pine_ref_to_string(inline),
+            group: pine_ref_to_string(group),
+        }));
+    }
+
+    let input_val = ctx_ins.copy_next_input()?;
+    match input_val {
+        Some(InputVal::Price(val)) | Some(InputVal::Float(val)) => Ok(PineRef::new_box(Some(val))),
+        Some(InputVal::Int(val)) => Ok(PineRef::new_box(Some(val as f64))),
         _ => match defval {
             Some(val) => Ok(val),
             _ => Err(RuntimeErr::NotValidParam),
@@ -331,8 +450,25 @@ fn input_for_float<'a>(
     }
 }
 
+// The arguments of all the input signatures end with the tooltip, inline and group.
+fn input_meta_types<'a>() -> Vec<(&'static str, SyntaxType<'a>)> {
+    vec![
+        ("tooltip", SyntaxType::string()),
+        ("inline", SyntaxType::string()),
+        ("group", SyntaxType::string()),
+    ]
+}
+
+fn gen_input_type<'a>(
+    mut args: Vec<(&'static str, SyntaxType<'a>)>,
+    ret: SyntaxType<'a>,
+) -> FunctionType<'a> {
+    args.append(&mut input_meta_types());
+    FunctionType::new((args, ret))
+}
+
 fn gen_bool_type<'a>() -> FunctionType<'a> {
-    FunctionType::new((
+    gen_input_type(
         vec![
             ("defval", SyntaxType::bool()),
             ("title", SyntaxType::string()),
@@ -340,11 +476,11 @@ fn gen_bool_type<'a>() -> FunctionType<'a> {
             ("confirm", SyntaxType::bool()),
         ],
         SyntaxType::bool(),
-    ))
+    )
 }
 
 fn gen_int_type<'a>() -> FunctionType<'a> {
-    FunctionType::new((
+    gen_input_type(
         vec![
             ("defval", SyntaxType::int()),
             ("title", SyntaxType::string()),
@@ -356,11 +492,11 @@ fn gen_int_type<'a>() -> FunctionType<'a> {
             ("options", SyntaxType::List(SimpleSyntaxType::Int)),
         ],
         SyntaxType::int(),
-    ))
+    )
 }
 
 fn gen_float_type<'a>() -> FunctionType<'a> {
-    FunctionType::new((
+    gen_input_type(
         vec![
             ("defval", SyntaxType::float()),
             ("title", SyntaxType::string()),
@@ -372,11 +508,11 @@ fn gen_float_type<'a>() -> FunctionType<'a> {
             ("options", SyntaxType::List(SimpleSyntaxType::Float)),
         ],
         SyntaxType::float(),
-    ))
+    )
 }
 
 fn gen_string_type<'a>() -> FunctionType<'a> {
-    FunctionType::new((
+    gen_input_type(
         vec![
             ("defval", SyntaxType::string()),
             ("title", SyntaxType::string()),
@@ -385,18 +521,41 @@ fn gen_string_type<'a>() -> FunctionType<'a> {
             ("options", SyntaxType::List(SimpleSyntaxType::String)),
         ],
         SyntaxType::string(),
-    ))
+    )
 }
 
 fn gen_source_type<'a>() -> FunctionType<'a> {
-    FunctionType::new((
+    gen_input_type(
         vec![
             ("defval", SyntaxType::Series(SimpleSyntaxType::Float)),
             ("title", SyntaxType::string()),
             ("type", SyntaxType::string()),
         ],
         SyntaxType::Series(SimpleSyntaxType::Float),
-    ))
+    )
+}
+
+fn gen_color_type<'a>() -> FunctionType<'a> {
+    gen_input_type(
+        vec![
+            ("defval", SyntaxType::color()),
+            ("title", SyntaxType::string()),
+            ("type", SyntaxType::string()),
+            ("confirm", SyntaxType::bool()),
+        ],
+        SyntaxType::color(),
+    )
+}
+
+fn gen_input_types<'a>() -> FunctionTypes<'a> {
+    FunctionTypes(vec![
+        gen_int_type(),
+        gen_float_type(),
+        gen_bool_type(),
+        gen_string_type(),
+        gen_source_type(),
+        gen_color_type(),
+    ])
 }
 
 fn pine_input<'a>(
@@ -404,20 +563,18 @@ fn pine_input<'a>(
     param: Vec<Option<PineRef<'a>>>,
     func_type: FunctionType<'a>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
-    if func_type.arg_names().len() == 4 {
+    if func_type == gen_bool_type() {
         input_for_bool(context, param)
-    } else if func_type.arg_names().len() == 8 {
-        if func_type == gen_int_type() {
-            input_for_int(context, param)
-        } else if func_type == gen_float_type() {
-            input_for_float(context, param)
-        } else {
-            unreachable!();
-        }
-    } else if func_type.arg_names().len() == 5 {
+    } else if func_type == gen_int_type() {
+        input_for_int(context, param)
+    } else if func_type == gen_float_type() {
+        input_for_float(context, param)
+    } else if func_type == gen_string_type() {
         input_for_string(context, param)
-    } else if func_type.arg_names().len() == 3 {
+    } else if func_type == gen_source_type() {
         input_for_source(context, param)
+    } else if func_type == gen_color_type() {
+        input_for_color(context, param)
     } else {
         unreachable!();
     }
@@ -433,13 +590,16 @@ impl<'a> PineClass<'a> for InputProps {
     fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
         match name {
             "bool" => Ok(PineRef::new_rc(String::from(BOOL_TYPE_STR))),
+            "color" => Ok(PineRef::new_rc(String::from(COLOR_TYPE_STR))),
             "float" => Ok(PineRef::new_rc(String::from(FLOAT_TYPE_STR))),
             "integer" => Ok(PineRef::new_rc(String::from(INT_TYPE_STR))),
-            "resolution" => Ok(PineRef::new_rc(String::from(STRING_TYPE_STR))),
-            "session" => Ok(PineRef::new_rc(String::from(STRING_TYPE_STR))),
+            "price" => Ok(PineRef::new_rc(String::from(PRICE_TYPE_STR))),
+            "resolution" => Ok(PineRef::new_rc(String::from(RESOLUTION_TYPE_STR))),
+            "session" => Ok(PineRef::new_rc(String::from(SESSION_TYPE_STR))),
             "source" => Ok(PineRef::new_rc(String::from(SOURCE_TYPE_STR))),
             "string" => Ok(PineRef::new_rc(String::from(STRING_TYPE_STR))),
-            "symbol" => Ok(PineRef::new_rc(String::from(STRING_TYPE_STR))),
+            "symbol" => Ok(PineRef::new_rc(String::from(SYMBOL_TYPE_STR))),
+            "time" => Ok(PineRef::new_rc(String::from(TIME_TYPE_STR))),
             _ => Err(RuntimeErr::NotImplement(str_replace(
                 NO_FIELD_IN_OBJECT,
                 vec![String::from(name), String::from("input")],
@@ -459,31 +619,399 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         Callable::new(None, Some(Box::new(InputCall::new())))
     }));
     /*
-        input(defval, title, type, confirm) → input bool
-        input(defval, title, type, minval, maxval, confirm, step, options) → input integer
-        input(defval, title, type, minval, maxval, confirm, step, options) → input float
-        input(defval, title, type, confirm, options) → input string
-        input(defval, title, type) → series[float]
+        input(defval, title, type, confirm, tooltip, inline, group) → input bool
+        input(defval, title, type, minval, maxval, confirm, step, options, tooltip, inline, group) → input integer
+        input(defval, title, type, minval, maxval, confirm, step, options, tooltip, inline, group) → input float
+        input(defval, title, type, confirm, options, tooltip, inline, group) → input string
+        input(defval, title, type, tooltip, inline, group) → series[float]
+        input(defval, title, type, confirm, tooltip, inline, group) → input color
     */
     let mut obj_type = BTreeMap::new();
-    obj_type.insert("bool", SyntaxType::string());
-    obj_type.insert("float", SyntaxType::string());
-    obj_type.insert("integer", SyntaxType::string());
-    obj_type.insert("resolution", SyntaxType::string());
-    obj_type.insert("session", SyntaxType::string());
-    obj_type.insert("source", SyntaxType::string());
-    obj_type.insert("string", SyntaxType::string());
-    obj_type.insert("symbol", SyntaxType::string());
-    let syntax_type = SyntaxType::ObjectFunction(
-        Rc::new(obj_type),
-        Rc::new(FunctionTypes(vec![
-            gen_int_type(),
-            gen_float_type(),
-            gen_bool_type(),
-            gen_string_type(),
-            gen_source_type(),
-        ])),
-    );
+    for name in &[
+        "bool",
+        "color",
+        "float",
+        "integer",
+        "price",
+        "resolution",
+        "session",
+        "source",
+        "string",
+        "symbol",
+        "time",
+    ] {
+        obj_type.insert(*name, SyntaxType::string());
+    }
+    let syntax_type = SyntaxType::ObjectFunction(Arc::new(obj_type), Arc::new(gen_input_types()));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+// The typed input kinds of Pine v5 such as input.int and input.source.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum TypedInput {
+    Bool,
+    Int,
+    Float,
+    String,
+    Source,
+    Time,
+    Price,
+    Color,
+    Session,
+    Resolution,
+    Symbol,
+}
+
+// The typed input function of Pine v5. It rearranges the arguments to the layout of
+// the v3/v4 input function and runs the same implementation.
+#[derive(Debug, PartialEq, Clone)]
+struct TypedInputCall<'a> {
+    kind: TypedInput,
+    call: InputCall<'a>,
+}
+
+impl<'a> TypedInputCall<'a> {
+    pub fn new(kind: TypedInput) -> TypedInputCall<'a> {
+        TypedInputCall {
+            kind,
+            call: InputCall::new(),
+        }
+    }
+}
+
+fn type_str_ref<'a>(type_str: &str) -> Option<PineRef<'a>> {
+    Some(PineRef::new_rc(String::from(type_str)))
+}
+
+impl<'a> SeriesCall<'a> for TypedInputCall<'a> {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        mut val: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        let (param, func_type) = match self.kind {
+            TypedInput::Bool | TypedInput::Color => {
+                move_tuplet!((defval, title, tooltip, inline, group, confirm) = val);
+                let (type_str, func_type) = if self.kind == TypedInput::Bool {
+                    (BOOL_TYPE_STR, gen_bool_type())
+                } else {
+                    (COLOR_TYPE_STR, gen_color_type())
+                };
+                (
+                    vec![
+                        defval,
+                        title,
+                        type_str_ref(type_str),
+                        confirm,
+                        tooltip,
+                        inline,
+                        group,
+                    ],
+                    func_type,
+                )
+            }
+            TypedInput::Int | TypedInput::Float => {
+                move_tuplet!(
+                    (
+                        defval, title, minval, maxval, step, tooltip, inline, group, confirm,
+                        options
+                    ) = val
+                );
+                let (type_str, func_type) = if self.kind == TypedInput::Int {
+                    (INT_TYPE_STR, gen_int_type())
+                } else {
+                    (FLOAT_TYPE_STR, gen_float_type())
+                };
+                (
+                    vec![
+                        defval,
+                        title,
+                        type_str_ref(type_str),
+                        minval,
+                        maxval,
+                        confirm,
+                        step,
+                        options,
+                        tooltip,
+                        inline,
+                        group,
+                    ],
+                    func_type,
+                )
+            }
+            TypedInput::Time | TypedInput::Price => {
+                move_tuplet!((defval, title, tooltip, inline, group, confirm) = val);
+                let (type_str, func_type) = if self.kind == TypedInput::Time {
+                    (TIME_TYPE_STR, gen_int_type())
+                } else {
+                    (PRICE_TYPE_STR, gen_float_type())
+                };
+                (
+                    vec![
+                        defval,
+                        title,
+                        type_str_ref(type_str),
+                        None,
+                        None,
+                        confirm,
+                        None,
+                        None,
+                        tooltip,
+                        inline,
+                        group,
+                    ],
+                    func_type,
+                )
+            }
+            TypedInput::String | TypedInput::Session | TypedInput::Resolution => {
+                move_tuplet!((defval, title, options, tooltip, inline, group, confirm) = val);
+                let type_str = match self.kind {
+                    TypedInput::Session => SESSION_TYPE_STR,
+                    TypedInput::Resolution => RESOLUTION_TYPE_STR,
+                    _ => STRING_TYPE_STR,
+                };
+                (
+                    vec![
+                        defval,
+                        title,
+                        type_str_ref(type_str),
+                        confirm,
+                        options,
+                        tooltip,
+                        inline,
+                        group,
+                    ],
+                    gen_string_type(),
+                )
+            }
+            TypedInput::Symbol => {
+                move_tuplet!((defval, title, tooltip, inline, group, confirm) = val);
+                (
+                    vec![
+                        defval,
+                        title,
+                        type_str_ref(SYMBOL_TYPE_STR),
+                        confirm,
+                        None,
+                        tooltip,
+                        inline,
+                        group,
+                    ],
+                    gen_string_type(),
+                )
+            }
+            TypedInput::Source => {
+                move_tuplet!((defval, title, tooltip, inline, group) = val);
+                (
+                    vec![
+                        defval,
+                        title,
+                        type_str_ref(SOURCE_TYPE_STR),
+                        tooltip,
+                        inline,
+                        group,
+                    ],
+                    gen_source_type(),
+                )
+            }
+        };
+        self.call.step(context, param, func_type)
+    }
+
+    fn run(&mut self, context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.call.run(context)
+    }
+
+    impl_no_state!();
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+// The arguments of Pine v5 input functions, the tooltip, inline and group are followed
+// by confirm except the source input.
+fn gen_typed_func_type<'a>(kind: TypedInput) -> FunctionType<'a> {
+    let (mut args, ret) = match kind {
+        TypedInput::Bool => (vec![("defval", SyntaxType::bool())], SyntaxType::bool()),
+        TypedInput::Int => (
+            vec![
+                ("defval", SyntaxType::int()),
+                ("title", SyntaxType::string()),
+                ("minval", SyntaxType::int()),
+                ("maxval", SyntaxType::int()),
+                ("step", SyntaxType::int()),
+            ],
+            SyntaxType::int(),
+        ),
+        TypedInput::Float => (
+            vec![
+                ("defval", SyntaxType::float()),
+                ("title", SyntaxType::string()),
+                ("minval", SyntaxType::float()),
+                ("maxval", SyntaxType::float()),
+                ("step", SyntaxType::float()),
+            ],
+            SyntaxType::float(),
+        ),
+        TypedInput::String | TypedInput::Session | TypedInput::Resolution => (
+            vec![
+                ("defval", SyntaxType::string()),
+                ("title", SyntaxType::string()),
+                ("options", SyntaxType::List(SimpleSyntaxType::String)),
+            ],
+            SyntaxType::string(),
+        ),
+        TypedInput::Source => (
+            vec![("defval", SyntaxType::Series(SimpleSyntaxType::Float))],
+            SyntaxType::Series(SimpleSyntaxType::Float),
+        ),
+        TypedInput::Time => (vec![("defval", SyntaxType::int())], SyntaxType::int()),
+        TypedInput::Price => (vec![("defval", SyntaxType::float())], SyntaxType::float()),
+        TypedInput::Color => (vec![("defval", SyntaxType::color())], SyntaxType::color()),
+        TypedInput::Symbol => (vec![("defval", SyntaxType::string())], SyntaxType::string()),
+    };
+    if args.len() == 1 {
+        args.push(("title", SyntaxType::string()));
+    }
+    args.append(&mut input_meta_types());
+    if kind != TypedInput::Source {
+        args.push(("confirm", SyntaxType::bool()));
+    }
+    match kind {
+        TypedInput::Int => args.push(("options", SyntaxType::List(SimpleSyntaxType::Int))),
+        TypedInput::Float => args.push(("options", SyntaxType::List(SimpleSyntaxType::Float))),
+        _ => {}
+    }
+    FunctionType::new((args, ret))
+}
+
+// The input fields of Pine v5 are the typed input functions.
+const V5_INPUT_FIELDS: &[(&str, TypedInput)] = &[
+    ("bool", TypedInput::Bool),
+    ("color", TypedInput::Color),
+    ("float", TypedInput::Float),
+    ("int", TypedInput::Int),
+    ("price", TypedInput::Price),
+    ("session", TypedInput::Session),
+    ("source", TypedInput::Source),
+    ("string", TypedInput::String),
+    ("symbol", TypedInput::Symbol),
+    ("time", TypedInput::Time),
+    ("timeframe", TypedInput::Resolution),
+];
+
+fn gen_typed_input<'a>(kind: TypedInput) -> PineRef<'a> {
+    let create: fn() -> Callable<'a> = match kind {
+        TypedInput::Bool => {
+            || Callable::new(None, Some(Box::new(TypedInputCall::new(TypedInput::Bool))))
+        }
+        TypedInput::Int => {
+            || Callable::new(None, Some(Box::new(TypedInputCall::new(TypedInput::Int))))
+        }
+        TypedInput::Float => {
+            || Callable::new(None, Some(Box::new(TypedInputCall::new(TypedInput::Float))))
+        }
+        TypedInput::String => || {
+            Callable::new(
+                None,
+                Some(Box::new(TypedInputCall::new(TypedInput::String))),
+            )
+        },
+        TypedInput::Source => || {
+            Callable::new(
+                None,
+                Some(Box::new(TypedInputCall::new(TypedInput::Source))),
+            )
+        },
+        TypedInput::Time => {
+            || Callable::new(None, Some(Box::new(TypedInputCall::new(TypedInput::Time))))
+        }
+        TypedInput::Price => {
+            || Callable::new(None, Some(Box::new(TypedInputCall::new(TypedInput::Price))))
+        }
+        TypedInput::Color => {
+            || Callable::new(None, Some(Box::new(TypedInputCall::new(TypedInput::Color))))
+        }
+        TypedInput::Session => || {
+            Callable::new(
+                None,
+                Some(Box::new(TypedInputCall::new(TypedInput::Session))),
+            )
+        },
+        TypedInput::Resolution => || {
+            Callable::new(
+                None,
+                Some(Box::new(TypedInputCall::new(TypedInput::Resolution))),
+            )
+        },
+        TypedInput::Symbol => || {
+            Callable::new(
+                None,
+                Some(Box::new(TypedInputCall::new(TypedInput::Symbol))),
+            )
+        },
+    };
+    // The simple callable object passes the arguments without copying them, so
+    // input.source can find the name of the origin source series.
+    PineRef::new(SimpleCallableObject::new(Box::new(TypedInputProps), create))
+}
+
+struct TypedInputProps;
+
+impl<'a> PineClass<'a> for TypedInputProps {
+    fn custom_type(&self) -> &str {
+        "input"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        Err(RuntimeErr::NotImplement(str_replace(
+            NO_FIELD_IN_OBJECT,
+            vec![String::from(name), String::from("input")],
+        )))
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(TypedInputProps)
+    }
+}
+
+struct InputV5Props;
+
+impl<'a> PineClass<'a> for InputV5Props {
+    fn custom_type(&self) -> &str {
+        "input"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match V5_INPUT_FIELDS.iter().find(|f| f.0 == name) {
+            Some(&(_, kind)) => Ok(gen_typed_input(kind)),
+            None => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("input")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(InputV5Props)
+    }
+}
+
+// The input of Pine v5, the fields like input.int are functions instead of type names.
+pub fn declare_v5_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(SimpleCallableObject::new(Box::new(InputV5Props), || {
+        Callable::new(None, Some(Box::new(InputCall::new())))
+    }));
+    let mut obj_type = BTreeMap::new();
+    for &(name, kind) in V5_INPUT_FIELDS {
+        obj_type.insert(
+            name,
+            SyntaxType::Function(Arc::new(FunctionTypes(vec![gen_typed_func_type(kind)]))),
+        );
+    }
+    let syntax_type = SyntaxType::ObjectFunction(Arc::new(obj_type), Arc::new(gen_input_types()));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -492,7 +1020,7 @@ mod tests {
     use super::*;
     use crate::ast::stat_expr_types::VarIndex;
     use crate::runtime::{AnySeries, NoneCallback, VarOperate};
-    use crate::types::PineRef;
+    use crate::types::{PineRef, Series};
     use crate::{LibInfo, PineParser, PineRunner};
 
     #[test]
@@ -566,6 +1094,9 @@ mod tests {
                 title: Some(String::from("title")),
                 input_type: String::from("bool"),
                 confirm: Some(false),
+                tooltip: None,
+                inline: None,
+                group: None,
             })]
         )
     }
@@ -592,7 +1123,7 @@ mod tests {
             Some(PineRef::new_box(Some(1)))
         );
 
-        runner.change_inputs(vec![Some(InputVal::Int(4))]);
+        runner.change_inputs(vec![Some(InputVal::Int(3))]);
         runner
             .run(
                 &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
@@ -601,7 +1132,7 @@ mod tests {
             .unwrap();
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(0, 0)),
-            Some(PineRef::new_box(Some(4)))
+            Some(PineRef::new_box(Some(3)))
         );
         assert_eq!(
             runner.get_io_info().get_inputs(),
@@ -613,7 +1144,10 @@ mod tests {
                 minval: Some(1),
                 maxval: Some(10),
                 step: Some(1),
-                options: Some(vec![1, 2, 3])
+                options: Some(vec![1, 2, 3]),
+                tooltip: None,
+                inline: None,
+                group: None,
             })]
         )
     }
@@ -698,6 +1232,9 @@ mod tests {
                     String::from("SMA"),
                     String::from("EMA")
                 ]),
+                tooltip: None,
+                inline: None,
+                group: None,
             })]
         );
     }
@@ -743,6 +1280,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn v5_typed_input_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_v5_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("open", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ],
+        );
+        let src = "m1 = input.int(1, 'hello', maxval=10, step=2)
+m2 = input.bool(true, 'b')
+m3 = input.timeframe('D', 'tf', options=['D', 'W'])
+m4 = input.source(close, 's')";
+
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        let data = vec![
+            ("close", AnySeries::from_float_vec(vec![Some(1f64)])),
+            ("open", AnySeries::from_float_vec(vec![Some(2f64)])),
+        ];
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_io_info().get_inputs(),
+            &vec![
+                InputInfo::Int(IntInputInfo {
+                    defval: Some(1),
+                    title: Some(String::from("hello")),
+                    input_type: String::from("int"),
+                    confirm: None,
+                    minval: None,
+                    maxval: Some(10),
+                    step: Some(2),
+                    options: None,
+                    tooltip: None,
+                    inline: None,
+                    group: None,
+                }),
+                InputInfo::Bool(BoolInputInfo {
+                    defval: Some(true),
+                    title: Some(String::from("b")),
+                    input_type: String::from("bool"),
+                    confirm: None,
+                    tooltip: None,
+                    inline: None,
+                    group: None,
+                }),
+                InputInfo::Resolution(StringInputInfo {
+                    defval: Some(String::from("D")),
+                    title: Some(String::from("tf")),
+                    input_type: String::from("resolution"),
+                    confirm: None,
+                    options: Some(vec![String::from("D"), String::from("W")]),
+                    tooltip: None,
+                    inline: None,
+                    group: None,
+                }),
+                InputInfo::Source(SourceInputInfo {
+                    defval: Some(String::from("close")),
+                    title: Some(String::from("s")),
+                    input_type: String::from("source"),
+                    tooltip: None,
+                    inline: None,
+                    group: None,
+                }),
+            ]
+        );
+
+        runner.change_inputs(vec![
+            Some(InputVal::Int(4)),
+            Some(InputVal::Bool(false)),
+            Some(InputVal::String(String::from("W"))),
+            Some(InputVal::String(String::from("open"))),
+        ]);
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_box(Some(4)))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_box(false))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new_rc(String::from("W")))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(2f64)])))
+        );
+    }
+
     #[test]
     fn input_fields_test() {
         use crate::types::Tuple;
@@ -773,11 +1403,11 @@ mod tests {
                 PineRef::new_rc(String::from(BOOL_TYPE_STR)),
                 PineRef::new_rc(String::from(FLOAT_TYPE_STR)),
                 PineRef::new_rc(String::from(INT_TYPE_STR)),
-                PineRef::new_rc(String::from(STRING_TYPE_STR)),
-                PineRef::new_rc(String::from(STRING_TYPE_STR)),
+                PineRef::new_rc(String::from(RESOLUTION_TYPE_STR)),
+                PineRef::new_rc(String::from(SESSION_TYPE_STR)),
                 PineRef::new_rc(String::from(SOURCE_TYPE_STR)),
                 PineRef::new_rc(String::from(STRING_TYPE_STR)),
-                PineRef::new_rc(String::from(STRING_TYPE_STR)),
+                PineRef::new_rc(String::from(SYMBOL_TYPE_STR)),
             ])
         );
     }
@@ -804,4 +1434,188 @@ mod tests {
             Some(PineRef::new_box(Some(2f64)))
         );
     }
+
+    #[test]
+    fn input_kinds_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_v5_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "t = input.time(1000, 'start', tooltip='tip', inline='l1', group='g')
+p = input.price(1.5, 'price', group='g')
+c = input.color(#ff0000, 'c')
+s = input.session('0930-1600', 'sess', options=['0930-1600', '0000-2359'])
+y = input.symbol('AAPL', 'sym')
+m = input.int(5, 'len', minval=1, maxval=10)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+        runner.run(&data, None).unwrap();
+
+        let inputs = runner.get_io_info().get_inputs();
+        assert_eq!(
+            inputs[0],
+            InputInfo::Time(TimeInputInfo {
+                defval: Some(1000),
+                title: Some(String::from("start")),
+                input_type: String::from("time"),
+                confirm: None,
+                tooltip: Some(String::from("tip")),
+                inline: Some(String::from("l1")),
+                group: Some(String::from("g")),
+            })
+        );
+        assert_eq!(
+            inputs[1],
+            InputInfo::Price(PriceInputInfo {
+                defval: Some(1.5),
+                title: Some(String::from("price")),
+                input_type: String::from("price"),
+                confirm: None,
+                tooltip: None,
+                inline: None,
+                group: Some(String::from("g")),
+            })
+        );
+        assert_eq!(
+            inputs[2],
+            InputInfo::Color(ColorInputInfo {
+                defval: Some(String::from("#ff0000")),
+                title: Some(String::from("c")),
+                input_type: String::from("color"),
+                confirm: None,
+                tooltip: None,
+                inline: None,
+                group: None,
+            })
+        );
+        assert_eq!(inputs[3].get_input_type(), "session");
+        assert_eq!(inputs[4].get_input_type(), "symbol");
+
+        runner.change_inputs(vec![
+            Some(InputVal::Time(2000)),
+            Some(InputVal::Price(2.5)),
+            Some(InputVal::Color(String::from("#00ff00"))),
+            Some(InputVal::Session(String::from("0000-2359"))),
+            Some(InputVal::Symbol(String::from("MSFT"))),
+            Some(InputVal::Int(7)),
+        ]);
+        runner.run(&data, None).unwrap();
+        let context = runner.get_context();
+        assert_eq!(
+            context.move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_box(Some(2000i64)))
+        );
+        assert_eq!(
+            context.move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_box(Some(2.5f64)))
+        );
+        // The color supplied by the client is owned by the runtime instead of being leaked.
+        let color = downcast_pf::<Color>(context.move_var(VarIndex::new(2, 0)).unwrap()).unwrap();
+        assert_eq!(color.0, "#00ff00");
+        assert!(matches!(color.0, Cow::Owned(_)));
+        assert_eq!(
+            context.move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(String::from("0000-2359")))
+        );
+        assert_eq!(
+            context.move_var(VarIndex::new(4, 0)),
+            Some(PineRef::new_rc(String::from("MSFT")))
+        );
+
+        // The values out of the range, the options or with the wrong types are rejected.
+        let io_info = runner.get_io_info().clone();
+        let check = |index: usize, val: InputVal| {
+            let mut inputs = vec![None; 6];
+            inputs[index] = Some(val);
+            io_info.check_inputs(&inputs)
+        };
+        assert_eq!(
+            check(5, InputVal::Int(11)),
+            Err(RuntimeErr::InvalidInputVal(
+                String::from("len"),
+                String::from("The value 11 is greater than the maximum value 10.")
+            ))
+        );
+        assert_eq!(
+            check(5, InputVal::Int(0)),
+            Err(RuntimeErr::InvalidInputVal(
+                String::from("len"),
+                String::from("The value 0 is less than the minimum value 1.")
+            ))
+        );
+        assert_eq!(
+            check(3, InputVal::Session(String::from("0800-1200"))),
+            Err(RuntimeErr::InvalidInputVal(
+                String::from("sess"),
+                String::from("The value 0800-1200 should be one of 0930-1600, 0000-2359.")
+            ))
+        );
+        assert_eq!(
+            check(2, InputVal::Color(String::from("red"))),
+            Err(RuntimeErr::InvalidInputVal(
+                String::from("c"),
+                String::from("The color red should be like #RRGGBB or #RRGGBBAA.")
+            ))
+        );
+        assert_eq!(
+            check(0, InputVal::String(String::from("a"))),
+            Err(RuntimeErr::InvalidInputVal(
+                String::from("start"),
+                String::from("time is expected, not a string.")
+            ))
+        );
+        assert_eq!(check(1, InputVal::Int(2)), Ok(()));
+
+        // The invalid value is also rejected when the script runs.
+        runner.change_inputs(vec![None, None, None, None, None, Some(InputVal::Int(11))]);
+        assert!(runner.run(&data, None).is_err());
+    }
+
+    #[test]
+    fn v4_input_kinds_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "t = input(1000, 'start', input.time, group='g')
+r = input('D', 'res', input.resolution)
+c = input(#ff0000, 'c', input.color, inline='l1')";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+        runner.run(&data, None).unwrap();
+
+        let inputs = runner.get_io_info().get_inputs();
+        assert_eq!(
+            inputs
+                .iter()
+                .map(|input| input.get_input_type().as_str())
+                .collect::<Vec<_>>(),
+            vec!["time", "resolution", "color"]
+        );
+        match &inputs[0] {
+            InputInfo::Time(info) => assert_eq!(info.group, Some(String::from("g"))),
+            _ => unreachable!(),
+        }
+        match &inputs[2] {
+            InputInfo::Color(info) => assert_eq!(info.inline, Some(String::from("l1"))),
+            _ => unreachable!(),
+        }
+
+        runner.change_inputs(vec![
+            Some(InputVal::Int(5)),
+            Some(InputVal::String(String::from("W"))),
+            None,
+        ]);
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_box(Some(5i64)))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(String::from("W")))
+        );
+    }
 }
@@ -11,6 +11,7 @@ use crate::helper::{
     pine_ref_to_f64_series, pine_ref_to_i64, require_param,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_float, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableCreator, CallableFactory, Evaluate,
@@ -19,7 +20,7 @@ use crate::types::{
 };
 use std::f64;
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub type ValGenerator<'a> = fn((Float, Float, Float)) -> PineRef<'a>;
 
@@ -111,6 +112,20 @@ impl<'a> SeriesCall<'a> for KcVal {
         Ok(func(self.process_kc(_ctx, param, _func_type)?))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            StateValue::Float(self.prev_basis),
+            StateValue::Float(self.prev_range_ema),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.prev_basis = load_float(state.next())?;
+        self.prev_range_ema = load_float(state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -133,13 +148,13 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             ("mult", SyntaxType::float()),
             ("useTrueRange", SyntaxType::bool()),
         ],
-        SyntaxType::Tuple(Rc::new(vec![
+        SyntaxType::Tuple(Arc::new(vec![
             SyntaxType::float_series(),
             SyntaxType::float_series(),
             SyntaxType::float_series(),
         ])),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "kc")
 }
 
@@ -17,7 +17,7 @@ use crate::types::{
     EvaluateVal, Float, Int, ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall,
     Tuple,
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn gen_kcw<'a>(vals: (Float, Float, Float)) -> PineRef<'a> {
     let (basis, ema1, ema2) = vals;
@@ -43,7 +43,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "kcw")
 }
 
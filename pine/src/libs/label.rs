@@ -20,6 +20,7 @@ use std::fmt;
 use std::mem;
 use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::Arc;
 
 pub type PerLabelItem = Rc<RefCell<Option<PerLabel>>>;
 
@@ -234,7 +235,7 @@ fn pine_ref_to_label<'a>(val: Option<PineRef<'a>>) -> PerLabelItem {
 }
 
 // The label definition that represent every label object.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PerLabel {
     // x, y, text, xloc, yloc, color, style, textcolor, size, textalign
     x: Int,
@@ -627,7 +628,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     let mut obj_type = BTreeMap::new();
     obj_type.insert(
         "delete",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("label"))],
             SyntaxType::Void,
         ))]))),
@@ -635,7 +636,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert(
         "new",
         // x, y, text, xloc, yloc, color, style, textcolor, size, textalign
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("x", SyntaxType::int_series()),
                 ("y", SyntaxType::float_series()),
@@ -653,21 +654,21 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "get_x",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("label"))],
             SyntaxType::int_series(),
         ))]))),
     );
     obj_type.insert(
         "get_y",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("label"))],
             SyntaxType::float_series(),
         ))]))),
     );
     obj_type.insert(
         "get_text",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("label"))],
             SyntaxType::string_series(),
         ))]))),
@@ -675,7 +676,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
 
     obj_type.insert(
         "set_x",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("x", SyntaxType::int_series()),
@@ -685,7 +686,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_y",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("x", SyntaxType::float_series()),
@@ -695,7 +696,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_xy",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("x", SyntaxType::int_series()),
@@ -706,7 +707,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_color",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("color", SyntaxType::color_series()),
@@ -716,7 +717,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_size",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("size", SyntaxType::string_series()),
@@ -726,7 +727,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_style",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("style", SyntaxType::string_series()),
@@ -736,7 +737,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_text",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("text", SyntaxType::string_series()),
@@ -746,7 +747,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_textalign",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("textalign", SyntaxType::string_series()),
@@ -756,7 +757,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_textcolor",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("textcolor", SyntaxType::color_series()),
@@ -766,7 +767,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_xloc",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("x", SyntaxType::int_series()),
@@ -777,7 +778,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_yloc",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("label")),
                 ("yloc", SyntaxType::string_series()),
@@ -802,7 +803,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("style_triangledown", SyntaxType::string());
     obj_type.insert("style_triangleup", SyntaxType::string());
     obj_type.insert("style_xcross", SyntaxType::string());
-    let syntax_type = SyntaxType::ObjectFunction(Rc::new(obj_type), Rc::new(func_type));
+    let syntax_type = SyntaxType::ObjectFunction(Arc::new(obj_type), Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -20,6 +20,7 @@ use std::fmt;
 use std::mem;
 use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::Arc;
 
 pub type PerLineItem = Rc<RefCell<Option<PerLine>>>;
 
@@ -123,7 +124,7 @@ fn pine_ref_to_line<'a>(val: Option<PineRef<'a>>) -> PerLineItem {
 }
 
 // The line definition that represent every line object.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PerLine {
     x1: Int,
     y1: Float,
@@ -255,10 +256,16 @@ impl<'a> SeriesCall<'a> for LineFromNaVal<'a> {
                         }
                     },
                 },
-                extend: 0,
-                color: None,
-                style: 0,
-                width: None,
+                extend: match pine_ref_to_string(extend) {
+                    None => 0,
+                    Some(s) => ExtendEnum::from_str(&s[..])? as i32,
+                },
+                color: pine_ref_to_color(color),
+                style: match pine_ref_to_string(style) {
+                    None => 0,
+                    Some(s) => StyleEnum::from_str(&s[..])? as i32,
+                },
+                width: pine_ref_to_f64(width).map(|v| v as i32),
             };
             self.lines.update(Rc::new(RefCell::new(Some(line))));
             Ok(RefData::clone(&self.lines).into_pf())
@@ -403,7 +410,7 @@ fn set_extend_func<'a>(
     _func_type: FunctionType<'a>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
     set_val_func(_context, param, |l, v| {
-        l.extend = match pine_ref_to_color(v) {
+        l.extend = match pine_ref_to_string(v) {
             None => 0,
             Some(v) => ExtendEnum::from_str(&v[..])? as i32,
         };
@@ -553,14 +560,14 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     let mut obj_type = BTreeMap::new();
     obj_type.insert(
         "delete",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("line"))],
             SyntaxType::Void,
         ))]))),
     );
     obj_type.insert(
         "new",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("x1", SyntaxType::int_series()),
                 ("y1", SyntaxType::float_series()),
@@ -577,35 +584,35 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "get_x1",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("line"))],
             SyntaxType::int_series(),
         ))]))),
     );
     obj_type.insert(
         "get_x2",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("line"))],
             SyntaxType::int_series(),
         ))]))),
     );
     obj_type.insert(
         "get_y1",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("line"))],
             SyntaxType::float_series(),
         ))]))),
     );
     obj_type.insert(
         "get_y2",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![("id", SyntaxType::ObjectClass("line"))],
             SyntaxType::float_series(),
         ))]))),
     );
     obj_type.insert(
         "set_color",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("color", SyntaxType::color_series()),
@@ -615,7 +622,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_extend",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("extend", SyntaxType::string_series()),
@@ -625,7 +632,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_style",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("extend", SyntaxType::string_series()),
@@ -635,7 +642,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_width",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("extend", SyntaxType::int_series()),
@@ -645,7 +652,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_x1",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("x", SyntaxType::int_series()),
@@ -655,7 +662,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_x2",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("x", SyntaxType::int_series()),
@@ -665,7 +672,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_y1",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("y", SyntaxType::float_series()),
@@ -675,7 +682,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_y2",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("y", SyntaxType::float_series()),
@@ -685,7 +692,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_xloc",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("x1", SyntaxType::int_series()),
@@ -697,7 +704,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_xy1",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("x", SyntaxType::int_series()),
@@ -708,7 +715,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "set_xy2",
-        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("id", SyntaxType::ObjectClass("line")),
                 ("x", SyntaxType::int_series()),
@@ -725,7 +732,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("style_dotted", SyntaxType::string());
     obj_type.insert("style_solid", SyntaxType::string());
 
-    let syntax_type = SyntaxType::ObjectFunction(Rc::new(obj_type), Rc::new(func_type));
+    let syntax_type = SyntaxType::ObjectFunction(Arc::new(obj_type), Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
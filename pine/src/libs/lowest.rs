@@ -14,7 +14,6 @@ use crate::types::{
     PineRef, RefData, RuntimeErr, Series, SeriesCall,
 };
 use std::mem;
-use std::rc::Rc;
 
 pub fn get_min_val<'a>(source: &Option<RefData<Series<Float>>>, length: i64) -> Float {
     let mut min_val = Some(std::f64::MAX);
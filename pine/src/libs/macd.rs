@@ -11,6 +11,7 @@ use crate::helper::{
     pine_ref_to_i64, require_param,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_series, save_series, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableCreator, CallableFactory, Evaluate,
@@ -18,7 +19,7 @@ use crate::types::{
     Tuple,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub type ValGenerator<'a> = fn((Float, Float, Float)) -> PineRef<'a>;
 
@@ -85,6 +86,22 @@ impl<'a> SeriesCall<'a> for KcVal<'a> {
         Ok(val_generator(self.process_macd(_ctx, param, _func_type)?))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            save_series(&self.ema1s),
+            save_series(&self.ema2s),
+            save_series(&self.dems),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        load_series(&mut self.ema1s, state.next())?;
+        load_series(&mut self.ema2s, state.next())?;
+        load_series(&mut self.dems, state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -107,13 +124,13 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             ("slowlen", SyntaxType::int()),
             ("siglen", SyntaxType::int()),
         ],
-        SyntaxType::Tuple(Rc::new(vec![
+        SyntaxType::Tuple(Arc::new(vec![
             SyntaxType::float_series(),
             SyntaxType::float_series(),
             SyntaxType::float_series(),
         ])),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "macd")
 }
 
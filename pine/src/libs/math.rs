@@ -0,0 +1,455 @@
+use super::cos::declare_math_var;
+use super::sum::sum_func;
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::helper::{
+    ge1_param_i64, move_element, pine_ref_to_f64, pine_ref_to_f64_series, pine_ref_to_i64,
+    require_param,
+};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_int, StateValue};
+use crate::types::{
+    Callable, CallableFactory, Float, ParamCollectCall, PineRef, RuntimeErr, Series, SeriesCall,
+};
+use std::f64::consts;
+use std::sync::Arc;
+
+// The seed of the random generator if the script does not specify it, so the backtests
+// are reproducible.
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+// Generate the simple or series value by the return type of the function.
+fn gen_float_ref<'a>(val: Float, func_type: &FunctionType<'a>) -> PineRef<'a> {
+    match func_type.signature.1 {
+        SyntaxType::Series(_) => PineRef::new_rc(Series::from(val)),
+        _ => PineRef::new_box(val),
+    }
+}
+
+fn declare_const_var<'a>(name: &'static str, val: f64) -> VarResult<'a> {
+    VarResult::new(PineRef::new_box(Some(val)), SyntaxType::float(), name)
+}
+
+pub fn declare_pi_var<'a>() -> VarResult<'a> {
+    declare_const_var("pi", consts::PI)
+}
+
+pub fn declare_e_var<'a>() -> VarResult<'a> {
+    declare_const_var("e", consts::E)
+}
+
+// The golden ratio and its reciprocal.
+pub fn declare_phi_var<'a>() -> VarResult<'a> {
+    declare_const_var("phi", (1f64 + 5f64.sqrt()) / 2f64)
+}
+
+pub fn declare_rphi_var<'a>() -> VarResult<'a> {
+    declare_const_var("rphi", (5f64.sqrt() - 1f64) / 2f64)
+}
+
+fn float_todegrees<'a>(xval: Option<PineRef<'a>>) -> Float {
+    pine_ref_to_f64(xval).map(|v| v.to_degrees())
+}
+
+pub fn declare_todegrees_var<'a>() -> VarResult<'a> {
+    declare_math_var("todegrees", float_todegrees)
+}
+
+fn float_toradians<'a>(xval: Option<PineRef<'a>>) -> Float {
+    pine_ref_to_f64(xval).map(|v| v.to_radians())
+}
+
+pub fn declare_toradians_var<'a>() -> VarResult<'a> {
+    declare_math_var("toradians", float_toradians)
+}
+
+fn round_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((x, precision) = param);
+    let x = pine_ref_to_f64(x);
+    if func_type.signature.0.len() == 1 {
+        let res = x.map(|v| v.round() as i64);
+        return match func_type.signature.1 {
+            SyntaxType::Series(_) => Ok(PineRef::new_rc(Series::from(res))),
+            _ => Ok(PineRef::new_box(res)),
+        };
+    }
+    let precision = require_param("precision", pine_ref_to_i64(precision))?;
+    let scale = 10f64.powi(precision as i32);
+    Ok(gen_float_ref(
+        x.map(|v| (v * scale).round() / scale),
+        &func_type,
+    ))
+}
+
+// The round function without the precision returns the integer, otherwise it rounds the
+// value to the number of the decimal places.
+pub fn declare_round_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Callable::new(Some(round_func), None));
+    let func_type = FunctionTypes(vec![
+        FunctionType::new((vec![("x", SyntaxType::float())], SyntaxType::int())),
+        FunctionType::new((
+            vec![("x", SyntaxType::float_series())],
+            SyntaxType::int_series(),
+        )),
+        FunctionType::new((
+            vec![("x", SyntaxType::float()), ("precision", SyntaxType::int())],
+            SyntaxType::float(),
+        )),
+        FunctionType::new((
+            vec![
+                ("x", SyntaxType::float_series()),
+                ("precision", SyntaxType::int()),
+            ],
+            SyntaxType::float_series(),
+        )),
+    ]);
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
+    VarResult::new(value, syntax_type, "round")
+}
+
+fn round_to_mintick_func<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let x = pine_ref_to_f64(move_element(&mut param, 0));
+    let mintick = match downcast_ctx(context.get_main_ctx()).get_syminfo() {
+        Some(syminfo) if syminfo.mintick > 0f64 => Some(syminfo.mintick),
+        _ => None,
+    };
+    // The value is not rounded without the symbol information.
+    let res = match mintick {
+        Some(mintick) => x.map(|v| (v / mintick).round() * mintick),
+        None => x,
+    };
+    Ok(gen_float_ref(res, &func_type))
+}
+
+pub fn declare_round_to_mintick_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Callable::new(Some(round_to_mintick_func), None));
+    let func_type = FunctionTypes(vec![
+        FunctionType::new((vec![("x", SyntaxType::float())], SyntaxType::float())),
+        FunctionType::new((
+            vec![("x", SyntaxType::float_series())],
+            SyntaxType::float_series(),
+        )),
+    ]);
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
+    VarResult::new(value, syntax_type, "round_to_mintick")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SumVal {}
+
+impl<'a> SeriesCall<'a> for SumVal {
+    fn step(
+        &mut self,
+        _ctx: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        if func_type.signature.0[1].0 == "length" {
+            move_tuplet!((source, length) = param);
+            let source = require_param("source", pine_ref_to_f64_series(source))?;
+            let length = ge1_param_i64("length", pine_ref_to_i64(length))?;
+            return Ok(PineRef::new(Series::from(sum_func(source, length)?)));
+        }
+        let mut vals = param.into_iter().filter_map(pine_ref_to_f64).peekable();
+        // The sum is na if all of the arguments are na.
+        let res = vals.peek().is_some().then(|| vals.sum());
+        Ok(gen_float_ref(res, &func_type))
+    }
+
+    impl_no_state!();
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+fn gen_variadic_params<'a>(syntax_type: SyntaxType<'a>) -> Vec<(&'static str, SyntaxType<'a>)> {
+    vec!["x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10"]
+        .into_iter()
+        .map(|name| (name, syntax_type.clone()))
+        .collect()
+}
+
+// The sum function calculates the sliding sum of the source for the length, or the sum of
+// all of the arguments like the avg function.
+pub fn declare_sum_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| {
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                SumVal {},
+            )))),
+        )
+    }));
+    let func_type = FunctionTypes(vec![
+        FunctionType::new((
+            vec![
+                ("source", SyntaxType::float_series()),
+                ("length", SyntaxType::int()),
+            ],
+            SyntaxType::float_series(),
+        )),
+        FunctionType::new((
+            gen_variadic_params(SyntaxType::float()),
+            SyntaxType::float(),
+        )),
+        FunctionType::new((
+            gen_variadic_params(SyntaxType::float_series()),
+            SyntaxType::float_series(),
+        )),
+    ]);
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
+    VarResult::new(value, syntax_type, "sum")
+}
+
+// The xorshift64* generator, it generates the same sequence for the same seed.
+#[derive(Debug, Clone, PartialEq)]
+struct RandomVal {
+    state: Option<u64>,
+    // The state before the current bar, it restores the generator when the bar is rolled back.
+    prev_state: Option<u64>,
+}
+
+impl RandomVal {
+    fn new() -> RandomVal {
+        RandomVal {
+            state: None,
+            prev_state: None,
+        }
+    }
+
+    fn next_f64(&mut self, seed: Option<i64>) -> f64 {
+        let mut x = match self.state {
+            Some(state) => state,
+            None => match seed.map(|s| s as u64) {
+                // Zero is the fixed point of xorshift.
+                Some(s) if s != 0 => s,
+                _ => DEFAULT_SEED,
+            },
+        };
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = Some(x);
+        // Use the high 53 bits to generate the value in [0, 1).
+        (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl<'a> SeriesCall<'a> for RandomVal {
+    fn step(
+        &mut self,
+        _ctx: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        move_tuplet!((min, max, seed) = param);
+        let min = pine_ref_to_f64(min).unwrap_or(0f64);
+        let max = pine_ref_to_f64(max).unwrap_or(1f64);
+
+        self.prev_state = self.state;
+        let val = self.next_f64(pine_ref_to_i64(seed));
+        Ok(PineRef::new_rc(Series::from(Some(min + (max - min) * val))))
+    }
+
+    fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.state = self.prev_state;
+        Ok(())
+    }
+
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        // The generator state is saved as the bits of the integer.
+        let to_state = |state: Option<u64>| StateValue::Int(state.map(|s| s as i64));
+        Some(vec![to_state(self.state), to_state(self.prev_state)])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.state = load_int(state.next())?.map(|s| s as u64);
+        self.prev_state = load_int(state.next())?.map(|s| s as u64);
+        Ok(())
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+pub fn declare_random_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| {
+        Callable::new(None, Some(Box::new(RandomVal::new())))
+    }));
+    let func_type = FunctionTypes(vec![FunctionType::new((
+        vec![
+            ("min", SyntaxType::float()),
+            ("max", SyntaxType::float()),
+            ("seed", SyntaxType::Simple(SimpleSyntaxType::Int)),
+        ],
+        SyntaxType::float_series(),
+    ))]);
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
+    VarResult::new(value, syntax_type, "random")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::namespace;
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::output::SymbolInfo;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::types::downcast_pf;
+    use crate::{LibInfo, PineParser, PineRunner};
+    use std::rc::Rc;
+
+    fn declare_math<'a>() -> VarResult<'a> {
+        namespace::declare_var(
+            "math",
+            vec![
+                declare_pi_var(),
+                declare_e_var(),
+                declare_phi_var(),
+                declare_rphi_var(),
+                declare_todegrees_var(),
+                declare_toradians_var(),
+                declare_round_var(),
+                declare_round_to_mintick_var(),
+                declare_sum_var(),
+                declare_random_var(),
+            ],
+        )
+    }
+
+    #[test]
+    fn math_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_math()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m1 = math.pi\nm2 = math.phi * math.rphi\nm3 = math.todegrees(math.pi)\n\
+                   m4 = math.round(2.345, 2)\nm5 = math.round(close)\n\
+                   m6 = math.round_to_mintick(close)\nm7 = math.sum(close, 2)\n\
+                   m8 = math.sum(close, 1.5, 2.5)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        let syminfo = Rc::new(SymbolInfo {
+            symbol_type: String::from("stock"),
+            timezone: String::from("America/New_York"),
+            ticker: String::from("MSFT"),
+            session: String::from(""),
+            trade_start: String::from(""),
+            trade_end: String::from(""),
+            root: None,
+            currency: String::from("USD"),
+            description: String::from(""),
+            mintick: 0.25,
+        });
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1.375f64), Some(2.625f64)]),
+                )],
+                Some(syminfo),
+            )
+            .unwrap();
+        let float_at = |runner: &mut PineRunner, i| {
+            pine_ref_to_f64(runner.get_context().move_var(VarIndex::new(i, 0)))
+        };
+        assert_eq!(float_at(&mut runner, 0), Some(consts::PI));
+        assert!((float_at(&mut runner, 1).unwrap() - 1f64).abs() < 1e-12);
+        assert_eq!(float_at(&mut runner, 2), Some(180f64));
+        assert_eq!(float_at(&mut runner, 3), Some(2.35f64));
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(4, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(1i64),
+                Some(3i64)
+            ])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(5, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(1.5f64),
+                Some(2.75f64)
+            ])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(6, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![None, Some(4f64)])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(7, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(5.375f64),
+                Some(6.625f64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn random_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_math()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m1 = math.random(10, 20, 42)\nm2 = math.random(10, 20, 42)\n\
+                   m3 = math.random()";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+        )];
+        let mut run = || {
+            runner.run(&data, None).unwrap();
+            (0..3)
+                .map(|i| runner.get_context().move_var(VarIndex::new(i, 0)))
+                .collect::<Vec<_>>()
+        };
+        let values = run();
+        // The calls with the same seed generate the same sequence.
+        assert_eq!(values[0], values[1]);
+        let history = |val: &Option<PineRef>| {
+            downcast_pf::<Series<Float>>(val.clone().unwrap())
+                .unwrap()
+                .get_history()
+                .clone()
+        };
+        let vals = history(&values[0]);
+        assert_eq!(vals.len(), 3);
+        assert!(vals
+            .iter()
+            .all(|v| v.is_some_and(|v| (10f64..20f64).contains(&v))));
+        assert_ne!(vals[0], vals[1]);
+        assert!(history(&values[2])
+            .iter()
+            .all(|v| v.is_some_and(|v| (0f64..1f64).contains(&v))));
+
+        // The values are reproducible run to run.
+        assert_eq!(run(), values);
+
+        // The rolled back bar generates the same value again.
+        runner.run(&data, None).unwrap();
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(3f64)]),
+            )])
+            .unwrap();
+        assert_eq!(
+            history(&runner.get_context().move_var(VarIndex::new(0, 0))),
+            vals
+        );
+    }
+}
@@ -8,7 +8,7 @@ use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::types::{Callable, Float, Int, PineFrom, PineRef, RuntimeErr, Series, SeriesCall, NA};
 use std::cmp;
 use std::mem::transmute;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct MinMaxCallVal {
@@ -61,6 +61,8 @@ impl<'a> SeriesCall<'a> for MinMaxCallVal {
         }
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -141,7 +143,7 @@ pub fn declare_minmax_var<'a>(
             SyntaxType::float_series(),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, name)
 }
 
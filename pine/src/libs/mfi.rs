@@ -14,13 +14,14 @@ use crate::helper::{
     pine_ref_to_f64, pine_ref_to_f64_series, pine_ref_to_i64, require_param, series_index,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_series, save_series, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, float2int, int2float, Arithmetic, Callable, CallableCreator, CallableFactory,
     Evaluate, EvaluateVal, Float, Int, ParamCollectCall, PineRef, RefData, RuntimeErr, Series,
     SeriesCall, Tuple,
 };
-use std::rc::Rc;
+use std::sync::Arc;
 #[derive(Debug, Clone, PartialEq)]
 pub struct KcVal<'a> {
     volume_index: VarIndex,
@@ -103,6 +104,20 @@ impl<'a> SeriesCall<'a> for KcVal<'a> {
         Ok(PineRef::new_rc(Series::from(res)))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            save_series(&self.upper_history),
+            save_series(&self.lower_history),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        load_series(&mut self.upper_history, state.next())?;
+        load_series(&mut self.lower_history, state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -125,7 +140,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "mfi")
 }
 
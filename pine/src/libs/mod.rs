@@ -1,5 +1,6 @@
 pub mod abs;
 pub mod accdist;
+pub mod alert;
 pub mod alma;
 pub mod atr;
 pub mod avg;
@@ -18,6 +19,7 @@ pub mod cum;
 pub mod display;
 pub mod dmi;
 pub mod ema;
+pub mod extend;
 pub mod falling;
 pub mod fill;
 pub mod fixnan;
@@ -37,9 +39,11 @@ pub mod line;
 pub mod lowest;
 pub mod lowestbars;
 pub mod macd;
+pub mod math;
 pub mod max;
 pub mod mfi;
 pub mod na;
+pub mod namespace;
 pub mod nz;
 pub mod ohlc4;
 pub mod plot;
@@ -61,6 +65,7 @@ pub mod sum;
 pub mod swma;
 pub mod syminfo;
 pub mod text;
+pub mod ticker;
 pub mod time;
 pub mod timenow;
 pub mod timestamp;
@@ -119,13 +124,18 @@ pub fn declare_vars<'a>() -> Vec<VarResult<'a>> {
         // plotshape::declare_var(),
         color::declare_var(),
         study::declare_var(),
-        // syminfo::declare_var(),
+        syminfo::declare_var(),
         // barstate::declare_var(),
         accdist::declare_var(),
         time::declare_var(),
         timenow::declare_var(),
         timestamp::declare_var(),
-        // security::declare_var(),
+        security::declare_var(),
+        ticker::declare_heikinashi_var(),
+        ticker::declare_renko_var(),
+        ticker::declare_linebreak_var(),
+        ticker::declare_kagi_var(),
+        ticker::declare_pointfigure_var(),
         year::declare_year_var(),
         year::declare_month_var(),
         year::declare_weekofyear_var(),
@@ -206,6 +216,7 @@ pub fn declare_vars<'a>() -> Vec<VarResult<'a>> {
         // size::declare_var(),
         // text::declare_var(),
         display::declare_var(),
+        alert::declare_var(),
     ];
     debug_assert!(
         check_names(&list).len() == 0,
@@ -216,6 +227,141 @@ pub fn declare_vars<'a>() -> Vec<VarResult<'a>> {
     list
 }
 
+// The builtin variables of Pine v5, most of the functions are grouped into the ta, math
+// and request namespaces while the implementations are the same as v3/v4.
+pub fn declare_vars_v5<'a>() -> Vec<VarResult<'a>> {
+    let list = vec![
+        plot::declare_var(),
+        input::declare_v5_var(),
+        color::declare_var(),
+        study::declare_indicator_var(),
+        study::declare_library_var(),
+        syminfo::declare_var(),
+        time::declare_var(),
+        timenow::declare_var(),
+        timestamp::declare_var(),
+        year::declare_year_var(),
+        year::declare_month_var(),
+        year::declare_weekofyear_var(),
+        year::declare_dayofweek_var(),
+        year::declare_dayofmonth_var(),
+        year::declare_hour_var(),
+        year::declare_minute_var(),
+        na::declare_var(),
+        nz::declare_var(),
+        fixnan::declare_var(),
+        hl2::declare_var(),
+        hlc3::declare_var(),
+        ohlc4::declare_var(),
+        fill::declare_var(),
+        format::declare_var(),
+        hline::declare_var(),
+        display::declare_var(),
+        alert::declare_var(),
+        namespace::declare_var(
+            "ta",
+            vec![
+                accdist::declare_var(),
+                alma::declare_var(),
+                sma::declare_sma_var(),
+                sma::declare_wma_var(),
+                sma::declare_dev_var(),
+                sma::declare_stdev_var(),
+                sma::declare_variance_var(),
+                ema::declare_ema_var(),
+                ema::declare_rma_var(),
+                atr::declare_var(),
+                bb::declare_var(),
+                bbw::declare_var(),
+                cci::declare_var(),
+                change::declare_mom_var(),
+                change::declare_change_var(),
+                cmo::declare_var(),
+                cum::declare_var(),
+                cog::declare_var(),
+                correlation::declare_var(),
+                dmi::declare_var(),
+                falling::declare_var(),
+                rising::declare_var(),
+                highest::declare_var(),
+                lowest::declare_var(),
+                highestbars::declare_var(),
+                lowestbars::declare_var(),
+                hma::declare_var(),
+                tr::declare_var(),
+                kc::declare_var(),
+                kcw::declare_var(),
+                macd::declare_var(),
+                rsi::declare_var(),
+                mfi::declare_var(),
+                swma::declare_var(),
+                vwma::declare_var(),
+                tsi::declare_var(),
+                stoch::declare_var(),
+            ],
+        ),
+        namespace::declare_var(
+            "math",
+            vec![
+                abs::declare_var(),
+                cos::declare_cos_var(),
+                cos::declare_acos_var(),
+                cos::declare_sin_var(),
+                cos::declare_asin_var(),
+                cos::declare_tan_var(),
+                cos::declare_atan_var(),
+                cos::declare_exp_var(),
+                cos::declare_sqrt_var(),
+                cos::declare_log_var(),
+                cos::declare_log10_var(),
+                cos::declare_sign_var(),
+                max::declare_max_var(),
+                max::declare_min_var(),
+                avg::declare_var(),
+                ceil::declare_ceil_var(),
+                ceil::declare_floor_var(),
+                math::declare_round_var(),
+                math::declare_round_to_mintick_var(),
+                math::declare_pi_var(),
+                math::declare_e_var(),
+                math::declare_phi_var(),
+                math::declare_rphi_var(),
+                math::declare_todegrees_var(),
+                math::declare_toradians_var(),
+                math::declare_random_var(),
+                pow::declare_var(),
+                math::declare_sum_var(),
+            ],
+        ),
+        namespace::declare_var("request", vec![security::declare_var()]),
+        namespace::declare_var(
+            "ticker",
+            vec![
+                ticker::declare_heikinashi_var(),
+                ticker::declare_renko_var(),
+                ticker::declare_linebreak_var(),
+                ticker::declare_kagi_var(),
+                ticker::declare_pointfigure_var(),
+            ],
+        ),
+    ];
+    debug_assert!(
+        check_names(&list).is_empty(),
+        "Duplicate function names {:?}",
+        check_names(&list)
+    );
+    list
+}
+
+// Select the builtin variables by the version of the script.
+pub fn declare_vars_for_version<'a>(version: i32) -> Vec<VarResult<'a>> {
+    if version >= 5 {
+        declare_vars_v5()
+    } else {
+        declare_vars()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -223,5 +369,6 @@ mod tests {
     #[test]
     fn libname_test() {
         declare_vars();
+        declare_vars_v5();
     }
 }
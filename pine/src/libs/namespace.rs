@@ -0,0 +1,119 @@
+use super::VarResult;
+use crate::ast::syntax_type::SyntaxType;
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::runtime::context::Ctx;
+use crate::types::{Object, PineClass, PineRef, RuntimeErr};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// The namespace object of Pine v5 such as ta and math, it groups the builtin variables
+// so the script can access them by ta.sma, math.abs and so on.
+struct NamespaceProps<'a> {
+    name: &'static str,
+    vars: Rc<Vec<(&'static str, PineRef<'a>)>>,
+}
+
+impl<'a> PineClass<'a> for NamespaceProps<'a> {
+    fn custom_type(&self) -> &str {
+        self.name
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match self.vars.iter().find(|v| v.0 == name) {
+            Some(v) => Ok(v.1.clone()),
+            None => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from(self.name)],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(NamespaceProps {
+            name: self.name,
+            vars: Rc::clone(&self.vars),
+        })
+    }
+}
+
+pub fn declare_var<'a>(name: &'static str, vars: Vec<VarResult<'a>>) -> VarResult<'a> {
+    let mut obj_type = BTreeMap::new();
+    let mut values = vec![];
+    for var in vars {
+        obj_type.insert(var.name, var.syntax_type);
+        values.push((var.name, var.value));
+    }
+    let value = PineRef::new(Object::new(Box::new(NamespaceProps {
+        name,
+        vars: Rc::new(values),
+    })));
+    let syntax_type = SyntaxType::Object(Arc::new(obj_type));
+    VarResult::new(value, syntax_type, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{abs, sma};
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::types::Series;
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn namespace_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(
+                "ta",
+                vec![sma::declare_sma_var(), abs::declare_var()],
+            )],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m1 = ta.sma(close, 2)\nm2 = ta.sma(close, 3)\nm3 = ta.abs(-close)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(3f64), Some(6f64), Some(9f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                None,
+                Some(4.5f64),
+                Some(7.5f64)
+            ])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new(Series::from_vec(vec![None, None, Some(6f64)])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                Some(3f64),
+                Some(6f64),
+                Some(9f64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn namespace_no_field_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var("ta", vec![sma::declare_sma_var()])],
+            vec![("close", SyntaxType::float_series())],
+        );
+        assert!(PineParser::new("m = ta.ema(close, 2)", &lib_info)
+            .parse_blk()
+            .is_err());
+    }
+}
@@ -6,7 +6,7 @@ use crate::helper::{
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::types::{Callable, Float, Int, PineFrom, PineRef, RuntimeErr, Series, NA};
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn int_nz<'a>(xval: Option<PineRef<'a>>, yval: Option<PineRef<'a>>) -> Int {
     match pine_ref_to_i64(xval) {
@@ -93,7 +93,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             SyntaxType::float_series(),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
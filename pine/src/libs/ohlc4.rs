@@ -7,6 +7,7 @@ use crate::helper::{
     pine_ref_to_string,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_floats, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Evaluate, EvaluateVal, Float, Int, PineRef, RuntimeErr,
@@ -61,6 +62,16 @@ impl<'a> EvaluateVal<'a> for AccDistVal {
         Ok(())
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![StateValue::Floats(self.ad_history.clone())])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        self.ad_history = load_floats(state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
         Box::new(self.clone())
     }
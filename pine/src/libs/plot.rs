@@ -13,7 +13,7 @@ use crate::types::{
     PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall, NA,
 };
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn resize_offset<'a, T>(data: &mut Vec<Option<T>>, offset: i64) {
     match offset {
@@ -49,13 +49,13 @@ pub fn plot_color<'a>(
     // plot_series(item_data.into_pf(), context)
     let mut items: RefData<Series<Color<'a>>> = Series::implicity_from(item_val).unwrap();
     let colors: Vec<Color<'a>> = items.move_history();
-    let mut options: Vec<&'a str> = vec![];
+    let mut options: Vec<String> = vec![];
     let mut values: Vec<Option<i32>> = vec![];
 
-    for color in colors.iter() {
-        match options.iter().position(|&x| x == color.0) {
+    for color in colors.into_iter() {
+        match options.iter().position(|x| *x == color.0) {
             None => {
-                options.push(color.0);
+                options.push(color.0.into_owned());
                 values.push(Some((options.len() - 1) as i32));
             }
             Some(i) => {
@@ -63,7 +63,6 @@ pub fn plot_color<'a>(
             }
         }
     }
-    let options = options.iter().map(|&x| String::from(x)).collect();
     // resize_offset(&mut values, offset);
     Ok(StrOptionsData { options, values })
 }
@@ -157,6 +156,8 @@ impl<'a> SeriesCall<'a> for PlotVal {
         pine_plot(_context, params, func_type)
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -254,7 +255,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("style_line", SyntaxType::string());
     obj_type.insert("style_linebr", SyntaxType::string());
     obj_type.insert("style_stepline", SyntaxType::string());
-    let syntax_type = SyntaxType::ObjectFunction(Rc::new(obj_type), Rc::new(func_type));
+    let syntax_type = SyntaxType::ObjectFunction(Arc::new(obj_type), Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
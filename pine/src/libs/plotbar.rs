@@ -12,7 +12,7 @@ use crate::types::{
     Bool, Callable, CallableFactory, CallableObject, DataType, Float, Int, ParamCollectCall,
     PineClass, PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, NA,
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn pine_plot<'a>(
     context: &mut dyn Ctx<'a>,
@@ -89,7 +89,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::Void,
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
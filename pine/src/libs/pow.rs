@@ -3,7 +3,7 @@ use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, Syn
 use crate::helper::{move_element, pine_ref_to_f64, pine_ref_to_i64};
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::types::{Callable, Float, Int, PineFrom, PineRef, RuntimeErr, Series, SeriesCall, NA};
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn float_pow<'a>(base: Option<PineRef<'a>>, exponent: Option<PineRef<'a>>) -> Float {
     match (pine_ref_to_f64(base), pine_ref_to_f64(exponent)) {
@@ -30,6 +30,8 @@ impl<'a> SeriesCall<'a> for MathCallVal {
         }
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -54,7 +56,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             SyntaxType::float_series(),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "pow")
 }
 
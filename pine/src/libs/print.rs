@@ -7,7 +7,7 @@ use crate::types::{
 };
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::rc::Rc;
+use std::sync::Arc;
 
 trait Format {
     fn fmt(&self) -> String;
@@ -60,7 +60,9 @@ where
         .map(|v| Format::fmt(v))
         .collect::<Vec<String>>()
         .join(",");
-    // context.get_callback().unwrap().print(s);
+    if let Some(callback) = context.get_callback() {
+        callback.print(s);
+    }
     Ok(())
 }
 
@@ -78,7 +80,9 @@ where
 
     // println!("items str {:?}", s);
 
-    context.get_callback().unwrap().print(s);
+    if let Some(callback) = context.get_callback() {
+        callback.print(s);
+    }
     Ok(())
 }
 
@@ -88,7 +92,9 @@ where
 {
     let item: RefData<D> = D::implicity_from(item_val).unwrap();
     let s: String = Format::fmt(&*item);
-    context.get_callback().unwrap().print(s);
+    if let Some(callback) = context.get_callback() {
+        callback.print(s);
+    }
     Ok(())
 }
 
@@ -133,7 +139,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     let value = PineRef::new(CallableFactory::new(|| {
         Callable::new(None, Some(Box::new(ParamCollectCall::new(pine_print))))
     }));
-    let syntax_type = SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+    let syntax_type = SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
         vec![("item", SyntaxType::Series(SimpleSyntaxType::Float))],
         SyntaxType::Void,
     ))])));
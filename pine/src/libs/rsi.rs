@@ -12,6 +12,7 @@ use crate::helper::{
     pine_ref_to_f64, pine_ref_to_f64_series, pine_ref_to_i64, require_param, series_index,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_series, save_series, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableCreator, CallableFactory, Evaluate,
@@ -19,7 +20,7 @@ use crate::types::{
     Tuple,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub fn calc_rsi(
     s0: Float,
@@ -103,6 +104,20 @@ impl<'a> SeriesCall<'a> for KcVal<'a> {
         Ok(PineRef::new_rc(Series::from(res)))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            save_series(&self.upwards),
+            save_series(&self.downwards),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        load_series(&mut self.upwards, state.next())?;
+        load_series(&mut self.downwards, state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -131,7 +146,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             SyntaxType::float_series(),
         )),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "rsi")
 }
 
@@ -1,42 +1,190 @@
 use super::VarResult;
 use crate::ast::input::StrRange;
+use crate::ast::name::VarName;
 use crate::ast::stat_expr_types::VarIndex;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::bars::{series_float_at, AggregateKind, BarAggregator, BarBuilder};
 use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
 use crate::helper::{
     move_element, pine_ref_to_bool, pine_ref_to_color, pine_ref_to_f64, pine_ref_to_i64,
     pine_ref_to_string,
 };
+use crate::helper::{Resolution, Session, TradeTimeSpan};
 use crate::runtime::context::{
     downcast_ctx, downcast_ctx_const, Context, ContextType, Ctx, VarOperate,
 };
 use crate::runtime::function::Function;
-use crate::runtime::output::InputSrc;
+use crate::runtime::output::{InputSrc, SymbolInfo};
 use crate::runtime::{AnySeries, AnySeriesType};
+use crate::synthetic::{Bar, ChartType, SyntheticBuilder, SyntheticTicker};
 use crate::types::{
     downcast_pf, Callable, CallableFactory, Color, DataType, Float, Int, PineFrom, PineRef,
     RefData, RuntimeErr, Series, SeriesCall, NA,
 };
+use chrono_tz::Tz;
+use std::borrow::Cow;
 use std::mem;
-use std::mem::ManuallyDrop;
 use std::rc::Rc;
+use std::sync::Arc;
+
+// The sources of the bars that the synthetic charts are built from.
+const BAR_SRCS: [&str; 5] = ["open", "high", "low", "close", "volume"];
+
+enum DerivedSource {
+    // Resample the chart data to the resolution of the ticker.
+    Chart {
+        builder: BarBuilder,
+        // The indexes of the chart variables for the sources.
+        var_indexes: Vec<Option<VarIndex>>,
+    },
+    // The data of the underlying symbol given by the client and the count of the bars read.
+    Input {
+        ticker: String,
+        len: usize,
+    },
+}
+
+// The data of the ticker derived from the chart data, or the synthetic chart built from the
+// data of the underlying symbol. It is used when the client does not provide the ticker data.
+struct DerivedData {
+    source: DerivedSource,
+    // The names of the sources of the bars, e.g. close and volume.
+    srcs: Vec<&'static str>,
+    synthetic: Option<SyntheticBuilder>,
+    // The indexes of the sources for the parameters of the expression.
+    param_srcs: Vec<Option<usize>>,
+    time_param: Option<usize>,
+    types: Vec<AnySeriesType>,
+    times: Vec<Int>,
+    columns: Vec<Vec<Float>>,
+}
+
+impl DerivedData {
+    fn update<'a>(&mut self, context: &mut dyn Ctx<'a>, time: i64) {
+        let mut bars = vec![];
+        match &mut self.source {
+            DerivedSource::Chart {
+                builder,
+                var_indexes,
+            } => {
+                let values: Vec<Float> = var_indexes
+                    .iter()
+                    .map(|index| {
+                        index.and_then(|index| pine_ref_to_f64(context.get_var(index).clone()))
+                    })
+                    .collect();
+                bars.extend(builder.push(time, &values));
+            }
+            DerivedSource::Input { ticker, len } => {
+                let ctx = downcast_ctx(context);
+                let times = match ctx.get_input_data(&format!("{}-_time", ticker)) {
+                    Some(times) => times.as_vec::<Int>(),
+                    None => return,
+                };
+                let srcs: Vec<_> = self
+                    .srcs
+                    .iter()
+                    .map(|src| ctx.get_input_data(&format!("{}-{}", ticker, src)))
+                    .collect();
+                for i in *len..times.len() {
+                    if let Some(time) = times[i] {
+                        let values = srcs
+                            .iter()
+                            .map(|series| series.and_then(|series| series_float_at(series, i)))
+                            .collect();
+                        bars.push((time, values));
+                    }
+                }
+                *len = times.len();
+            }
+        }
+        for (time, values) in bars {
+            self.add_bar(time, values);
+        }
+    }
+
+    fn add_bar(&mut self, time: i64, values: Vec<Float>) {
+        if self.synthetic.is_none() {
+            return self.add_row(time, &values);
+        }
+        // The sources start with the bar sources open, high, low, close and volume.
+        let close = match values[3] {
+            None => return,
+            Some(close) => close,
+        };
+        let bar = Bar::new(
+            time,
+            values[0].unwrap_or(close),
+            values[1].unwrap_or(close),
+            values[2].unwrap_or(close),
+            close,
+            values[4],
+        );
+        let bars = self.synthetic.as_mut().unwrap().push(&bar);
+        for bar in bars {
+            let mut values = vec![None; self.srcs.len()];
+            values[..BAR_SRCS.len()].copy_from_slice(&[
+                Some(bar.open),
+                Some(bar.high),
+                Some(bar.low),
+                Some(bar.close),
+                bar.volume,
+            ]);
+            self.add_row(bar.time, &values);
+        }
+    }
+
+    fn add_row(&mut self, time: i64, values: &[Float]) {
+        self.times.push(Some(time));
+        for (i, column) in self.columns.iter_mut().enumerate() {
+            if self.time_param == Some(i) {
+                column.push(Some(time as f64));
+            } else {
+                column.push(self.param_srcs[i].and_then(|src| values[src]));
+            }
+        }
+    }
+
+    fn value_at<'a>(&self, param: usize, i: isize) -> PineRef<'a> {
+        let val = self.columns[param][i as usize];
+        match self.types[param] {
+            AnySeriesType::Int => PineRef::new_rc(Series::from(val.map(|v| v as i64))),
+            AnySeriesType::Float => PineRef::new_rc(Series::from(val)),
+        }
+    }
+}
+
+// Whether the symbol refers to the symbol of the chart, e.g. "AAPL" for "NASDAQ:AAPL".
+fn is_chart_symbol(symbol: &str, syminfo: &Option<Rc<SymbolInfo>>) -> bool {
+    match syminfo {
+        None => true,
+        Some(info) => {
+            info.ticker.is_empty()
+                || info.ticker == symbol
+                || info.ticker.ends_with(&format!(":{}", symbol))
+        }
+    }
+}
 
 struct SecurityInfo<'a> {
     ticker: Option<String>,
+    // The underlying symbol of the synthetic chart or the symbol.
+    symbol: Option<String>,
+    resolution: Option<String>,
+    synthetic: Option<ChartType>,
     ctx: Option<Box<dyn Ctx<'a>>>,
     fun_def: Option<RefData<Function<'a>>>,
     time_index: Option<VarIndex>,
     start_time_data_index: isize,
     data_names: Vec<String>,
     last_result: Option<PineRef<'a>>,
+    derived: Option<DerivedData>,
+    is_derived_ready: bool,
 }
 
-fn gen_ticker<'a>(
-    symbol: Option<PineRef<'a>>,
-    resolution: Option<PineRef<'a>>,
-) -> Result<String, RuntimeErr> {
-    match (pine_ref_to_string(symbol), pine_ref_to_string(resolution)) {
+fn gen_ticker(symbol: &Option<String>, resolution: &Option<String>) -> Result<String, RuntimeErr> {
+    match (symbol, resolution) {
         (Some(s), Some(r)) => Ok(format!("{}-{}", s, r)),
         _ => Err(RuntimeErr::InvalidParameters(str_replace(
             REQUIRED_PARAMETERS,
@@ -59,12 +207,17 @@ impl<'a> SecurityInfo<'a> {
     pub fn new() -> SecurityInfo<'a> {
         SecurityInfo {
             ticker: None,
+            symbol: None,
+            resolution: None,
+            synthetic: None,
             ctx: None,
             fun_def: None,
             time_index: None,
             start_time_data_index: 0,
             data_names: vec![],
             last_result: None,
+            derived: None,
+            is_derived_ready: false,
         }
     }
 
@@ -75,7 +228,7 @@ impl<'a> SecurityInfo<'a> {
         resolution: Option<PineRef<'a>>,
         expression: Option<PineRef<'a>>,
     ) -> Result<(), RuntimeErr> {
-        self.ticker = Some(gen_ticker(symbol, resolution)?);
+        self.parse_ticker(symbol, resolution)?;
         let func_ins = get_func(expression)?;
         let var_i = downcast_ctx(_context)
             .get_top_varname_index("_time")
@@ -83,7 +236,30 @@ impl<'a> SecurityInfo<'a> {
         self.time_index = Some(var_i);
 
         self.fun_def = Some(func_ins);
-        let params = &self.fun_def.as_ref().unwrap().get_def().params;
+        let input_src = self.gen_input_src(&self.fun_def.as_ref().unwrap().get_def().params);
+        // Add the new ticker information to input sources.
+        downcast_ctx(_context).add_input_src(input_src);
+        Ok(())
+    }
+
+    fn parse_ticker(
+        &mut self,
+        symbol: Option<PineRef<'a>>,
+        resolution: Option<PineRef<'a>>,
+    ) -> Result<(), RuntimeErr> {
+        self.symbol = pine_ref_to_string(symbol);
+        self.resolution = pine_ref_to_string(resolution);
+        // The synthetic chart is built from the data of the underlying symbol.
+        if let Some(ticker) = self.symbol.as_ref().and_then(|s| SyntheticTicker::parse(s)) {
+            self.symbol = Some(ticker.symbol);
+            self.synthetic = Some(ticker.chart);
+        }
+        self.ticker = Some(gen_ticker(&self.symbol, &self.resolution)?);
+        Ok(())
+    }
+
+    // The params are the source names that the expression function depends on.
+    fn gen_input_src(&self, params: &[VarName<'a>]) -> InputSrc {
         // Client user should not care the bar index.
         let mut names: Vec<_> = params
             .iter()
@@ -98,9 +274,14 @@ impl<'a> SecurityInfo<'a> {
         if !names.contains(&String::from("time")) {
             names.push(String::from("time"));
         }
-        // Add the new ticker information to input sources.
-        downcast_ctx(_context).add_input_src(InputSrc::new(self.ticker.clone(), names));
-        Ok(())
+        if self.synthetic.is_some() {
+            for src in BAR_SRCS.iter() {
+                if !names.iter().any(|name| name == src) {
+                    names.push(String::from(*src));
+                }
+            }
+        }
+        InputSrc::new(self.ticker.clone(), names)
     }
 
     pub fn init_subctx(&mut self, _context: &mut dyn Ctx<'a>) {
@@ -131,6 +312,104 @@ impl<'a> SecurityInfo<'a> {
         self.ctx = Some(ctx);
     }
 
+    // Derive the ticker data if it is not provided by the client. The synthetic chart is built
+    // from the data of the underlying symbol, which is resampled from the chart data if the
+    // client does not provide it either.
+    fn init_derived(&self, context: &mut dyn Ctx<'a>) -> Result<Option<DerivedData>, RuntimeErr> {
+        let ticker = self.ticker.clone().unwrap();
+        let is_provided = downcast_ctx(context)
+            .get_input_data(&format!("{}-_time", ticker))
+            .is_some();
+        if is_provided && self.synthetic.is_none() {
+            return Ok(None);
+        }
+
+        let params = &self.fun_def.as_ref().unwrap().get_def().params;
+        let mut srcs: Vec<&'static str> = BAR_SRCS.to_vec();
+        for param in params.iter() {
+            match param.value {
+                "bar_index" | "_time" => {}
+                name if srcs.contains(&name) => {}
+                name => srcs.push(unsafe { mem::transmute::<&str, &'static str>(name) }),
+            }
+        }
+        let param_srcs = params
+            .iter()
+            .map(|param| srcs.iter().position(|src| *src == param.value))
+            .collect();
+
+        let source = if is_provided {
+            DerivedSource::Input { ticker, len: 0 }
+        } else {
+            let syminfo = downcast_ctx(context.get_main_ctx()).get_syminfo().clone();
+            if !is_chart_symbol(self.symbol.as_ref().unwrap(), &syminfo) {
+                return Ok(None);
+            }
+            let (tz, session) = match &syminfo {
+                Some(info) => {
+                    let tz = info.timezone.parse::<Tz>().map_err(|_| {
+                        RuntimeErr::InvalidParameters(str_replace(
+                            UNRECONGNIZED_TZ,
+                            vec![info.timezone.clone()],
+                        ))
+                    })?;
+                    let session = if info.trade_start.is_empty() || info.trade_end.is_empty() {
+                        None
+                    } else {
+                        Some(Session::from_span(TradeTimeSpan::parse_str(
+                            &info.trade_start,
+                            &info.trade_end,
+                        )))
+                    };
+                    (tz, session)
+                }
+                None => (Tz::America__New_York, None),
+            };
+            let resolution = Resolution::parse(self.resolution.as_ref().unwrap())?;
+            let kinds = srcs
+                .iter()
+                .map(|src| AggregateKind::from_name(src))
+                .collect();
+            DerivedSource::Chart {
+                builder: BarBuilder::new(BarAggregator::new(resolution, session, tz), kinds),
+                var_indexes: srcs
+                    .iter()
+                    .map(|src| context.get_top_varname_index(src))
+                    .collect(),
+            }
+        };
+
+        // The parameters keep the types of the chart variables or the ticker data.
+        let types = params
+            .iter()
+            .map(|param| match param.value {
+                "bar_index" | "_time" => AnySeriesType::Int,
+                name => match &source {
+                    DerivedSource::Chart { .. } => match context.get_top_varname_index(name) {
+                        Some(index) => match context.get_var(index) {
+                            Some(val) if val.get_type().0 == DataType::Int => AnySeriesType::Int,
+                            _ => AnySeriesType::Float,
+                        },
+                        None => AnySeriesType::Float,
+                    },
+                    DerivedSource::Input { ticker, .. } => downcast_ctx(context)
+                        .get_input_data(&format!("{}-{}", ticker, name))
+                        .map_or(AnySeriesType::Float, |series| series.get_type()),
+                },
+            })
+            .collect();
+        Ok(Some(DerivedData {
+            source,
+            srcs,
+            synthetic: self.synthetic.map(SyntheticBuilder::new),
+            param_srcs,
+            time_param: params.iter().position(|param| param.value == "_time"),
+            types,
+            times: vec![],
+            columns: vec![vec![]; params.len()],
+        }))
+    }
+
     fn get_subctx(&mut self) -> &mut dyn Ctx<'a> {
         &mut **self.ctx.as_mut().unwrap()
     }
@@ -155,10 +434,14 @@ impl<'a> SecurityInfo<'a> {
         let input_params: Vec<PineRef<'a>> = self
             .data_names
             .iter()
-            .map(|data_name| match data_name.as_str() {
-                "bar_index" => PineRef::new_rc(Series::from(Some(i as i64))),
-                data_name => self.get_input_at(data_name, i),
-            })
+            .enumerate()
+            .map(
+                |(param, data_name)| match (data_name.as_str(), &self.derived) {
+                    ("bar_index", _) => PineRef::new_rc(Series::from(Some(i as i64))),
+                    (_, Some(derived)) => derived.value_at(param, i),
+                    (data_name, None) => self.get_input_at(data_name, i),
+                },
+            )
             .collect();
 
         // TODO: bar_index need be handled specially.
@@ -168,6 +451,9 @@ impl<'a> SecurityInfo<'a> {
             vec![],
             StrRange::new_empty(),
         );
+        // The result may be the series of a variable in the sub context like the expression
+        // `close`, so it is copied before the commit moves the series to the next bar.
+        let result = result.map(|val| val.copy_inner());
         downcast_ctx(self.get_subctx()).commit();
         match result {
             Ok(val) => Ok(val),
@@ -176,7 +462,7 @@ impl<'a> SecurityInfo<'a> {
     }
 }
 
-fn find_nearest_index(data: &ManuallyDrop<Vec<Int>>, val: &Int, is_ge: bool) -> isize {
+fn find_nearest_index(data: &[Int], val: &Int, is_ge: bool) -> isize {
     match data.binary_search(val) {
         Ok(index) => index as isize,
         Err(index) => {
@@ -199,7 +485,7 @@ fn gen_def_val<'a>(func_type: &FunctionType<'a>) -> Option<PineRef<'a>> {
         }
         &SyntaxType::Series(SimpleSyntaxType::Bool) => Some(PineRef::new_rc(Series::from(false))),
         &SyntaxType::Series(SimpleSyntaxType::Color) => {
-            Some(PineRef::new_rc(Series::from(Color(""))))
+            Some(PineRef::new_rc(Series::from(Color(Cow::Borrowed("")))))
         }
         &SyntaxType::Series(SimpleSyntaxType::String) => {
             Some(PineRef::new_rc(Series::from(String::from(""))))
@@ -224,8 +510,14 @@ impl<'a> SeriesCall<'a> for SecurityInfo<'a> {
         if self.ctx.is_none() {
             self.init_subctx(_context);
         }
+        let time_name = format!("{}-_time", self.ticker.as_ref().unwrap());
+        if !self.is_derived_ready {
+            self.is_derived_ready = true;
+            self.derived = self.init_derived(_context)?;
+        }
         let gaps = pine_ref_to_bool(gaps).unwrap_or(false);
-        let lookahead = pine_ref_to_bool(lookahead).unwrap_or(false);
+        // The derived bar is only available after it is completed, so it never looks ahead.
+        let lookahead = pine_ref_to_bool(lookahead).unwrap_or(false) && self.derived.is_none();
         // let func_ins = self.fun_def.as_ref().unwrap();
 
         let time_index = self.time_index.clone().unwrap();
@@ -233,15 +525,25 @@ impl<'a> SeriesCall<'a> for SecurityInfo<'a> {
         match time {
             None => Ok(PineRef::new_box(NA)),
             Some(cur_time) => {
-                let time_name = format!("{}-_time", self.ticker.as_ref().unwrap());
-                match downcast_ctx(self.get_subctx()).get_input_data(&time_name) {
-                    Some(series) => {
-                        let time_data = series.as_vec::<Int>();
-                        // If the lookahead is false, we will find the point that the time is equal or less thant current time.
-                        // else if the lookahead is true, we will find the point that the time is equal or greater than current time.
-                        let end_index =
-                            find_nearest_index(&time_data, &Some(cur_time), lookahead) + 1;
-
+                if let Some(derived) = self.derived.as_mut() {
+                    derived.update(_context, cur_time);
+                }
+                let subctx = &**self.ctx.as_ref().unwrap();
+                let end_index = match &self.derived {
+                    Some(derived) => {
+                        Some(find_nearest_index(&derived.times, &Some(cur_time), false) + 1)
+                    }
+                    // If the lookahead is false, we will find the point that the time is equal or less thant current time.
+                    // else if the lookahead is true, we will find the point that the time is equal or greater than current time.
+                    None => downcast_ctx_const(subctx)
+                        .get_input_data(&time_name)
+                        .map(|series| {
+                            find_nearest_index(&series.as_vec::<Int>(), &Some(cur_time), lookahead)
+                                + 1
+                        }),
+                };
+                match end_index {
+                    Some(end_index) => {
                         // Will run the data in the range start_time_data_index..end_index
                         if end_index > self.start_time_data_index {
                             let mut results: Vec<PineRef<'a>> = vec![];
@@ -275,22 +577,41 @@ impl<'a> SeriesCall<'a> for SecurityInfo<'a> {
     fn run(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
         self.start_time_data_index = 0;
         self.last_result = None;
+        self.derived = None;
+        self.is_derived_ready = false;
         Ok(())
     }
 
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(SecurityInfo {
             ticker: self.ticker.clone(),
+            symbol: self.symbol.clone(),
+            resolution: self.resolution.clone(),
+            synthetic: self.synthetic,
             ctx: None,
             fun_def: None,
             time_index: None,
             start_time_data_index: 0,
             data_names: vec![],
             last_result: None,
+            derived: None,
+            is_derived_ready: false,
         })
     }
 }
 
+// Generate the input source of the security call without running it, the params are the
+// parameters of the function generated for the expression.
+pub fn gen_input_src<'a>(
+    symbol: Option<PineRef<'a>>,
+    resolution: Option<PineRef<'a>>,
+    params: &[VarName<'a>],
+) -> Result<InputSrc, RuntimeErr> {
+    let mut info = SecurityInfo::new();
+    info.parse_ticker(symbol, resolution)?;
+    Ok(info.gen_input_src(params))
+}
+
 pub const VAR_NAME: &'static str = "security";
 
 pub fn declare_var<'a>() -> VarResult<'a> {
@@ -310,7 +631,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -583,4 +904,203 @@ mod tests {
             ])))
         );
     }
+
+    #[test]
+    fn security_resample_test() {
+        use chrono::TimeZone;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("high", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "m = security('MSFT', 'D', close * 2)\nn = security('MSFT', 'D', high - 1)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        // Two bars per day without the data of the ticker, the daily bars are resampled from
+        // the chart data in the New York timezone.
+        let ts = |d, h| {
+            Some(
+                Tz::America__New_York
+                    .with_ymd_and_hms(2020, 2, d, h, 0, 0)
+                    .unwrap()
+                    .timestamp_millis(),
+            )
+        };
+        let data = vec![
+            (
+                "close",
+                AnySeries::from_float_vec((1..7).map(|v| Some(v as f64)).collect()),
+            ),
+            (
+                "high",
+                AnySeries::from_float_vec(vec![
+                    Some(3f64),
+                    Some(5f64),
+                    Some(4f64),
+                    Some(2f64),
+                    Some(1f64),
+                    Some(1f64),
+                ]),
+            ),
+            (
+                "_time",
+                AnySeries::from_int_vec(vec![
+                    ts(17, 10),
+                    ts(17, 14),
+                    ts(18, 10),
+                    ts(18, 14),
+                    ts(19, 10),
+                    ts(19, 14),
+                ]),
+            ),
+        ];
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                None,
+                None,
+                Some(4f64),
+                Some(4f64),
+                Some(8f64),
+                Some(8f64)
+            ])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                None,
+                None,
+                Some(4f64),
+                Some(4f64),
+                Some(3f64),
+                Some(3f64)
+            ])))
+        );
+
+        // The data of other symbols is not resampled.
+        let syminfo = Some(Rc::new(SymbolInfo {
+            symbol_type: String::from("stock"),
+            timezone: String::from("America/New_York"),
+            ticker: String::from("NASDAQ:MSFT"),
+            session: String::from(""),
+            trade_start: String::from(""),
+            trade_end: String::from(""),
+            root: None,
+            currency: String::from("USD"),
+            description: String::from(""),
+            mintick: 0.01,
+        }));
+        assert!(is_chart_symbol("MSFT", &syminfo));
+        assert!(is_chart_symbol("NASDAQ:MSFT", &syminfo));
+        assert!(!is_chart_symbol("AAPL", &syminfo));
+        assert!(is_chart_symbol("AAPL", &None));
+    }
+
+    #[test]
+    fn security_synthetic_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("open", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "m = security('heikinashi(MSFT)', '1D', close * 1)\n\
+                   n = security('heikinashi(MSFT)', '1D', open * 1)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        // The Heikin Ashi bars are built from the data of the underlying symbol.
+        runner
+            .run(
+                &vec![
+                    (
+                        "close",
+                        AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                    ),
+                    (
+                        "_time",
+                        AnySeries::from_int_vec(vec![Some(10i64), Some(20i64)]),
+                    ),
+                    (
+                        "MSFT-1D-_time",
+                        AnySeries::from_int_vec(vec![Some(5i64), Some(15i64)]),
+                    ),
+                    (
+                        "MSFT-1D-close",
+                        AnySeries::from_float_vec(vec![Some(2f64), Some(4f64)]),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(2f64),
+                Some(4f64)
+            ])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(2f64),
+                Some(2f64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn security_synthetic_resample_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "m = security('renko(MSFT,Traditional,1)', '1', close * 1)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        // The renko bricks are built from the completed minute bars of the chart, the first of
+        // the bricks completed by the bar is returned.
+        let minute = 60 * 1000;
+        runner
+            .run(
+                &vec![
+                    (
+                        "close",
+                        AnySeries::from_float_vec(vec![
+                            Some(10f64),
+                            Some(12.5f64),
+                            Some(13f64),
+                            Some(11f64),
+                        ]),
+                    ),
+                    (
+                        "_time",
+                        AnySeries::from_int_vec((0..4).map(|i| Some(i * minute)).collect()),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                None,
+                None,
+                Some(11f64),
+                Some(13f64)
+            ])))
+        );
+    }
 }
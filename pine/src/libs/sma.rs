@@ -16,7 +16,7 @@ use crate::types::{
 };
 use std::f64;
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub fn series_wma<'a>(series: &Series<Float>, length: i64) -> Result<Float, RuntimeErr> {
     let mut norm = 0f64;
@@ -142,6 +142,8 @@ impl<'a> SeriesCall<'a> for SmaVal {
         Ok(PineRef::new(Series::from(func(source, length)?)))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -185,7 +187,7 @@ pub fn declare_ma_var<'a>(name: &'static str, handle: HandleFunc) -> VarResult<'
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, name)
 }
 
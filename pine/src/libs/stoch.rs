@@ -21,7 +21,7 @@ use crate::types::{
     Tuple,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct KcVal {}
@@ -64,6 +64,8 @@ impl<'a> SeriesCall<'a> for KcVal {
         Ok(PineRef::new_rc(Series::from(res)))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -88,7 +90,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "stoch")
 }
 
@@ -3,10 +3,11 @@ use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
 use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
 use crate::helper::{move_element, pine_ref_to_bool, pine_ref_to_i64, pine_ref_to_string};
+use crate::library::LIBRARY_NAME;
 use crate::runtime::context::{downcast_ctx, Ctx};
-use crate::runtime::{ScriptPurpose, StudyScript};
+use crate::runtime::{LibraryScript, ScriptPurpose, StudyScript};
 use crate::types::{Callable, CallableFactory, PineRef, RuntimeErr, NA};
-use std::rc::Rc;
+use std::sync::Arc;
 
 fn study<'a>(
     context: &mut dyn Ctx<'a>,
@@ -49,10 +50,56 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::Void,
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
+fn library<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((title, overlay) = param);
+    if !downcast_ctx(context).check_is_input_info_ready() {
+        if let Some(title) = pine_ref_to_string(title) {
+            let library = LibraryScript {
+                title,
+                overlay: pine_ref_to_bool(overlay),
+            };
+            downcast_ctx(context).set_script_type(ScriptPurpose::Library(library));
+        } else {
+            return Err(RuntimeErr::MissingParameters(str_replace(
+                REQUIRED_PARAMETERS,
+                vec![String::from("title")],
+            )));
+        }
+    }
+    Ok(PineRef::new(NA))
+}
+
+// The library function declares the script as a library of Pine v5.
+pub fn declare_library_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| Callable::new(Some(library), None)));
+
+    let func_type = FunctionTypes(vec![FunctionType::new((
+        vec![
+            ("title", SyntaxType::string()),
+            ("overlay", SyntaxType::bool()),
+        ],
+        SyntaxType::Void,
+    ))]);
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
+    VarResult::new(value, syntax_type, LIBRARY_NAME)
+}
+
+pub const INDICATOR_NAME: &str = "indicator";
+
+// The study function is renamed to indicator in Pine v5.
+pub fn declare_indicator_var<'a>() -> VarResult<'a> {
+    let study = declare_var();
+    VarResult::new(study.value, study.syntax_type, INDICATOR_NAME)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,7 +19,7 @@ pub fn series_sum<'a>(source: &Series<Float>, length: i64) -> Result<Float, Runt
     Ok(sum_val)
 }
 
-fn sum_func<'a>(source: RefData<Series<Float>>, length: i64) -> Result<Float, RuntimeErr> {
+pub fn sum_func(source: RefData<Series<Float>>, length: i64) -> Result<Float, RuntimeErr> {
     let mut sum_val = Some(0f64);
     for i in 0..length {
         let val = source.index_value(i as usize).unwrap();
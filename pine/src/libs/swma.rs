@@ -14,7 +14,7 @@ use crate::types::{
     Float, Int, ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall, NA,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct EmaVal;
@@ -45,6 +45,8 @@ impl<'a> SeriesCall<'a> for EmaVal {
         Ok(PineRef::new(Series::from(val)))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -64,7 +66,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         vec![("source", SyntaxType::float_series())],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "swma")
 }
 
@@ -14,7 +14,7 @@ use crate::types::{
 };
 use regex::Regex;
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 struct SymInfoProps;
 
@@ -110,7 +110,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("tickerid", SyntaxType::string());
     obj_type.insert("timezone", SyntaxType::string());
     obj_type.insert("type", SyntaxType::string());
-    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    let syntax_type = SyntaxType::Object(Arc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -119,6 +119,7 @@ mod tests {
     use super::*;
     use crate::runtime::{AnySeries, NoneCallback, SymbolInfo};
     use crate::{LibInfo, PineParser, PineRunner};
+    use std::rc::Rc;
 
     #[test]
     fn syminfo_fields_test() {
@@ -10,7 +10,7 @@ use crate::types::{
     NA,
 };
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 struct PlotProps;
 
@@ -45,7 +45,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("align_center", SyntaxType::string());
     obj_type.insert("align_left", SyntaxType::string());
     obj_type.insert("align_right", SyntaxType::string());
-    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    let syntax_type = SyntaxType::Object(Arc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -0,0 +1,226 @@
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{
+    ge1_param_i64, move_element, pine_ref_to_f64, pine_ref_to_i64, pine_ref_to_string,
+    require_param,
+};
+use crate::runtime::context::Ctx;
+use crate::synthetic::{BoxSize, ChartType, PnfSource, SyntheticTicker};
+use crate::types::{Callable, PineRef, RuntimeErr, StepHandleFunc};
+use std::sync::Arc;
+
+fn gen_tickerid<'a>(
+    symbol: Option<PineRef<'a>>,
+    chart: ChartType,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let symbol = require_param("symbol", pine_ref_to_string(symbol))?;
+    Ok(PineRef::new_rc(
+        SyntheticTicker::new(symbol, chart).to_tickerid(),
+    ))
+}
+
+fn box_size<'a>(
+    style: Option<PineRef<'a>>,
+    param: Option<PineRef<'a>>,
+) -> Result<BoxSize, RuntimeErr> {
+    let style = require_param("style", pine_ref_to_string(style))?;
+    let param = require_param("param", pine_ref_to_f64(param))?;
+    BoxSize::parse(&style, param)
+}
+
+fn heikinashi_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let symbol = move_element(&mut param, 0);
+    gen_tickerid(symbol, ChartType::HeikinAshi)
+}
+
+fn renko_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((symbol, style, param) = param);
+    gen_tickerid(symbol, ChartType::Renko(box_size(style, param)?))
+}
+
+fn linebreak_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((symbol, number_of_lines) = param);
+    let lines = ge1_param_i64("number_of_lines", pine_ref_to_i64(number_of_lines))?;
+    gen_tickerid(symbol, ChartType::LineBreak(lines as usize))
+}
+
+fn kagi_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((symbol, reversal) = param);
+    match require_param("reversal", pine_ref_to_f64(reversal))? {
+        reversal if reversal > 0f64 => gen_tickerid(symbol, ChartType::Kagi(reversal)),
+        _ => Err(RuntimeErr::InvalidParameters(str_replace(
+            INVALID_VALS,
+            vec![String::from("reversal")],
+        ))),
+    }
+}
+
+fn pointfigure_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((symbol, source, style, param, reversal) = param);
+    let source = match pine_ref_to_string(source).as_deref() {
+        Some("hl") => PnfSource::HighLow,
+        Some("close") => PnfSource::Close,
+        _ => {
+            return Err(RuntimeErr::InvalidParameters(str_replace(
+                INVALID_VALS,
+                vec![String::from("source")],
+            )))
+        }
+    };
+    let box_size = box_size(style, param)?;
+    let reversal = ge1_param_i64("reversal", pine_ref_to_i64(reversal))?;
+    gen_tickerid(
+        symbol,
+        ChartType::PointFigure {
+            source,
+            box_size,
+            reversal: reversal as usize,
+        },
+    )
+}
+
+fn declare_ticker_var<'a>(
+    name: &'static str,
+    func: StepHandleFunc<'a>,
+    params: Vec<(&'static str, SyntaxType<'a>)>,
+) -> VarResult<'a> {
+    let value = PineRef::new(Callable::new(Some(func), None));
+    let func_type = FunctionTypes(vec![FunctionType::new((params, SyntaxType::string()))]);
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
+    VarResult::new(value, syntax_type, name)
+}
+
+pub fn declare_heikinashi_var<'a>() -> VarResult<'a> {
+    declare_ticker_var(
+        "heikinashi",
+        heikinashi_func,
+        vec![("symbol", SyntaxType::string())],
+    )
+}
+
+pub fn declare_renko_var<'a>() -> VarResult<'a> {
+    declare_ticker_var(
+        "renko",
+        renko_func,
+        vec![
+            ("symbol", SyntaxType::string()),
+            ("style", SyntaxType::string()),
+            ("param", SyntaxType::float()),
+        ],
+    )
+}
+
+pub fn declare_linebreak_var<'a>() -> VarResult<'a> {
+    declare_ticker_var(
+        "linebreak",
+        linebreak_func,
+        vec![
+            ("symbol", SyntaxType::string()),
+            ("number_of_lines", SyntaxType::int()),
+        ],
+    )
+}
+
+pub fn declare_kagi_var<'a>() -> VarResult<'a> {
+    declare_ticker_var(
+        "kagi",
+        kagi_func,
+        vec![
+            ("symbol", SyntaxType::string()),
+            ("reversal", SyntaxType::float()),
+        ],
+    )
+}
+
+pub fn declare_pointfigure_var<'a>() -> VarResult<'a> {
+    declare_ticker_var(
+        "pointfigure",
+        pointfigure_func,
+        vec![
+            ("symbol", SyntaxType::string()),
+            ("source", SyntaxType::string()),
+            ("style", SyntaxType::string()),
+            ("param", SyntaxType::float()),
+            ("reversal", SyntaxType::int()),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn ticker_test() {
+        let lib_info = LibInfo::new(
+            vec![
+                declare_heikinashi_var(),
+                declare_renko_var(),
+                declare_linebreak_var(),
+                declare_kagi_var(),
+                declare_pointfigure_var(),
+            ],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m1 = heikinashi('MSFT')\nm2 = renko('MSFT', 'ATR', 10)\n\
+                   m3 = linebreak('MSFT', 3)\nm4 = kagi('MSFT', 1.5)\n\
+                   m5 = pointfigure('MSFT', 'hl', 'Traditional', 2, 3)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+        let tickers = [
+            "heikinashi(MSFT)",
+            "renko(MSFT,ATR,10)",
+            "linebreak(MSFT,3)",
+            "kagi(MSFT,1.5)",
+            "pointfigure(MSFT,hl,Traditional,2,3)",
+        ];
+        for (i, ticker) in tickers.iter().enumerate() {
+            assert_eq!(
+                runner.get_context().move_var(VarIndex::new(i as i32, 0)),
+                Some(PineRef::new_rc(String::from(*ticker)))
+            );
+        }
+
+        let src = "m = renko('MSFT', 'Box', 10)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert!(runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .is_err());
+    }
+}
@@ -9,7 +9,7 @@ use crate::types::{
 };
 use chrono_tz::Tz;
 use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 struct TimeVal {
@@ -37,6 +37,8 @@ impl<'a> EvaluateVal<'a> for TimeVal {
         }
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
         Box::new(self.clone())
     }
@@ -84,6 +86,8 @@ impl<'a> SeriesCall<'a> for TimeCallVal {
         )
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -135,7 +139,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         SyntaxType::int_series(),
     ))]);
     let syntax_type =
-        SyntaxType::ValFunction(Box::new(SyntaxType::int_series()), Rc::new(func_type));
+        SyntaxType::ValFunction(Box::new(SyntaxType::int_series()), Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -147,6 +151,7 @@ mod tests {
     use crate::runtime::{AnySeries, NoneCallback, SymbolInfo, VarOperate};
     use crate::{LibInfo, PineParser, PineRunner};
     use chrono::TimeZone;
+    use std::rc::Rc;
 
     #[test]
     fn time_test() {
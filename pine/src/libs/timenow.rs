@@ -28,6 +28,8 @@ impl<'a> EvaluateVal<'a> for TimenowVal {
         Ok(PineRef::new_box(Some(self.now_time)))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
         Box::new(TimenowVal::new())
     }
@@ -9,7 +9,7 @@ use crate::types::{Callable, CallableFactory, Int, PineFrom, PineRef, RuntimeErr
 use chrono::TimeZone;
 use chrono_tz::Tz;
 use regex::Regex;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub fn pine_ref_to_i64_or<'a>(val: Option<PineRef<'a>>, defval: i64) -> i64 {
     if val.is_none() {
@@ -232,7 +232,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         gen_sig3_type(),
         gen_sig4_type(),
     ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -7,6 +7,7 @@ use crate::helper::{
     pine_ref_to_f64_series, pine_ref_to_i64, require_param, series_index, series_index2,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_float, state_err, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableEvaluate, CallableFactory, Evaluate,
@@ -14,7 +15,7 @@ use crate::types::{
     NA,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub fn series_tr(high: Float, low: Float, close: &Series<Float>) -> Float {
     tr_func(high, low, series_index2(close, 1))
@@ -91,6 +92,15 @@ impl<'a> EvaluateVal<'a> for TrVal {
         self.calc_tr(ctx, false)
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![StateValue::Float(Some(self.prev_val))])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        self.prev_val = load_float(state.into_iter().next())?.ok_or_else(state_err)?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
         Box::new(self.clone())
     }
@@ -108,6 +118,15 @@ impl<'a> SeriesCall<'a> for TrVal {
         self.calc_tr(ctx, handle_na.unwrap_or(false))
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![StateValue::Float(Some(self.prev_val))])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        self.prev_val = load_float(state.into_iter().next())?.ok_or_else(state_err)?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -131,7 +150,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         SyntaxType::float_series(),
     ))]);
     let syntax_type =
-        SyntaxType::ValFunction(Box::new(SyntaxType::float_series()), Rc::new(func_type));
+        SyntaxType::ValFunction(Box::new(SyntaxType::float_series()), Arc::new(func_type));
     VarResult::new(value, syntax_type, "tr")
 }
 
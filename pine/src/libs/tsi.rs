@@ -12,6 +12,7 @@ use crate::helper::{
     pine_ref_to_f64_series, pine_ref_to_i64, require_param, series_index,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::snapshot::{load_series, save_series, StateValue};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableCreator, CallableFactory, Evaluate,
@@ -19,7 +20,7 @@ use crate::types::{
     Tuple,
 };
 use std::mem;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct KcVal<'a> {
@@ -102,6 +103,26 @@ impl<'a> SeriesCall<'a> for KcVal<'a> {
         Ok(())
     }
 
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        Some(vec![
+            save_series(&self.closes),
+            save_series(&self.smooth1),
+            save_series(&self.smooth2),
+            save_series(&self.asmooth1),
+            save_series(&self.asmooth2),
+        ])
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        load_series(&mut self.closes, state.next())?;
+        load_series(&mut self.smooth1, state.next())?;
+        load_series(&mut self.smooth2, state.next())?;
+        load_series(&mut self.asmooth1, state.next())?;
+        load_series(&mut self.asmooth2, state.next())?;
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -120,7 +141,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         ],
         SyntaxType::float_series(),
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    let syntax_type = SyntaxType::Function(Arc::new(func_type));
     VarResult::new(value, syntax_type, "tsi")
 }
 
@@ -14,7 +14,7 @@ use crate::types::{
     NA,
 };
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 struct PlotProps;
 
@@ -50,7 +50,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     let mut obj_type = BTreeMap::new();
     obj_type.insert("bar_index", SyntaxType::string());
     obj_type.insert("bar_time", SyntaxType::string());
-    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    let syntax_type = SyntaxType::Object(Arc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
 
@@ -15,7 +15,7 @@ use chrono::{DateTime, Datelike, Local, TimeZone, Timelike};
 use chrono_tz::Tz;
 use std::collections::BTreeMap;
 use std::mem::transmute;
-use std::rc::Rc;
+use std::sync::Arc;
 
 // pub fn parse_time_from_ctx<'a>(ctx: &mut dyn Ctx<'a>) -> (VarIndex, Tz) {
 //     let time_index;
@@ -144,6 +144,8 @@ impl<'a> EvaluateVal<'a> for TimeVal {
         ))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
         Box::new(self.clone())
     }
@@ -183,6 +185,8 @@ impl<'a> SeriesCall<'a> for TimeCallVal {
         ))
     }
 
+    impl_no_state!();
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -200,7 +204,7 @@ pub fn declare_time_var<'a>(
         SyntaxType::int_series(),
     ))]);
     let syntax_type =
-        SyntaxType::ValFunction(Box::new(SyntaxType::int_series()), Rc::new(func_type));
+        SyntaxType::ValFunction(Box::new(SyntaxType::int_series()), Arc::new(func_type));
     VarResult::new(value, syntax_type, name)
 }
 
@@ -388,8 +392,8 @@ pub fn declare_dayofweek_var<'a>() -> VarResult<'a> {
     obj_type.insert("saturday", SyntaxType::int());
     let syntax_type = SyntaxType::ValObjectFunction(
         Box::new(SyntaxType::int_series()),
-        Rc::new(obj_type),
-        Rc::new(func_type),
+        Arc::new(obj_type),
+        Arc::new(func_type),
     );
     VarResult::new(value, syntax_type, "dayofweek")
 }
@@ -437,6 +441,7 @@ mod tests {
     use crate::runtime::{AnySeries, NoneCallback, SymbolInfo, VarOperate};
     use crate::{LibInfo, PineParser, PineRunner};
     use chrono::TimeZone;
+    use std::rc::Rc;
 
     fn get_syminfo(timezone: String) -> SymbolInfo {
         SymbolInfo {
@@ -0,0 +1,733 @@
+use crate::compiled::{CompiledScript, ScriptRunner};
+use crate::runtime::context::PineRuntimeError;
+use crate::runtime::error_format::{ErrorFormater, PineFormatError};
+use crate::runtime::output::{
+    IOInfo, InputInfo, InputVal, OutputData, OutputDataCollect, StringInputInfo,
+};
+use crate::runtime::output::{StrOptionsData, SymbolInfo, TraceData};
+use crate::runtime::snapshot::SnapshotSeries;
+use crate::runtime::AnySeries;
+use crate::types::RuntimeErr;
+use std::sync::Arc;
+use std::thread;
+
+// The candidate values of one input.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParamRange {
+    // The input keeps this value for all the candidates.
+    Fixed(Option<InputVal>),
+    Values(Vec<InputVal>),
+    Int { min: i64, max: i64, step: i64 },
+    Float { min: f64, max: f64, step: f64 },
+}
+
+impl ParamRange {
+    // The values and the int and float ranges must have at least one candidate, and the
+    // steps must be positive so that the range can be stepped through.
+    fn check(&self) -> Result<(), PineFormatError> {
+        match self {
+            ParamRange::Fixed(_) => Ok(()),
+            ParamRange::Values(vals) if vals.is_empty() => {
+                Err(invalid_param("The values of the input range are empty."))
+            }
+            ParamRange::Values(_) => Ok(()),
+            ParamRange::Int { min, max, step } => {
+                if *step <= 0 {
+                    Err(invalid_param(
+                        "The step of the input range must be positive.",
+                    ))
+                } else if max < min {
+                    Err(invalid_param(
+                        "The max of the input range is less than the min.",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            ParamRange::Float { min, max, step } => {
+                if !min.is_finite() || !max.is_finite() || !step.is_finite() {
+                    Err(invalid_param("The input range must be finite."))
+                } else if *step <= 0f64 {
+                    Err(invalid_param(
+                        "The step of the input range must be positive.",
+                    ))
+                } else if max < min {
+                    Err(invalid_param(
+                        "The max of the input range is less than the min.",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn values(&self) -> Vec<Option<InputVal>> {
+        match self {
+            ParamRange::Fixed(val) => vec![val.clone()],
+            ParamRange::Values(vals) => vals.iter().cloned().map(Some).collect(),
+            ParamRange::Int { min, max, step } => {
+                let mut vals = vec![];
+                let mut val = Some(*min);
+                while let Some(v) = val.filter(|v| v <= max) {
+                    vals.push(Some(InputVal::Int(v)));
+                    // Stop at the i64 limits instead of overflowing.
+                    val = v.checked_add(*step);
+                }
+                vals
+            }
+            ParamRange::Float { min, max, step } => {
+                let count = ((max - min) / step + 1e-9).floor() as i64 + 1;
+                (0..count.max(0))
+                    .map(|i| Some(InputVal::Float(min + i as f64 * step)))
+                    .collect()
+            }
+        }
+    }
+
+    fn random(&self, rng: &mut XorShiftRng) -> Option<InputVal> {
+        match self {
+            ParamRange::Int { min, max, step } => {
+                // The width of the range may exceed i64, e.g. from i64::MIN to i64::MAX.
+                let count = ((*max as i128 - *min as i128) / *step as i128) as u128 + 1;
+                let index = rng.next_u64() as u128 % count;
+                Some(InputVal::Int(
+                    (*min as i128 + index as i128 * *step as i128) as i64,
+                ))
+            }
+            _ => {
+                let vals = self.values();
+                vals[(rng.next_u64() % vals.len() as u64) as usize].clone()
+            }
+        }
+    }
+}
+
+// The search space of all the inputs, the order is the same as IOInfo's inputs.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParamSpace {
+    ranges: Vec<ParamRange>,
+}
+
+// The string inputs with the options search all the options.
+fn str_options_range(info: &StringInputInfo, f: fn(String) -> InputVal) -> ParamRange {
+    match &info.options {
+        Some(options) => checked_or(
+            ParamRange::Values(options.iter().map(|v| f(v.clone())).collect()),
+            info.defval.clone().map(f),
+        ),
+        None => ParamRange::Fixed(info.defval.clone().map(f)),
+    }
+}
+
+// The inputs with the invalid range settings keep the default values.
+fn checked_or(range: ParamRange, defval: Option<InputVal>) -> ParamRange {
+    match range.check() {
+        Ok(_) => range,
+        Err(_) => ParamRange::Fixed(defval),
+    }
+}
+
+impl ParamSpace {
+    pub fn new(ranges: Vec<ParamRange>) -> Result<ParamSpace, PineFormatError> {
+        for range in ranges.iter() {
+            range.check()?;
+        }
+        Ok(ParamSpace { ranges })
+    }
+
+    // Generate the search space from the minval, maxval, step and options of the inputs.
+    // The inputs without these settings or with the invalid settings keep the default values.
+    pub fn from_io_info(io_info: &IOInfo) -> ParamSpace {
+        let ranges = io_info
+            .get_inputs()
+            .iter()
+            .map(|input| match input {
+                InputInfo::Int(info) => match (&info.options, info.minval, info.maxval) {
+                    (Some(options), _, _) => checked_or(
+                        ParamRange::Values(options.iter().map(|v| InputVal::Int(*v)).collect()),
+                        info.defval.map(InputVal::Int),
+                    ),
+                    (None, Some(min), Some(max)) => checked_or(
+                        ParamRange::Int {
+                            min,
+                            max,
+                            step: info.step.unwrap_or(1),
+                        },
+                        info.defval.map(InputVal::Int),
+                    ),
+                    _ => ParamRange::Fixed(info.defval.map(InputVal::Int)),
+                },
+                InputInfo::Float(info) => match (&info.options, info.minval, info.maxval) {
+                    (Some(options), _, _) => checked_or(
+                        ParamRange::Values(options.iter().map(|v| InputVal::Float(*v)).collect()),
+                        info.defval.map(InputVal::Float),
+                    ),
+                    (None, Some(min), Some(max)) => checked_or(
+                        ParamRange::Float {
+                            min,
+                            max,
+                            step: info.step.unwrap_or(1f64),
+                        },
+                        info.defval.map(InputVal::Float),
+                    ),
+                    _ => ParamRange::Fixed(info.defval.map(InputVal::Float)),
+                },
+                InputInfo::Bool(info) => ParamRange::Fixed(info.defval.map(InputVal::Bool)),
+                InputInfo::String(info) => str_options_range(info, InputVal::String),
+                InputInfo::Source(info) => {
+                    ParamRange::Fixed(info.defval.clone().map(InputVal::Source))
+                }
+                InputInfo::Time(info) => ParamRange::Fixed(info.defval.map(InputVal::Time)),
+                InputInfo::Price(info) => ParamRange::Fixed(info.defval.map(InputVal::Price)),
+                InputInfo::Color(info) => {
+                    ParamRange::Fixed(info.defval.clone().map(InputVal::Color))
+                }
+                InputInfo::Session(info) => str_options_range(info, InputVal::Session),
+                InputInfo::Resolution(info) => str_options_range(info, InputVal::Resolution),
+                InputInfo::Symbol(info) => str_options_range(info, InputVal::Symbol),
+            })
+            .collect();
+        ParamSpace { ranges }
+    }
+
+    pub fn set_range(&mut self, index: usize, range: ParamRange) -> Result<(), PineFormatError> {
+        range.check()?;
+        match self.ranges.get_mut(index) {
+            Some(r) => {
+                *r = range;
+                Ok(())
+            }
+            None => Err(invalid_param(
+                "The index of the input is out of the search space.",
+            )),
+        }
+    }
+
+    pub fn get_ranges(&self) -> &Vec<ParamRange> {
+        &self.ranges
+    }
+
+    // The cartesian product of all the input values.
+    pub fn grid(&self) -> Vec<Vec<Option<InputVal>>> {
+        let mut candidates: Vec<Vec<Option<InputVal>>> = vec![vec![]];
+        for range in self.ranges.iter() {
+            let vals = range.values();
+            candidates = candidates
+                .into_iter()
+                .flat_map(|c| {
+                    vals.iter().map(move |v| {
+                        let mut c = c.clone();
+                        c.push(v.clone());
+                        c
+                    })
+                })
+                .collect();
+        }
+        candidates
+    }
+
+    pub fn random(&self, count: usize, seed: u64) -> Vec<Vec<Option<InputVal>>> {
+        let mut rng = XorShiftRng::new(seed);
+        (0..count)
+            .map(|_| self.ranges.iter().map(|r| r.random(&mut rng)).collect())
+            .collect()
+    }
+}
+
+// The xorshift64* generator, it's enough for sampling the inputs and keeps the
+// search reproducible for the same seed.
+#[derive(Debug, Clone)]
+struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    fn new(seed: u64) -> XorShiftRng {
+        XorShiftRng {
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SearchMethod {
+    Grid,
+    Random { count: usize, seed: u64 },
+}
+
+// Split the data into windows, every window is optimized with the first
+// in_sample_ratio part of bars and validated with the rest bars.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct WalkForward {
+    pub windows: usize,
+    pub in_sample_ratio: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct OptimizeResult {
+    pub inputs: Vec<Option<InputVal>>,
+    pub score: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct WalkForwardResult {
+    pub in_sample: (i32, i32),
+    pub out_of_sample: (i32, i32),
+    // The best inputs of the in-sample bars.
+    pub best: OptimizeResult,
+    // The score of the best inputs on the out-of-sample bars.
+    pub out_of_sample_score: Option<f64>,
+}
+
+pub struct Optimizer {
+    compiled: Arc<CompiledScript>,
+    space: ParamSpace,
+    method: SearchMethod,
+    threads: usize,
+}
+
+impl Optimizer {
+    pub fn new(compiled: Arc<CompiledScript>) -> Optimizer {
+        let space = ParamSpace::from_io_info(compiled.get_io_info());
+        Optimizer {
+            compiled,
+            space,
+            method: SearchMethod::Grid,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    pub fn set_space(&mut self, space: ParamSpace) {
+        self.space = space;
+    }
+
+    pub fn get_space(&self) -> &ParamSpace {
+        &self.space
+    }
+
+    pub fn set_method(&mut self, method: SearchMethod) {
+        self.method = method;
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    fn candidates(&self) -> Vec<Vec<Option<InputVal>>> {
+        match self.method {
+            SearchMethod::Grid => self.space.grid(),
+            SearchMethod::Random { count, seed } => self.space.random(count, seed),
+        }
+    }
+
+    // Run all the candidates over the data and return the results ranked by the score
+    // in descending order. The candidates the objective returns None or NaN for are
+    // dropped, and the search stops with the error of the first candidate that fails to run.
+    pub fn run<F>(
+        &self,
+        data: &Vec<(&'static str, AnySeries)>,
+        syminfo: Option<SymbolInfo>,
+        objective: F,
+    ) -> Result<Vec<OptimizeResult>, PineFormatError>
+    where
+        F: Fn(&OutputDataCollect) -> Option<f64> + Sync,
+    {
+        let datalen = check_datalen(data)?;
+        let data = to_snapshot_data(data, 0, datalen);
+        self.search(&data, &syminfo, 0, &objective)
+    }
+
+    pub fn run_walk_forward<F>(
+        &self,
+        data: &Vec<(&'static str, AnySeries)>,
+        syminfo: Option<SymbolInfo>,
+        walk_forward: WalkForward,
+        objective: F,
+    ) -> Result<Vec<WalkForwardResult>, PineFormatError>
+    where
+        F: Fn(&OutputDataCollect) -> Option<f64> + Sync,
+    {
+        let datalen = check_datalen(data)?;
+        let windows = walk_forward.windows.max(1);
+        let window_len = datalen / windows;
+        let in_sample_len = (window_len as f64 * walk_forward.in_sample_ratio) as usize;
+        if in_sample_len == 0 || in_sample_len >= window_len {
+            return Err(invalid_param("The walk-forward windows are too small."));
+        }
+
+        let mut results = vec![];
+        for i in 0..windows {
+            let start = i * window_len;
+            let end = if i + 1 == windows {
+                datalen
+            } else {
+                start + window_len
+            };
+            let in_sample = to_snapshot_data(data, start, start + in_sample_len);
+            let ranked = self.search(&in_sample, &syminfo, 0, &objective)?;
+            let best = match ranked.into_iter().next() {
+                Some(best) => best,
+                None => continue,
+            };
+
+            // Run the whole window so that the out-of-sample bars have the history of the
+            // in-sample bars, and only score the out-of-sample part.
+            let window = to_snapshot_data(data, start, end);
+            let mut runner = self.compiled.runner();
+            let out_of_sample_score = run_candidate(
+                &mut runner,
+                &window,
+                &syminfo,
+                best.inputs.clone(),
+                in_sample_len,
+                &objective,
+            )?;
+            results.push(WalkForwardResult {
+                in_sample: (start as i32, (start + in_sample_len) as i32),
+                out_of_sample: ((start + in_sample_len) as i32, end as i32),
+                best,
+                out_of_sample_score,
+            });
+        }
+        Ok(results)
+    }
+
+    fn search<F>(
+        &self,
+        data: &Vec<(&'static str, SnapshotSeries)>,
+        syminfo: &Option<SymbolInfo>,
+        skip: usize,
+        objective: &F,
+    ) -> Result<Vec<OptimizeResult>, PineFormatError>
+    where
+        F: Fn(&OutputDataCollect) -> Option<f64> + Sync,
+    {
+        let candidates = self.candidates();
+        let chunk_size = candidates.len().div_ceil(self.threads);
+        let results: Vec<Result<Vec<OptimizeResult>, PineFormatError>> = thread::scope(|s| {
+            let handles: Vec<_> = candidates
+                .chunks(chunk_size.max(1))
+                .map(|chunk| {
+                    let mut runner = self.compiled.runner();
+                    s.spawn(move || {
+                        let mut results = vec![];
+                        for inputs in chunk.iter() {
+                            let score = run_candidate(
+                                &mut runner,
+                                data,
+                                syminfo,
+                                inputs.clone(),
+                                skip,
+                                objective,
+                            )?;
+                            if let Some(score) = score {
+                                results.push(OptimizeResult {
+                                    inputs: inputs.clone(),
+                                    score,
+                                });
+                            }
+                        }
+                        Ok(results)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join())
+                .collect::<Result<_, _>>()
+        })
+        .map_err(|_| invalid_param("The candidate run is aborted by a panic."))?;
+
+        // The NaN scores can't be ranked, so they are dropped like the None scores.
+        let mut results: Vec<OptimizeResult> = results
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .filter(|r| !r.score.is_nan())
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(results)
+    }
+}
+
+fn invalid_param(msg: &str) -> PineFormatError {
+    PineFormatError::from_runtime_error(
+        &ErrorFormater::new(),
+        PineRuntimeError::new_no_range(RuntimeErr::InvalidParameters(String::from(msg))),
+    )
+}
+
+fn check_datalen(data: &Vec<(&'static str, AnySeries)>) -> Result<usize, PineFormatError> {
+    let len = data.first().map_or(0, |(_, s)| s.len());
+    if data.iter().any(|(_, s)| s.len() != len) {
+        return Err(invalid_param("The data series must have the same length."));
+    }
+    Ok(len)
+}
+
+fn to_snapshot_data(
+    data: &Vec<(&'static str, AnySeries)>,
+    from: usize,
+    to: usize,
+) -> Vec<(&'static str, SnapshotSeries)> {
+    data.iter()
+        .map(|(name, series)| {
+            let series = match SnapshotSeries::from_any_series(series) {
+                SnapshotSeries::Int(v) => SnapshotSeries::Int(v[from..to].to_vec()),
+                SnapshotSeries::Float(v) => SnapshotSeries::Float(v[from..to].to_vec()),
+            };
+            (*name, series)
+        })
+        .collect()
+}
+
+fn run_candidate<F>(
+    runner: &mut ScriptRunner,
+    data: &Vec<(&'static str, SnapshotSeries)>,
+    syminfo: &Option<SymbolInfo>,
+    inputs: Vec<Option<InputVal>>,
+    skip: usize,
+    objective: &F,
+) -> Result<Option<f64>, PineFormatError>
+where
+    F: Fn(&OutputDataCollect) -> Option<f64>,
+{
+    let datalen = match data.first() {
+        Some((_, SnapshotSeries::Int(v))) => v.len(),
+        Some((_, SnapshotSeries::Float(v))) => v.len(),
+        None => 0,
+    };
+    let data = data
+        .iter()
+        .map(|(name, series)| (*name, series.clone().into_any_series()))
+        .collect();
+    let output = runner.runl(inputs, data, datalen, syminfo.clone())?;
+    Ok(objective(&skip_output(output, skip)))
+}
+
+// Remove the first skip bars from the output data.
+fn skip_output(output: OutputDataCollect, skip: usize) -> OutputDataCollect {
+    if skip == 0 {
+        return output;
+    }
+    let skip_vec = |v: Vec<Option<f64>>| v.into_iter().skip(skip).collect();
+    let data_list = output
+        .data_list
+        .into_iter()
+        .map(|data| {
+            data.map(|d| {
+                OutputData::new_with_sc(
+                    d.series.into_iter().map(skip_vec).collect(),
+                    d.colors
+                        .into_iter()
+                        .map(|c| StrOptionsData {
+                            options: c.options,
+                            values: c.values.into_iter().skip(skip).collect(),
+                        })
+                        .collect(),
+                )
+            })
+        })
+        .collect();
+    let traces = output
+        .traces
+        .into_iter()
+        .map(|t| TraceData::new(t.name, t.values.into_iter().skip(skip).collect()))
+        .collect();
+    OutputDataCollect::new_with_traces(output.from + skip as i32, output.to, data_list, traces)
+}
+
+// Sum the values of the first series of the first output. It's the profit when the
+// script plots the profit of every bar.
+pub fn sum_objective(output: &OutputDataCollect) -> Option<f64> {
+    match output.data_list.first() {
+        Some(Some(data)) => data
+            .series
+            .first()
+            .map(|s| s.iter().filter_map(|v| *v).sum()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn param_space_test() {
+        let compiled = CompiledScript::compile(String::from(
+            "a = input(1, 'a', 'int', minval = 1, maxval = 3)\nb = input(true)\nc = input(1.0, 'c', 'float', minval = 0.5, maxval = 1.5, step = 0.5)\nplot(a + c)",
+        ))
+        .unwrap();
+        let space = ParamSpace::from_io_info(compiled.get_io_info());
+        assert_eq!(
+            space.get_ranges(),
+            &vec![
+                ParamRange::Int {
+                    min: 1,
+                    max: 3,
+                    step: 1
+                },
+                ParamRange::Fixed(Some(InputVal::Bool(true))),
+                ParamRange::Float {
+                    min: 0.5,
+                    max: 1.5,
+                    step: 0.5
+                },
+            ]
+        );
+        assert_eq!(space.grid().len(), 9);
+        assert_eq!(
+            space.grid()[1],
+            vec![
+                Some(InputVal::Int(1)),
+                Some(InputVal::Bool(true)),
+                Some(InputVal::Float(1.0))
+            ]
+        );
+
+        let samples = space.random(5, 7);
+        assert_eq!(samples.len(), 5);
+        assert_eq!(samples, space.random(5, 7));
+        assert!(samples.iter().all(|s| match s[0] {
+            Some(InputVal::Int(v)) => (1..=3).contains(&v),
+            _ => false,
+        }));
+
+        // The empty, inverted and non-positive step ranges are rejected.
+        assert!(ParamSpace::new(vec![ParamRange::Values(vec![])]).is_err());
+        assert!(ParamSpace::new(vec![ParamRange::Int {
+            min: 3,
+            max: 1,
+            step: 1
+        }])
+        .is_err());
+        assert!(ParamSpace::new(vec![ParamRange::Int {
+            min: 1,
+            max: 3,
+            step: 0
+        }])
+        .is_err());
+        assert!(ParamSpace::new(vec![ParamRange::Float {
+            min: 1.5,
+            max: 0.5,
+            step: 0.5
+        }])
+        .is_err());
+        assert!(ParamSpace::new(vec![ParamRange::Float {
+            min: 0.5,
+            max: 1.5,
+            step: -0.5
+        }])
+        .is_err());
+
+        // The ranges at the i64 limits don't overflow.
+        let space = ParamSpace::new(vec![ParamRange::Int {
+            min: i64::MIN,
+            max: i64::MAX,
+            step: i64::MAX,
+        }])
+        .unwrap();
+        assert_eq!(
+            space.grid(),
+            vec![
+                vec![Some(InputVal::Int(i64::MIN))],
+                vec![Some(InputVal::Int(-1))],
+                vec![Some(InputVal::Int(i64::MAX - 1))],
+            ]
+        );
+        assert_eq!(space.random(10, 3).len(), 10);
+    }
+
+    #[test]
+    fn optimize_test() {
+        let compiled = CompiledScript::compile(String::from(
+            "m = input(1, 'm', 'int', minval = 1, maxval = 4)\nplot(close - m * m + 4 * m)",
+        ))
+        .unwrap();
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64), Some(4f64)]),
+        )];
+        let mut optimizer = Optimizer::new(compiled);
+        optimizer.set_threads(2);
+        let results = optimizer.run(&data, None, sum_objective).unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.inputs.clone()).collect::<Vec<_>>(),
+            vec![
+                vec![Some(InputVal::Int(2))],
+                vec![Some(InputVal::Int(1))],
+                vec![Some(InputVal::Int(3))],
+                vec![Some(InputVal::Int(4))],
+            ]
+        );
+        assert_eq!(results[0].score, 26f64);
+
+        optimizer.set_method(SearchMethod::Random { count: 3, seed: 1 });
+        assert_eq!(optimizer.run(&data, None, sum_objective).unwrap().len(), 3);
+
+        let walk_forward = WalkForward {
+            windows: 2,
+            in_sample_ratio: 0.5,
+        };
+        optimizer.set_method(SearchMethod::Grid);
+        let results = optimizer
+            .run_walk_forward(&data, None, walk_forward, sum_objective)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].in_sample, (0, 1));
+        assert_eq!(results[0].out_of_sample, (1, 2));
+        assert_eq!(results[0].best.inputs, vec![Some(InputVal::Int(2))]);
+        assert_eq!(results[0].out_of_sample_score, Some(6f64));
+        assert_eq!(results[1].out_of_sample_score, Some(8f64));
+
+        let bad_data = vec![
+            ("close", AnySeries::from_float_vec(vec![Some(1f64)])),
+            ("open", AnySeries::from_float_vec(vec![])),
+        ];
+        assert!(optimizer.run(&bad_data, None, sum_objective).is_err());
+
+        // The NaN scores are dropped instead of being ranked.
+        let results = optimizer
+            .run(&data, None, |output| {
+                sum_objective(output).map(|v| if v > 20f64 { v } else { f64::NAN })
+            })
+            .unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.inputs.clone()).collect::<Vec<_>>(),
+            vec![
+                vec![Some(InputVal::Int(2))],
+                vec![Some(InputVal::Int(1))],
+                vec![Some(InputVal::Int(3))],
+            ]
+        );
+
+        let mut space = optimizer.get_space().clone();
+        assert!(space.set_range(0, ParamRange::Fixed(None)).is_ok());
+        assert!(space.set_range(1, ParamRange::Fixed(None)).is_err());
+        assert!(space.set_range(0, ParamRange::Values(vec![])).is_err());
+
+        // The error of the candidate that fails to run is returned.
+        space
+            .set_range(
+                0,
+                ParamRange::Values(vec![InputVal::Int(1), InputVal::Bool(true)]),
+            )
+            .unwrap();
+        optimizer.set_space(space);
+        assert!(optimizer.run(&data, None, sum_objective).is_err());
+    }
+}
@@ -1,14 +1,18 @@
 use super::data_src::Callback;
+use super::debug::{inspect_current, Debugger, VarValue};
 use super::output::InputVal;
 use super::output::{
-    IOInfo, InputInfo, InputSrc, OutputData, OutputInfo, ScriptPurpose, SymbolInfo,
+    AlertEvent, IOInfo, InputInfo, InputSrc, OutputData, OutputInfo, ScriptPurpose, SymbolInfo,
+};
+use super::snapshot::{
+    load_instance, load_value, save_values, state_err, ContextState, StateValue,
 };
 use crate::ast::input::{Position, StrRange};
 use crate::ast::stat_expr_types::VarIndex;
 use crate::runtime::AnySeries;
 use crate::types::{
-    Bool, Callable, Color, DataType, Float, Int, PineFrom, PineRef, PineStaticType, PineType,
-    RefData, Runnable, RuntimeErr, SecondType, Series, NA,
+    Bool, Color, DataType, Float, Int, PineFrom, PineRef, PineStaticType, PineType, RefData,
+    Runnable, RuntimeErr, SecondType, Series, UserObject, UserObjectItem,
 };
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -43,7 +47,8 @@ pub trait Ctx<'a>: VarOperate<'a> {
 
     fn get_top_varname_index(&self, name: &str) -> Option<VarIndex>;
 
-    fn create_runnable(&mut self, call: Rc<RefCell<dyn Runnable<'a> + 'a>>);
+    // Register the callable instance with the range of the call that creates it.
+    fn create_runnable(&mut self, call: Rc<RefCell<dyn Runnable<'a> + 'a>>, range: StrRange);
 
     fn move_fun_instance(&mut self, index: i32) -> Option<PineRef<'a>>;
 
@@ -83,12 +88,18 @@ pub trait Ctx<'a>: VarOperate<'a> {
 
     fn get_callback(&self) -> Option<&'a dyn Callback>;
 
+    fn get_debugger(&self) -> Option<&'a dyn Debugger<'a>>;
+
     fn set_iterindex(&mut self, index: i32);
 
     fn get_iterindex(&self) -> i32;
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+// The call site of the input function, the ids of the contexts from the main context to the
+// context of the call and the range of the call.
+pub type InputSite = (Vec<i32>, StrRange);
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum ContextType {
     Library,
     Main,
@@ -103,6 +114,8 @@ pub struct Context<'a, 'b, 'c> {
     // input: &'a str,
     parent: Option<&'b mut (dyn 'b + Ctx<'a>)>,
     context_type: ContextType,
+    // The index of the context in the sub contexts of the parent.
+    ctxid: i32,
 
     // Child contexts that with parent self lifetime 'c
     sub_contexts: Vec<Option<Box<dyn 'c + Ctx<'a>>>>,
@@ -114,9 +127,11 @@ pub struct Context<'a, 'b, 'c> {
 
     // function and evaluate instances
     fun_instances: Vec<Option<PineRef<'a>>>,
+    // The restored states of the instances that are created by the later bars.
+    pending_instances: HashMap<i32, Vec<StateValue>>,
 
     // runnables contains all the callable instance that need commit or rollback
-    runnables: Vec<Rc<RefCell<dyn Runnable<'a> + 'a>>>,
+    runnables: Vec<(Rc<RefCell<dyn Runnable<'a> + 'a>>, StrRange)>,
     // declare_vars: HashSet<&'a str>,
 
     // Custom shapes(line, label, ...etc)
@@ -132,11 +147,29 @@ pub struct Context<'a, 'b, 'c> {
     inputs: Vec<Option<InputVal>>,
     // The input index will increment after input function is invoked
     input_index: i32,
+    // The call sites of the input functions in the order of the inputs. If they are found from
+    // the syntax tree, the input functions take the inputs of their call sites instead of the
+    // inputs in the order they run, so the inputs in the branches keep their slots.
+    input_sites: Vec<InputSite>,
+    // The range of the builtin function call that is being called.
+    call_range: StrRange,
 
     // Input data for some external ticker.
     input_data: HashMap<String, AnySeries>,
     // The output data that will be exported.
     output_data: Vec<Option<OutputData>>,
+    // The alert events triggered by the alert function since the output data is moved.
+    alerts: Vec<AlertEvent>,
+    // The count of the roll backs, so the alert instances know the bar is run again.
+    rollback_count: u32,
+    // The field writes of the objects with the replaced values since the last but one commit,
+    // and the count of the writes before the last commit, so the writes of the rolled back bar
+    // can be undone.
+    field_writes: Vec<(Rc<UserObject<'a>>, usize, PineRef<'a>)>,
+    field_write_mark: usize,
+    // The variables whose values are recorded after every bar and the recorded values.
+    trace_vars: Vec<VarIndex>,
+    trace_values: Vec<Vec<Option<VarValue>>>,
 
     io_info: IOInfo,
     // Check if input_info is ready
@@ -152,6 +185,8 @@ pub struct Context<'a, 'b, 'c> {
 
     // The output values
     callback: Option<&'a dyn Callback>,
+    // The debugger is shared by the main context and all the sub contexts.
+    debugger: Option<&'a dyn Debugger<'a>>,
     first_commit: bool,
 
     is_run: bool,
@@ -207,6 +242,7 @@ pub fn commit_series_for_operator<'a>(operator: &mut dyn VarOperate<'a>) {
                     use crate::libs::label::PerLabelItem;
                     commit_series::<PerLabelItem>(val)
                 }
+                (DataType::UserObject, SecondType::Series) => commit_series::<UserObjectItem>(val),
                 _ => val,
             };
             operator.update_var(index, ret_val);
@@ -239,6 +275,9 @@ pub fn rollback_series_for_operator<'a>(operator: &mut dyn VarOperate<'a>) {
                     use crate::libs::label::PerLabelItem;
                     roll_back_series::<PerLabelItem>(val)
                 }
+                (DataType::UserObject, SecondType::Series) => {
+                    roll_back_series::<UserObjectItem>(val)
+                }
                 _ => val,
             };
             operator.update_var(index, ret_val);
@@ -257,23 +296,36 @@ where
 
 impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
     pub fn new(parent: Option<&'b mut (dyn 'b + Ctx<'a>)>, t: ContextType) -> Context<'a, 'b, 'c> {
+        let debugger = parent.as_ref().and_then(|p| p.get_debugger());
+        let callback = parent.as_ref().and_then(|p| p.get_callback());
         Context {
             parent,
             context_type: t,
+            ctxid: -1,
             sub_contexts: Vec::new(),
             vars: Vec::new(),
             varname_indexs: HashMap::new(),
             fun_instances: Vec::new(),
+            pending_instances: HashMap::new(),
             runnables: vec![],
             shapes: vec![],
             reqcom_shapes: vec![],
             iterindex: 0,
             // declare_vars: HashSet::new(),
-            callback: None,
+            callback,
+            debugger,
             inputs: vec![],
             input_index: -1,
+            input_sites: vec![],
+            call_range: StrRange::new_empty(),
             input_data: HashMap::new(),
             output_data: vec![],
+            alerts: vec![],
+            rollback_count: 0,
+            field_writes: vec![],
+            field_write_mark: 0,
+            trace_vars: vec![],
+            trace_values: vec![],
             io_info: IOInfo::new(),
             is_input_info_ready: false,
             is_output_info_ready: false,
@@ -288,20 +340,31 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         Context {
             parent: None,
             context_type: ContextType::Normal,
+            ctxid: -1,
             sub_contexts: Vec::new(),
             vars: Vec::new(),
             varname_indexs: HashMap::new(),
             fun_instances: Vec::new(),
+            pending_instances: HashMap::new(),
             runnables: vec![],
             shapes: vec![],
             reqcom_shapes: vec![],
             iterindex: 0,
             // declare_vars: HashSet::new(),
             callback: Some(callback),
+            debugger: None,
             inputs: vec![],
             input_index: -1,
+            input_sites: vec![],
+            call_range: StrRange::new_empty(),
             input_data: HashMap::new(),
             output_data: vec![],
+            alerts: vec![],
+            rollback_count: 0,
+            field_writes: vec![],
+            field_write_mark: 0,
+            trace_vars: vec![],
+            trace_values: vec![],
             io_info: IOInfo::new(),
             is_input_info_ready: false,
             is_output_info_ready: false,
@@ -344,6 +407,7 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         if self.context_type == ContextType::Main {
             debug_assert!(
                 self.io_info.get_inputs().is_empty()
+                    || inputs.is_empty()
                     || inputs.len() == self.io_info.get_inputs().len()
             );
             self.inputs = inputs;
@@ -364,17 +428,48 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         }
     }
 
-    pub fn copy_next_input(&mut self) -> Option<InputVal> {
+    // The ids of the contexts from the main context to this context.
+    fn get_ctx_path(&self) -> Vec<i32> {
+        match &self.parent {
+            Some(p) if self.context_type != ContextType::Main => {
+                let mut path = downcast_ctx_const(*p).get_ctx_path();
+                path.push(self.ctxid);
+                path
+            }
+            _ => vec![],
+        }
+    }
+
+    // The input value is checked by the input info before it's used by the script.
+    pub fn copy_next_input(&mut self) -> Result<Option<InputVal>, RuntimeErr> {
+        let path = self.get_ctx_path();
+        self.copy_site_input(path)
+    }
+
+    fn copy_site_input(&mut self, path: Vec<i32>) -> Result<Option<InputVal>, RuntimeErr> {
         if self.context_type == ContextType::Main {
             self.input_index += 1;
 
-            if self.input_index as usize >= self.inputs.len() {
-                None
+            let site = (path, self.call_range);
+            let index = if self.input_sites.is_empty() {
+                self.input_index as usize
             } else {
-                self.inputs[self.input_index as usize].clone()
+                match self.input_sites.iter().position(|s| *s == site) {
+                    Some(index) => index,
+                    None => return Ok(None),
+                }
+            };
+            match self.inputs.get(index) {
+                Some(Some(val)) => {
+                    if let Some(info) = self.io_info.get_inputs().get(index) {
+                        info.check_input(index, val)?;
+                    }
+                    Ok(Some(val.clone()))
+                }
+                _ => Ok(None),
             }
         } else if let Some(p) = &mut self.parent {
-            downcast_ctx(*p).copy_next_input()
+            downcast_ctx(*p).copy_site_input(path)
         } else {
             unreachable!()
         }
@@ -412,9 +507,29 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         }
     }
 
+    // Set the inputs and their call sites found from the syntax tree before running.
+    pub fn set_input_sites(&mut self, inputs: Vec<InputInfo>, sites: Vec<InputSite>) {
+        debug_assert!(self.is_main());
+        debug_assert_eq!(inputs.len(), sites.len());
+        self.io_info.set_inputs(inputs);
+        self.input_sites = sites;
+    }
+
+    pub fn set_call_range(&mut self, range: StrRange) {
+        match &mut self.parent {
+            Some(p) if self.context_type != ContextType::Main => {
+                downcast_ctx(*p).set_call_range(range)
+            }
+            _ => self.call_range = range,
+        }
+    }
+
     pub fn push_input_info(&mut self, input: InputInfo) {
         if self.context_type == ContextType::Main {
-            self.io_info.push_input(input);
+            // The inputs found from the syntax tree are set before running.
+            if self.input_sites.is_empty() {
+                self.io_info.push_input(input);
+            }
         } else if let Some(p) = &mut self.parent {
             downcast_ctx(*p).push_input_info(input)
         } else {
@@ -523,12 +638,69 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         }
     }
 
+    // Whether the output data of all the outputs is generated and not moved.
+    pub fn has_output_data(&self) -> bool {
+        self.output_data.len() == self.io_info.get_outputs().len()
+    }
+
     pub fn move_output_data(&mut self) -> Vec<Option<OutputData>> {
         debug_assert!(self.is_main());
         debug_assert_eq!(self.output_data.len(), self.io_info.get_outputs().len());
         mem::replace(&mut self.output_data, vec![])
     }
 
+    pub fn push_alert(&mut self, alert: AlertEvent) {
+        if self.context_type == ContextType::Main {
+            self.alerts.push(alert);
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).push_alert(alert)
+        } else {
+            unreachable!()
+        }
+    }
+
+    // Record the field write of the object, so it can be undone when the bar is rolled back.
+    pub fn record_field_write(&mut self, obj: Rc<UserObject<'a>>, index: usize, old: PineRef<'a>) {
+        self.field_writes.push((obj, index, old));
+    }
+
+    pub fn get_rollback_count(&self) -> u32 {
+        if self.context_type == ContextType::Main {
+            self.rollback_count
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).get_rollback_count()
+        } else {
+            unreachable!()
+        }
+    }
+
+    pub fn move_alerts(&mut self) -> Vec<AlertEvent> {
+        debug_assert!(self.is_main());
+        mem::take(&mut self.alerts)
+    }
+
+    pub fn set_trace_vars(&mut self, vars: Vec<VarIndex>) {
+        self.trace_values = vars.iter().map(|_| vec![]).collect();
+        self.trace_vars = vars;
+    }
+
+    // Record the current values of the traced variables, it's invoked after the bar has run.
+    pub fn record_traces(&mut self) {
+        let values: Vec<_> = self
+            .trace_vars
+            .iter()
+            .map(|index| self.get_var(*index).as_ref().and_then(inspect_current))
+            .collect();
+        for (trace, val) in self.trace_values.iter_mut().zip(values) {
+            trace.push(val);
+        }
+    }
+
+    pub fn move_traces(&mut self) -> Vec<Vec<Option<VarValue>>> {
+        let empty = self.trace_vars.iter().map(|_| vec![]).collect();
+        mem::replace(&mut self.trace_values, empty)
+    }
+
     pub fn set_syminfo(&mut self, syminfo: Rc<SymbolInfo>) {
         if self.context_type == ContextType::Main {
             self.syminfo = Some(syminfo);
@@ -567,7 +739,9 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         'b: 'c,
     {
         let mut subctx = Box::new(Context::new(None, t));
+        subctx.ctxid = index;
         subctx.init(var_count, subctx_count, libfun_count);
+        subctx.debugger = self.debugger;
         unsafe {
             // Force the &Context to &mut Context to prevent the rust's borrow checker
             // When the sub context borrow the parent context, the parent context should not
@@ -619,6 +793,12 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
             self.first_commit = true;
         }
 
+        // Only the writes of the committed bar can be rolled back.
+        if self.context_type == ContextType::Main {
+            self.field_writes.drain(..self.field_write_mark);
+            self.field_write_mark = self.field_writes.len();
+        }
+
         // Commit all of the shapes(Line, Label)
         for shape in self.reqcom_shapes.iter_mut() {
             match shape.get_type() {
@@ -641,15 +821,22 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
 
     pub fn roll_back(&mut self) -> Result<(), PineRuntimeError> {
         rollback_series_for_operator(self);
+        self.rollback_count += 1;
 
         let mut callables = mem::replace(&mut self.runnables, vec![]);
-        for callable in callables.iter_mut() {
+        for (callable, range) in callables.iter_mut() {
             if let Err(code) = callable.borrow_mut().back(self) {
-                return Err(PineRuntimeError::new_no_range(code));
+                return Err(PineRuntimeError::new(code, *range));
             }
         }
         mem::replace(&mut self.runnables, callables);
 
+        // Undo the field writes of the rolled back bar in the reverse order.
+        while let Some((obj, index, val)) = self.field_writes.pop() {
+            obj.restore_field(index, val);
+        }
+        self.field_write_mark = 0;
+
         // Roll back all of the shapes(Line, Label)
         for shape in self.reqcom_shapes.iter_mut() {
             match shape.get_type() {
@@ -671,15 +858,30 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         Ok(())
     }
 
-    pub fn run_callbacks(&mut self) -> Result<(), RuntimeErr> {
+    pub fn run_callbacks(&mut self) -> Result<(), PineRuntimeError> {
         let mut callables = mem::replace(&mut self.runnables, vec![]);
-        for callable in callables.iter_mut() {
-            callable.borrow_mut().run(self)?;
+        for (callable, range) in callables.iter_mut() {
+            if let Err(code) = callable.borrow_mut().run(self) {
+                return Err(PineRuntimeError::new(code, *range));
+            }
         }
         mem::replace(&mut self.runnables, callables);
         Ok(())
     }
 
+    // The sub contexts created after it get the callback from the parent.
+    pub fn set_callback(&mut self, callback: Option<&'a dyn Callback>) {
+        self.callback = callback;
+    }
+
+    // Set the debugger for this context and all the created sub contexts.
+    pub fn set_debugger(&mut self, debugger: Option<&'a dyn Debugger<'a>>) {
+        self.debugger = debugger;
+        for subctx in self.sub_contexts.iter_mut().flatten() {
+            downcast_ctx(subctx.as_mut()).set_debugger(debugger);
+        }
+    }
+
     pub fn contains_sub_context(&self, index: i32) -> bool {
         self.sub_contexts[index as usize].is_some()
     }
@@ -725,6 +927,93 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         self.context_type == ContextType::Main
     }
 
+    // Save the state of the variables, instances and sub contexts, it's None if some
+    // values can't be saved.
+    pub fn save_state(&self) -> Option<ContextState> {
+        let sub_contexts = self
+            .sub_contexts
+            .iter()
+            .map(|ctx| match ctx {
+                Some(ctx) => downcast_ctx_const(&**ctx).save_state().map(Some),
+                None => Some(None),
+            })
+            .collect::<Option<_>>()?;
+        let mut fun_instances = save_values(&self.fun_instances)?;
+        // The pending states are saved for the instances that are not created yet.
+        for (index, state) in self.pending_instances.iter() {
+            fun_instances[*index as usize] = StateValue::Instance(state.clone());
+        }
+        Some(ContextState {
+            context_type: self.context_type,
+            vars: save_values(&self.vars)?,
+            fun_instances,
+            sub_contexts,
+        })
+    }
+
+    // Load the state into the context that has run the first bar. The sub contexts and
+    // instances that are created by the later bars are created from the state, or loaded
+    // when they are created.
+    pub fn load_state(&mut self, state: ContextState) -> Result<(), RuntimeErr>
+    where
+        'a: 'c,
+        'b: 'c,
+    {
+        if state.context_type != self.context_type
+            || state.vars.len() != self.vars.len()
+            || state.fun_instances.len() != self.fun_instances.len()
+            || state.sub_contexts.len() != self.sub_contexts.len()
+        {
+            return Err(state_err());
+        }
+        for (var, state) in self.vars.iter_mut().zip(state.vars) {
+            load_value(var, state)?;
+        }
+        for (i, state) in state.fun_instances.into_iter().enumerate() {
+            match (state, &mut self.fun_instances[i]) {
+                (StateValue::Instance(state), Some(instance)) => load_instance(instance, state)?,
+                (StateValue::Instance(state), None) => {
+                    self.pending_instances.insert(i as i32, state);
+                }
+                (StateValue::Empty, _) => {}
+                _ => return Err(state_err()),
+            }
+        }
+        for (i, state) in state.sub_contexts.into_iter().enumerate() {
+            let state = match state {
+                Some(state) => state,
+                None => continue,
+            };
+            if self.sub_contexts[i].is_none() {
+                let mut subctx = Box::new(Context::new(None, state.context_type));
+                subctx.ctxid = i as i32;
+                subctx.init(
+                    state.vars.len() as i32,
+                    state.sub_contexts.len() as i32,
+                    state.fun_instances.len() as i32,
+                );
+                subctx.debugger = self.debugger;
+                // The sub context refers to the parent like create_sub_context.
+                let ptr: *mut Context<'a, 'b, 'c> = self;
+                subctx.parent = Some(unsafe { ptr.as_mut().unwrap() });
+                self.sub_contexts[i] = Some(subctx);
+            }
+            let subctx = self.sub_contexts[i].as_mut().unwrap();
+            downcast_ctx(subctx.as_mut()).load_state(state)?;
+        }
+        Ok(())
+    }
+
+    // Move the restored state of the instance that is created now.
+    pub fn take_pending_instance(&mut self, index: i32) -> Option<Vec<StateValue>> {
+        self.pending_instances.remove(&index)
+    }
+
+    pub fn set_rollback_count(&mut self, count: u32) {
+        debug_assert!(self.is_main());
+        self.rollback_count = count;
+    }
+
     pub fn get_varcount(&self) -> usize {
         self.vars.len()
     }
@@ -813,13 +1102,13 @@ impl<'a, 'b, 'c> Ctx<'a> for Context<'a, 'b, 'c> {
         }
     }
 
-    fn create_runnable(&mut self, call: Rc<RefCell<dyn Runnable<'a> + 'a>>) {
+    fn create_runnable(&mut self, call: Rc<RefCell<dyn Runnable<'a> + 'a>>, range: StrRange) {
         if self.context_type == ContextType::Main {
             if !self.first_commit {
-                self.runnables.push(call);
+                self.runnables.push((call, range));
             }
         } else if let Some(ref mut v) = self.parent {
-            v.create_runnable(call);
+            v.create_runnable(call, range);
         }
     }
 
@@ -916,6 +1205,10 @@ impl<'a, 'b, 'c> Ctx<'a> for Context<'a, 'b, 'c> {
         self.callback
     }
 
+    fn get_debugger(&self) -> Option<&'a dyn Debugger<'a>> {
+        self.debugger
+    }
+
     fn set_iterindex(&mut self, index: i32) {
         debug_assert!(self.context_type == ContextType::Main);
         self.iterindex = index;
@@ -932,22 +1225,59 @@ impl<'a, 'b, 'c> Ctx<'a> for Context<'a, 'b, 'c> {
     }
 }
 
+// The user-defined function call that leads to the runtime error.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct CallFrame {
+    pub name: String,
+    pub range: StrRange,
+}
+
+impl CallFrame {
+    pub fn new(name: String, range: StrRange) -> CallFrame {
+        CallFrame { name, range }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct PineRuntimeError {
     pub code: RuntimeErr,
     pub range: StrRange,
+    // The bar index and the time of the bar at which the error occurred.
+    pub bar_index: Option<i32>,
+    pub time: Option<i64>,
+    // The chain of user-defined function calls, the outermost call comes first.
+    pub call_stack: Vec<CallFrame>,
 }
 
 impl PineRuntimeError {
     pub fn new(code: RuntimeErr, range: StrRange) -> PineRuntimeError {
-        PineRuntimeError { code, range }
+        PineRuntimeError {
+            code,
+            range,
+            bar_index: None,
+            time: None,
+            call_stack: vec![],
+        }
     }
 
     pub fn new_no_range(code: RuntimeErr) -> PineRuntimeError {
-        PineRuntimeError {
-            code,
-            range: StrRange::from_start("", Position::new(0, 0)),
+        PineRuntimeError::new(code, StrRange::from_start("", Position::new(0, 0)))
+    }
+
+    // Record the bar if the error has not been bound to a bar yet.
+    pub fn with_bar(mut self, bar_index: i32, time: Option<i64>) -> PineRuntimeError {
+        if self.bar_index.is_none() {
+            self.bar_index = Some(bar_index);
+            self.time = time;
         }
+        self
+    }
+
+    // Push the user-defined function call when the error propagates out of the function.
+    pub fn push_call(mut self, name: &str, range: StrRange) -> PineRuntimeError {
+        self.call_stack
+            .insert(0, CallFrame::new(String::from(name), range));
+        self
     }
 }
 
@@ -983,7 +1313,7 @@ pub trait StmtRunner<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Int, PineFrom};
+    use crate::types::{Callable, Int, PineFrom};
 
     #[test]
     fn context_test() {
@@ -1019,23 +1349,35 @@ mod tests {
     fn callable_context_test() {
         // Parent context create callable
         let mut context1 = Context::new(None, ContextType::Main);
-        context1.create_runnable(RefData::new_rc(Callable::new(None, None)).into_rc());
+        context1.create_runnable(
+            RefData::new_rc(Callable::new(None, None)).into_rc(),
+            StrRange::new_empty(),
+        );
         assert_eq!(context1.runnables.len(), 1);
 
         {
             // Child context create callable
             let mut context2 = Context::new(Some(&mut context1), ContextType::Normal);
-            context2.create_runnable(RefData::new_rc(Callable::new(None, None)).into_rc());
+            context2.create_runnable(
+                RefData::new_rc(Callable::new(None, None)).into_rc(),
+                StrRange::new_empty(),
+            );
         }
         assert_eq!(context1.runnables.len(), 2);
 
         context1.commit();
 
         // After commit, parent context and child context should not add callable by create callable
-        context1.create_runnable(RefData::new_rc(Callable::new(None, None)).into_rc());
+        context1.create_runnable(
+            RefData::new_rc(Callable::new(None, None)).into_rc(),
+            StrRange::new_empty(),
+        );
         {
             let mut context2 = Context::new(Some(&mut context1), ContextType::Normal);
-            context2.create_runnable(RefData::new_rc(Callable::new(None, None)).into_rc());
+            context2.create_runnable(
+                RefData::new_rc(Callable::new(None, None)).into_rc(),
+                StrRange::new_empty(),
+            );
         }
         assert_eq!(context1.runnables.len(), 2);
 
@@ -1,8 +1,12 @@
 use super::context::{
-    downcast_ctx, Context, ContextType, Ctx, PineRuntimeError, Runner, VarOperate,
+    downcast_ctx, downcast_ctx_const, Context, ContextType, Ctx, InputSite, PineRuntimeError,
+    Runner, VarOperate,
 };
+use super::debug::{Debugger, VarValue};
 // use super::ctxid_parser::CtxIdParser;
-use super::output::{InputSrc, InputVal, SymbolInfo};
+use super::output::{IOInfo, InputInfo, InputSrc, InputVal, SymbolInfo};
+use super::snapshot::ScriptState;
+use super::static_io::extract_io_info;
 use super::{AnySeries, AnySeriesType};
 use crate::ast::stat_expr_types::{Block, VarIndex};
 use crate::types::{
@@ -29,18 +33,24 @@ pub struct DataSrc<'a> {
     pub callback: &'a dyn Callback,
     inputs: Vec<Option<InputVal>>,
     input_srcs: Option<InputSrc>,
+    // The inputs and their call sites found from the syntax tree.
+    input_sites: Option<(Vec<InputInfo>, Vec<InputSite>)>,
+    trace_vars: Vec<VarIndex>,
+    debugger: Option<&'a dyn Debugger<'a>>,
     has_run: bool,
 }
 
-pub fn parse_datalen<'a>(
-    data: &Vec<(&'static str, AnySeries)>,
-    names: &Vec<(&'a str, AnySeriesType)>,
+pub fn parse_datalen<N: AsRef<str>>(
+    data: &[(N, AnySeries)],
+    names: &Vec<(&str, AnySeriesType)>,
 ) -> Result<usize, PineRuntimeError> {
     let lens: Vec<usize> = data
         .iter()
-        .filter_map(|(name, v)| match names.iter().find(|(n, _)| n == name) {
-            Some(_) => Some(v.len()),
-            None => None,
+        .filter_map(|(name, v)| {
+            names
+                .iter()
+                .find(|(n, _)| *n == name.as_ref())
+                .map(|_| v.len())
         })
         .collect();
     if lens.len() == 0 {
@@ -55,6 +65,28 @@ pub fn parse_datalen<'a>(
     Ok(lens[0])
 }
 
+// Check the input data has the declared types and is long enough for the count of the
+// bars to run, because the data is read without bounds checking.
+pub fn check_data<N: AsRef<str>>(
+    data: &[(N, AnySeries)],
+    names: &Vec<(&str, AnySeriesType)>,
+    len: usize,
+) -> Result<(), PineRuntimeError> {
+    for (name, v) in data.iter() {
+        if let Some((_, series_type)) = names.iter().find(|(n, _)| *n == name.as_ref()) {
+            if *series_type != v.get_type() {
+                return Err(PineRuntimeError::new_no_range(
+                    RuntimeErr::DataTypeMismatch(String::from(name.as_ref())),
+                ));
+            }
+            if v.len() < len {
+                return Err(PineRuntimeError::new_no_range(RuntimeErr::InvalidDataLen));
+            }
+        }
+    }
+    Ok(())
+}
+
 impl<'a> DataSrc<'a> {
     pub fn new(
         blk: &'a Block<'a>,
@@ -65,6 +97,7 @@ impl<'a> DataSrc<'a> {
         let input_index = lib_vars.len() as i32;
 
         let mut context = Box::new(Context::new(None, ContextType::Library));
+        context.set_callback(Some(callback));
         let libvar_count = input_index + input_names.len() as i32;
         context.init(libvar_count, 1, 0);
 
@@ -105,6 +138,9 @@ impl<'a> DataSrc<'a> {
             callback,
             inputs: vec![],
             input_srcs: None,
+            input_sites: None,
+            trace_vars: vec![],
+            debugger: None,
             has_run: false,
         }
     }
@@ -128,6 +164,11 @@ impl<'a> DataSrc<'a> {
             self.blk.subctx_count,
             self.blk.libfun_count,
         );
+        if let Some((inputs, sites)) = self.input_sites.as_ref() {
+            main_ctx.set_input_sites(inputs.clone(), sites.clone());
+        }
+        main_ctx.set_trace_vars(self.trace_vars.clone());
+        main_ctx.set_debugger(self.debugger);
         self.context = Box::new(main_ctx);
     }
 
@@ -142,16 +183,52 @@ impl<'a> DataSrc<'a> {
             .add_input_src(self.input_srcs.as_ref().unwrap().clone());
     }
 
-    fn run_data(
+    // Trace the variables of the main context, the values are recorded after every bar.
+    pub fn set_trace_vars(&mut self, vars: Vec<VarIndex>) {
+        self.trace_vars = vars;
+        downcast_ctx(self.context.as_mut()).set_trace_vars(self.trace_vars.clone());
+    }
+
+    pub fn move_traces(&mut self) -> Vec<Vec<Option<VarValue>>> {
+        downcast_ctx(self.context.as_mut()).move_traces()
+    }
+
+    pub fn set_debugger(&mut self, debugger: Option<&'a dyn Debugger<'a>>) {
+        self.debugger = debugger;
+        downcast_ctx(self.context.as_mut()).set_debugger(debugger);
+    }
+
+    // Extract the IOInfo from the syntax tree into the main context that has not run.
+    pub fn extract_io_info(&mut self) -> Result<IOInfo, RuntimeErr> {
+        extract_io_info(self.context.as_mut(), self.blk).map(|(io_info, _)| io_info)
+    }
+
+    // Extract the inputs and their call sites from the syntax tree into the main context that
+    // has not run.
+    pub fn extract_input_sites(&mut self) -> Result<(Vec<InputInfo>, Vec<InputSite>), RuntimeErr> {
+        let (io_info, sites) = extract_io_info(self.context.as_mut(), self.blk)?;
+        Ok((io_info.get_inputs().clone(), sites))
+    }
+
+    // The inputs are taken by their call sites instead of the order the input calls run.
+    pub fn set_input_sites(&mut self, inputs: Vec<InputInfo>, sites: Vec<InputSite>) {
+        downcast_ctx(self.context.as_mut()).set_input_sites(inputs.clone(), sites.clone());
+        self.input_sites = Some((inputs, sites));
+    }
+
+    fn run_data<N: AsRef<str>>(
         &mut self,
-        data: &Vec<(&'static str, AnySeries)>,
+        data: &[(N, AnySeries)],
         start: i64,
         len: usize,
     ) -> Result<(), PineRuntimeError> {
         let bar_index = self.input_names.iter().position(|(s, _)| *s == "bar_index");
+        let time_index = data
+            .iter()
+            .position(|(k, v)| k.as_ref() == "_time" && v.get_type() == AnySeriesType::Int);
         let name_indexs: Vec<Option<usize>> = data
             .iter()
-            .map(|(k, _)| self.input_names.iter().position(|(s, _)| *s == *k))
+            .map(|(k, _)| self.input_names.iter().position(|(s, _)| *s == k.as_ref()))
             .collect();
         for iter_i in start..(start + len as i64) {
             // Extract data into context
@@ -177,7 +254,7 @@ impl<'a> DataSrc<'a> {
                 } else {
                     // TODO: Remove this data copy.
                     downcast_ctx(self.lib_context.as_mut())
-                        .insert_input_data(String::from(*_k), v.clone());
+                        .insert_input_data(String::from(_k.as_ref()), v.clone());
                 }
             }
 
@@ -190,13 +267,18 @@ impl<'a> DataSrc<'a> {
             }
 
             self.context.set_iterindex(iter_i as i32);
-            self.blk.run(self.context.as_mut())?;
+            if let Err(err) = self.blk.run(self.context.as_mut()) {
+                let time =
+                    time_index.and_then(|i| data[i].1.index::<Int>((iter_i - start) as isize));
+                return Err(err.with_bar(iter_i as i32, time));
+            }
 
             let lib_ctx = downcast_ctx(self.lib_context.as_mut());
             // main context is not children of Library context, so commit it alone.
             lib_ctx.commit();
 
             let main_ctx = downcast_ctx(self.context.as_mut());
+            main_ctx.record_traces();
             main_ctx.commit();
             main_ctx.clear_is_run();
             // self.context.clear_declare();
@@ -205,7 +287,8 @@ impl<'a> DataSrc<'a> {
         }
         let main_ctx = downcast_ctx(self.context.as_mut());
         match downcast_ctx(main_ctx).run_callbacks() {
-            Err(err) => Err(PineRuntimeError::new_no_range(err)),
+            // The callbacks are run after the last bar.
+            Err(err) => Err(err.with_bar(start as i32 + len as i32 - 1, None)),
             _ => {
                 main_ctx.let_output_info_ready();
                 Ok(())
@@ -228,6 +311,16 @@ impl<'a> DataSrc<'a> {
         len: usize,
         syminfo: Option<Rc<SymbolInfo>>,
     ) -> Result<(), PineRuntimeError> {
+        self.runl_named(data, len, syminfo)
+    }
+
+    pub(crate) fn runl_named<N: AsRef<str>>(
+        &mut self,
+        data: &[(N, AnySeries)],
+        len: usize,
+        syminfo: Option<Rc<SymbolInfo>>,
+    ) -> Result<(), PineRuntimeError> {
+        check_data(data, &self.input_names, len)?;
         // Update the range of data.
         self.reset_vars();
         let main_ctx = downcast_ctx(self.context.as_mut());
@@ -253,14 +346,31 @@ impl<'a> DataSrc<'a> {
         data: &Vec<(&'static str, AnySeries)>,
         len: usize,
     ) -> Result<(), PineRuntimeError> {
+        self.updatel_named(data, len)
+    }
+
+    pub(crate) fn updatel_named<N: AsRef<str>>(
+        &mut self,
+        data: &[(N, AnySeries)],
+        len: usize,
+    ) -> Result<(), PineRuntimeError> {
+        check_data(data, &self.input_names, len)?;
         let main_ctx = downcast_ctx(self.context.as_mut());
 
         // Get the range of exist running data.
         let range = main_ctx.get_data_range();
         // The new data's start index.
-        let start = range.1.unwrap() - 1;
+        let start = match range.1 {
+            Some(end) if end > 0 => end - 1,
+            _ => return Err(PineRuntimeError::new_no_range(RuntimeErr::ScriptNotRun)),
+        };
+        if len == 0 {
+            return Err(PineRuntimeError::new_no_range(RuntimeErr::InvalidDataLen));
+        }
         main_ctx.update_data_range((Some(start), Some(start + len as i32)));
-        main_ctx.roll_back()?;
+        if let Err(err) = main_ctx.roll_back() {
+            return Err(err.with_bar(start, None));
+        }
         self.run_data(data, start as i64, len)
     }
 
@@ -280,19 +390,84 @@ impl<'a> DataSrc<'a> {
         from: i32,
         len: usize,
     ) -> Result<(), PineRuntimeError> {
+        self.update_froml_named(data, from, len)
+    }
+
+    pub(crate) fn update_froml_named<N: AsRef<str>>(
+        &mut self,
+        data: &[(N, AnySeries)],
+        from: i32,
+        len: usize,
+    ) -> Result<(), PineRuntimeError> {
+        check_data(data, &self.input_names, len)?;
         let main_ctx = downcast_ctx(self.context.as_mut());
 
         let range = main_ctx.get_data_range();
+        let end = match range.1 {
+            Some(end) => end,
+            None => return Err(PineRuntimeError::new_no_range(RuntimeErr::ScriptNotRun)),
+        };
+        if from < 0 || from > end {
+            return Err(PineRuntimeError::new_no_range(
+                RuntimeErr::InvalidUpdateFrom(from),
+            ));
+        }
         // Calculate the count of roll_back invocation
-        let roll_count = range.1.unwrap() - from;
+        let roll_count = end - from;
         main_ctx.update_data_range((Some(from), Some(from + len as i32)));
 
-        for _ in 0..roll_count {
-            main_ctx.roll_back()?;
+        for i in 0..roll_count {
+            if let Err(err) = main_ctx.roll_back() {
+                return Err(err.with_bar(end - 1 - i, None));
+            }
         }
         self.run_data(data, from as i64, len)
     }
 
+    // Save the state of the library context and the main context that have run.
+    pub fn save_state(&self) -> Option<ScriptState> {
+        let main_ctx = downcast_ctx_const(self.context.as_ref());
+        Some(ScriptState {
+            iterindex: main_ctx.get_iterindex(),
+            rollback_count: main_ctx.get_rollback_count(),
+            library: downcast_ctx_const(self.lib_context.as_ref()).save_state()?,
+            main: main_ctx.save_state()?,
+        })
+    }
+
+    // Restore the state saved after running the bars of the data. Only the first bar is run
+    // to create the runtime of the script, then the state replaces the values of the bar, so
+    // the outputs, alerts and traces of the first bar are dropped.
+    pub fn restore<N: AsRef<str>>(
+        &mut self,
+        data: &[(N, AnySeries)],
+        syminfo: Option<Rc<SymbolInfo>>,
+        state: ScriptState,
+        data_range: (Option<i32>, Option<i32>),
+    ) -> Result<(), PineRuntimeError> {
+        self.runl_named(data, 1, syminfo)?;
+        let main_ctx = downcast_ctx(self.context.as_mut());
+        main_ctx.move_output_data();
+        main_ctx.move_alerts();
+        main_ctx.move_traces();
+
+        let ScriptState {
+            iterindex,
+            rollback_count,
+            library,
+            main,
+        } = state;
+        let lib_ctx = downcast_ctx(self.lib_context.as_mut());
+        lib_ctx
+            .load_state(library)
+            .and_then(|_| main_ctx.load_state(main))
+            .map_err(PineRuntimeError::new_no_range)?;
+        main_ctx.set_iterindex(iterindex);
+        main_ctx.set_rollback_count(rollback_count);
+        main_ctx.update_data_range(data_range);
+        Ok(())
+    }
+
     pub fn get_context(&mut self) -> &mut dyn Ctx<'a> {
         unsafe { mem::transmute::<_, &mut dyn Ctx<'a>>(self.context.as_mut()) }
     }
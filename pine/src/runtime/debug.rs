@@ -0,0 +1,344 @@
+use crate::ast::input::StrRange;
+use crate::ast::stat_expr_types::{
+    Assignment, Block, Exp, ForRange, FunctionDef, IfThenElse, Statement,
+};
+use crate::runtime::context::Ctx;
+use crate::types::{
+    downcast_pf_ref, Bool, Color, DataType, Float, Int, PineRef, PineStaticType, PineType,
+    SecondType, Series,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Debug;
+
+// The hooks invoked by the runtime when it runs the blocks of the script. The context
+// passed to the hooks is the context of the running block.
+pub trait Debugger<'a> {
+    // Invoked before the statements of the block run.
+    fn enter_block(&self, _context: &mut dyn Ctx<'a>, _blk: &'a Block<'a>) {}
+
+    // Invoked before every statement and the return expression of the block run.
+    fn on_stmt(&self, context: &mut dyn Ctx<'a>, blk: &'a Block<'a>, range: StrRange);
+
+    // Invoked after the block has run, whether the block fails or not.
+    fn exit_block(&self, _context: &mut dyn Ctx<'a>, _blk: &'a Block<'a>) {}
+}
+
+// The value of the simple type variable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum VarValue {
+    Float(Float),
+    Int(Int),
+    Bool(bool),
+    String(String),
+    Color(String),
+}
+
+impl fmt::Display for VarValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VarValue::Float(Some(v)) => write!(f, "{}", v),
+            VarValue::Int(Some(v)) => write!(f, "{}", v),
+            VarValue::Float(None) | VarValue::Int(None) => write!(f, "na"),
+            VarValue::Bool(v) => write!(f, "{}", v),
+            VarValue::String(v) => write!(f, "{:?}", v),
+            VarValue::Color(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+// The current value of the variable and the history values if the variable is series.
+// The history values are ordered from the oldest bar to the latest committed bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarInspect {
+    pub series: bool,
+    pub current: VarValue,
+    pub history: Vec<VarValue>,
+}
+
+fn inspect_typed<'a, D, F>(val: &PineRef<'a>, with_history: bool, f: F) -> Option<VarInspect>
+where
+    D: PineStaticType + PineType<'a> + Default + PartialEq + Clone + Debug + 'a,
+    F: Fn(D) -> VarValue,
+{
+    match val.get_type().1 {
+        SecondType::Series => {
+            let series = downcast_pf_ref::<Series<D>>(val).ok()?;
+            let history = if with_history {
+                series.get_history().iter().cloned().map(&f).collect()
+            } else {
+                vec![]
+            };
+            Some(VarInspect {
+                series: true,
+                current: f(series.get_current()),
+                history,
+            })
+        }
+        SecondType::Simple => {
+            let simple = downcast_pf_ref::<D>(val).ok()?;
+            Some(VarInspect {
+                series: false,
+                current: f(simple.clone()),
+                history: vec![],
+            })
+        }
+        SecondType::Array => None,
+    }
+}
+
+fn inspect_with<'a>(val: &PineRef<'a>, with_history: bool) -> Option<VarInspect> {
+    match val.get_type().0 {
+        DataType::Float => inspect_typed::<Float, _>(val, with_history, VarValue::Float),
+        DataType::Int => inspect_typed::<Int, _>(val, with_history, VarValue::Int),
+        DataType::Bool => inspect_typed::<Bool, _>(val, with_history, VarValue::Bool),
+        DataType::String => inspect_typed::<String, _>(val, with_history, VarValue::String),
+        DataType::Color => {
+            inspect_typed::<Color, _>(val, with_history, |c| VarValue::Color(String::from(c.0)))
+        }
+        _ => None,
+    }
+}
+
+// Read the value of the variable, only the float, int, bool, string and color values
+// can be inspected.
+pub fn inspect_var<'a>(val: &PineRef<'a>) -> Option<VarInspect> {
+    inspect_with(val, true)
+}
+
+// Read the current value of the variable without copying the history values.
+pub fn inspect_current<'a>(val: &PineRef<'a>) -> Option<VarValue> {
+    inspect_with(val, false).map(|v| v.current)
+}
+
+// The variables declared in the block. For the body of user function, the func_name
+// is the function name and the variables contain the parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeInfo<'a> {
+    pub func_name: Option<&'a str>,
+    pub vars: Vec<(&'a str, i32)>,
+}
+
+pub type ScopeMap<'a> = HashMap<*const Block<'a>, ScopeInfo<'a>>;
+
+// Collect the variable names of all the blocks that own a context, the key is the
+// address of block which is the same as the block passed to the Debugger hooks.
+pub fn collect_scopes<'a>(blk: &'a Block<'a>) -> ScopeMap<'a> {
+    let mut scopes = HashMap::new();
+    collect_block(blk, None, vec![], &mut scopes);
+    scopes
+}
+
+fn collect_block<'a>(
+    blk: &'a Block<'a>,
+    func_name: Option<&'a str>,
+    mut vars: Vec<(&'a str, i32)>,
+    scopes: &mut ScopeMap<'a>,
+) {
+    for stmt in blk.stmts.iter() {
+        match stmt {
+            Statement::Assignment(assign) => collect_assign(assign, &mut vars, scopes),
+            Statement::VarAssignment(assign) => collect_exp(&assign.val, &mut vars, scopes),
+            Statement::Ite(ite) => collect_ite(ite, scopes),
+            Statement::ForRange(for_range) => collect_for_range(for_range, scopes),
+            Statement::FuncDef(func_def) => {
+                vars.push((func_def.name.value, func_def.name_varid));
+                collect_func_def(func_def, scopes);
+            }
+            Statement::Import(import) => {
+                for func_def in import.defs.iter() {
+                    vars.push((func_def.name.value, func_def.name_varid));
+                    collect_func_def(func_def, scopes);
+                }
+            }
+            Statement::Exp(exp) => collect_exp(exp, &mut vars, scopes),
+            Statement::FuncCall(_)
+            | Statement::TypeDef(_)
+            | Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::None(_)
+            | Statement::Error(_) => {}
+        }
+    }
+    if let Some(exp) = &blk.ret_stmt {
+        collect_exp(exp, &mut vars, scopes);
+    }
+    scopes.insert(blk, ScopeInfo { func_name, vars });
+}
+
+// Only the specific definitions are run, so the generic definition is skipped.
+fn collect_func_def<'a>(func_def: &'a FunctionDef<'a>, scopes: &mut ScopeMap<'a>) {
+    if let Some(spec_defs) = &func_def.spec_defs {
+        for def in spec_defs.iter() {
+            let params = def
+                .params
+                .iter()
+                .enumerate()
+                .map(|(i, p)| match &def.varids {
+                    Some(varids) => (p.value, varids[i]),
+                    None => (p.value, i as i32),
+                })
+                .collect();
+            collect_block(&def.body, Some(func_def.name.value), params, scopes);
+        }
+    }
+}
+
+fn collect_assign<'a>(
+    assign: &'a Assignment<'a>,
+    vars: &mut Vec<(&'a str, i32)>,
+    scopes: &mut ScopeMap<'a>,
+) {
+    if let Some(varids) = &assign.varids {
+        for (name, varid) in assign.names.iter().zip(varids.iter()) {
+            vars.push((name.value, *varid));
+        }
+    }
+    collect_exp(&assign.val, vars, scopes);
+}
+
+fn collect_exp<'a>(exp: &'a Exp<'a>, vars: &mut Vec<(&'a str, i32)>, scopes: &mut ScopeMap<'a>) {
+    match exp {
+        Exp::Ite(ite) => collect_ite(ite, scopes),
+        Exp::ForRange(for_range) => collect_for_range(for_range, scopes),
+        Exp::Assignment(assign) => collect_assign(assign, vars, scopes),
+        Exp::VarAssignment(assign) => collect_exp(&assign.val, vars, scopes),
+        _ => {}
+    }
+}
+
+fn collect_ite<'a>(ite: &'a IfThenElse<'a>, scopes: &mut ScopeMap<'a>) {
+    collect_block(&ite.then_blk, None, vec![], scopes);
+    if let Some(else_blk) = &ite.else_blk {
+        collect_block(else_blk, None, vec![], scopes);
+    }
+}
+
+// The iterator variable is declared in the context of the loop body.
+fn collect_for_range<'a>(for_range: &'a ForRange<'a>, scopes: &mut ScopeMap<'a>) {
+    let iter_var = vec![(for_range.var.value, for_range.varid)];
+    collect_block(&for_range.do_blk, None, iter_var, scopes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::{LibInfo, PineParser, PineRunner};
+    use std::cell::RefCell;
+
+    // The line of statement, the bar index and the variables in the block.
+    type StmtRecord<'a> = (u32, i32, Vec<(&'a str, VarValue)>);
+
+    struct RecordDebugger<'a> {
+        scopes: ScopeMap<'a>,
+        stmts: RefCell<Vec<StmtRecord<'a>>>,
+        depth: RefCell<i32>,
+    }
+
+    impl<'a> Debugger<'a> for RecordDebugger<'a> {
+        fn enter_block(&self, _context: &mut dyn Ctx<'a>, _blk: &'a Block<'a>) {
+            *self.depth.borrow_mut() += 1;
+        }
+
+        fn on_stmt(&self, context: &mut dyn Ctx<'a>, blk: &'a Block<'a>, range: StrRange) {
+            let vars = self.scopes[&(blk as *const Block)]
+                .vars
+                .iter()
+                .filter_map(|(name, varid)| {
+                    let val = context.get_var(VarIndex::new(*varid, 0)).as_ref()?;
+                    Some((*name, inspect_var(val)?.current))
+                })
+                .collect();
+            self.stmts
+                .borrow_mut()
+                .push((range.start.get_line(), context.get_iterindex(), vars));
+        }
+
+        fn exit_block(&self, _context: &mut dyn Ctx<'a>, _blk: &'a Block<'a>) {
+            *self.depth.borrow_mut() -= 1;
+        }
+    }
+
+    fn gen_lib_info<'a>() -> LibInfo<'a> {
+        LibInfo::new(
+            vec![],
+            vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+        )
+    }
+
+    #[test]
+    fn debugger_test() {
+        let src = "a = close + 1\nf(x) =>\n    y = x * 2\n    y\nb = f(a)";
+        let blk = PineParser::new(src, &gen_lib_info()).parse_blk().unwrap();
+        let scopes = collect_scopes(&blk);
+        assert_eq!(scopes[&(&blk as *const Block)].func_name, None);
+
+        let debugger = RecordDebugger {
+            scopes,
+            stmts: RefCell::new(vec![]),
+            depth: RefCell::new(0),
+        };
+        // The debugger borrows the block, so the runner must be created after it.
+        let lib_info = gen_lib_info();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.set_debugger(Some(&debugger));
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(*debugger.depth.borrow(), 0);
+
+        let stmts = debugger.stmts.borrow();
+        let lines: Vec<_> = stmts.iter().map(|s| (s.0, s.1)).collect();
+        assert_eq!(
+            lines,
+            vec![
+                (0, 0),
+                (1, 0),
+                (4, 0),
+                (2, 0),
+                (3, 0),
+                (0, 1),
+                (1, 1),
+                (4, 1),
+                (2, 1),
+                (3, 1)
+            ]
+        );
+        // The return expression of the function can see the parameter and the local variable.
+        assert_eq!(
+            stmts[9].2,
+            vec![
+                ("x", VarValue::Float(Some(3f64))),
+                ("y", VarValue::Float(Some(6f64)))
+            ]
+        );
+        assert_eq!(
+            stmts[7].2,
+            vec![
+                ("a", VarValue::Float(Some(3f64))),
+                ("b", VarValue::Float(None))
+            ]
+        );
+
+        let a = runner.get_context().move_var(VarIndex::new(0, 0)).unwrap();
+        assert_eq!(
+            inspect_var(&a),
+            Some(VarInspect {
+                series: true,
+                current: VarValue::Float(None),
+                history: vec![VarValue::Float(Some(2f64)), VarValue::Float(Some(3f64))],
+            })
+        );
+        assert_eq!(format!("{}", VarValue::Int(None)), "na");
+    }
+}
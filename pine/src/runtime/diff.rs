@@ -0,0 +1,454 @@
+use super::context::Ctx;
+use super::output::{AlertEvent, OutputData, OutputDataCollect};
+use crate::libs::label::{PerLabel, PerLabelItem};
+use crate::libs::line::{PerLine, PerLineItem};
+use crate::types::{DataType, PineFrom, RuntimeErr, SecondType, Series};
+use std::collections::{BTreeMap, HashSet};
+use std::rc::Rc;
+
+// The drawing is identified by the index of the shape (the label or line call that creates it)
+// and the bar that creates it, so the drawing created again by the updated bar keeps its id.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+pub struct DrawingId {
+    pub shape: usize,
+    pub bar: i32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DrawingData {
+    Label(PerLabel),
+    Line(PerLine),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "change")]
+pub enum DrawingChange {
+    Added { id: DrawingId, data: DrawingData },
+    Modified { id: DrawingId, data: DrawingData },
+    Deleted { id: DrawingId },
+}
+
+// The values of the output since the `from` bar, they replace all the values since the bar.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct OutputRange {
+    pub index: usize,
+    pub from: i32,
+    pub data: OutputData,
+}
+
+// The changes of the outputs from the base revision to the revision, `to` is the count of bars.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct OutputDiff {
+    pub base_revision: u64,
+    pub revision: u64,
+    pub to: i32,
+    pub outputs: Vec<OutputRange>,
+    pub drawings: Vec<DrawingChange>,
+    pub alerts: Vec<AlertEvent>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+// The changes made by one revision.
+#[derive(Debug, PartialEq, Clone)]
+struct RevisionChange {
+    revision: u64,
+    // The first changed bar of every output.
+    outputs: Vec<Option<usize>>,
+    drawings: BTreeMap<DrawingId, ChangeKind>,
+    alerts: Vec<AlertEvent>,
+}
+
+// Collect the drawings that are not deleted from the label and line shapes of the context
+// that has run `bar_count` bars.
+pub fn collect_drawings<'a>(
+    context: &dyn Ctx<'a>,
+    bar_count: i32,
+) -> BTreeMap<DrawingId, DrawingData> {
+    let mut drawings = BTreeMap::new();
+    for (shape, val) in context.get_shapes().iter().enumerate() {
+        match val.get_type() {
+            (DataType::Label, SecondType::Series) => {
+                if let Ok(series) = Series::<PerLabelItem>::implicity_from(val.clone()) {
+                    add_drawings(
+                        &mut drawings,
+                        shape,
+                        bar_count,
+                        series.get_history(),
+                        |item| item.borrow().clone().map(DrawingData::Label),
+                    );
+                }
+            }
+            (DataType::Line, SecondType::Series) => {
+                if let Ok(series) = Series::<PerLineItem>::implicity_from(val.clone()) {
+                    add_drawings(
+                        &mut drawings,
+                        shape,
+                        bar_count,
+                        series.get_history(),
+                        |item| item.borrow().clone().map(DrawingData::Line),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    drawings
+}
+
+// Every distinct item in the history of the shape is a drawing created by the first bar
+// that refers to it. The history starts at the bar that the shape is declared.
+fn add_drawings<T>(
+    drawings: &mut BTreeMap<DrawingId, DrawingData>,
+    shape: usize,
+    bar_count: i32,
+    history: &[Rc<T>],
+    data: impl Fn(&Rc<T>) -> Option<DrawingData>,
+) {
+    let mut items = HashSet::new();
+    let first_bar = bar_count - history.len() as i32;
+    for (i, item) in history.iter().enumerate() {
+        if items.insert(Rc::as_ptr(item)) {
+            if let Some(data) = data(item) {
+                let id = DrawingId {
+                    shape,
+                    bar: first_bar + i as i32,
+                };
+                drawings.insert(id, data);
+            }
+        }
+    }
+}
+
+// The first index that the new values since `from` differ from the values.
+fn first_changed<T: PartialEq>(values: &[T], new: &[T], from: usize) -> Option<usize> {
+    let old = values.get(from..).unwrap_or(&[]);
+    match old.iter().zip(new).position(|(o, n)| o != n) {
+        Some(i) => Some(from + i),
+        None if old.len() != new.len() => Some(from + old.len().min(new.len())),
+        None => None,
+    }
+}
+
+// Replace the values since `from` by the new values, the empty values are kept empty.
+fn merge_values<T: Clone>(values: &mut Vec<Option<T>>, new: &[Option<T>], from: usize) {
+    values.truncate(from);
+    if !new.is_empty() {
+        values.resize(from, None);
+        values.extend_from_slice(new);
+    }
+}
+
+// Track the outputs of every run as a revision, and generate the diff from the last
+// acknowledged revision to the latest revision.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DiffTracker {
+    revision: u64,
+    acked: u64,
+    to: i32,
+    // The output data, drawings and alert events of the latest revision.
+    data_list: Vec<Option<OutputData>>,
+    drawings: BTreeMap<DrawingId, DrawingData>,
+    alerts: Vec<AlertEvent>,
+    // The changes of the revisions that are not acknowledged.
+    changes: Vec<RevisionChange>,
+}
+
+impl DiffTracker {
+    pub fn new() -> DiffTracker {
+        DiffTracker::default()
+    }
+
+    pub fn get_revision(&self) -> u64 {
+        self.revision
+    }
+
+    // Record the output of the run as the next revision. The data of the output starts at the
+    // `from` bar of the output while the alert events are triggered since the `start` bar.
+    pub fn commit(
+        &mut self,
+        output: &OutputDataCollect,
+        start: i32,
+        drawings: BTreeMap<DrawingId, DrawingData>,
+    ) -> u64 {
+        let from = output.from.max(0) as usize;
+        if self.data_list.len() != output.data_list.len() {
+            self.data_list = output.data_list.iter().map(|_| None).collect();
+        }
+        let mut outputs = vec![];
+        for (old, new) in self.data_list.iter_mut().zip(&output.data_list) {
+            outputs.push(merge_output(old, new, from));
+        }
+
+        let mut drawing_changes = BTreeMap::new();
+        for (id, data) in drawings.iter() {
+            match self.drawings.get(id) {
+                None => {
+                    drawing_changes.insert(*id, ChangeKind::Added);
+                }
+                Some(old) if old != data => {
+                    drawing_changes.insert(*id, ChangeKind::Modified);
+                }
+                _ => {}
+            }
+        }
+        for id in self.drawings.keys() {
+            if !drawings.contains_key(id) {
+                drawing_changes.insert(*id, ChangeKind::Deleted);
+            }
+        }
+        self.drawings = drawings;
+
+        // The events of the bars run again are new unless they are triggered before.
+        let keep = self
+            .alerts
+            .iter()
+            .take_while(|a| a.bar_index < start)
+            .count();
+        let old_alerts = self.alerts.split_off(keep);
+        let same = old_alerts
+            .iter()
+            .zip(&output.alerts)
+            .take_while(|(o, n)| o == n)
+            .count();
+        let alerts = output.alerts[same..].to_vec();
+        self.alerts.extend(output.alerts.iter().cloned());
+
+        self.revision += 1;
+        self.to = output.to;
+        self.changes.push(RevisionChange {
+            revision: self.revision,
+            outputs,
+            drawings: drawing_changes,
+            alerts,
+        });
+        self.revision
+    }
+
+    // The client has applied the diff of the revision, the next diff starts at the revision.
+    pub fn ack(&mut self, revision: u64) -> Result<(), RuntimeErr> {
+        if revision > self.revision {
+            return Err(RuntimeErr::InvalidRevision(revision));
+        }
+        if revision > self.acked {
+            self.acked = revision;
+            self.changes.retain(|c| c.revision > revision);
+        }
+        Ok(())
+    }
+
+    // The diff from the last acknowledged revision to the latest revision.
+    pub fn diff(&self) -> OutputDiff {
+        let mut output_from: Vec<Option<usize>> = self.data_list.iter().map(|_| None).collect();
+        let mut drawing_kinds: BTreeMap<DrawingId, ChangeKind> = BTreeMap::new();
+        let mut alerts = vec![];
+        for change in self.changes.iter() {
+            for (from, changed) in output_from.iter_mut().zip(&change.outputs) {
+                *from = match (*from, *changed) {
+                    (Some(f), Some(c)) => Some(f.min(c)),
+                    (f, c) => f.or(c),
+                };
+            }
+            for (id, kind) in change.drawings.iter() {
+                match (drawing_kinds.get(id).cloned(), kind) {
+                    (Some(ChangeKind::Added), ChangeKind::Deleted) => {
+                        drawing_kinds.remove(id);
+                    }
+                    (Some(ChangeKind::Added), _) => {}
+                    (Some(ChangeKind::Deleted), ChangeKind::Added) => {
+                        drawing_kinds.insert(*id, ChangeKind::Modified);
+                    }
+                    _ => {
+                        drawing_kinds.insert(*id, *kind);
+                    }
+                }
+            }
+            alerts.extend(change.alerts.iter().cloned());
+        }
+
+        let outputs = self
+            .data_list
+            .iter()
+            .zip(output_from)
+            .enumerate()
+            .filter_map(|(index, (data, from))| match (data, from) {
+                (Some(data), Some(from)) => Some(OutputRange {
+                    index,
+                    from: from as i32,
+                    data: slice_output(data, from),
+                }),
+                _ => None,
+            })
+            .collect();
+        let drawings = drawing_kinds
+            .into_iter()
+            .map(|(id, kind)| match (kind, self.drawings.get(&id)) {
+                (ChangeKind::Deleted, _) | (_, None) => DrawingChange::Deleted { id },
+                (ChangeKind::Added, Some(data)) => DrawingChange::Added {
+                    id,
+                    data: data.clone(),
+                },
+                (ChangeKind::Modified, Some(data)) => DrawingChange::Modified {
+                    id,
+                    data: data.clone(),
+                },
+            })
+            .collect();
+        OutputDiff {
+            base_revision: self.acked,
+            revision: self.revision,
+            to: self.to,
+            outputs,
+            drawings,
+            alerts,
+        }
+    }
+}
+
+// Merge the new data since the `from` bar into the output data, return the first changed bar.
+fn merge_output(
+    old: &mut Option<OutputData>,
+    new: &Option<OutputData>,
+    from: usize,
+) -> Option<usize> {
+    let new = match new {
+        Some(new) => new,
+        None => return old.take().map(|_| 0),
+    };
+    let mut changed = None;
+    let same_shape = match old {
+        Some(o) => o.series.len() == new.series.len() && o.colors.len() == new.colors.len(),
+        None => false,
+    };
+    if !same_shape {
+        // All the bars are changed if the output is new.
+        changed = Some(0);
+        *old = Some(slice_output(new, usize::MAX));
+    }
+    let data = match old.as_mut() {
+        Some(data) => data,
+        None => return changed,
+    };
+    let mut update = |c: Option<usize>| {
+        changed = match (changed, c) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    };
+    for (o, n) in data.series.iter_mut().zip(&new.series) {
+        update(first_changed(o, n, from));
+        merge_values(o, n, from);
+    }
+    for (o, n) in data.colors.iter_mut().zip(&new.colors) {
+        if o.options != n.options {
+            update(Some(0));
+            o.options = n.options.clone();
+        }
+        update(first_changed(&o.values, &n.values, from));
+        merge_values(&mut o.values, &n.values, from);
+    }
+    changed
+}
+
+// The values of the output data since the `from` bar.
+fn slice_output(data: &OutputData, from: usize) -> OutputData {
+    OutputData::new_with_sc(
+        data.series
+            .iter()
+            .map(|s| s.get(from..).unwrap_or(&[]).to_vec())
+            .collect(),
+        data.colors
+            .iter()
+            .map(|c| {
+                let mut c = c.clone();
+                c.values = c.values.get(from..).unwrap_or(&[]).to_vec();
+                c
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::output::StrOptionsData;
+
+    #[test]
+    fn diff_tracker_test() {
+        let mut colors = StrOptionsData::new();
+        colors.options = vec![String::from("red")];
+        colors.values = vec![Some(0), None];
+        let output = |from: i32, to: i32, vals: Vec<Option<f64>>, alerts: Vec<AlertEvent>| {
+            let mut output = OutputDataCollect::new(
+                from,
+                to,
+                vec![
+                    Some(OutputData::new(vec![vals])),
+                    Some(OutputData::new_with_sc(vec![], vec![colors.clone()])),
+                    None,
+                ],
+            );
+            output.alerts = alerts;
+            output
+        };
+        let alert = |bar: i32| AlertEvent::new(bar, String::from("a"), String::from("all"));
+        let label = DrawingData::Label(PerLabel::new());
+        let id = |bar: i32| DrawingId { shape: 0, bar };
+
+        let mut tracker = DiffTracker::new();
+        let mut drawings = BTreeMap::new();
+        drawings.insert(id(0), label.clone());
+        tracker.commit(
+            &output(0, 2, vec![Some(1f64), Some(2f64)], vec![alert(1)]),
+            0,
+            drawings,
+        );
+        tracker.ack(1).unwrap();
+
+        // The unchanged values, the same alerts and the added then deleted drawings are not in the diff.
+        let mut drawings = BTreeMap::new();
+        drawings.insert(id(1), label.clone());
+        tracker.commit(
+            &output(1, 3, vec![Some(2f64), Some(3f64)], vec![alert(1), alert(1)]),
+            1,
+            drawings,
+        );
+        tracker.commit(
+            &output(1, 3, vec![Some(2f64), Some(4f64)], vec![]),
+            1,
+            BTreeMap::new(),
+        );
+        let diff = tracker.diff();
+        assert_eq!((diff.base_revision, diff.revision, diff.to), (1, 3, 3));
+        assert_eq!(
+            diff.outputs,
+            vec![
+                OutputRange {
+                    index: 0,
+                    from: 2,
+                    data: OutputData::new(vec![vec![Some(4f64)]]),
+                },
+                OutputRange {
+                    index: 1,
+                    from: 1,
+                    data: OutputData::new_with_sc(
+                        vec![],
+                        vec![StrOptionsData {
+                            options: colors.options.clone(),
+                            values: vec![Some(0), None],
+                        }]
+                    ),
+                }
+            ]
+        );
+        assert_eq!(diff.drawings, vec![DrawingChange::Deleted { id: id(0) }]);
+        assert_eq!(diff.alerts, vec![alert(1)]);
+        assert_eq!(tracker.ack(4), Err(RuntimeErr::InvalidRevision(4)));
+    }
+}
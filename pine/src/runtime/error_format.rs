@@ -2,7 +2,7 @@ use crate::ast::error::PineErrorKind;
 use crate::ast::input::StrRange;
 use crate::ast::state::PineInputError;
 use crate::helper::str_replace;
-use crate::runtime::context::PineRuntimeError;
+use crate::runtime::context::{CallFrame, PineRuntimeError};
 use crate::types::error::RuntimeErr;
 use std::collections::HashMap;
 use std::string::ToString;
@@ -53,7 +53,13 @@ static ERROR_MAP: &[(&'static str, &'static str)] = &[
     ("VarHasDeclare", "You can't declare the same variable twice."),
     ("BreakNotInForStmt", "The break statement can only be used in a for-range statement."),
     ("ContinueNotInForStmt", "The continue statement can only be used in a for-range statement."),
+    ("LibraryNotResolved", "The imported library cannot be found."),
+    ("LibraryInvalid", "The imported library is not a valid library script."),
+    ("LibFuncNotExported", "The function is not exported by the imported library."),
+    ("ImportNotInGlobal", "The import statement can only be used in the global scope."),
+    ("TypeNotInGlobal", "The type declaration can only be used in the global scope."),
     ("NonRecongnizeStmt", "This statement is invalid."),
+    ("VersionNotMatch", "The script of version {} can't be parsed with the builtins of version {}."),
 
     ("NotValidParam", "The parameters are invalid."),
     ("NotSupportOperator", "The operation is not available now."),
@@ -68,7 +74,21 @@ static ERROR_MAP: &[(&'static str, &'static str)] = &[
     ("UnknownRuntimeErr", "Unknown runtime error."),
     ("Continue", "Continue statement."),
     ("Break", "Break statement."),
-    ("ForRangeIndexIsNA", "The index used in for-range statement can't be na.")
+    ("ForRangeIndexIsNA", "The index used in for-range statement can't be na."),
+    ("InvalidSnapshot", "The runtime snapshot can't be restored. {}"),
+    ("TraceVarNotFound", "The traced variable {} is not declared in the global scope."),
+    ("NaObjectField", "The field of the na object can't be assigned."),
+    ("NegativeHistoryRef", "The offset {} of the history reference can't be negative."),
+    ("ScriptNotParsed", "The script must be parsed successfully before running."),
+    ("ScriptNotRun", "The script must be run with the data before updating."),
+    ("InvalidDataLen", "The length of the data is less than the count of the bars."),
+    ("InvalidUpdateFrom", "The update start index {} is out of the range of the run data."),
+    ("UpdateDataMismatch", "The data sources of the update don't match the run data."),
+    ("UnknownDataSource", "The data source {} is unknown."),
+    ("DataTypeMismatch", "The type of the data source {} doesn't match the declared type."),
+    ("InvalidInputVal", "The value of the input {} is invalid. {}"),
+    ("OutputDiffNotEnabled", "The output diff must be enabled before running."),
+    ("InvalidRevision", "The revision {} of the outputs is not generated."),
 ];
 
 pub struct ErrorFormater {
@@ -143,7 +163,16 @@ impl ErrorFormater {
             PineErrorKind::ContinueNotInForStmt => {
                 String::from(self.error_map["ContinueNotInForStmt"])
             }
+            PineErrorKind::LibraryNotResolved => String::from(self.error_map["LibraryNotResolved"]),
+            PineErrorKind::LibraryInvalid => String::from(self.error_map["LibraryInvalid"]),
+            PineErrorKind::LibFuncNotExported => String::from(self.error_map["LibFuncNotExported"]),
+            PineErrorKind::ImportNotInGlobal => String::from(self.error_map["ImportNotInGlobal"]),
+            PineErrorKind::TypeNotInGlobal => String::from(self.error_map["TypeNotInGlobal"]),
             PineErrorKind::NonRecongnizeStmt => String::from(self.error_map["NonRecongnizeStmt"]),
+            PineErrorKind::VersionNotMatch { script, builtins } => str_replace(
+                self.error_map["VersionNotMatch"],
+                vec![script.to_string(), builtins.to_string()],
+            ),
         }
     }
 
@@ -171,6 +200,38 @@ impl ErrorFormater {
             RuntimeErr::Continue => String::from(self.error_map["Continue"]),
             RuntimeErr::Break => String::from(self.error_map["Break"]),
             RuntimeErr::ForRangeIndexIsNA => String::from(self.error_map["ForRangeIndexIsNA"]),
+            RuntimeErr::InvalidSnapshot(s) => {
+                str_replace(self.error_map["InvalidSnapshot"], vec![s])
+            }
+            RuntimeErr::TraceVarNotFound(s) => {
+                str_replace(self.error_map["TraceVarNotFound"], vec![s])
+            }
+            RuntimeErr::NaObjectField => String::from(self.error_map["NaObjectField"]),
+            RuntimeErr::NegativeHistoryRef(i) => {
+                str_replace(self.error_map["NegativeHistoryRef"], vec![i.to_string()])
+            }
+            RuntimeErr::ScriptNotParsed => String::from(self.error_map["ScriptNotParsed"]),
+            RuntimeErr::ScriptNotRun => String::from(self.error_map["ScriptNotRun"]),
+            RuntimeErr::InvalidDataLen => String::from(self.error_map["InvalidDataLen"]),
+            RuntimeErr::InvalidUpdateFrom(i) => {
+                str_replace(self.error_map["InvalidUpdateFrom"], vec![i.to_string()])
+            }
+            RuntimeErr::UpdateDataMismatch => String::from(self.error_map["UpdateDataMismatch"]),
+            RuntimeErr::UnknownDataSource(s) => {
+                str_replace(self.error_map["UnknownDataSource"], vec![s])
+            }
+            RuntimeErr::DataTypeMismatch(s) => {
+                str_replace(self.error_map["DataTypeMismatch"], vec![s])
+            }
+            RuntimeErr::InvalidInputVal(name, reason) => {
+                str_replace(self.error_map["InvalidInputVal"], vec![name, reason])
+            }
+            RuntimeErr::OutputDiffNotEnabled => {
+                String::from(self.error_map["OutputDiffNotEnabled"])
+            }
+            RuntimeErr::InvalidRevision(r) => {
+                str_replace(self.error_map["InvalidRevision"], vec![r.to_string()])
+            }
         }
     }
 }
@@ -179,6 +240,10 @@ impl ErrorFormater {
 pub struct PineFormatError {
     pub message: String,
     pub range: StrRange,
+    // The bar information and user-defined function calls only exist for the runtime error.
+    pub bar_index: Option<i32>,
+    pub time: Option<i64>,
+    pub call_stack: Vec<CallFrame>,
 }
 
 impl PineFormatError {
@@ -186,6 +251,9 @@ impl PineFormatError {
         PineFormatError {
             range: input_err.range,
             message: formatter.format_error(input_err.code),
+            bar_index: None,
+            time: None,
+            call_stack: vec![],
         }
     }
 
@@ -193,6 +261,9 @@ impl PineFormatError {
         PineFormatError {
             range: runtime_err.range,
             message: formatter.format_runtime_error(runtime_err.code),
+            bar_index: runtime_err.bar_index,
+            time: runtime_err.time,
+            call_stack: runtime_err.call_stack,
         }
     }
 }
@@ -8,13 +8,12 @@ use crate::ast::num::Numeral;
 pub use crate::ast::stat_expr_types::{
     Condition, DataType, Exp, FunctionCall, PrefixExp, RVVarName, RefCall, Statement, TypeCast,
 };
-use crate::ast::syntax_type::{FunctionType, SimpleSyntaxType, SyntaxType};
 use crate::types::{
-    downcast_pf, downcast_pf_mut, Bool, CallObjEval, Callable, CallableEvaluate, CallableFactory,
-    CallableObject, Color, DataType as FirstType, Evaluate, EvaluateFactory, Float, Int, Object,
-    PineFrom, PineRef, PineStaticType, PineType, PineVar, RefData, RuntimeErr, SecondType, Series,
-    SimpleCallableObject, Tuple, NA,
+    downcast_pf, na_field, Bool, CallObjEval, CallableObject, Color, DataType as FirstType,
+    Evaluate, Float, Int, Object, PineFrom, PineRef, PineStaticType, PineType, PineVar, RefData,
+    RuntimeErr, SecondType, Series, SimpleCallableObject, Tuple, UserObjectItem, NA,
 };
+use std::borrow::Cow;
 use std::fmt::Debug;
 
 impl<'a> Runner<'a> for Exp<'a> {
@@ -25,7 +24,7 @@ impl<'a> Runner<'a> for Exp<'a> {
             Exp::Num(Numeral::Float(f)) => Ok(PineRef::new_box(Some(f.value))),
             Exp::Num(Numeral::Int(n)) => Ok(PineRef::new_box(Some(n.value))),
             Exp::Str(ref s) => Ok(PineRef::new_rc(String::from(s.value.clone()))),
-            Exp::Color(s) => Ok(PineRef::new_box(Color(s.value))),
+            Exp::Color(s) => Ok(PineRef::new_box(Color(Cow::Borrowed(s.value)))),
             Exp::VarName(s) => Ok(PineRef::new_box(PineVar(s.name.value))),
             Exp::Tuple(ref tuple) => {
                 let mut col: Vec<PineRef<'a>> = vec![];
@@ -58,14 +57,15 @@ impl<'a> RVRunner<'a> for Exp<'a> {
                     let ret = match s.get_type() {
                         (FirstType::Evaluate, SecondType::Simple) => {
                             let mut eval_val = downcast_pf::<Evaluate>(s.clone()).unwrap();
-                            context.create_runnable(RefData::clone(&eval_val).into_rc());
+                            context
+                                .create_runnable(RefData::clone(&eval_val).into_rc(), self.range());
                             // let eval_val = downcast_pf_mut::<Evaluate>(&mut s).unwrap();
                             eval_val.call(context)
                         }
                         (FirstType::CallableEvaluate, SecondType::Simple)
                         | (FirstType::CallableObjectEvaluate, SecondType::Simple)
                         | (FirstType::EvaluateFactory, SecondType::Simple) => {
-                            call_eval_factory(context, name.eval_id, s.copy())
+                            call_eval_factory(context, name.eval_id, s.copy(), self.range())
                         }
                         _ => Ok(s.copy()),
                     };
@@ -83,6 +83,20 @@ impl<'a> RVRunner<'a> for Exp<'a> {
                 }
                 Ok(PineRef::new_box(Tuple(col)))
             }
+            Exp::PrefixExp(prefix) => {
+                let s = prefix.run(context)?;
+                match s.get_type() {
+                    (FirstType::CallableEvaluate, SecondType::Simple)
+                    | (FirstType::CallableObjectEvaluate, SecondType::Simple)
+                    | (FirstType::EvaluateFactory, SecondType::Simple) => {
+                        match call_eval_factory(context, prefix.eval_id, s, self.range()) {
+                            Ok(val) => Ok(val),
+                            Err(e) => Err(PineRuntimeError::new(e, self.range())),
+                        }
+                    }
+                    _ => Ok(s),
+                }
+            }
             _ => self.run(context),
         }
     }
@@ -139,7 +153,8 @@ impl<'a> RunnerForFunc<'a> for Exp<'a> {
                     let ret = match s.get_type() {
                         (FirstType::Evaluate, SecondType::Simple) => {
                             let mut eval_val = downcast_pf::<Evaluate>(s.clone()).unwrap();
-                            context.create_runnable(RefData::clone(&eval_val).into_rc());
+                            context
+                                .create_runnable(RefData::clone(&eval_val).into_rc(), self.range());
                             // let eval_val = downcast_pf_mut::<Evaluate>(&mut s).unwrap();
                             eval_val.call(context)
                         }
@@ -152,6 +167,8 @@ impl<'a> RunnerForFunc<'a> for Exp<'a> {
                     }
                 }
             },
+            // The field of the object is the function itself such as ta.tr(true).
+            Exp::PrefixExp(prefix) => prefix.run(context),
             _ => self.rv_run(context),
         }
     }
@@ -166,11 +183,12 @@ impl<'a> RunnerForObj<'a> for Exp<'a> {
                     let ret = match s.get_type() {
                         (FirstType::Evaluate, SecondType::Simple) => {
                             let mut eval_val = downcast_pf::<Evaluate>(s.clone()).unwrap();
-                            context.create_runnable(RefData::clone(&eval_val).into_rc());
+                            context
+                                .create_runnable(RefData::clone(&eval_val).into_rc(), self.range());
                             eval_val.call(context)
                         }
                         (FirstType::CallableEvaluate, SecondType::Simple) => {
-                            call_eval_factory(context, name.eval_id, s.copy())
+                            call_eval_factory(context, name.eval_id, s.copy(), self.range())
                         }
                         _ => Ok(s.copy()),
                     };
@@ -226,7 +244,13 @@ impl<'a> Runner<'a> for TypeCast<'a> {
                 Ok(s.into_pf())
             }
             (&DataType::Custom(_), SecondType::Simple) => {
-                match type_cast_custom(context, self.cast_index, self.func_index, result) {
+                match type_cast_custom(
+                    context,
+                    self.cast_index,
+                    self.func_index,
+                    result,
+                    self.range,
+                ) {
                     Ok(v) => Ok(v),
                     Err(e) => Err(PineRuntimeError::new(e, self.range)),
                 }
@@ -239,6 +263,18 @@ impl<'a> Runner<'a> for TypeCast<'a> {
     }
 }
 
+impl<'a> PrefixExp<'a> {
+    // The field of the na object is the na value of the field type.
+    fn na_field(&self) -> Result<PineRef<'a>, PineRuntimeError> {
+        match &self.field_type {
+            Some(data_type) => {
+                na_field(data_type).map_err(|code| PineRuntimeError::new(code, self.range))
+            }
+            None => Ok(PineRef::new_box(NA)),
+        }
+    }
+}
+
 impl<'a> Runner<'a> for PrefixExp<'a> {
     fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
         let var = self.left_exp.run_for_obj(context)?;
@@ -264,6 +300,18 @@ impl<'a> Runner<'a> for PrefixExp<'a> {
                 let subobj = object.get(context, self.right_name.value).unwrap();
                 Ok(subobj)
             }
+            (FirstType::UserObject, _) => match UserObjectItem::implicity_from(var) {
+                Ok(item) => match item.into_inner() {
+                    Some(obj) => obj
+                        .get_field(self.right_name.value)
+                        .map_err(|code| PineRuntimeError::new(code, self.range)),
+                    // The field of the na object is na.
+                    None => self.na_field(),
+                },
+                Err(code) => Err(PineRuntimeError::new(code, self.range)),
+            },
+            // The na object like `p.next` whose next field is not assigned.
+            (FirstType::NA, _) => self.na_field(),
             _ => Err(PineRuntimeError::new(
                 RuntimeErr::UnknownRuntimeErr,
                 self.range,
@@ -317,6 +365,7 @@ where
     let i = Int::implicity_from(arg)?;
     match *i {
         None => Err(RuntimeErr::UnknownRuntimeErr),
+        Some(i) if i < 0 => Err(RuntimeErr::NegativeHistoryRef(i)),
         Some(i) => {
             let res = PineRef::new_rc(s.index(i as usize)?);
             // context.update_var(name, s.into_pf());
@@ -353,6 +402,7 @@ impl<'a> Runner<'a> for RefCall<'a> {
                 use crate::libs::label::PerLabelItem;
                 get_slice::<PerLabelItem>(var, arg)
             }
+            (FirstType::UserObject, _) => get_slice::<UserObjectItem>(var, arg),
             _ => Err(RuntimeErr::NotSupportOperator),
         };
         match result {
@@ -540,7 +590,10 @@ mod tests {
             )),
             String::from("hello"),
         );
-        simple_exp(Exp::Color(ColorNode::from_str("#12")), Color("#12"));
+        simple_exp(
+            Exp::Color(ColorNode::from_str("#12")),
+            Color(Cow::Borrowed("#12")),
+        );
         simple_exp(
             Exp::VarName(RVVarName::new_no_range("name")),
             PineVar("name"),
@@ -585,7 +638,10 @@ mod tests {
             )),
             String::from("hello"),
         );
-        simple_rv_exp(Exp::Color(ColorNode::from_str("#12")), Color("#12"));
+        simple_rv_exp(
+            Exp::Color(ColorNode::from_str("#12")),
+            Color(Cow::Borrowed("#12")),
+        );
 
         let mut name = RVVarName::new_no_range("name");
         name.var_index = VarIndex::new(0, 0);
@@ -156,4 +156,49 @@ mod tests {
             )))
         );
     }
+
+    #[test]
+    fn func_default_args_test() {
+        use crate::ast::syntax_type::SyntaxType;
+        use crate::runtime::{AnySeries, NoneCallback};
+        use crate::{LibInfo, PineParser, PineRunner};
+
+        let lib_info = LibInfo::new(vec![], vec![("close", SyntaxType::float_series())]);
+        let src = "f(src, length = 2, simple float mult = 1) => src * length * mult\n\
+                   m1 = f(close)\nm2 = f(close, mult = 3)\nm3 = f(mult = 2, src = close, length = 1)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        let expected = [[2f64, 4f64], [6f64, 12f64], [2f64, 4f64]];
+        for (i, vals) in expected.iter().enumerate() {
+            assert_eq!(
+                runner
+                    .get_context()
+                    .move_var(VarIndex::new(i as i32 + 1, 0)),
+                Some(PineRef::new(Series::from_vec(vec![
+                    Some(vals[0]),
+                    Some(vals[1])
+                ])))
+            );
+        }
+
+        // The arguments must match the names and the types of the parameters.
+        for src in &[
+            "f(src, length) => src\nm = f(close)",
+            "f(src) => src\nm = f(close, len = 1)",
+            "f(src) => src\nm = f(close, src = close)",
+            "f(simple float x) => x\nm = f(close)",
+            "f(int x) => x\nm = f(1.5)",
+        ] {
+            assert!(PineParser::new(src, &lib_info).parse_blk().is_err());
+        }
+    }
 }
@@ -1,4 +1,6 @@
+use super::context::downcast_ctx;
 use super::context::{Ctx, PineRuntimeError, RVRunner, Runner, RunnerForFunc, RunnerForObj};
+use crate::ast::input::StrRange;
 pub use crate::ast::stat_expr_types::{
     Condition, DataType, Exp, FunctionCall, PrefixExp, RVVarName, RefCall, Statement, TypeCast,
     VarIndex,
@@ -17,11 +19,12 @@ pub fn call_eval_factory<'a>(
     context: &mut dyn Ctx<'a>,
     eval_id: i32,
     s: PineRef<'a>,
+    range: StrRange,
 ) -> Result<PineRef<'a>, RuntimeErr> {
     // Get evaluate instance from context
     let mut eval_instance = context.move_fun_instance(eval_id);
     if eval_instance.is_none() {
-        let eval_val = match s.get_type().0 {
+        let mut eval_val = match s.get_type().0 {
             FirstType::CallableEvaluate => {
                 let eval_factory = downcast_pf::<CallableEvaluate>(s.clone()).unwrap();
                 eval_factory.create_eval()
@@ -35,6 +38,9 @@ pub fn call_eval_factory<'a>(
                 factory.create()
             }
         };
+        if let Some(state) = downcast_ctx(context).take_pending_instance(eval_id) {
+            eval_val.load_state(state)?;
+        }
         // let factory = downcast_pf::<EvaluateFactory>(s.clone()).unwrap();
         context.create_fun_instance(eval_id, PineRef::new_rc(eval_val));
         eval_instance = context.move_fun_instance(eval_id);
@@ -43,10 +49,37 @@ pub fn call_eval_factory<'a>(
     let result = eval_val.call(context);
 
     context.create_fun_instance(eval_id, RefData::clone(&eval_val).into_pf());
-    context.create_runnable(RefData::clone(&eval_val).into_rc());
+    context.create_runnable(RefData::clone(&eval_val).into_rc(), range);
     result
 }
 
+// Create the callable instance from the builtin function value such as the callable factory.
+pub fn create_callable<'a>(s: PineRef<'a>) -> Result<Callable<'a>, RuntimeErr> {
+    match s.get_type().0 {
+        FirstType::CallableFactory => {
+            let factory = downcast_pf::<CallableFactory>(s).unwrap();
+            Ok(factory.create())
+        }
+        FirstType::CallableObject => {
+            let factory = downcast_pf::<CallableObject>(s).unwrap();
+            Ok(factory.create())
+        }
+        FirstType::SimpleCallableObject => {
+            let factory = downcast_pf::<SimpleCallableObject>(s).unwrap();
+            Ok(factory.create())
+        }
+        FirstType::CallableEvaluate => {
+            let factory = downcast_pf::<CallableEvaluate>(s).unwrap();
+            Ok(factory.create())
+        }
+        FirstType::CallableObjectEvaluate => {
+            let factory = downcast_pf::<CallObjEval>(s).unwrap();
+            Ok(factory.create())
+        }
+        _ => Err(RuntimeErr::NotSupportOperator),
+    }
+}
+
 pub fn call_func_factory<'a>(
     context: &mut dyn Ctx<'a>,
     func_id: i32,
@@ -54,41 +87,25 @@ pub fn call_func_factory<'a>(
     pos_args: Vec<PineRef<'a>>,
     dict_args: Vec<(&'a str, PineRef<'a>)>,
     func_type: FunctionType<'a>,
+    range: StrRange,
 ) -> Result<PineRef<'a>, RuntimeErr> {
     let mut opt_instance = context.move_fun_instance(func_id);
     if opt_instance.is_none() {
-        let func_val = match s.get_type().0 {
-            FirstType::CallableFactory => {
-                let factory = downcast_pf::<CallableFactory>(s).unwrap();
-                factory.create()
-            }
-            FirstType::CallableObject => {
-                let factory = downcast_pf::<CallableObject>(s).unwrap();
-                factory.create()
-            }
-            FirstType::SimpleCallableObject => {
-                let factory = downcast_pf::<SimpleCallableObject>(s).unwrap();
-                factory.create()
-            }
-            FirstType::CallableEvaluate => {
-                let factory = downcast_pf::<CallableEvaluate>(s).unwrap();
-                factory.create()
-            }
-            FirstType::CallableObjectEvaluate => {
-                let factory = downcast_pf::<CallObjEval>(s).unwrap();
-                factory.create()
-            }
-            _ => unreachable!(),
-        };
+        let mut func_val = create_callable(s)?;
+        if let Some(state) = downcast_ctx(context).take_pending_instance(func_id) {
+            func_val.load_state(state)?;
+        }
         context.create_fun_instance(func_id, PineRef::new_rc(func_val));
         opt_instance = context.move_fun_instance(func_id);
     }
     let mut callable = downcast_pf::<Callable>(opt_instance.unwrap()).unwrap();
 
+    // The input functions find their call sites by the range.
+    downcast_ctx(context).set_call_range(range);
     let result = callable.call(context, pos_args, dict_args, func_type);
 
     context.create_fun_instance(func_id, RefData::clone(&callable).into_pf());
-    context.create_runnable(callable.into_rc());
+    context.create_runnable(callable.into_rc(), range);
     result
 }
 
@@ -97,6 +114,7 @@ pub fn type_cast_custom<'a>(
     cast_index: VarIndex,
     func_index: i32,
     result: PineRef<'a>,
+    range: StrRange,
 ) -> Result<PineRef<'a>, RuntimeErr> {
     match context.move_var(cast_index) {
         None => Err(RuntimeErr::VarNotFound),
@@ -112,6 +130,7 @@ pub fn type_cast_custom<'a>(
                 vec![result],
                 vec![],
                 func_type,
+                range,
             );
             context.update_var(cast_index, s);
             result
@@ -1,20 +1,28 @@
 pub mod any_series;
 pub mod context;
 pub mod data_src;
+pub mod debug;
+pub mod diff;
 pub mod error_format;
 pub mod exp;
 pub mod function;
 pub mod instance_caller;
 pub mod op;
 pub mod output;
+pub mod render;
 pub mod runtime_convert;
+pub mod snapshot;
+pub mod static_io;
 pub mod statement;
+pub mod user_type;
 
 pub use any_series::*;
 pub use context::*;
 pub use data_src::*;
+pub use debug::*;
 pub use error_format::*;
 pub use output::*;
+pub use snapshot::*;
 // use crate::ast::stat_expr_types::Block;
 // use crate::types::PineRef;
 // use context::{Context, ContextType, PineRuntimeError, Runner, VarOperate};
@@ -177,6 +177,11 @@ pub fn binary_op_run<'a, 'b>(
     binary_exp: &'a BinaryExp<'a>,
     context: &mut (dyn 'b + Ctx<'a>),
 ) -> Result<PineRef<'a>, PineRuntimeError> {
+    if let Some(program) = &binary_exp.program {
+        if let Some(val) = program.run(context) {
+            return Ok(val);
+        }
+    }
     match binary_exp.op {
         BinaryOp::BoolAnd => {
             //TODO: That can be generate many small temporary object that can be avoided.
@@ -1,3 +1,8 @@
+use super::debug::VarValue;
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::types::RuntimeErr;
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct StudyScript {
     pub title: String,
@@ -7,10 +12,17 @@ pub struct StudyScript {
     pub precision: Option<i64>,
 }
 
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LibraryScript {
+    pub title: String,
+    pub overlay: Option<bool>,
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ScriptPurpose {
     Study(StudyScript),
+    Library(LibraryScript),
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -19,6 +31,9 @@ pub struct BoolInputInfo {
     pub title: Option<String>,
     pub input_type: String,
     pub confirm: Option<bool>,
+    pub tooltip: Option<String>,
+    pub inline: Option<String>,
+    pub group: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -31,6 +46,9 @@ pub struct IntInputInfo {
     pub confirm: Option<bool>,
     pub step: Option<i64>,
     pub options: Option<Vec<i64>>,
+    pub tooltip: Option<String>,
+    pub inline: Option<String>,
+    pub group: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -43,6 +61,9 @@ pub struct FloatInputInfo {
     pub confirm: Option<bool>,
     pub step: Option<f64>,
     pub options: Option<Vec<f64>>,
+    pub tooltip: Option<String>,
+    pub inline: Option<String>,
+    pub group: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -52,6 +73,9 @@ pub struct StringInputInfo {
     pub input_type: String,
     pub confirm: Option<bool>,
     pub options: Option<Vec<String>>,
+    pub tooltip: Option<String>,
+    pub inline: Option<String>,
+    pub group: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -59,8 +83,45 @@ pub struct SourceInputInfo {
     pub defval: Option<String>,
     pub title: Option<String>,
     pub input_type: String,
+    pub tooltip: Option<String>,
+    pub inline: Option<String>,
+    pub group: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TimeInputInfo {
+    pub defval: Option<i64>,
+    pub title: Option<String>,
+    pub input_type: String,
+    pub confirm: Option<bool>,
+    pub tooltip: Option<String>,
+    pub inline: Option<String>,
+    pub group: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PriceInputInfo {
+    pub defval: Option<f64>,
+    pub title: Option<String>,
+    pub input_type: String,
+    pub confirm: Option<bool>,
+    pub tooltip: Option<String>,
+    pub inline: Option<String>,
+    pub group: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ColorInputInfo {
+    pub defval: Option<String>,
+    pub title: Option<String>,
+    pub input_type: String,
+    pub confirm: Option<bool>,
+    pub tooltip: Option<String>,
+    pub inline: Option<String>,
+    pub group: Option<String>,
 }
 
+// The session, resolution and symbol inputs are strings with their own meanings.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum InputInfo {
@@ -69,6 +130,139 @@ pub enum InputInfo {
     Float(FloatInputInfo),
     String(StringInputInfo),
     Source(SourceInputInfo),
+    Time(TimeInputInfo),
+    Price(PriceInputInfo),
+    Color(ColorInputInfo),
+    Session(StringInputInfo),
+    Resolution(StringInputInfo),
+    Symbol(StringInputInfo),
+}
+
+fn check_range<T: PartialOrd + ToString>(
+    val: T,
+    minval: Option<T>,
+    maxval: Option<T>,
+) -> Result<(), String> {
+    match (minval, maxval) {
+        (Some(min), _) if val < min => Err(str_replace(
+            INPUT_LT_MIN,
+            vec![val.to_string(), min.to_string()],
+        )),
+        (_, Some(max)) if val > max => Err(str_replace(
+            INPUT_GT_MAX,
+            vec![val.to_string(), max.to_string()],
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_options<T: PartialEq + ToString>(val: &T, options: &Option<Vec<T>>) -> Result<(), String> {
+    match options {
+        Some(options) if !options.contains(val) => {
+            let options: Vec<_> = options.iter().map(|v| v.to_string()).collect();
+            Err(str_replace(
+                INPUT_NOT_IN_OPTIONS,
+                vec![val.to_string(), options.join(", ")],
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+// The color must be the hex string like #RRGGBB or #RRGGBBAA.
+fn check_color(val: &str) -> Result<(), String> {
+    let is_color = (val.len() == 7 || val.len() == 9)
+        && val.starts_with('#')
+        && val[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if is_color {
+        Ok(())
+    } else {
+        Err(str_replace(INVALID_COLOR, vec![String::from(val)]))
+    }
+}
+
+impl InputInfo {
+    pub fn get_title(&self) -> &Option<String> {
+        match self {
+            InputInfo::Bool(info) => &info.title,
+            InputInfo::Int(info) => &info.title,
+            InputInfo::Float(info) => &info.title,
+            InputInfo::String(info) => &info.title,
+            InputInfo::Source(info) => &info.title,
+            InputInfo::Time(info) => &info.title,
+            InputInfo::Price(info) => &info.title,
+            InputInfo::Color(info) => &info.title,
+            InputInfo::Session(info) => &info.title,
+            InputInfo::Resolution(info) => &info.title,
+            InputInfo::Symbol(info) => &info.title,
+        }
+    }
+
+    pub fn get_input_type(&self) -> &String {
+        match self {
+            InputInfo::Bool(info) => &info.input_type,
+            InputInfo::Int(info) => &info.input_type,
+            InputInfo::Float(info) => &info.input_type,
+            InputInfo::String(info) => &info.input_type,
+            InputInfo::Source(info) => &info.input_type,
+            InputInfo::Time(info) => &info.input_type,
+            InputInfo::Price(info) => &info.input_type,
+            InputInfo::Color(info) => &info.input_type,
+            InputInfo::Session(info) => &info.input_type,
+            InputInfo::Resolution(info) => &info.input_type,
+            InputInfo::Symbol(info) => &info.input_type,
+        }
+    }
+
+    // The int values are accepted by the float inputs, and the string values are accepted
+    // by the inputs like source and session that were string inputs before.
+    fn check_val(&self, val: &InputVal) -> Result<(), String> {
+        match (self, val) {
+            (InputInfo::Bool(_), InputVal::Bool(_)) => Ok(()),
+            (InputInfo::Int(info), InputVal::Int(v)) => {
+                check_range(*v, info.minval, info.maxval)?;
+                check_options(v, &info.options)
+            }
+            (InputInfo::Float(info), InputVal::Float(_))
+            | (InputInfo::Float(info), InputVal::Int(_)) => {
+                let v = val.as_f64().unwrap();
+                check_range(v, info.minval, info.maxval)?;
+                check_options(&v, &info.options)
+            }
+            (InputInfo::String(info), InputVal::String(v)) => check_options(v, &info.options),
+            (InputInfo::Source(_), InputVal::Source(_))
+            | (InputInfo::Source(_), InputVal::String(_)) => Ok(()),
+            (InputInfo::Time(_), InputVal::Time(_)) | (InputInfo::Time(_), InputVal::Int(_)) => {
+                Ok(())
+            }
+            (InputInfo::Price(_), InputVal::Price(_))
+            | (InputInfo::Price(_), InputVal::Float(_))
+            | (InputInfo::Price(_), InputVal::Int(_)) => Ok(()),
+            (InputInfo::Color(_), InputVal::Color(v)) => check_color(v),
+            (InputInfo::Session(info), InputVal::Session(v))
+            | (InputInfo::Session(info), InputVal::String(v))
+            | (InputInfo::Resolution(info), InputVal::Resolution(v))
+            | (InputInfo::Resolution(info), InputVal::String(v))
+            | (InputInfo::Symbol(info), InputVal::Symbol(v))
+            | (InputInfo::Symbol(info), InputVal::String(v)) => check_options(v, &info.options),
+            _ => Err(str_replace(
+                EXP_VAL_BUT_GET_VAL,
+                vec![
+                    self.get_input_type().clone(),
+                    String::from(val.get_type_name()),
+                ],
+            )),
+        }
+    }
+
+    // Check the value supplied by the client for the input at the index, the input is
+    // named by the title in the error if it has one.
+    pub fn check_input(&self, index: usize, val: &InputVal) -> Result<(), RuntimeErr> {
+        self.check_val(val).map_err(|reason| {
+            let name = self.get_title().clone().unwrap_or(index.to_string());
+            RuntimeErr::InvalidInputVal(name, reason)
+        })
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -163,6 +357,7 @@ pub struct FillInfo {
     pub opacity: Option<i64>,
     pub editable: Option<bool>,
     pub show_last: Option<i64>,
+    pub display: Option<i64>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -173,6 +368,7 @@ pub struct HLineInfo {
     pub linestyle: Option<String>,
     pub linewidth: Option<i64>,
     pub editable: Option<bool>,
+    pub display: Option<i64>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -245,6 +441,10 @@ impl IOInfo {
         self.inputs.push(input);
     }
 
+    pub fn set_inputs(&mut self, inputs: Vec<InputInfo>) {
+        self.inputs = inputs;
+    }
+
     pub fn push_output(&mut self, output: OutputInfo) {
         self.outputs.push(output);
     }
@@ -291,6 +491,16 @@ impl IOInfo {
         &self.inputs
     }
 
+    // Check the input values against the inputs, the missing values use the default values.
+    pub fn check_inputs(&self, inputs: &[Option<InputVal>]) -> Result<(), RuntimeErr> {
+        for (i, (info, val)) in self.inputs.iter().zip(inputs.iter()).enumerate() {
+            if let Some(val) = val {
+                info.check_input(i, val)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_outputs(&self) -> &Vec<OutputInfo> {
         &self.outputs
     }
@@ -343,11 +553,48 @@ impl OutputData {
     }
 }
 
+// The values of the traced variable for every bar from the `from` bar to the `to` bar.
+// The value is None if the variable hasn't been created or can't be inspected.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TraceData {
+    pub name: String,
+    pub values: Vec<Option<VarValue>>,
+}
+
+impl TraceData {
+    pub fn new(name: String, values: Vec<Option<VarValue>>) -> TraceData {
+        TraceData { name, values }
+    }
+}
+
+// The event triggered by the alert function on the bar, freq is the frequency name like
+// "once_per_bar" that decides when the event is delivered.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub bar_index: i32,
+    pub message: String,
+    pub freq: String,
+}
+
+impl AlertEvent {
+    pub fn new(bar_index: i32, message: String, freq: String) -> AlertEvent {
+        AlertEvent {
+            bar_index,
+            message,
+            freq,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct OutputDataCollect {
     pub from: i32,
     pub to: i32,
     pub data_list: Vec<Option<OutputData>>,
+    #[serde(default)]
+    pub traces: Vec<TraceData>,
+    #[serde(default)]
+    pub alerts: Vec<AlertEvent>,
 }
 
 impl OutputDataCollect {
@@ -356,6 +603,8 @@ impl OutputDataCollect {
             from,
             to,
             data_list,
+            traces: vec![],
+            alerts: vec![],
         }
     }
 
@@ -364,6 +613,23 @@ impl OutputDataCollect {
             from,
             to,
             data_list: vec![Some(OutputData::new(vec![data]))],
+            traces: vec![],
+            alerts: vec![],
+        }
+    }
+
+    pub fn new_with_traces(
+        from: i32,
+        to: i32,
+        data_list: Vec<Option<OutputData>>,
+        traces: Vec<TraceData>,
+    ) -> OutputDataCollect {
+        OutputDataCollect {
+            from,
+            to,
+            data_list,
+            traces,
+            alerts: vec![],
         }
     }
 }
@@ -376,6 +642,38 @@ pub enum InputVal {
     Bool(bool),
     String(String),
     Source(String),
+    Time(i64),
+    Price(f64),
+    Color(String),
+    Session(String),
+    Resolution(String),
+    Symbol(String),
+}
+
+impl InputVal {
+    pub fn get_type_name(&self) -> &'static str {
+        match self {
+            InputVal::Int(_) => "int",
+            InputVal::Float(_) => "float",
+            InputVal::Bool(_) => "bool",
+            InputVal::String(_) => "string",
+            InputVal::Source(_) => "source",
+            InputVal::Time(_) => "time",
+            InputVal::Price(_) => "price",
+            InputVal::Color(_) => "color",
+            InputVal::Session(_) => "session",
+            InputVal::Resolution(_) => "resolution",
+            InputVal::Symbol(_) => "symbol",
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            InputVal::Int(v) | InputVal::Time(v) => Some(*v as f64),
+            InputVal::Float(v) | InputVal::Price(v) => Some(*v),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -0,0 +1,328 @@
+use super::output::{OutputData, OutputInfo};
+
+// How the output is rendered on the chart, it is None if the output is hidden.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct RenderProps {
+    offset: i64,
+    show_last: Option<i64>,
+}
+
+fn props(offset: Option<i64>, show_last: Option<i64>, display: Option<i64>) -> Option<RenderProps> {
+    // display.none is 0
+    if display == Some(0) {
+        return None;
+    }
+    Some(RenderProps {
+        offset: offset.unwrap_or(0),
+        show_last,
+    })
+}
+
+fn render_props(info: &OutputInfo) -> Option<RenderProps> {
+    match info {
+        OutputInfo::Plot(i) => props(i.offset, i.show_last, i.display),
+        OutputInfo::PlotArrow(i) => props(i.offset, i.show_last, i.display),
+        OutputInfo::PlotChar(i) => props(i.offset, i.show_last, i.display),
+        OutputInfo::PlotShape(i) => props(i.offset, i.show_last, i.display),
+        OutputInfo::PlotBar(i) => props(None, i.show_last, i.display),
+        OutputInfo::PlotCandle(i) => props(None, i.show_last, i.display),
+        OutputInfo::Fill(i) => props(None, i.show_last, i.display),
+        OutputInfo::HLine(i) => props(None, None, i.display),
+    }
+}
+
+impl RenderProps {
+    // The first bar that is not masked by show_last.
+    fn visible_start(&self, len: usize) -> i64 {
+        match self.show_last {
+            Some(n) => len as i64 - n,
+            None => 0,
+        }
+    }
+
+    // The first rendered bar that may change after the bars since `from` are updated and
+    // the count of bars changes from `old_len` to `new_len`.
+    fn changed_start(&self, from: usize, old_len: usize, new_len: usize) -> i64 {
+        let mut start = from as i64;
+        if self.show_last.is_some() && old_len != new_len {
+            start = start.min(self.visible_start(old_len.min(new_len)));
+        }
+        start + self.offset.min(0)
+    }
+
+    // Shift the values by the offset and mask the values older than the last show_last bars.
+    // The rendered values start at the `from` bar and end at the last bar plus the positive offset.
+    fn render<T: Clone>(&self, values: &[Option<T>], from: usize) -> Vec<Option<T>> {
+        let len = values.len();
+        let visible_start = self.visible_start(len);
+        let end = len as i64 + self.offset.max(0);
+        (from as i64..end)
+            .map(|bar| {
+                let i = bar - self.offset;
+                if i >= 0 && i >= visible_start && i < len as i64 {
+                    values[i as usize].clone()
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+// The output data of all the bars since the first bar, so the updated bars can be rendered
+// with the values of the previous bars.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct OutputHistory {
+    len: usize,
+    data_list: Vec<Option<OutputData>>,
+}
+
+impl OutputHistory {
+    pub fn new() -> OutputHistory {
+        OutputHistory {
+            len: 0,
+            data_list: vec![],
+        }
+    }
+
+    // The count of bars of the data.
+    pub fn bar_count(&self) -> usize {
+        self.len
+    }
+
+    // Replace the data since the `from` bar by the data of the bars from `from` to `to`.
+    pub fn merge(&mut self, from: usize, to: usize, data_list: Vec<Option<OutputData>>) {
+        let len = self.len;
+        let keep = from.min(len);
+        // The bars before `from` are unknown if the history is not aligned with the data.
+        let fill = from - keep;
+        if self.data_list.len() != data_list.len() {
+            self.data_list = data_list.iter().map(|_| None).collect();
+        }
+        for (old, new) in self.data_list.iter_mut().zip(data_list) {
+            *old = match (old.take(), new) {
+                (_, None) => None,
+                (Some(mut old), Some(new))
+                    if old.series.len() == new.series.len()
+                        && old.colors.len() == new.colors.len() =>
+                {
+                    for (o, n) in old.series.iter_mut().zip(new.series) {
+                        merge_values(o, n, keep, fill);
+                    }
+                    for (o, n) in old.colors.iter_mut().zip(new.colors) {
+                        o.options = n.options;
+                        merge_values(&mut o.values, n.values, keep, fill);
+                    }
+                    Some(old)
+                }
+                (_, Some(mut new)) => {
+                    for s in new.series.iter_mut() {
+                        s.splice(0..0, (0..from).map(|_| None));
+                    }
+                    for c in new.colors.iter_mut() {
+                        c.values.splice(0..0, (0..from).map(|_| None));
+                    }
+                    Some(new)
+                }
+            };
+        }
+        self.len = to;
+    }
+
+    // Render the data of the outputs from the first bar that may change since the `from` bar,
+    // the count of bars is `old_len` before the update. Return the first rendered bar and the data.
+    pub fn render(
+        &self,
+        outputs: &[OutputInfo],
+        from: usize,
+        old_len: usize,
+    ) -> (usize, Vec<Option<OutputData>>) {
+        let all_props: Vec<_> = outputs.iter().map(render_props).collect();
+        let start = all_props
+            .iter()
+            .filter_map(|p| p.map(|p| p.changed_start(from, old_len, self.len)))
+            .fold(from as i64, i64::min)
+            .max(0) as usize;
+        let data_list = self
+            .data_list
+            .iter()
+            .zip(all_props)
+            .map(|(data, props)| match (data, props) {
+                (Some(data), Some(props)) => Some(OutputData::new_with_sc(
+                    data.series
+                        .iter()
+                        .map(|s| render_series(&props, s, start))
+                        .collect(),
+                    data.colors
+                        .iter()
+                        .map(|c| {
+                            let mut c = c.clone();
+                            c.values = render_series(&props, &c.values, start);
+                            c
+                        })
+                        .collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+        (start, data_list)
+    }
+}
+
+// Keep the first `keep` values, pad `fill` None values and append the new values.
+fn merge_values<T>(values: &mut Vec<Option<T>>, new: Vec<Option<T>>, keep: usize, fill: usize) {
+    values.truncate(keep);
+    values.extend((0..fill).map(|_| None));
+    values.extend(new);
+}
+
+// The empty values like the series of fill with the series color are not rendered.
+fn render_series<T: Clone>(
+    props: &RenderProps,
+    values: &[Option<T>],
+    from: usize,
+) -> Vec<Option<T>> {
+    if values.is_empty() {
+        vec![]
+    } else {
+        props.render(values, from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::output::{FillInfo, HLineInfo, PlotInfo, StrOptionsData};
+
+    fn plot_info(offset: Option<i64>, show_last: Option<i64>, display: Option<i64>) -> OutputInfo {
+        OutputInfo::Plot(PlotInfo {
+            title: None,
+            color: None,
+            linewidth: None,
+            style: None,
+            opacity: None,
+            trackprice: None,
+            histbase: None,
+            offset,
+            join: None,
+            editable: None,
+            show_last,
+            display,
+        })
+    }
+
+    fn series(vals: &[i32]) -> Vec<Option<f64>> {
+        vals.iter()
+            .map(|&v| if v < 0 { None } else { Some(v as f64) })
+            .collect()
+    }
+
+    fn plot_data(vals: &[i32]) -> Option<OutputData> {
+        Some(OutputData::new(vec![series(vals)]))
+    }
+
+    #[test]
+    fn render_test() {
+        let outputs = vec![
+            plot_info(Some(2), None, None),
+            plot_info(Some(-1), Some(2), None),
+            plot_info(None, None, Some(0)),
+            OutputInfo::Fill(FillInfo {
+                fill_type: String::from("plot"),
+                start: 0,
+                end: 1,
+                title: None,
+                color: None,
+                opacity: None,
+                editable: None,
+                show_last: Some(1),
+                display: None,
+            }),
+            OutputInfo::HLine(HLineInfo {
+                price: Some(1f64),
+                title: None,
+                color: None,
+                linestyle: None,
+                linewidth: None,
+                editable: None,
+                display: None,
+            }),
+            OutputInfo::Fill(FillInfo {
+                fill_type: String::from("plot"),
+                start: 0,
+                end: 1,
+                title: None,
+                color: None,
+                opacity: None,
+                editable: None,
+                show_last: None,
+                display: Some(0),
+            }),
+        ];
+        let mut colors = StrOptionsData::new();
+        colors.options = vec![String::from("red")];
+        colors.values = vec![Some(0), Some(0), Some(0)];
+
+        let mut history = OutputHistory::new();
+        history.merge(
+            0,
+            3,
+            vec![
+                plot_data(&[1, 2, 3]),
+                plot_data(&[1, 2, 3]),
+                plot_data(&[1, 2, 3]),
+                Some(OutputData::new_with_sc(vec![], vec![colors.clone()])),
+                None,
+                Some(OutputData::new_with_sc(vec![], vec![colors.clone()])),
+            ],
+        );
+        let (start, data_list) = history.render(&outputs, 0, 0);
+        assert_eq!(start, 0);
+        // The positive offset shifts the values into the bars after the last bar.
+        assert_eq!(data_list[0], plot_data(&[-1, -1, 1, 2, 3]));
+        assert_eq!(data_list[1], plot_data(&[2, 3, -1]));
+        assert_eq!(data_list[2], None);
+        colors.values = vec![None, None, Some(0)];
+        assert_eq!(
+            data_list[3],
+            Some(OutputData::new_with_sc(vec![], vec![colors.clone()]))
+        );
+        assert_eq!(data_list[4], None);
+        // The fill hidden by display.none is not rendered.
+        assert_eq!(data_list[5], None);
+
+        // Update the last bar and add a new bar, the shifted and masked bars are rendered again.
+        colors.values = vec![Some(0), Some(0)];
+        history.merge(
+            2,
+            4,
+            vec![
+                plot_data(&[4, 5]),
+                plot_data(&[4, 5]),
+                plot_data(&[4, 5]),
+                Some(OutputData::new_with_sc(vec![], vec![colors.clone()])),
+                None,
+                Some(OutputData::new_with_sc(vec![], vec![colors.clone()])),
+            ],
+        );
+        let (start, data_list) = history.render(&outputs, 2, 3);
+        assert_eq!(start, 0);
+        assert_eq!(data_list[0], plot_data(&[-1, -1, 1, 2, 4, 5]));
+        assert_eq!(data_list[1], plot_data(&[-1, 4, 5, -1]));
+        colors.values = vec![None, None, None, Some(0)];
+        assert_eq!(
+            data_list[3],
+            Some(OutputData::new_with_sc(vec![], vec![colors]))
+        );
+
+        // Without show_last, only the bars since the update and the negative offset are rendered.
+        let outputs = vec![plot_info(Some(-1), None, None)];
+        let mut history = OutputHistory::new();
+        history.merge(0, 3, vec![plot_data(&[1, 2, 3])]);
+        history.merge(2, 3, vec![plot_data(&[4])]);
+        assert_eq!(
+            history.render(&outputs, 2, 3),
+            (1, vec![plot_data(&[4, -1])])
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use super::context::ContextType;
+use super::output::{InputVal, SymbolInfo};
+use super::{AnySeries, AnySeriesType};
+use crate::types::{
+    downcast_pf_mut, downcast_pf_ref, Bool, Callable, Color, DataType, Evaluate, Float, Int,
+    PineRef, PineStaticType, PineType, RuntimeErr, SecondType, Series, Tuple, NA,
+};
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+// The format version of the runtime snapshot. Increase it when the layout of
+// RuntimeSnapshot changes so that old snapshots are rejected instead of misread.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "content")]
+pub enum SnapshotSeries {
+    Int(Vec<Int>),
+    Float(Vec<Float>),
+}
+
+impl SnapshotSeries {
+    pub fn from_any_series(series: &AnySeries) -> SnapshotSeries {
+        match series.get_type() {
+            AnySeriesType::Int => SnapshotSeries::Int(series.as_vec::<Int>().to_vec()),
+            AnySeriesType::Float => SnapshotSeries::Float(series.as_vec::<Float>().to_vec()),
+        }
+    }
+
+    pub fn into_any_series(self) -> AnySeries {
+        match self {
+            SnapshotSeries::Int(v) => AnySeries::from_int_vec(v),
+            SnapshotSeries::Float(v) => AnySeries::from_float_vec(v),
+        }
+    }
+}
+
+// The state of a value in the runtime tree. The values that don't change with the bars like
+// the functions are kept as the restored runtime creates them from the script.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "content")]
+pub enum StateValue {
+    Empty,
+    Keep,
+    Na,
+    Int(Int),
+    Float(Float),
+    Bool(Bool),
+    Color(String),
+    String(String),
+    Floats(Vec<Float>),
+    // The current value and the history of the series.
+    IntSeries(Int, Vec<Int>),
+    FloatSeries(Float, Vec<Float>),
+    BoolSeries(Bool, Vec<Bool>),
+    ColorSeries(String, Vec<String>),
+    StringSeries(String, Vec<String>),
+    Tuple(Vec<StateValue>),
+    // The state of the callable or evaluate instance of the builtin function.
+    Instance(Vec<StateValue>),
+}
+
+// The state of the variables, function instances and sub contexts of the context.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ContextState {
+    pub context_type: ContextType,
+    pub vars: Vec<StateValue>,
+    pub fun_instances: Vec<StateValue>,
+    pub sub_contexts: Vec<Option<ContextState>>,
+}
+
+// The state of the library context with the input series and the main context of the script.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ScriptState {
+    pub iterindex: i32,
+    pub rollback_count: u32,
+    pub library: ContextState,
+    pub main: ContextState,
+}
+
+// The runtime state of a script that has been run. The state has the series histories, the
+// var variables and the internal state of the builtin functions, so the restore continues
+// from the last bar without running the bars again. The bars are kept to run the script
+// again when the inputs change. The state is None if the script has values that can't be
+// saved like the drawings, the objects and the security calls, then the restore runs all
+// the bars again to rebuild the runtime.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RuntimeSnapshot {
+    pub version: u32,
+    // The source code of the script, used to check the snapshot is restored
+    // against the same script.
+    pub source: String,
+    pub inputs: Vec<Option<InputVal>>,
+    pub syminfo: Option<SymbolInfo>,
+    pub data_range: (Option<i32>, Option<i32>),
+    pub datalen: usize,
+    pub data: Vec<(String, SnapshotSeries)>,
+    pub state: Option<ScriptState>,
+}
+
+impl RuntimeSnapshot {
+    pub fn new(
+        source: String,
+        inputs: Vec<Option<InputVal>>,
+        syminfo: Option<SymbolInfo>,
+        data_range: (Option<i32>, Option<i32>),
+        datalen: usize,
+        data: &[(Cow<'static, str>, AnySeries)],
+        state: Option<ScriptState>,
+    ) -> RuntimeSnapshot {
+        RuntimeSnapshot {
+            version: SNAPSHOT_VERSION,
+            source,
+            inputs,
+            syminfo,
+            data_range,
+            datalen,
+            data: data
+                .iter()
+                .map(|(name, series)| {
+                    (
+                        String::from(name.as_ref()),
+                        SnapshotSeries::from_any_series(series),
+                    )
+                })
+                .collect(),
+            state,
+        }
+    }
+}
+
+pub fn state_err() -> RuntimeErr {
+    RuntimeErr::InvalidSnapshot(String::from("The saved state doesn't match the script."))
+}
+
+// The value types of the series that can be saved.
+pub trait StateItem<'a>: Default + Clone + Debug + PineStaticType + PineType<'a> + 'a {
+    fn series_state(current: Self, history: Vec<Self>) -> StateValue;
+
+    fn from_series_state(state: StateValue) -> Option<(Self, Vec<Self>)>;
+}
+
+impl<'a> StateItem<'a> for Int {
+    fn series_state(current: Int, history: Vec<Int>) -> StateValue {
+        StateValue::IntSeries(current, history)
+    }
+
+    fn from_series_state(state: StateValue) -> Option<(Int, Vec<Int>)> {
+        match state {
+            StateValue::IntSeries(current, history) => Some((current, history)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> StateItem<'a> for Float {
+    fn series_state(current: Float, history: Vec<Float>) -> StateValue {
+        StateValue::FloatSeries(current, history)
+    }
+
+    fn from_series_state(state: StateValue) -> Option<(Float, Vec<Float>)> {
+        match state {
+            StateValue::FloatSeries(current, history) => Some((current, history)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> StateItem<'a> for Bool {
+    fn series_state(current: Bool, history: Vec<Bool>) -> StateValue {
+        StateValue::BoolSeries(current, history)
+    }
+
+    fn from_series_state(state: StateValue) -> Option<(Bool, Vec<Bool>)> {
+        match state {
+            StateValue::BoolSeries(current, history) => Some((current, history)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> StateItem<'a> for String {
+    fn series_state(current: String, history: Vec<String>) -> StateValue {
+        StateValue::StringSeries(current, history)
+    }
+
+    fn from_series_state(state: StateValue) -> Option<(String, Vec<String>)> {
+        match state {
+            StateValue::StringSeries(current, history) => Some((current, history)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> StateItem<'a> for Color<'a> {
+    fn series_state(current: Color<'a>, history: Vec<Color<'a>>) -> StateValue {
+        let history = history.into_iter().map(|c| c.0.into_owned()).collect();
+        StateValue::ColorSeries(current.0.into_owned(), history)
+    }
+
+    fn from_series_state(state: StateValue) -> Option<(Color<'a>, Vec<Color<'a>>)> {
+        match state {
+            StateValue::ColorSeries(current, history) => Some((
+                Color(Cow::Owned(current)),
+                history.into_iter().map(|c| Color(Cow::Owned(c))).collect(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+pub fn save_series<'a, D: StateItem<'a>>(series: &Series<'a, D>) -> StateValue {
+    D::series_state(series.get_current(), series.get_history().clone())
+}
+
+pub fn load_series<'a, D: StateItem<'a>>(
+    series: &mut Series<'a, D>,
+    state: Option<StateValue>,
+) -> Result<(), RuntimeErr> {
+    let (current, history) = state.and_then(D::from_series_state).ok_or_else(state_err)?;
+    *series = Series::from_cur_history(current, history);
+    Ok(())
+}
+
+pub fn load_float(state: Option<StateValue>) -> Result<Float, RuntimeErr> {
+    match state {
+        Some(StateValue::Float(val)) => Ok(val),
+        _ => Err(state_err()),
+    }
+}
+
+pub fn load_int(state: Option<StateValue>) -> Result<Int, RuntimeErr> {
+    match state {
+        Some(StateValue::Int(val)) => Ok(val),
+        _ => Err(state_err()),
+    }
+}
+
+pub fn load_floats(state: Option<StateValue>) -> Result<Vec<Float>, RuntimeErr> {
+    match state {
+        Some(StateValue::Floats(vals)) => Ok(vals),
+        _ => Err(state_err()),
+    }
+}
+
+fn save_typed_series<'a, D: StateItem<'a> + PartialEq>(val: &PineRef<'a>) -> Option<StateValue> {
+    downcast_pf_ref::<Series<D>>(val).ok().map(save_series)
+}
+
+// Save the state of the value, it is None if the value can't be saved.
+pub fn save_value<'a>(val: &PineRef<'a>) -> Option<StateValue> {
+    match val.get_type() {
+        (DataType::NA, _) => Some(StateValue::Na),
+        (DataType::Int, SecondType::Simple) => {
+            Some(StateValue::Int(*downcast_pf_ref::<Int>(val).ok()?))
+        }
+        (DataType::Float, SecondType::Simple) => {
+            Some(StateValue::Float(*downcast_pf_ref::<Float>(val).ok()?))
+        }
+        (DataType::Bool, SecondType::Simple) => {
+            Some(StateValue::Bool(*downcast_pf_ref::<Bool>(val).ok()?))
+        }
+        (DataType::Color, SecondType::Simple) => Some(StateValue::Color(
+            downcast_pf_ref::<Color>(val).ok()?.0.to_string(),
+        )),
+        (DataType::String, SecondType::Simple) => Some(StateValue::String(
+            downcast_pf_ref::<String>(val).ok()?.clone(),
+        )),
+        (DataType::Int, SecondType::Series) => save_typed_series::<Int>(val),
+        (DataType::Float, SecondType::Series) => save_typed_series::<Float>(val),
+        (DataType::Bool, SecondType::Series) => save_typed_series::<Bool>(val),
+        (DataType::Color, SecondType::Series) => save_typed_series::<Color>(val),
+        (DataType::String, SecondType::Series) => save_typed_series::<String>(val),
+        (DataType::Tuple, _) => {
+            let items = downcast_pf_ref::<Tuple>(val).ok()?.0.iter().map(save_value);
+            Some(StateValue::Tuple(items.collect::<Option<_>>()?))
+        }
+        (DataType::Callable, _) => Some(StateValue::Instance(
+            downcast_pf_ref::<Callable>(val).ok()?.save_state()?,
+        )),
+        (DataType::Evaluate, _) => Some(StateValue::Instance(
+            downcast_pf_ref::<Evaluate>(val).ok()?.save_state()?,
+        )),
+        (DataType::PineVar, _)
+        | (DataType::CallableFactory, _)
+        | (DataType::Function, _)
+        | (DataType::Object, _)
+        | (DataType::SimpleCallableObject, _)
+        | (DataType::CallableObject, _)
+        | (DataType::EvaluateFactory, _)
+        | (DataType::CallableEvaluate, _)
+        | (DataType::CallableObjectEvaluate, _) => Some(StateValue::Keep),
+        // The drawings and the objects refer to each other, they are not saved.
+        _ => None,
+    }
+}
+
+fn load_typed_series<'a, D: StateItem<'a> + PartialEq>(
+    dest: &mut Option<PineRef<'a>>,
+    state: StateValue,
+) -> Result<(), RuntimeErr> {
+    match dest {
+        // Load the series in place since the series may be shared by the variables.
+        Some(val) if val.get_type() == Series::<D>::static_type() => {
+            load_series(downcast_pf_mut::<Series<D>>(val)?, Some(state))
+        }
+        _ => {
+            let mut series = Series::<D>::new();
+            load_series(&mut series, Some(state))?;
+            *dest = Some(PineRef::new(series));
+            Ok(())
+        }
+    }
+}
+
+// Load the state of the callable or evaluate instance.
+pub fn load_instance<'a>(val: &mut PineRef<'a>, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+    match val.get_type() {
+        (DataType::Callable, _) => downcast_pf_mut::<Callable>(val)?.load_state(state),
+        (DataType::Evaluate, _) => downcast_pf_mut::<Evaluate>(val)?.load_state(state),
+        _ => Err(state_err()),
+    }
+}
+
+// Load the saved state into the value that the restored runtime has created.
+pub fn load_value<'a>(dest: &mut Option<PineRef<'a>>, state: StateValue) -> Result<(), RuntimeErr> {
+    match state {
+        StateValue::Empty => *dest = None,
+        StateValue::Keep => {}
+        StateValue::Na => *dest = Some(PineRef::new_box(NA)),
+        StateValue::Int(val) => *dest = Some(PineRef::new_box(val)),
+        StateValue::Float(val) => *dest = Some(PineRef::new_box(val)),
+        StateValue::Bool(val) => *dest = Some(PineRef::new_box(val)),
+        StateValue::Color(val) => *dest = Some(PineRef::new_box(Color(Cow::Owned(val)))),
+        StateValue::String(val) => *dest = Some(PineRef::new(val)),
+        StateValue::IntSeries(..) => load_typed_series::<Int>(dest, state)?,
+        StateValue::FloatSeries(..) => load_typed_series::<Float>(dest, state)?,
+        StateValue::BoolSeries(..) => load_typed_series::<Bool>(dest, state)?,
+        StateValue::ColorSeries(..) => load_typed_series::<Color>(dest, state)?,
+        StateValue::StringSeries(..) => load_typed_series::<String>(dest, state)?,
+        StateValue::Tuple(items) => {
+            let mut vals = vec![];
+            for item in items {
+                let mut val = None;
+                load_value(&mut val, item)?;
+                vals.push(val.ok_or_else(state_err)?);
+            }
+            *dest = Some(PineRef::new_box(Tuple(vals)));
+        }
+        StateValue::Instance(state) => match dest {
+            Some(val) => load_instance(val, state)?,
+            None => return Err(state_err()),
+        },
+        StateValue::Floats(_) => return Err(state_err()),
+    }
+    Ok(())
+}
+
+// Save the values like the variables, the values not created yet are empty.
+pub fn save_values<'a>(vals: &[Option<PineRef<'a>>]) -> Option<Vec<StateValue>> {
+    vals.iter()
+        .map(|v| match v {
+            Some(v) => save_value(v),
+            None => Some(StateValue::Empty),
+        })
+        .collect()
+}
+
+// Load the values saved by save_values, the count of the values is the same as the saved one.
+pub fn load_values<'a>(
+    vals: &mut Vec<Option<PineRef<'a>>>,
+    state: Vec<StateValue>,
+) -> Result<(), RuntimeErr> {
+    vals.resize_with(state.len(), || None);
+    for (val, state) in vals.iter_mut().zip(state) {
+        load_value(val, state)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_series_test() {
+        let series = AnySeries::from_float_vec(vec![Some(1f64), None]);
+        let snapshot = SnapshotSeries::from_any_series(&series);
+        assert_eq!(snapshot, SnapshotSeries::Float(vec![Some(1f64), None]));
+        assert_eq!(
+            snapshot.into_any_series().into_vec::<Float>(),
+            vec![Some(1f64), None]
+        );
+
+        let series = AnySeries::from_int_vec(vec![Some(1i64), Some(2i64)]);
+        assert_eq!(
+            SnapshotSeries::from_any_series(&series),
+            SnapshotSeries::Int(vec![Some(1i64), Some(2i64)])
+        );
+    }
+}
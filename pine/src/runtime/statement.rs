@@ -2,6 +2,7 @@ use super::context::{
     downcast_ctx, ContextType, Ctx, PineRuntimeError, RVRunner, Runner, RunnerForAssign,
     RunnerForFunc, StmtRunner, VarOperate,
 };
+use super::debug::Debugger;
 use super::function::Function;
 use super::instance_caller::*;
 use super::runtime_convert::convert;
@@ -15,14 +16,14 @@ use crate::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
 use crate::types::{
     downcast_pf, Bool, CallObjEval, Callable, CallableEvaluate, CallableFactory, CallableObject,
     Color, DataType as FirstType, Float, Int, PineFrom, PineRef, PineStaticType, PineType, RefData,
-    RuntimeErr, SecondType, Series, Tuple, NA,
+    RuntimeErr, SecondType, Series, Tuple, UserObjectItem, NA,
 };
 use std::fmt::Debug;
 
 impl<'a> StmtRunner<'a> for Statement<'a> {
     fn st_run(&'a self, context: &mut dyn Ctx<'a>) -> Result<(), PineRuntimeError> {
         match *self {
-            Statement::None(_) => Ok(()),
+            Statement::None(_) | Statement::Error(_) => Ok(()),
             Statement::Break(range) => Err(PineRuntimeError::new(RuntimeErr::Break, range)),
             Statement::Continue(range) => Err(PineRuntimeError::new(RuntimeErr::Continue, range)),
             Statement::Assignment(ref assign) => assign.st_run(context),
@@ -31,6 +32,13 @@ impl<'a> StmtRunner<'a> for Statement<'a> {
             Statement::ForRange(ref fr) => StmtRunner::st_run(fr.as_ref(), context),
             Statement::FuncCall(ref fun_call) => StmtRunner::st_run(fun_call.as_ref(), context),
             Statement::FuncDef(ref fun_def) => fun_def.st_run(context),
+            Statement::Import(ref import) => {
+                for def in import.defs.iter() {
+                    def.st_run(context)?;
+                }
+                Ok(())
+            }
+            Statement::TypeDef(ref type_def) => type_def.st_run(context),
             Statement::Exp(ref exp) => exp.st_run(context),
         }
     }
@@ -159,6 +167,9 @@ pub fn process_assign_val<'a>(
                 use crate::libs::label::PerLabelItem;
                 update_series::<PerLabelItem>(context, index, current_val, true_val)
             }
+            ((FirstType::UserObject, SecondType::Series), _) => {
+                update_series::<UserObjectItem>(context, index, current_val, true_val)
+            }
             ((_, SecondType::Series), _) | (_, (_, SecondType::Series)) => {
                 // Err(RuntimeErr::TypeMismatch(format!(
                 //     "Series type can only be Int, Float, Bool, Color, String, but get {:?}",
@@ -238,6 +249,38 @@ where
     Ok(v.into_pf())
 }
 
+// Set the current value of the series to the value of the last bar.
+fn carry_forward_series<'a, D>(val: PineRef<'a>) -> Result<PineRef<'a>, RuntimeErr>
+where
+    D: Default + PineType<'a> + PineStaticType + 'a + PineFrom<'a, D> + Clone + PartialEq + Debug,
+{
+    let mut s = Series::<D>::implicity_from(val)?;
+    if let Some(last) = s.get_history().last().cloned() {
+        s.update(last);
+    }
+    Ok(s.into_pf())
+}
+
+fn carry_forward<'a>(val: PineRef<'a>) -> Result<PineRef<'a>, RuntimeErr> {
+    match val.get_type() {
+        (FirstType::Bool, SecondType::Series) => carry_forward_series::<Bool>(val),
+        (FirstType::Float, SecondType::Series) => carry_forward_series::<Float>(val),
+        (FirstType::Int, SecondType::Series) => carry_forward_series::<Int>(val),
+        (FirstType::Color, SecondType::Series) => carry_forward_series::<Color>(val),
+        (FirstType::String, SecondType::Series) => carry_forward_series::<String>(val),
+        (FirstType::Line, SecondType::Series) => {
+            use crate::libs::line::PerLineItem;
+            carry_forward_series::<PerLineItem>(val)
+        }
+        (FirstType::Label, SecondType::Series) => {
+            use crate::libs::label::PerLabelItem;
+            carry_forward_series::<PerLabelItem>(val)
+        }
+        (FirstType::UserObject, SecondType::Series) => carry_forward_series::<UserObjectItem>(val),
+        _ => Ok(val),
+    }
+}
+
 fn update_series_range<'a, 'b, D>(
     context: &mut (dyn 'b + VarOperate<'a>),
     var_index: VarIndex,
@@ -278,13 +321,13 @@ impl<'a> RunnerForName<'a> for Assignment<'a> {
         if _vn.value == "_" {
             return Ok(val);
         }
-        // For variable declare with var type, it only need initialize once.
+        // For variable declare with var type, it only need initialize once, and the series
+        // keeps the value of the last bar.
         if self.var && context.contains_var_scope(varid) {
-            return Ok(context
-                .get_var(VarIndex::new(varid, 0))
-                .as_ref()
-                .unwrap()
-                .clone());
+            let index = VarIndex::new(varid, 0);
+            let val = carry_forward(context.move_var(index).unwrap())?;
+            context.update_var(index, val.clone());
+            return Ok(val);
         }
         // let val = self.val.rv_run(context)?;
         let true_val: PineRef<'a> = match self.var_type {
@@ -309,9 +352,13 @@ impl<'a> RunnerForName<'a> for Assignment<'a> {
                 SecondType::Series => Series::<String>::implicity_from(val)?.into_pf(),
                 _ => String::implicity_from(val)?.into_pf(),
             },
-            Some(DataType::Custom(_)) => {
-                type_cast_custom(context, self.cast_index, self.cast_func_index, val)?
-            } // Some(DataType::Custom(_)) => val,
+            Some(DataType::Custom(_)) => type_cast_custom(
+                context,
+                self.cast_index,
+                self.cast_func_index,
+                val,
+                self.range,
+            )?, // Some(DataType::Custom(_)) => val,
         };
         if let (FirstType::NA, _) = true_val.get_type() {
             return Err(RuntimeErr::InvalidNADeclarer);
@@ -376,11 +423,44 @@ impl<'a> StmtRunner<'a> for Assignment<'a> {
     }
 }
 
+// Assign the value to the field of the object like `p.next.price := 1`.
+fn assign_field<'a>(
+    context: &mut dyn Ctx<'a>,
+    index: VarIndex,
+    fields: &[VarName<'a>],
+    val: PineRef<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let mut obj = match context.get_var(index) {
+        Some(var) => UserObjectItem::implicity_from(var.clone())?.into_inner(),
+        None => return Err(RuntimeErr::VarNotFound),
+    };
+    let (last, path) = fields.split_last().unwrap();
+    for field in path {
+        let next = match obj {
+            Some(obj) => obj.get_field(field.value)?,
+            None => return Err(RuntimeErr::NaObjectField),
+        };
+        obj = UserObjectItem::implicity_from(next)?.into_inner();
+    }
+    match obj {
+        Some(obj) => {
+            let (field_index, old) = obj.set_field(last.value, val.clone())?;
+            downcast_ctx(context.get_main_ctx()).record_field_write(obj, field_index, old);
+        }
+        None => return Err(RuntimeErr::NaObjectField),
+    }
+    Ok(val)
+}
+
 impl<'a> Runner<'a> for VarAssignment<'a> {
     fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
         let val = self.val.rv_run(context)?;
 
         let index = self.var_index;
+        if !self.fields.is_empty() {
+            return assign_field(context, index, &self.fields, val)
+                .map_err(|code| PineRuntimeError::new(code, self.range));
+        }
         let exist_val = context.move_var(index).unwrap();
         let ctx_instance = downcast_ctx(context);
         match exist_val.get_type() {
@@ -407,6 +487,13 @@ impl<'a> Runner<'a> for VarAssignment<'a> {
                 use crate::libs::label::PerLabelItem;
                 update_series_range::<PerLabelItem>(ctx_instance, index, exist_val, val, self.range)
             }
+            (FirstType::UserObject, _) => update_series_range::<UserObjectItem>(
+                ctx_instance,
+                index,
+                exist_val,
+                val,
+                self.range,
+            ),
             _ => Err(PineRuntimeError::new(
                 RuntimeErr::NotSupportOperator,
                 self.range,
@@ -523,7 +610,7 @@ impl<'a> Runner<'a> for ForRange<'a> {
                 }
                 Err(PineRuntimeError {
                     code: RuntimeErr::Break,
-                    range: _,
+                    ..
                 }) => {
                     if let Some(ref exp) = self.do_blk.ret_stmt {
                         ret_val = exp.rv_run(subctx)?
@@ -532,7 +619,7 @@ impl<'a> Runner<'a> for ForRange<'a> {
                 }
                 Err(PineRuntimeError {
                     code: RuntimeErr::Continue,
-                    range: _,
+                    ..
                 }) => {
                     if let Some(ref exp) = self.do_blk.ret_stmt {
                         ret_val = exp.rv_run(subctx)?
@@ -646,7 +733,7 @@ fn assign_run<'a>(
 
             let result = callable.call(context, pos_args, dict_args, func_type);
             // ctx_ref.set_is_run(true);
-            context.create_runnable(callable.into_rc());
+            context.create_runnable(callable.into_rc(), fun_call.range);
             result
         }
         (FirstType::SimpleCallableObject, SecondType::Simple) => {
@@ -659,6 +746,7 @@ fn assign_run<'a>(
                 pos_args,
                 dict_args,
                 func_type,
+                fun_call.range,
             )
         }
         (FirstType::CallableFactory, SecondType::Simple)
@@ -674,6 +762,7 @@ fn assign_run<'a>(
                 pos_args,
                 dict_args,
                 func_type,
+                fun_call.range,
             )
         }
         (FirstType::Function, SecondType::Simple) => {
@@ -693,7 +782,7 @@ fn assign_run<'a>(
             );
             let result = function.call(ctx_ref, pos_args, dict_args, fun_call.range);
             ctx_ref.set_is_run(true);
-            return result;
+            return result.map_err(|e| e.push_call(def.name.value, fun_call.range));
         }
         _ => Err(RuntimeErr::NotSupportOperator),
     };
@@ -750,10 +839,44 @@ impl<'a> StmtRunner<'a> for FunctionDef<'a> {
     }
 }
 
+impl<'a> Block<'a> {
+    // Run the block and notify the debugger before every statement.
+    fn debug_run(
+        &'a self,
+        context: &mut dyn Ctx<'a>,
+        debugger: &'a dyn Debugger<'a>,
+    ) -> Result<PineRef<'a>, PineRuntimeError> {
+        debugger.enter_block(context, self);
+        let mut run = || {
+            for st in self.stmts.iter() {
+                debugger.on_stmt(context, self, st.range());
+                st.st_run(context)?;
+            }
+            if let Some(ref exp) = self.ret_stmt {
+                debugger.on_stmt(context, self, exp.range());
+                exp.rv_run(context)
+            } else {
+                Ok(PineRef::new_box(NA))
+            }
+        };
+        let result = run();
+        debugger.exit_block(context, self);
+        result
+    }
+}
+
 impl<'a> Runner<'a> for Block<'a> {
     fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
-        for st in self.stmts.iter() {
-            st.st_run(context)?;
+        if let Some(debugger) = context.get_debugger() {
+            return self.debug_run(context, debugger);
+        }
+        match self.program {
+            Some(ref program) => program.run(self, context)?,
+            None => {
+                for st in self.stmts.iter() {
+                    st.st_run(context)?;
+                }
+            }
         }
         if let Some(ref exp) = self.ret_stmt {
             exp.rv_run(context)
@@ -775,6 +898,7 @@ mod tests {
     use crate::runtime::exp::Exp;
     use crate::syntax::SyntaxParser;
     use crate::types::{PineClass, RefData};
+    use std::sync::Arc;
 
     #[test]
     fn assignment_test() {
@@ -917,6 +1041,34 @@ mod tests {
         context.update_var(myvar_index, val.into_pf());
     }
 
+    #[test]
+    fn var_series_assignment_test() {
+        let myvar_index = VarIndex::new(0, 0);
+        let newvar_index = VarIndex::new(1, 0);
+        let assign = Statement::Assignment(Box::new(Assignment::new_with_varids(
+            vec![VarName::new_no_input("myvar")],
+            Exp::VarName(RVVarName::new_with_index("newvar", newvar_index)),
+            true,
+            None,
+            vec![myvar_index.varid],
+        )));
+        let mut context = Context::new(None, ContextType::Normal);
+        context.init(2, 0, 0);
+        context.create_var(newvar_index.varid, PineRef::new_rc(Series::from(Some(100))));
+        assert_eq!(assign.st_run(&mut context), Ok(()));
+        context.commit();
+
+        // The var series keeps the value of the last bar instead of the new value.
+        context.create_var(newvar_index.varid, PineRef::new_rc(Series::from(Some(200))));
+        assert_eq!(assign.st_run(&mut context), Ok(()));
+        let val: RefData<Series<Int>> =
+            Series::explicity_from(context.move_var(myvar_index).unwrap()).unwrap();
+        let mut dest_s = RefData::new_rc(Series::from_vec(vec![Some(100)]));
+        dest_s.update(Some(100));
+        assert_eq!(val, dest_s);
+        context.update_var(myvar_index, val.into_pf());
+    }
+
     #[test]
     fn var_assignment_test() {
         let hello_index = VarIndex::new(0, 0);
@@ -1305,13 +1457,12 @@ mod tests {
         use crate::syntax::SimpleInputSrcDetector;
         use crate::syntax::SyntaxParser;
         use std::mem;
-        use std::rc::Rc;
 
         let input_detector = SimpleInputSrcDetector::new(vec!["close"]);
         let mut parser = SyntaxParser::new_with_vars(&vec![
             (
                 "security",
-                SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+                SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
                     vec![(
                         "m",
                         SyntaxType::DynamicExpr(Box::new(SyntaxType::float_series())),
@@ -0,0 +1,767 @@
+// Extract the IOInfo from the syntax tree without running the script. The builtin functions
+// that declare the inputs and outputs are called once for every call in the source, whether
+// the branch containing it is taken or not, with the arguments folded from the literals. The
+// io calls in the function bodies are found at the calls of the functions. The call sites of
+// the inputs are also returned, so the runtime takes the input of the call site instead of the
+// input in the order the calls run.
+use super::context::{downcast_ctx, Ctx, InputSite, RVRunner};
+use super::instance_caller::create_callable;
+use super::output::IOInfo;
+use crate::ast::op::{BinaryOp, UnaryOp};
+use crate::ast::stat_expr_types::{
+    Assignment, BinaryExp, Block, Exp, ForRange, FunctionCall, FunctionDef, IfThenElse, PrefixExp,
+    Statement, UnaryExp, VarAssignment,
+};
+use crate::ast::syntax_type::{FunctionType, SimpleSyntaxType, SyntaxType};
+use crate::libs::security;
+use crate::types::{
+    downcast_pf, Arithmetic, Bool, Callable, CallableObject, DataType, Float, Int, Negative,
+    Object, PineFrom, PineRef, PineType, Runnable, RuntimeErr, SecondType, SimpleCallableObject,
+    Tuple, NA,
+};
+use std::collections::{HashMap, HashSet};
+
+// The builtin functions that declare the inputs, the outputs and the script type.
+const IO_FUNCS: [&str; 12] = [
+    "input",
+    "plot",
+    "plotshape",
+    "plotchar",
+    "plotarrow",
+    "plotbar",
+    "plotcandle",
+    "hline",
+    "fill",
+    "study",
+    "indicator",
+    "library",
+];
+
+const SECURITY_FUNCS: [&str; 2] = ["security", "request.security"];
+
+const INPUT_FUNCS: [&str; 1] = ["input"];
+
+struct IOExtractor<'a> {
+    // The global variables assigned once by the constant values or the results of the io
+    // functions, such as `len = input(14)` and `p1 = plot(close)`.
+    consts: HashMap<&'a str, PineRef<'a>>,
+    // The names declared by the script, they hide the builtin variables of the same names.
+    user_names: HashSet<&'a str>,
+    // The functions generated for the dynamic expressions like the security expression.
+    gen_defs: HashMap<i32, &'a FunctionDef<'a>>,
+    // The functions defined by the script.
+    funcs: HashMap<&'a str, &'a FunctionDef<'a>>,
+    callables: Vec<Callable<'a>>,
+    depth: usize,
+    // The ids of the sub contexts the runtime creates for the blocks and the function calls.
+    ctx_path: Vec<i32>,
+    input_sites: Vec<InputSite>,
+}
+
+// Only the plain values can be passed to the builtin functions as the folded arguments.
+fn is_data<'a>(val: &PineRef<'a>) -> bool {
+    matches!(
+        val.get_type().0,
+        DataType::Float
+            | DataType::Int
+            | DataType::Bool
+            | DataType::Color
+            | DataType::String
+            | DataType::NA
+            | DataType::Tuple
+    )
+}
+
+fn get_field<'a>(
+    context: &mut dyn Ctx<'a>,
+    obj: PineRef<'a>,
+    name: &'a str,
+) -> Option<PineRef<'a>> {
+    match obj.get_type() {
+        (DataType::Object, SecondType::Simple) => {
+            downcast_pf::<Object>(obj).ok()?.get(context, name).ok()
+        }
+        (DataType::CallableObject, SecondType::Simple) => downcast_pf::<CallableObject>(obj)
+            .ok()?
+            .get(context, name)
+            .ok(),
+        (DataType::SimpleCallableObject, SecondType::Simple) => {
+            downcast_pf::<SimpleCallableObject>(obj)
+                .ok()?
+                .get(context, name)
+                .ok()
+        }
+        _ => None,
+    }
+}
+
+fn fold_unary<'a>(exp: &UnaryExp<'a>, val: PineRef<'a>) -> Option<PineRef<'a>> {
+    if val.get_type().1 != SecondType::Simple {
+        return None;
+    }
+    match (&exp.op, val.get_type().0) {
+        (UnaryOp::Plus, _) => Some(val),
+        (UnaryOp::Minus, DataType::Int) => Some(PineRef::new_box(
+            Int::implicity_from(val).ok()?.into_inner().negative(),
+        )),
+        (UnaryOp::Minus, DataType::Float) => Some(PineRef::new_box(
+            Float::implicity_from(val).ok()?.into_inner().negative(),
+        )),
+        (UnaryOp::BoolNot, DataType::Bool) => Some(PineRef::new_box(
+            !Bool::implicity_from(val).ok()?.into_inner(),
+        )),
+        _ => None,
+    }
+}
+
+fn arith_op<D: Arithmetic>(op: &BinaryOp, d1: D, d2: D) -> Option<D> {
+    match op {
+        BinaryOp::Plus => Some(d1.add(d2)),
+        BinaryOp::Minus => Some(d1.minus(d2)),
+        BinaryOp::Mul => Some(d1.mul(d2)),
+        BinaryOp::Div => Some(d1.div(d2)),
+        _ => None,
+    }
+}
+
+fn fold_binary<'a>(
+    exp: &BinaryExp<'a>,
+    val1: PineRef<'a>,
+    val2: PineRef<'a>,
+) -> Option<PineRef<'a>> {
+    match exp.result_type {
+        SyntaxType::Simple(SimpleSyntaxType::Int) => {
+            let d1 = Int::implicity_from(val1).ok()?.into_inner();
+            let d2 = Int::implicity_from(val2).ok()?.into_inner();
+            Some(PineRef::new_box(arith_op(&exp.op, d1, d2)?))
+        }
+        SyntaxType::Simple(SimpleSyntaxType::Float) => {
+            let d1 = Float::implicity_from(val1).ok()?.into_inner();
+            let d2 = Float::implicity_from(val2).ok()?.into_inner();
+            Some(PineRef::new_box(arith_op(&exp.op, d1, d2)?))
+        }
+        SyntaxType::Simple(SimpleSyntaxType::String) if exp.op == BinaryOp::Plus => {
+            let s1 = String::implicity_from(val1).ok()?.into_inner();
+            let s2 = String::implicity_from(val2).ok()?.into_inner();
+            Some(PineRef::new_rc(s1 + &s2))
+        }
+        _ => None,
+    }
+}
+
+// The argument expression of the parameter name, passed by position or by name.
+fn arg_exp<'a>(
+    func_call: &'a FunctionCall<'a>,
+    func_type: &FunctionType<'a>,
+    name: &str,
+) -> Option<&'a Exp<'a>> {
+    let index = func_type.arg_names().iter().position(|&n| n == name)?;
+    match func_call.pos_args.get(index) {
+        Some(exp) => Some(exp),
+        None => func_call
+            .dict_args
+            .iter()
+            .find(|(n, _)| n.value == name)
+            .map(|(_, exp)| exp),
+    }
+}
+
+impl<'a> IOExtractor<'a> {
+    fn new() -> IOExtractor<'a> {
+        IOExtractor {
+            consts: HashMap::new(),
+            user_names: HashSet::new(),
+            gen_defs: HashMap::new(),
+            funcs: HashMap::new(),
+            callables: vec![],
+            depth: 0,
+            ctx_path: vec![],
+            input_sites: vec![],
+        }
+    }
+
+    // Get the value of the constant variable or the builtin variable.
+    fn get_var(&self, context: &mut dyn Ctx<'a>, name: &str) -> Option<PineRef<'a>> {
+        if let Some(val) = self.consts.get(name) {
+            return Some(val.clone());
+        }
+        if self.user_names.contains(name) {
+            return None;
+        }
+        let index = context.get_top_varname_index(name)?;
+        context.get_var(index).clone()
+    }
+
+    fn get_prefix(&self, context: &mut dyn Ctx<'a>, exp: &'a PrefixExp<'a>) -> Option<PineRef<'a>> {
+        let obj = match &exp.left_exp {
+            Exp::VarName(name) => self.get_var(context, name.name.value)?,
+            Exp::PrefixExp(prefix) => self.get_prefix(context, prefix)?,
+            _ => return None,
+        };
+        get_field(context, obj, exp.right_name.value)
+    }
+
+    // The name of the builtin function like `plot` and `input.int`.
+    fn func_name(&self, method: &'a Exp<'a>) -> Option<String> {
+        let is_builtin =
+            |name: &str| !self.consts.contains_key(name) && !self.user_names.contains(name);
+        match method {
+            Exp::VarName(name) if is_builtin(name.name.value) => {
+                Some(String::from(name.name.value))
+            }
+            Exp::PrefixExp(prefix) => match &prefix.left_exp {
+                Exp::VarName(name) if is_builtin(name.name.value) => {
+                    Some(format!("{}.{}", name.name.value, prefix.right_name.value))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn walk_blk(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        blk: &'a Block<'a>,
+    ) -> Result<(), RuntimeErr> {
+        for stmt in blk.stmts.iter() {
+            self.walk_stmt(context, stmt)?;
+        }
+        if let Some(exp) = &blk.ret_stmt {
+            self.walk_exp(context, exp)?;
+        }
+        Ok(())
+    }
+
+    // The names declared in the nested block are only visible in the block.
+    fn walk_sub_blk(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        blk: &'a Block<'a>,
+    ) -> Result<(), RuntimeErr> {
+        let names = self.user_names.clone();
+        self.depth += 1;
+        let res = self.walk_blk(context, blk);
+        self.depth -= 1;
+        self.user_names = names;
+        res
+    }
+
+    // The block run in the sub context of the id.
+    fn walk_ctx_blk(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        ctxid: i32,
+        blk: &'a Block<'a>,
+    ) -> Result<(), RuntimeErr> {
+        self.ctx_path.push(ctxid);
+        let res = self.walk_sub_blk(context, blk);
+        self.ctx_path.pop();
+        res
+    }
+
+    fn declare(&mut self, name: &'a str) {
+        self.consts.remove(name);
+        self.user_names.insert(name);
+    }
+
+    fn walk_stmt(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        stmt: &'a Statement<'a>,
+    ) -> Result<(), RuntimeErr> {
+        match stmt {
+            Statement::Assignment(assign) => self.walk_assign(context, assign),
+            Statement::VarAssignment(assign) => self.walk_var_assign(context, assign),
+            Statement::Ite(ite) => self.walk_ite(context, ite),
+            Statement::ForRange(for_range) => self.walk_for_range(context, for_range),
+            Statement::FuncCall(func_call) => self.walk_call(context, func_call).map(|_| ()),
+            Statement::FuncDef(def) => {
+                self.gen_defs.insert(def.name_varid, def);
+                self.declare(def.name.value);
+                // The function body is walked at the calls of the function.
+                self.funcs.insert(def.name.value, def);
+                Ok(())
+            }
+            Statement::Import(import) => {
+                self.declare(import.alias.value);
+                Ok(())
+            }
+            Statement::TypeDef(def) => {
+                self.declare(def.name.value);
+                Ok(())
+            }
+            Statement::Exp(exp) => self.walk_exp(context, exp).map(|_| ()),
+            Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::None(_)
+            | Statement::Error(_) => Ok(()),
+        }
+    }
+
+    fn walk_assign(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        assign: &'a Assignment<'a>,
+    ) -> Result<(), RuntimeErr> {
+        let val = self.walk_exp(context, &assign.val)?;
+        for name in assign.names.iter() {
+            self.declare(name.value);
+        }
+        // Only the global variables without the type casting are saved as the constants.
+        match val {
+            Some(val)
+                if self.depth == 0 && assign.names.len() == 1 && assign.var_type.is_none() =>
+            {
+                self.consts.insert(assign.names[0].value, val);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn walk_var_assign(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        assign: &'a VarAssignment<'a>,
+    ) -> Result<(), RuntimeErr> {
+        self.walk_exp(context, &assign.val)?;
+        self.consts.remove(assign.name.value);
+        Ok(())
+    }
+
+    fn walk_ite(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        ite: &'a IfThenElse<'a>,
+    ) -> Result<(), RuntimeErr> {
+        self.walk_exp(context, &ite.cond)?;
+        self.walk_ctx_blk(context, ite.then_ctxid, &ite.then_blk)?;
+        if let Some(blk) = &ite.else_blk {
+            self.walk_ctx_blk(context, ite.else_ctxid, blk)?;
+        }
+        Ok(())
+    }
+
+    fn walk_for_range(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        for_range: &'a ForRange<'a>,
+    ) -> Result<(), RuntimeErr> {
+        self.walk_exp(context, &for_range.start)?;
+        self.walk_exp(context, &for_range.end)?;
+        if let Some(step) = &for_range.step {
+            self.walk_exp(context, step)?;
+        }
+        let names = self.user_names.clone();
+        self.declare(for_range.var.value);
+        let res = self.walk_ctx_blk(context, for_range.ctxid, &for_range.do_blk);
+        self.user_names = names;
+        res
+    }
+
+    // Walk the expression for the io function calls, and return the value if it's constant.
+    fn walk_exp(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        exp: &'a Exp<'a>,
+    ) -> Result<Option<PineRef<'a>>, RuntimeErr> {
+        match exp {
+            Exp::Na(_) | Exp::Bool(_) | Exp::Num(_) | Exp::Str(_) | Exp::Color(_) => {
+                Ok(exp.rv_run(context).ok())
+            }
+            Exp::VarName(name) => Ok(self
+                .get_var(context, name.name.value)
+                .filter(|v| is_data(v))),
+            Exp::PrefixExp(prefix) => match self.get_prefix(context, prefix) {
+                Some(val) => Ok(Some(val).filter(|v| is_data(v))),
+                None => {
+                    self.walk_exp(context, &prefix.left_exp)?;
+                    Ok(None)
+                }
+            },
+            Exp::Tuple(tuple) => {
+                let mut vals = vec![];
+                for exp in tuple.exps.iter() {
+                    vals.push(self.walk_exp(context, exp)?);
+                }
+                match vals.into_iter().collect::<Option<Vec<_>>>() {
+                    Some(vals) => Ok(Some(PineRef::new_box(Tuple(vals)))),
+                    None => Ok(None),
+                }
+            }
+            Exp::UnaryExp(unary) => match self.walk_exp(context, &unary.exp)? {
+                Some(val) => Ok(fold_unary(unary, val)),
+                None => Ok(None),
+            },
+            Exp::BinaryExp(binary) => {
+                let val1 = self.walk_exp(context, &binary.exp1)?;
+                let val2 = self.walk_exp(context, &binary.exp2)?;
+                match (val1, val2) {
+                    (Some(val1), Some(val2)) => Ok(fold_binary(binary, val1, val2)),
+                    _ => Ok(None),
+                }
+            }
+            Exp::Condition(cond) => {
+                let val = self.walk_exp(context, &cond.cond)?;
+                let val1 = self.walk_exp(context, &cond.exp1)?;
+                let val2 = self.walk_exp(context, &cond.exp2)?;
+                match val.filter(|v| v.get_type() == (DataType::Bool, SecondType::Simple)) {
+                    Some(val) => match *Bool::implicity_from(val)? {
+                        true => Ok(val1),
+                        false => Ok(val2),
+                    },
+                    None => Ok(None),
+                }
+            }
+            Exp::TypeCast(type_cast) => {
+                self.walk_exp(context, &type_cast.exp)?;
+                Ok(None)
+            }
+            Exp::RefCall(ref_call) => {
+                self.walk_exp(context, &ref_call.name)?;
+                self.walk_exp(context, &ref_call.arg)?;
+                Ok(None)
+            }
+            Exp::FuncCall(func_call) => self.walk_call(context, func_call),
+            Exp::Ite(ite) => self.walk_ite(context, ite).map(|_| None),
+            Exp::ForRange(for_range) => self.walk_for_range(context, for_range).map(|_| None),
+            Exp::Assignment(assign) => self.walk_assign(context, assign).map(|_| None),
+            Exp::VarAssignment(assign) => self.walk_var_assign(context, assign).map(|_| None),
+        }
+    }
+
+    fn walk_call(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        func_call: &'a FunctionCall<'a>,
+    ) -> Result<Option<PineRef<'a>>, RuntimeErr> {
+        let name = self.func_name(&func_call.method);
+        let is_io = |names: &[&str]| match &name {
+            Some(name) => names
+                .iter()
+                .any(|n| name == n || name.starts_with(&format!("{}.", n))),
+            None => false,
+        };
+        if is_io(&SECURITY_FUNCS) {
+            return self.add_security_src(context, func_call);
+        }
+        if let Some(def) = self.user_func(func_call) {
+            return self.walk_user_call(context, func_call, def);
+        }
+        if !is_io(&IO_FUNCS) {
+            for exp in func_call.pos_args.iter() {
+                self.walk_exp(context, exp)?;
+            }
+            for (_, exp) in func_call.dict_args.iter() {
+                self.walk_exp(context, exp)?;
+            }
+            return Ok(None);
+        }
+        let func_type = match &func_call.func_type {
+            Some(func_type) => func_type.clone(),
+            None => return Err(RuntimeErr::NotSupportOperator),
+        };
+        let method = match &func_call.method {
+            Exp::VarName(name) => self.get_var(context, name.name.value),
+            Exp::PrefixExp(prefix) => self.get_prefix(context, prefix),
+            _ => None,
+        };
+        let method = method.ok_or(RuntimeErr::NotSupportOperator)?;
+
+        let mut pos_args = vec![];
+        for (i, exp) in func_call.pos_args.iter().enumerate() {
+            let val = self.walk_exp(context, exp)?;
+            pos_args.push(arg_val(val, func_type.get_type(i))?);
+        }
+        let mut dict_args = vec![];
+        for (name, exp) in func_call.dict_args.iter() {
+            let val = self.walk_exp(context, exp)?;
+            dict_args.push((
+                name.value,
+                arg_val(val, func_type.get_type_by_name(name.value))?,
+            ));
+        }
+        let mut callable = create_callable(method)?;
+        let result = callable.call(context, pos_args, dict_args, func_type)?;
+        self.callables.push(callable);
+        if is_io(&INPUT_FUNCS) {
+            self.input_sites
+                .push((self.ctx_path.clone(), func_call.range));
+        }
+        Ok(Some(result).filter(|v| is_data(v)))
+    }
+
+    // The function defined by the script that the call calls.
+    fn user_func(&self, func_call: &'a FunctionCall<'a>) -> Option<&'a FunctionDef<'a>> {
+        match &func_call.method {
+            Exp::VarName(name) if !self.consts.contains_key(name.name.value) => {
+                self.funcs.get(name.name.value).copied()
+            }
+            _ => None,
+        }
+    }
+
+    // The function body runs in the sub context of the call, so the io calls in the body are
+    // walked for every call of the function.
+    fn walk_user_call(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        func_call: &'a FunctionCall<'a>,
+        def: &'a FunctionDef<'a>,
+    ) -> Result<Option<PineRef<'a>>, RuntimeErr> {
+        for exp in func_call.pos_args.iter() {
+            self.walk_exp(context, exp)?;
+        }
+        for (_, exp) in func_call.dict_args.iter() {
+            self.walk_exp(context, exp)?;
+        }
+        let spec_def = def
+            .spec_defs
+            .as_ref()
+            .and_then(|defs| defs.get(func_call.spec_index as usize))
+            .ok_or(RuntimeErr::NotSupportOperator)?;
+        let names = self.user_names.clone();
+        for param in spec_def.params.iter() {
+            self.declare(param.value);
+        }
+        let res = self.walk_ctx_blk(context, func_call.ctxid, &spec_def.body);
+        self.user_names = names;
+        res.map(|_| None)
+    }
+
+    // The security call adds the input source of the ticker instead of the outputs.
+    fn add_security_src(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        func_call: &'a FunctionCall<'a>,
+    ) -> Result<Option<PineRef<'a>>, RuntimeErr> {
+        let func_type = match &func_call.func_type {
+            Some(func_type) => func_type.clone(),
+            None => return Err(RuntimeErr::NotSupportOperator),
+        };
+        let mut arg = |name| match arg_exp(func_call, &func_type, name) {
+            Some(exp) => self.walk_exp(context, exp),
+            None => Ok(None),
+        };
+        let symbol = arg("symbol")?.ok_or(RuntimeErr::NotSupportOperator)?;
+        let resolution = arg("resolution")?.ok_or(RuntimeErr::NotSupportOperator)?;
+        let def = match arg_exp(func_call, &func_type, "expression") {
+            Some(Exp::VarName(name)) => self.gen_defs.get(&name.var_index.varid),
+            _ => None,
+        };
+        let def = def.ok_or(RuntimeErr::NotSupportOperator)?;
+        let input_src = security::gen_input_src(Some(symbol), Some(resolution), &def.params)?;
+        downcast_ctx(context).add_input_src(input_src);
+        Ok(None)
+    }
+}
+
+// The value of the argument, the series arguments that are not constant are passed as na.
+fn arg_val<'a>(
+    val: Option<PineRef<'a>>,
+    syntax_type: Option<&SyntaxType<'a>>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    match (val, syntax_type) {
+        (Some(val), _) => Ok(val),
+        (None, Some(SyntaxType::Series(_))) => Ok(PineRef::new_box(NA)),
+        _ => Err(RuntimeErr::NotSupportOperator),
+    }
+}
+
+// Extract the IOInfo into the main context that has not run, the callbacks of the builtin
+// functions are run after all the calls like running the last bar. The call sites of the inputs
+// are in the order of the inputs.
+pub fn extract_io_info<'a>(
+    context: &mut dyn Ctx<'a>,
+    blk: &'a Block<'a>,
+) -> Result<(IOInfo, Vec<InputSite>), RuntimeErr> {
+    let mut extractor = IOExtractor::new();
+    extractor.walk_blk(context, blk)?;
+    for callable in extractor.callables.iter_mut() {
+        callable.run(context)?;
+    }
+    let io_info = downcast_ctx(context).get_io_info().clone();
+    if io_info.get_inputs().len() != extractor.input_sites.len() {
+        return Err(RuntimeErr::NotSupportOperator);
+    }
+    Ok((io_info, extractor.input_sites))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::output::{HLineInfo, InputInfo, InputSrc, InputVal, OutputInfo, PlotInfo};
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::PineScript;
+
+    #[test]
+    fn static_io_test() {
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script
+            .parse_src(String::from(
+                "//@version=5\nindicator('hello', overlay=true)\n\
+                 len = input.int(14, 'len', minval=1)\nsrc = input.source(close, 'src')\n\
+                 m = ta.sma(src, -1)\n\
+                 if close > open\n    plot(m, 'up', color=color.red, linewidth=len * 2)\n\
+                 else\n    hline(50 + 10, 'mid')\n\
+                 s = request.security('AAPL', 'D', high)",
+            ))
+            .unwrap();
+        // The script fails on the data because of the invalid length.
+        let data = vec![
+            ("close", AnySeries::from_float_vec(vec![Some(0f64)])),
+            ("open", AnySeries::from_float_vec(vec![Some(0f64)])),
+            ("high", AnySeries::from_float_vec(vec![Some(0f64)])),
+        ];
+        assert!(script.run_with_data(data, None).is_err());
+
+        let io_info = script.gen_io_info().unwrap();
+        assert!(io_info.get_script_type().is_some());
+        assert_eq!(io_info.get_inputs().len(), 2);
+        assert_eq!(
+            io_info.get_outputs(),
+            &vec![
+                OutputInfo::Plot(PlotInfo {
+                    title: Some(String::from("up")),
+                    color: Some(String::from("#FF5252")),
+                    linewidth: Some(28),
+                    style: None,
+                    opacity: None,
+                    trackprice: None,
+                    histbase: None,
+                    offset: None,
+                    join: None,
+                    editable: None,
+                    show_last: None,
+                    display: None,
+                }),
+                OutputInfo::HLine(HLineInfo {
+                    price: Some(60f64),
+                    title: Some(String::from("mid")),
+                    color: None,
+                    linestyle: None,
+                    linewidth: None,
+                    editable: None,
+                    display: None,
+                })
+            ]
+        );
+        assert_eq!(
+            io_info.get_input_srcs(),
+            &vec![
+                InputSrc::new(
+                    None,
+                    vec![
+                        String::from("close"),
+                        String::from("open"),
+                        String::from("high")
+                    ]
+                ),
+                InputSrc::new(
+                    Some(String::from("AAPL-D")),
+                    vec![String::from("high"), String::from("time")]
+                )
+            ]
+        );
+
+        // The plot in the function body is found for every call of the function.
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script
+            .parse_src(String::from(
+                "f(x) =>\n    plot(x)\n    1\nm = f(close) + f(open)",
+            ))
+            .unwrap();
+        let io_info = script.new_runner().unwrap().extract_io_info().unwrap();
+        assert_eq!(io_info.get_outputs().len(), 2);
+    }
+
+    #[test]
+    fn conditional_io_test() {
+        // The io calls in all the branches are found in the source order.
+        for (src, input_count, output_count) in &[
+            (
+                "if close > open\n    a = input(3, 'a')\n    plot(close)\nelse\n    plot(open)",
+                1,
+                2,
+            ),
+            ("if close > open\n    plot(close)", 0, 1),
+            (
+                "m = close > open ? input(1, 'a') : input(2, 'b')\nplot(m)",
+                2,
+                1,
+            ),
+            (
+                "m = close > open or input(true, 'b')\nplot(m ? 1 : 0)",
+                1,
+                1,
+            ),
+            ("for i = 1 to 2\n    plot(close)", 0, 1),
+            ("m = input(true, 'b') ? 1 : 2\nplot(m)", 1, 1),
+        ] {
+            let mut script = PineScript::new(Some(&NoneCallback()));
+            script.parse_src(String::from(*src)).unwrap();
+            let io_info = script.new_runner().unwrap().extract_io_info().unwrap();
+            assert_eq!(io_info.get_inputs().len(), *input_count);
+            assert_eq!(io_info.get_outputs().len(), *output_count);
+        }
+
+        // The inputs keep the slots of their call sites whichever branch runs.
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script
+            .parse_src(String::from(
+                "m = 0\nif close > open\n    m := input(1, 'a')\n\
+                 else\n    m := input(2, 'b')\nplot(m)",
+            ))
+            .unwrap();
+        let io_info = script.gen_io_info().unwrap();
+        let titles: Vec<_> = io_info
+            .get_inputs()
+            .iter()
+            .map(|input| match input {
+                InputInfo::Int(info) => info.title.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            titles,
+            vec![Some(String::from("a")), Some(String::from("b"))]
+        );
+
+        script
+            .change_inputs(vec![Some(InputVal::Int(10)), Some(InputVal::Int(20))])
+            .unwrap();
+        let data = vec![
+            (
+                "close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(4f64)]),
+            ),
+            (
+                "open",
+                AnySeries::from_float_vec(vec![Some(2f64), Some(1f64), Some(5f64)]),
+            ),
+        ];
+        let output = script.run_with_data(data, None).unwrap();
+        assert_eq!(
+            output.data_list[0].as_ref().unwrap().series,
+            vec![vec![Some(20f64), Some(10f64), Some(20f64)]]
+        );
+
+        // The input in the function body has a slot for every call of the function.
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        script
+            .parse_src(String::from(
+                "f(x) =>\n    input(1, 'a') + x\nm = f(1) + f(2)\nplot(m)",
+            ))
+            .unwrap();
+        assert_eq!(script.gen_io_info().unwrap().get_inputs().len(), 2);
+        script
+            .change_inputs(vec![Some(InputVal::Int(10)), Some(InputVal::Int(20))])
+            .unwrap();
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+        let output = script.run_with_data(data, None).unwrap();
+        assert_eq!(
+            output.data_list[0].as_ref().unwrap().series,
+            vec![vec![Some(33f64)]]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use super::context::{Ctx, PineRuntimeError, RVRunner, StmtRunner};
+use crate::ast::stat_expr_types::TypeDef;
+use crate::ast::syntax_type::FunctionType;
+use crate::types::{
+    convert_field, Callable, CallableObject, PineClass, PineFrom, PineRef, RuntimeErr, Series,
+    SeriesCall, UserObject, UserObjectItem, NA,
+};
+use std::mem;
+use std::rc::Rc;
+
+// The class of the user-defined type that provides the constructor like `Pivot.new()`.
+struct TypeClass<'a> {
+    def: &'a TypeDef<'a>,
+    // The default values of the fields evaluated when the type is declared.
+    defaults: Vec<Option<PineRef<'a>>>,
+}
+
+impl<'a> PineClass<'a> for TypeClass<'a> {
+    fn custom_type(&self) -> &str {
+        self.def.name.value
+    }
+
+    fn get(&self, _context: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "new" => Ok(PineRef::new(Callable::new(
+                None,
+                Some(Box::new(NewObject {
+                    def: self.def,
+                    defaults: self.defaults.clone(),
+                })),
+            ))),
+            _ => Err(RuntimeErr::NotSupportOperator),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(TypeClass {
+            def: self.def,
+            defaults: self.defaults.clone(),
+        })
+    }
+}
+
+// Create the new object, the missing arguments are filled by the default values of the fields.
+#[derive(Debug, Clone)]
+struct NewObject<'a> {
+    def: &'a TypeDef<'a>,
+    defaults: Vec<Option<PineRef<'a>>>,
+}
+
+impl<'a> SeriesCall<'a> for NewObject<'a> {
+    fn step(
+        &mut self,
+        _context: &mut dyn Ctx<'a>,
+        p: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        let mut fields = vec![];
+        for ((arg, field), default) in p.into_iter().zip(&self.def.fields).zip(&self.defaults) {
+            let val = arg
+                .or_else(|| default.clone())
+                .unwrap_or_else(|| PineRef::new_box(NA));
+            fields.push(convert_field(&field.data_type, val)?);
+        }
+        let obj: UserObjectItem = Some(Rc::new(UserObject::new(self.def, fields)));
+        Ok(PineRef::new(Series::from(obj)))
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+// Convert the value to the object reference for the declaration like `Pivot p = na`.
+#[derive(Debug, Clone)]
+struct ObjectFromVal;
+
+impl<'a> SeriesCall<'a> for ObjectFromVal {
+    fn step(
+        &mut self,
+        _context: &mut dyn Ctx<'a>,
+        mut p: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        let obj = match mem::take(&mut p[0]) {
+            Some(val) => UserObjectItem::implicity_from(val)?.into_inner(),
+            None => None,
+        };
+        Ok(PineRef::new(Series::from(obj)))
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+impl<'a> StmtRunner<'a> for TypeDef<'a> {
+    fn st_run(&'a self, context: &mut dyn Ctx<'a>) -> Result<(), PineRuntimeError> {
+        let mut defaults = vec![];
+        for field in self.fields.iter() {
+            defaults.push(match field.default {
+                Some(ref exp) => Some(exp.rv_run(context)?),
+                None => None,
+            });
+        }
+        let class = TypeClass {
+            def: self,
+            defaults,
+        };
+        let value = CallableObject::new(Box::new(class), || {
+            Callable::new(None, Some(Box::new(ObjectFromVal)))
+        });
+        context.create_var(self.name_varid, PineRef::new_rc(value));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::ast::syntax_type::SyntaxType;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::types::{PineRef, Series};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn user_type_test() {
+        let lib_info = LibInfo::new(vec![], vec![("close", SyntaxType::float_series())]);
+        let src = "type Pivot\n    float price = 1.0\n    int bar\n    Pivot prev\n\
+                   method double(Pivot this) => this.price * 2\n\
+                   p = Pivot.new(bar = 3)\n\
+                   p.price := p.price + close\n\
+                   q = Pivot.new(close, prev = p)\n\
+                   Pivot r = na\n\
+                   m1 = p.price\nm2 = p[1].price\nm3 = q.double()\nm4 = q.prev.bar\nm5 = r.price";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        let expected = [
+            vec![Some(2f64), Some(3f64)],
+            vec![None, Some(2f64)],
+            vec![Some(2f64), Some(4f64)],
+        ];
+        for (i, vals) in expected.iter().enumerate() {
+            assert_eq!(
+                runner
+                    .get_context()
+                    .move_var(VarIndex::new(i as i32 + 5, 0)),
+                Some(PineRef::new(Series::from_vec(vals.clone())))
+            );
+        }
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(8, 0)),
+            Some(PineRef::new(Series::from_vec(vec![Some(3i64), Some(3i64)])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(9, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                None,
+                None as Option<f64>
+            ])))
+        );
+
+        // The fields and the values must match the declared types.
+        for src in &[
+            "type P\n    float x\nm = P.new(1).y",
+            "type P\n    float x\np = P.new(1)\np.y := 1",
+            "type P\n    int x = 1.5",
+            "type P\n    Q x",
+            "type P\n    float x\np = P.new(1)\np.x := \"a\"",
+            "f() =>\n    type P\n        float x\n    1\nm = f()",
+        ] {
+            assert!(PineParser::new(src, &lib_info).parse_blk().is_err());
+        }
+    }
+
+    #[test]
+    fn user_type_rollback_test() {
+        let lib_info = LibInfo::new(vec![], vec![("close", SyntaxType::float_series())]);
+        let src = "type Counter\n    int count = 0\n\
+                   Counter c = na\n\
+                   c := close == 1 ? Counter.new() : c[1]\n\
+                   c.count := c.count + 1\n\
+                   m = c.count";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let close = |v: Vec<f64>| {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(v.into_iter().map(Some).collect()),
+            )]
+        };
+        runner.run(&close(vec![1f64, 2f64]), None).unwrap();
+
+        // The field writes of the updated bar must be undone before the bar is run again.
+        runner.update(&close(vec![2f64])).unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(2, 0)),
+            &Some(PineRef::new(Series::from_vec(vec![Some(1i64), Some(2i64)])))
+        );
+        runner.update(&close(vec![2f64, 2f64])).unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(2, 0)),
+            &Some(PineRef::new(Series::from_vec(vec![
+                Some(1i64),
+                Some(2i64),
+                Some(3i64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn user_type_var_test() {
+        let lib_info = LibInfo::new(vec![], vec![("close", SyntaxType::float_series())]);
+        let src = "type Pivot\n    float price = 0\n\
+                   var p = Pivot.new(close)\n\
+                   p.price := p.price + 1\n\
+                   m = p.price";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let close = |v: Vec<f64>| {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(v.into_iter().map(Some).collect()),
+            )]
+        };
+
+        // The object created on the first bar is kept and mutated by the following bars.
+        runner.run(&close(vec![1f64, 5f64, 9f64]), None).unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(2, 0)),
+            &Some(PineRef::new(Series::from_vec(vec![
+                Some(2f64),
+                Some(3f64),
+                Some(4f64)
+            ])))
+        );
+        runner.update(&close(vec![9f64, 9f64])).unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(2, 0)),
+            &Some(PineRef::new(Series::from_vec(vec![
+                Some(2f64),
+                Some(3f64),
+                Some(4f64),
+                Some(5f64)
+            ])))
+        );
+    }
+}
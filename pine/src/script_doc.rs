@@ -0,0 +1,340 @@
+// Extract the documentation of the user scripts from the annotations like
+//
+// //@description The moving averages.
+// library("Averages")
+//
+// //@function The simple moving average of the source.
+// //@param source The series of values to process.
+// //@param length The number of bars.
+// //@returns The moving average.
+// export avg(float source, int length = 14) => ...
+//
+// The annotations of the function are the comment lines right above the definition,
+// the line without the tag continues the previous annotation.
+use crate::ast::input::{Position, StrRange};
+use crate::ast::stat_expr_types::{
+    Block, DataType, Exp, FunctionCall, FunctionDef, Statement, TypeQualifier,
+};
+use crate::library::LIBRARY_NAME;
+use crate::parse_ast;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParamDoc {
+    pub name: String,
+    // The declaration of the parameter like `simple int length = 14`.
+    pub signature: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDoc {
+    pub name: String,
+    // The declaration of the function like `avg(float source, int length = 14)`.
+    pub signature: String,
+    pub description: String,
+    pub params: Vec<ParamDoc>,
+    pub returns: String,
+    // Whether the function is exported by the library.
+    pub export: bool,
+    pub range: StrRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScriptDoc {
+    // The title of the library if the script is a library.
+    pub library: Option<String>,
+    pub description: String,
+    pub functions: Vec<FunctionDoc>,
+}
+
+impl ScriptDoc {
+    pub fn find_function(&self, name: &str) -> Option<&FunctionDoc> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Annotations {
+    function: String,
+    description: String,
+    params: Vec<(String, String)>,
+    returns: String,
+}
+
+// Parse the annotation lines, the text of each line is the text after `//`.
+fn parse_annotations<'a>(lines: impl Iterator<Item = &'a str>) -> Annotations {
+    let mut annotations = Annotations::default();
+    let mut last: Option<&mut String> = None;
+    for line in lines {
+        let line = line.trim();
+        let (tag, text) = match line.strip_prefix('@') {
+            Some(tagged) => match tagged.find(char::is_whitespace) {
+                Some(i) => (Some(&tagged[..i]), tagged[i..].trim()),
+                None => (Some(tagged), ""),
+            },
+            None => (None, line),
+        };
+        last = match tag {
+            Some("function") => Some(&mut annotations.function),
+            Some("description") => Some(&mut annotations.description),
+            Some("returns") => Some(&mut annotations.returns),
+            Some("param") => {
+                let (name, desc) = match text.find(char::is_whitespace) {
+                    Some(i) => (&text[..i], text[i..].trim()),
+                    None => (text, ""),
+                };
+                annotations
+                    .params
+                    .push((String::from(name), String::from(desc)));
+                last = annotations.params.last_mut().map(|p| &mut p.1);
+                continue;
+            }
+            // The unknown tags like `@version` end the previous annotation.
+            Some(_) => None,
+            None => last,
+        };
+        if let Some(s) = last.as_mut() {
+            if !s.is_empty() && !text.is_empty() {
+                s.push('\n');
+            }
+            s.push_str(text);
+        }
+    }
+    annotations
+}
+
+// The comment lines right above the line, the text after `//` of each line.
+fn comments_above(lines: &[&str], line: usize) -> Vec<String> {
+    let mut comments: Vec<String> = lines[..line.min(lines.len())]
+        .iter()
+        .rev()
+        .map(|l| l.trim())
+        .take_while(|l| l.starts_with("//"))
+        .map(|l| String::from(&l[2..]))
+        .collect();
+    comments.reverse();
+    comments
+}
+
+fn byte_offset(lines: &[&str], pos: Position) -> Option<usize> {
+    let line = pos.get_line() as usize;
+    let mut offset: usize = lines[..line.min(lines.len())]
+        .iter()
+        .map(|l| l.len() + 1)
+        .sum();
+    let text = lines.get(line)?;
+    offset += text
+        .char_indices()
+        .nth(pos.get_character() as usize)
+        .map_or(text.len(), |(i, _)| i);
+    Some(offset)
+}
+
+fn source_text<'s>(src: &'s str, lines: &[&str], range: StrRange) -> &'s str {
+    match (
+        byte_offset(lines, range.start),
+        byte_offset(lines, range.end),
+    ) {
+        (Some(start), Some(end)) if start <= end && end <= src.len() => &src[start..end],
+        _ => "",
+    }
+}
+
+fn type_name<'a>(data_type: &DataType<'a>) -> &'a str {
+    match data_type {
+        DataType::Float => "float",
+        DataType::Int => "int",
+        DataType::Bool => "bool",
+        DataType::Color => "color",
+        DataType::String => "string",
+        DataType::Custom(name) => name,
+    }
+}
+
+fn param_signatures(src: &str, lines: &[&str], def: &FunctionDef) -> Vec<(String, String)> {
+    def.params
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let mut words = vec![];
+            if let Some(spec) = def.param_specs.get(i) {
+                match spec.qualifier {
+                    Some(TypeQualifier::Simple) => words.push("simple"),
+                    Some(TypeQualifier::Series) => words.push("series"),
+                    None => {}
+                }
+                if let Some(data_type) = spec.data_type.as_ref() {
+                    words.push(type_name(data_type));
+                }
+            }
+            words.push(name.value);
+            let mut signature = words.join(" ");
+            if let Some(default) = def.param_specs.get(i).and_then(|s| s.default.as_ref()) {
+                signature = format!(
+                    "{} = {}",
+                    signature,
+                    source_text(src, lines, default.range())
+                );
+            }
+            (String::from(name.value), signature)
+        })
+        .collect()
+}
+
+fn gen_function_doc(src: &str, lines: &[&str], def: &FunctionDef) -> FunctionDoc {
+    let comments = comments_above(lines, def.range.start.get_line() as usize);
+    let mut annotations = parse_annotations(comments.iter().map(|s| s.as_str()));
+    let params = param_signatures(src, lines, def);
+    let signature = format!(
+        "{}({})",
+        def.name.value,
+        params
+            .iter()
+            .map(|p| p.1.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    // The params are listed in the order of the definition.
+    let params = params
+        .into_iter()
+        .map(|(name, signature)| {
+            let description = match annotations.params.iter().position(|p| p.0 == name) {
+                Some(i) => annotations.params.remove(i).1,
+                None => String::new(),
+            };
+            ParamDoc {
+                name,
+                signature,
+                description,
+            }
+        })
+        .collect();
+    let description = if annotations.function.is_empty() {
+        annotations.description
+    } else {
+        annotations.function
+    };
+    FunctionDoc {
+        name: String::from(def.name.value),
+        signature,
+        description,
+        params,
+        returns: annotations.returns,
+        export: def.export,
+        range: def.range,
+    }
+}
+
+fn library_title(func_call: &FunctionCall) -> Option<String> {
+    match &func_call.method {
+        Exp::VarName(name) if name.name.value == LIBRARY_NAME => {
+            let title = func_call.pos_args.first().or_else(|| {
+                func_call
+                    .dict_args
+                    .iter()
+                    .find(|(n, _)| n.value == "title")
+                    .map(|(_, e)| e)
+            });
+            match title {
+                Some(Exp::Str(s)) => Some(s.value.clone()),
+                _ => Some(String::new()),
+            }
+        }
+        _ => None,
+    }
+}
+
+// Generate the documentation of the top-level functions of the block.
+pub fn gen_script_doc(src: &str, blk: &Block) -> ScriptDoc {
+    let lines: Vec<&str> = src.split('\n').map(|l| l.trim_end_matches('\r')).collect();
+    let mut doc = ScriptDoc::default();
+    let mut library_line = None;
+    for stmt in blk.stmts.iter() {
+        match stmt {
+            Statement::FuncDef(def) => doc.functions.push(gen_function_doc(src, &lines, def)),
+            Statement::FuncCall(func_call) | Statement::Exp(Exp::FuncCall(func_call)) => {
+                if let Some(title) = library_title(func_call) {
+                    doc.library = Some(title);
+                    library_line = Some(func_call.range.start.get_line() as usize);
+                }
+            }
+            _ => {}
+        }
+    }
+    // The description of the script is above the library declaration or at the beginning.
+    let line = library_line.unwrap_or_else(|| {
+        lines
+            .iter()
+            .take_while(|l| l.trim().starts_with("//"))
+            .count()
+    });
+    let comments = comments_above(&lines, line);
+    doc.description = parse_annotations(comments.iter().map(|s| s.as_str())).description;
+    doc
+}
+
+// Parse the script and generate the documentation, the functions that are parsed before
+// the syntax error are also documented.
+pub fn parse_script_doc(src: &str) -> ScriptDoc {
+    match parse_ast(src) {
+        Ok(blk) | Err((Some(blk), _)) => gen_script_doc(src, &blk),
+        Err((None, _)) => ScriptDoc::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_doc_test() {
+        let src = "//@version=5
+//@description The moving averages
+// of the sources.
+library(\"Averages\")
+
+//@function The simple moving average
+// of the source.
+//@param source The series of values.
+//@param length The number of bars.
+//@returns The moving average.
+export avg(float source, simple int length = 14) =>
+    math.sum(source, length) / length
+
+// The helper function.
+helper(x) => x * 2
+";
+        let doc = parse_script_doc(src);
+        assert_eq!(doc.library, Some(String::from("Averages")));
+        assert_eq!(doc.description, "The moving averages\nof the sources.");
+        assert_eq!(doc.functions.len(), 2);
+
+        let avg = doc.find_function("avg").unwrap();
+        assert_eq!(avg.signature, "avg(float source, simple int length = 14)");
+        assert_eq!(avg.description, "The simple moving average\nof the source.");
+        assert_eq!(
+            avg.params,
+            vec![
+                ParamDoc {
+                    name: String::from("source"),
+                    signature: String::from("float source"),
+                    description: String::from("The series of values."),
+                },
+                ParamDoc {
+                    name: String::from("length"),
+                    signature: String::from("simple int length = 14"),
+                    description: String::from("The number of bars."),
+                },
+            ]
+        );
+        assert_eq!(avg.returns, "The moving average.");
+        assert!(avg.export);
+
+        // The comments without tags are not annotations.
+        let helper = doc.find_function("helper").unwrap();
+        assert_eq!(helper.signature, "helper(x)");
+        assert_eq!(helper.description, "");
+        assert_eq!(helper.params[0].description, "");
+        assert!(!helper.export);
+    }
+}
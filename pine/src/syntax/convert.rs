@@ -1,5 +1,5 @@
 use super::{SimpleSyntaxType, SyntaxType};
-use std::rc::Rc;
+use std::sync::Arc;
 
 // Series variable can only be implicity converted to Series, Simple can be implicity converted to Simple and Series
 pub fn implicity_convert<'a>(origin_type: &SyntaxType<'a>, dest_type: &SyntaxType<'a>) -> bool {
@@ -13,10 +13,13 @@ pub fn implicity_convert<'a>(origin_type: &SyntaxType<'a>, dest_type: &SyntaxTyp
         SyntaxType::Simple(SimpleSyntaxType::Na) => match dest_type {
             SyntaxType::Simple(_) => true,
             SyntaxType::Series(_) => true,
+            // The na object reference.
+            SyntaxType::ObjectClass(_) => true,
             _ => false,
         },
         SyntaxType::Series(SimpleSyntaxType::Na) => match dest_type {
             SyntaxType::Series(_) => true,
+            SyntaxType::ObjectClass(_) => true,
             _ => false,
         },
 
@@ -183,7 +186,7 @@ pub fn common_type<'a>(type1: &SyntaxType<'a>, type2: &SyntaxType<'a>) -> Option
                     None
                 } else {
                     let res: Vec<_> = res_tuple.into_iter().map(|d| d.unwrap()).collect();
-                    Some(SyntaxType::Tuple(Rc::new(res)))
+                    Some(SyntaxType::Tuple(Arc::new(res)))
                 }
             }
         }
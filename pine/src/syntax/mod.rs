@@ -5,16 +5,17 @@ use crate::ast::num::Numeral;
 use crate::ast::op::{BinaryOp, UnaryOp};
 use crate::ast::stat_expr_types::{
     Assignment, BinaryExp, Block, Condition, DataType, Exp, ForRange, FunctionCall, FunctionDef,
-    IfThenElse, PrefixExp, RVVarName, RefCall, Statement, TupleNode, TypeCast, UnaryExp,
-    VarAssignment, VarIndex,
+    FunctionParam, IfThenElse, ImportStmt, NaNode, PrefixExp, RVVarName, RefCall, Statement,
+    TupleNode, TypeCast, TypeDef, TypeQualifier, UnaryExp, VarAssignment, VarIndex,
 };
 use crate::ast::state::PineInputError;
-use crate::ast::syntax_type::{FunctionTypes, SimpleSyntaxType, SyntaxType};
-use std::collections::HashMap;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::From;
 use std::mem;
 use std::ptr::NonNull;
 use std::rc::Rc;
+use std::sync::Arc;
 
 mod convert;
 pub mod ctxid_parser;
@@ -294,6 +295,9 @@ impl<'a> SyntaxContext<'a> {
     }
 }
 
+// The field name to the declared data type and the syntax type of the field.
+type UserTypeFields<'a> = BTreeMap<&'a str, (DataType<'a>, SyntaxType<'a>)>;
+
 pub struct SyntaxParser<'a> {
     _lib_ctx: Box<SyntaxContext<'a>>,
     _root_ctx: Box<SyntaxContext<'a>>,
@@ -304,6 +308,12 @@ pub struct SyntaxParser<'a> {
     func_defs: Vec<FunctionDef<'a>>,
     // user defined function name to function definition map
     user_funcs: HashMap<&'a str, *mut FunctionDef<'a>>,
+    // The alias of imported library to the exported function names and linked names.
+    imports: HashMap<&'a str, Vec<(&'a str, &'a str)>>,
+    // The user-defined type name to the types of the fields.
+    user_types: HashMap<&'a str, Rc<UserTypeFields<'a>>>,
+    // The names of the user-defined methods that can be called like `obj.method()`.
+    methods: HashSet<&'a str>,
     // The types id generator that generate same id for the same types.
     types_id_gen: TypesIdGen<'a>,
     errors: Vec<PineInputError>,
@@ -348,6 +358,9 @@ impl<'a> SyntaxParser<'a> {
             name_rel_parser,
             func_defs: vec![],
             user_funcs: HashMap::new(),
+            imports: HashMap::new(),
+            user_types: HashMap::new(),
+            methods: HashSet::new(),
             types_id_gen: TypesIdGen::new(),
             errors: vec![],
         }
@@ -373,6 +386,9 @@ impl<'a> SyntaxParser<'a> {
             name_rel_parser,
             func_defs: vec![],
             user_funcs: HashMap::new(),
+            imports: HashMap::new(),
+            user_types: HashMap::new(),
+            methods: HashSet::new(),
             types_id_gen: TypesIdGen::new(),
             errors: vec![],
         }
@@ -399,6 +415,9 @@ impl<'a> SyntaxParser<'a> {
             name_rel_parser,
             func_defs: vec![],
             user_funcs: HashMap::new(),
+            imports: HashMap::new(),
+            user_types: HashMap::new(),
+            methods: HashSet::new(),
             types_id_gen: TypesIdGen::new(),
             errors: vec![],
         }
@@ -411,6 +430,23 @@ impl<'a> SyntaxParser<'a> {
         downcast_ctx(self.context).get_inputnames()
     }
 
+    // Get the index of the variable declared in the global scope of the script.
+    pub fn get_global_var_index(&self, name: &str) -> Option<VarIndex> {
+        self._root_ctx
+            .var_indexs
+            .get(name)
+            .map(|i| VarIndex::new(*i, 0))
+    }
+
+    // Get the indexes of all the variables declared in the global scope of the script.
+    pub fn get_global_var_indexes(&self) -> HashMap<String, VarIndex> {
+        self._root_ctx
+            .var_indexs
+            .iter()
+            .map(|(name, i)| (name.clone(), VarIndex::new(*i, 0)))
+            .collect()
+    }
+
     pub fn get_context(&mut self) -> *mut (dyn SyntaxCtx<'a> + 'a) {
         self.context
     }
@@ -470,7 +506,7 @@ impl<'a> SyntaxParser<'a> {
     fn parse_std_func_call(
         &mut self,
         func_call: &mut FunctionCall<'a>,
-        fun_type: &Rc<FunctionTypes<'a>>,
+        fun_type: &Arc<FunctionTypes<'a>>,
     ) -> ParseResult<'a> {
         let mut pos_arg_type = vec![];
         for arg in func_call.pos_args.iter_mut() {
@@ -573,55 +609,183 @@ impl<'a> SyntaxParser<'a> {
         parent_context.subctxs.push(sub_ctx);
         parent_context.declare_user_func(
             func_name,
-            SyntaxType::UserFunction(Rc::new((names.clone(), parse_res.syntax_type.clone()))),
+            SyntaxType::UserFunction(Arc::new((names.clone(), parse_res.syntax_type.clone()))),
             spec_index,
         );
         func_call.spec_index = spec_index;
         Ok(ParseValue::new_with_type(parse_res.syntax_type))
     }
 
+    // Cast the argument to the declared type of the parameter, e.g. the int argument is
+    // converted to float for the parameter `float x`.
+    fn cast_user_func_arg(
+        &mut self,
+        arg: &mut Exp<'a>,
+        arg_type: SyntaxType<'a>,
+        spec: &FunctionParam<'a>,
+    ) -> Result<SyntaxType<'a>, PineInputError> {
+        let range = arg.range();
+        if let (Some(TypeQualifier::Simple), SyntaxType::Series(_)) =
+            (&spec.qualifier, arg_type.get_v_for_vf())
+        {
+            return Err(PineInputError::new(
+                PineErrorKind::FuncCallSignatureNotMatch,
+                range,
+            ));
+        }
+        let data_type = match &spec.data_type {
+            None => return Ok(arg_type),
+            Some(data_type) => data_type,
+        };
+        let (is_cast_err, result) = implicity_type_cast(&arg_type, data_type);
+        if is_cast_err {
+            return Err(PineInputError::new(
+                PineErrorKind::FuncCallSignatureNotMatch,
+                range,
+            ));
+        }
+        if &result == arg_type.get_v_for_vf() {
+            return Ok(arg_type);
+        }
+        let exp = mem::replace(arg, Exp::Na(NaNode::new(range)));
+        *arg = Exp::TypeCast(Box::new(TypeCast::new(data_type.clone(), exp, range)));
+        Ok(result)
+    }
+
     fn parse_user_func_call(
         &mut self,
         func_call: &mut FunctionCall<'a>,
         names: &Vec<&'a str>,
         method_name: &'a str,
     ) -> ParseResult<'a> {
-        if func_call.dict_args.len() > 0 {
-            Err(PineInputError::new(
-                PineErrorKind::ForbiddenDictArgsForUserFunc,
-                func_call.range,
-            ))
-        } else if func_call.pos_args.len() != names.len() {
-            Err(PineInputError::new(
+        if func_call.pos_args.len() > names.len() {
+            return Err(PineInputError::new(
                 PineErrorKind::FuncCallSignatureNotMatch,
                 func_call.range,
-            ))
-        } else {
-            let mut pos_arg_type = vec![];
-            for arg in func_call.pos_args.iter_mut() {
-                pos_arg_type.push(self.parse_exp(arg)?.syntax_type);
+            ));
+        }
+        let param_specs = unsafe { self.user_funcs[method_name].as_ref().unwrap() }
+            .param_specs
+            .clone();
+
+        // Move the named arguments to the positions of the parameters and fill the missing
+        // arguments with the default values.
+        let mut args: Vec<Option<Exp<'a>>> = mem::take(&mut func_call.pos_args)
+            .into_iter()
+            .map(Some)
+            .collect();
+        args.resize(names.len(), None);
+        for (name, exp) in mem::take(&mut func_call.dict_args) {
+            match names.iter().position(|n| *n == name.value) {
+                Some(i) if args[i].is_none() => args[i] = Some(exp),
+                _ => {
+                    return Err(PineInputError::new(
+                        PineErrorKind::FuncCallSignatureNotMatch,
+                        name.range,
+                    ))
+                }
             }
-            let typeid = self.types_id_gen.get(&pos_arg_type);
-            // Generate name by the argument types.
-            let fun_name = format!("{}@{}", method_name, typeid);
+        }
+        for (i, spec) in param_specs.iter().enumerate() {
+            if args[i].is_none() {
+                match &spec.default {
+                    Some(default) => args[i] = Some(default.clone()),
+                    None => {
+                        return Err(PineInputError::new(
+                            PineErrorKind::FuncCallSignatureNotMatch,
+                            func_call.range,
+                        ))
+                    }
+                }
+            }
+        }
+        func_call.pos_args = args.into_iter().map(Option::unwrap).collect();
 
-            let context = downcast_ctx(self.context);
-            func_call.ctxid = context.gen_child_ctx_index();
+        let mut pos_arg_type = vec![];
+        for (arg, spec) in func_call.pos_args.iter_mut().zip(param_specs.iter()) {
+            let arg_type = self.parse_exp(arg)?.syntax_type;
+            pos_arg_type.push(self.cast_user_func_arg(arg, arg_type, spec)?);
+        }
+        let typeid = self.types_id_gen.get(&pos_arg_type);
+        // Generate name by the argument types.
+        let fun_name = format!("{}@{}", method_name, typeid);
 
-            match context.get_user_func(&fun_name) {
-                Some((SyntaxType::UserFunction(func_type), index)) => {
-                    func_call.spec_index = *index;
-                    Ok(ParseValue::new_with_type(func_type.1.clone()))
+        let context = downcast_ctx(self.context);
+        func_call.ctxid = context.gen_child_ctx_index();
+
+        match context.get_user_func(&fun_name) {
+            Some((SyntaxType::UserFunction(func_type), index)) => {
+                func_call.spec_index = *index;
+                Ok(ParseValue::new_with_type(func_type.1.clone()))
+            }
+            None => self.gen_new_func_def(func_call, names, pos_arg_type, method_name, fun_name),
+            Some(_) => unreachable!(),
+        }
+    }
+
+    // Replace the method like `alias.func` by the linked function name of the imported library.
+    fn link_import_method(
+        &mut self,
+        func_call: &mut FunctionCall<'a>,
+    ) -> Result<(), PineInputError> {
+        let linked_name = match &func_call.method {
+            Exp::PrefixExp(prefix) => match &prefix.left_exp {
+                Exp::VarName(alias) if self.imports.contains_key(alias.name.value) => {
+                    let exports = &self.imports[alias.name.value];
+                    match exports.iter().find(|e| e.0 == prefix.right_name.value) {
+                        Some(e) => (e.1, prefix.range),
+                        None => {
+                            return Err(PineInputError::new(
+                                PineErrorKind::LibFuncNotExported,
+                                prefix.range,
+                            ))
+                        }
+                    }
                 }
-                None => {
-                    self.gen_new_func_def(func_call, names, pos_arg_type, method_name, fun_name)
+                _ => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        func_call.method = Exp::VarName(RVVarName::new_with_str(linked_name.0, linked_name.1));
+        Ok(())
+    }
+
+    // Whether the call like `p.describe()` is the call of the user-defined method.
+    fn is_method_call(&self, func_call: &FunctionCall<'a>) -> bool {
+        match &func_call.method {
+            Exp::PrefixExp(prefix) if self.methods.contains(prefix.right_name.value) => {
+                match &prefix.left_exp {
+                    // The namespace like `math.max` and the library alias are not objects.
+                    Exp::VarName(name) => match downcast_ctx(self.context).get_var(name.name.value)
+                    {
+                        Some(SyntaxType::Object(_))
+                        | Some(SyntaxType::ObjectFunction(_, _))
+                        | Some(SyntaxType::ValObjectFunction(_, _, _)) => false,
+                        _ => !self.imports.contains_key(name.name.value),
+                    },
+                    _ => true,
                 }
-                Some(_) => unreachable!(),
             }
+            _ => false,
+        }
+    }
+
+    // Rewrite the method call like `p.describe(1)` to the function call `describe(p, 1)`.
+    fn link_method_call(&mut self, func_call: &mut FunctionCall<'a>) {
+        if !self.is_method_call(func_call) {
+            return;
+        }
+        let method = mem::replace(&mut func_call.method, Exp::Na(NaNode::new(func_call.range)));
+        if let Exp::PrefixExp(prefix) = method {
+            let prefix = *prefix;
+            func_call.method = Exp::VarName(RVVarName::new(prefix.right_name));
+            func_call.pos_args.insert(0, prefix.left_exp);
         }
     }
 
     fn parse_func_call(&mut self, func_call: &mut FunctionCall<'a>) -> ParseResult<'a> {
+        self.link_import_method(func_call)?;
+        self.link_method_call(func_call);
         let method_type = self.parse_exp(&mut func_call.method)?;
         match method_type.syntax_type {
             SyntaxType::Function(fun_type) => self.parse_std_func_call(func_call, &fun_type),
@@ -650,7 +814,7 @@ impl<'a> SyntaxParser<'a> {
         for arg in tuple.exps.iter_mut() {
             tuple_type.push(self.parse_exp(arg)?.syntax_type);
         }
-        Ok(ParseValue::new_with_type(SyntaxType::Tuple(Rc::new(
+        Ok(ParseValue::new_with_type(SyntaxType::Tuple(Arc::new(
             tuple_type,
         ))))
     }
@@ -735,11 +899,53 @@ impl<'a> SyntaxParser<'a> {
         }
     }
 
+    // Get the type of the field of the user-defined type.
+    fn get_field_type(
+        &self,
+        obj: &SyntaxType<'a>,
+        name: &VarName<'a>,
+    ) -> Result<(DataType<'a>, SyntaxType<'a>), PineInputError> {
+        let fields = match obj.get_v_for_vf() {
+            SyntaxType::ObjectClass(t) if self.user_types.contains_key(t) => &self.user_types[t],
+            _ => {
+                return Err(PineInputError::new(
+                    PineErrorKind::RefObjTypeNotObj,
+                    name.range,
+                ))
+            }
+        };
+        match fields.get(name.value) {
+            Some(field_type) => Ok(field_type.clone()),
+            None => Err(PineInputError::new(
+                PineErrorKind::RefKeyNotExist,
+                name.range,
+            )),
+        }
+    }
+
     fn parse_prefix(&mut self, prefix: &mut PrefixExp<'a>) -> ParseResult<'a> {
         let left_res = self.parse_exp(&mut prefix.left_exp)?;
         let name = prefix.right_name;
+        if let SyntaxType::ObjectClass(_) = left_res.syntax_type.get_v_for_vf() {
+            let (data_type, field_type) = self
+                .get_field_type(&left_res.syntax_type, &name)
+                .map_err(|e| PineInputError::new(e.code, prefix.range))?;
+            prefix.field_type = Some(data_type);
+            return Ok(ParseValue::new_with_type(field_type));
+        }
         match Self::get_for_obj(&left_res.syntax_type, &[name.value]) {
-            Ok(val_type) => Ok(ParseValue::new_with_type(val_type)),
+            Ok(val_type) => {
+                match val_type {
+                    SyntaxType::Val(_)
+                    | SyntaxType::ValFunction(_, _)
+                    | SyntaxType::ValObjectFunction(_, _, _) => {
+                        // The evaluate field like ta.tr also needs one index to cache.
+                        prefix.eval_id = downcast_ctx(self.context).gen_lib_func_index();
+                    }
+                    _ => {}
+                }
+                Ok(ParseValue::new_with_type(val_type))
+            }
             Err(code) => Err(PineInputError::new(code, prefix.range)),
         }
     }
@@ -994,6 +1200,12 @@ impl<'a> SyntaxParser<'a> {
         let (is_cast_err, result) = explicity_type_cast(&origin_type, &type_cast.data_type);
         match result {
             SyntaxType::ObjectClass(obj_cls) => {
+                if downcast_ctx(self.context).get_var(obj_cls).is_none() {
+                    return Err(PineInputError::new(
+                        PineErrorKind::VarNotDeclare,
+                        type_cast.range,
+                    ));
+                }
                 type_cast.cast_index = downcast_ctx(self.context).get_var_index(obj_cls);
                 type_cast.func_index = downcast_ctx(self.context).gen_lib_func_index();
             }
@@ -1168,6 +1380,13 @@ impl<'a> SyntaxParser<'a> {
             // If the assign need type cast, then we need record the cast index
             match result {
                 SyntaxType::ObjectClass(obj_cls) => {
+                    // The custom type must be declared before the variable.
+                    if downcast_ctx(self.context).get_var(obj_cls).is_none() {
+                        return Err(PineInputError::new(
+                            PineErrorKind::VarNotDeclare,
+                            assign.range,
+                        ));
+                    }
                     assign.cast_index = downcast_ctx(self.context).get_var_index(obj_cls);
                     assign.cast_func_index = downcast_ctx(self.context).gen_lib_func_index();
                 }
@@ -1217,7 +1436,7 @@ impl<'a> SyntaxParser<'a> {
                         })
                         .collect();
                     assign.varids = Some(varids);
-                    Ok(ParseValue::new_with_type(SyntaxType::Tuple(Rc::new(
+                    Ok(ParseValue::new_with_type(SyntaxType::Tuple(Arc::new(
                         ret_tuple,
                     ))))
                 }
@@ -1258,6 +1477,10 @@ impl<'a> SyntaxParser<'a> {
             )),
             Some(cur_type) => {
                 assign.var_index = downcast_ctx(self.context).get_var_index(assign.name.value);
+                if !assign.fields.is_empty() {
+                    let cur_type = cur_type.clone();
+                    return self.parse_field_assign(assign, cur_type);
+                }
                 let last_type = simple_to_series(cur_type.clone());
                 let val_res = self.parse_exp(&mut assign.val)?;
                 context.update_var(assign.name.value, last_type.clone());
@@ -1277,12 +1500,38 @@ impl<'a> SyntaxParser<'a> {
         }
     }
 
+    // Check the field assignment like `p.price := 1`, the type of the variable is not changed.
+    fn parse_field_assign(
+        &mut self,
+        assign: &mut VarAssignment<'a>,
+        var_type: SyntaxType<'a>,
+    ) -> ParseResult<'a> {
+        let mut field_type = var_type;
+        for field in assign.fields.iter() {
+            field_type = self.get_field_type(&field_type, field)?.1;
+        }
+        let val_res = self.parse_exp(&mut assign.val)?;
+        if !implicity_convert(&val_res.syntax_type, &field_type) {
+            self.catch(PineInputError::new(
+                PineErrorKind::InvalidTypeCast {
+                    origin: SimpleSyntaxType::from(val_res.syntax_type),
+                    cast: SimpleSyntaxType::from(field_type.clone()),
+                },
+                assign.range,
+            ));
+        }
+        Ok(ParseValue::new_with_type(field_type))
+    }
+
     fn parse_func_def(&mut self, func_def: &mut FunctionDef<'a>) -> ParseResult<'a> {
         let context = downcast_ctx(self.context);
         let name = func_def.name.value;
+        if func_def.method {
+            self.methods.insert(name);
+        }
         self.user_funcs.insert(name, func_def);
         let param_names: Vec<_> = func_def.params.iter().map(|v| v.value).collect();
-        let name_type = SyntaxType::UserFunction(Rc::new((param_names, SyntaxType::Any)));
+        let name_type = SyntaxType::UserFunction(Arc::new((param_names, SyntaxType::Any)));
         if context.contain_var_index_scope(name) {
             self.catch(PineInputError::new(
                 PineErrorKind::VarHasDeclare,
@@ -1296,6 +1545,108 @@ impl<'a> SyntaxParser<'a> {
         Ok(ParseValue::new_with_type(name_type))
     }
 
+    fn parse_import(&mut self, import: &mut ImportStmt<'a>) -> ParseResult<'a> {
+        if downcast_ctx(self.context).get_type() != ContextType::Main {
+            return Err(PineInputError::new(
+                PineErrorKind::ImportNotInGlobal,
+                import.range,
+            ));
+        }
+        let alias = import.alias.value;
+        if self.imports.contains_key(alias) {
+            self.catch(PineInputError::new(
+                PineErrorKind::VarHasDeclare,
+                import.alias.range,
+            ));
+        }
+        self.imports.insert(alias, import.exports.clone());
+        for def in import.defs.iter_mut() {
+            self.parse_func_def(def)?;
+        }
+        Ok(ParseValue::new_with_type(SyntaxType::Void))
+    }
+
+    // The type of the field value, the primitive fields are series because they can be changed.
+    fn get_decl_type(
+        &self,
+        data_type: &DataType<'a>,
+        range: StrRange,
+    ) -> Result<SyntaxType<'a>, PineInputError> {
+        match data_type {
+            DataType::Custom(t) => match downcast_ctx(self.context).get_var(t) {
+                Some(_) => Ok(SyntaxType::ObjectClass(t)),
+                None => Err(PineInputError::new(PineErrorKind::VarNotDeclare, range)),
+            },
+            t => Ok(SyntaxType::Series(SimpleSyntaxType::from(t.clone()))),
+        }
+    }
+
+    fn parse_type_def(&mut self, type_def: &mut TypeDef<'a>) -> ParseResult<'a> {
+        let context = downcast_ctx(self.context);
+        if context.get_type() != ContextType::Main {
+            return Err(PineInputError::new(
+                PineErrorKind::TypeNotInGlobal,
+                type_def.range,
+            ));
+        }
+        let name = type_def.name.value;
+        let obj_type = SyntaxType::ObjectClass(name);
+        if context.contain_var_index_scope(name) {
+            self.catch(PineInputError::new(
+                PineErrorKind::VarHasDeclare,
+                type_def.name.range,
+            ));
+            type_def.name_varid = context.get_var_index(name).varid;
+        } else {
+            type_def.name_varid = context.gen_var_index(name);
+        }
+        // Declare the type before the fields, so the field can refer to the type itself.
+        context.declare_var(name, obj_type.clone());
+        self.user_types.insert(name, Rc::new(BTreeMap::new()));
+
+        let mut fields = BTreeMap::new();
+        let mut params = vec![];
+        for field in type_def.fields.iter_mut() {
+            let field_type = self.get_decl_type(&field.data_type, field.range)?;
+            if let Some(default) = field.default.as_mut() {
+                let default_type = self.parse_exp(default)?.syntax_type;
+                if !implicity_convert(&default_type, &field_type) {
+                    self.catch(PineInputError::new(
+                        PineErrorKind::InvalidTypeCast {
+                            origin: SimpleSyntaxType::from(default_type),
+                            cast: SimpleSyntaxType::from(field_type.clone()),
+                        },
+                        field.range,
+                    ));
+                }
+            }
+            fields.insert(
+                field.name.value,
+                (field.data_type.clone(), field_type.clone()),
+            );
+            params.push((field.name.value, field_type));
+        }
+        self.user_types.insert(name, Rc::new(fields));
+
+        // The type is the constructor object like `Pivot.new()` and the function that
+        // converts na to the object reference.
+        let mut obj = BTreeMap::new();
+        obj.insert(
+            "new",
+            SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
+                params,
+                obj_type.clone(),
+            ))]))),
+        );
+        let cast_type = FunctionTypes(vec![FunctionType::new((
+            vec![("x", SyntaxType::Simple(SimpleSyntaxType::Na))],
+            obj_type,
+        ))]);
+        let name_type = SyntaxType::ObjectFunction(Arc::new(obj), Arc::new(cast_type));
+        downcast_ctx(self.context).declare_var(name, name_type);
+        Ok(ParseValue::new_with_type(SyntaxType::Void))
+    }
+
     fn parse_exp(&mut self, exp: &mut Exp<'a>) -> ParseResult<'a> {
         match exp {
             Exp::Na(_) => Ok(ParseValue::new_with_type(SyntaxType::Simple(
@@ -1358,7 +1709,11 @@ impl<'a> SyntaxParser<'a> {
             Statement::Assignment(assign) => self.parse_assign(assign),
             Statement::VarAssignment(assign) => self.parse_var_assign(assign),
             Statement::FuncDef(func_def) => self.parse_func_def(func_def),
-            Statement::None(_) => Ok(ParseValue::new_with_type(SyntaxType::Void)),
+            Statement::Import(import) => self.parse_import(import),
+            Statement::TypeDef(type_def) => self.parse_type_def(type_def),
+            Statement::None(_) | Statement::Error(_) => {
+                Ok(ParseValue::new_with_type(SyntaxType::Void))
+            }
             Statement::Exp(exp) => self.parse_exp(exp),
         }
     }
@@ -1464,7 +1819,7 @@ mod tests {
         assert_eq!(
             parser.parse_func_def(&mut func_def),
             Ok(ParseValue::new_with_type(SyntaxType::UserFunction(
-                Rc::new((vec!["hello", "hello2"], SyntaxType::Any))
+                Arc::new((vec!["hello", "hello2"], SyntaxType::Any))
             )))
         );
         assert_eq!(func_def.name_varid, 0);
@@ -1476,7 +1831,7 @@ mod tests {
         assert_eq!(
             parser2.parse_func_def(&mut func_def),
             Ok(ParseValue::new_with_type(SyntaxType::UserFunction(
-                Rc::new((vec!["hello", "hello2"], SyntaxType::Any))
+                Arc::new((vec!["hello", "hello2"], SyntaxType::Any))
             )))
         );
         assert_eq!(
@@ -1491,7 +1846,7 @@ mod tests {
         assert_eq!(
             parser2.parse_func_def(&mut func_def),
             Ok(ParseValue::new_with_type(SyntaxType::UserFunction(
-                Rc::new((vec!["hello", "hello2"], SyntaxType::Any))
+                Arc::new((vec!["hello", "hello2"], SyntaxType::Any))
             )))
         );
         assert_eq!(
@@ -1508,7 +1863,7 @@ mod tests {
         let mut parser = SyntaxParser::new();
         downcast_ctx(parser.context).declare_var_with_index(
             "func",
-            SyntaxType::Function(Rc::new(FunctionTypes(vec![
+            SyntaxType::Function(Arc::new(FunctionTypes(vec![
                 FunctionType::new((vec![("arg1", INT_TYPE), ("arg2", INT_TYPE)], INT_TYPE)),
                 FunctionType::new((vec![("arg1", FLOAT_TYPE), ("arg2", FLOAT_TYPE)], FLOAT_TYPE)),
             ]))),
@@ -1696,7 +2051,7 @@ mod tests {
             assert_eq!(
                 downcast_ctx(parser.context).get_user_func("fun@1"),
                 Some(&(
-                    SyntaxType::UserFunction(Rc::new((
+                    SyntaxType::UserFunction(Arc::new((
                         vec!["a1", "a2"],
                         SyntaxType::Series(SimpleSyntaxType::Float)
                     ))),
@@ -1733,7 +2088,7 @@ mod tests {
             assert_eq!(
                 downcast_ctx(parser.context).get_user_func("fun@2"),
                 Some(&(
-                    SyntaxType::UserFunction(Rc::new((
+                    SyntaxType::UserFunction(Arc::new((
                         vec!["a1", "a2"],
                         SyntaxType::Series(SimpleSyntaxType::Int)
                     ))),
@@ -1897,7 +2252,7 @@ mod tests {
                 ],
                 StrRange::new_empty()
             )))),
-            Ok(ParseValue::new_with_type(SyntaxType::Tuple(Rc::new(vec![
+            Ok(ParseValue::new_with_type(SyntaxType::Tuple(Arc::new(vec![
                 INT_TYPE, FLOAT_TYPE
             ]))))
         );
@@ -2428,11 +2783,11 @@ mod tests {
             .iter()
             .cloned()
             .collect();
-        let map1: BTreeMap<_, _> = [("key1", SyntaxType::Object(Rc::new(map2)))]
+        let map1: BTreeMap<_, _> = [("key1", SyntaxType::Object(Arc::new(map2)))]
             .iter()
             .cloned()
             .collect();
-        context.declare_var_with_index("var", SyntaxType::Object(Rc::new(map1)));
+        context.declare_var_with_index("var", SyntaxType::Object(Arc::new(map1)));
 
         let mut prefix_exp = PrefixExp::new_no_input(
             Exp::PrefixExp(Box::new(PrefixExp::new_no_input(
@@ -2527,7 +2882,7 @@ mod tests {
         let mut assign = assign_stmt(input, &AstState::new()).unwrap().1;
         assert_eq!(
             parser.parse_assign(&mut assign),
-            Ok(ParseValue::new_with_type(SyntaxType::Tuple(Rc::new(vec![
+            Ok(ParseValue::new_with_type(SyntaxType::Tuple(Arc::new(vec![
                 val_type.clone(),
                 val_type.clone()
             ]))))
@@ -2538,7 +2893,7 @@ mod tests {
         let input = Input::new_with_str("int [a1, a2] = [1.0, 2.0]");
         assert_eq!(
             parser.parse_assign(&mut assign_stmt(input, &AstState::new()).unwrap().1),
-            Ok(ParseValue::new_with_type(SyntaxType::Tuple(Rc::new(vec![
+            Ok(ParseValue::new_with_type(SyntaxType::Tuple(Arc::new(vec![
                 val_type.clone(),
                 val_type.clone()
             ]))))
@@ -2610,7 +2965,7 @@ mod tests {
         let context = downcast_ctx(parser.context);
         context.declare_var_with_index(
             "security",
-            SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
                 vec![(
                     "m",
                     SyntaxType::DynamicExpr(Box::new(SyntaxType::float_series())),
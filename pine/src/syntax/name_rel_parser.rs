@@ -112,13 +112,16 @@ impl<'a> GenNameFinder<'a> for Exp<'a> {
 impl<'a> DepNameFinder<'a> for Statement<'a> {
     fn find_names(&self) -> Vec<&'a str> {
         match self {
-            Statement::Break(_) | Statement::Continue(_) | Statement::None(_) => vec![],
+            Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::None(_)
+            | Statement::Error(_) => vec![],
             Statement::Assignment(assign) => assign.find_names(),
             Statement::VarAssignment(assign) => assign.find_names(),
             Statement::Ite(ite) => ite.cond.find_names(),
             Statement::ForRange(fr) => fr.find_names(),
             Statement::FuncCall(f) => f.find_names(),
-            Statement::FuncDef(_) => vec![],
+            Statement::FuncDef(_) | Statement::Import(_) | Statement::TypeDef(_) => vec![],
             Statement::Exp(e) => e.find_names(),
         }
     }
@@ -127,13 +130,18 @@ impl<'a> DepNameFinder<'a> for Statement<'a> {
 impl<'a> GenNameFinder<'a> for Statement<'a> {
     fn find_gen_names(&self) -> Vec<&'a str> {
         match self {
-            Statement::Break(_) | Statement::Continue(_) | Statement::None(_) => vec![],
+            Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::None(_)
+            | Statement::Error(_) => vec![],
             Statement::Assignment(assign) => assign.find_gen_names(),
             Statement::VarAssignment(assign) => assign.find_gen_names(),
             Statement::Ite(_) => vec![],
             Statement::ForRange(_) => vec![],
             Statement::FuncCall(_) => vec![],
             Statement::FuncDef(d) => vec![d.name.value],
+            Statement::Import(import) => import.defs.iter().map(|d| d.name.value).collect(),
+            Statement::TypeDef(d) => vec![d.name.value],
             Statement::Exp(e) => e.find_gen_names(),
         }
     }
@@ -361,7 +369,7 @@ mod tests {
     use crate::ast::syntax_type::*;
     use crate::syntax::SimpleInputSrcDetector;
     use crate::syntax::{ContextType, SyntaxContext, SyntaxParser};
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn parse_stmt_test() {
@@ -404,7 +412,7 @@ mod tests {
         ctx.declare_var_with_index("close", SyntaxType::int_series());
         ctx.declare_var_with_index(
             "funca",
-            SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
                 vec![("arg1", SyntaxType::int_series())],
                 SyntaxType::int_series(),
             ))]))),
@@ -434,7 +442,7 @@ mod tests {
         ctx.declare_var_with_index("close", SyntaxType::int_series());
         ctx.declare_var_with_index(
             "funca",
-            SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            SyntaxType::Function(Arc::new(FunctionTypes(vec![FunctionType::new((
                 vec![("arg1", SyntaxType::int_series())],
                 SyntaxType::int_series(),
             ))]))),
@@ -0,0 +1,630 @@
+// The synthetic chart types like Heikin Ashi and Renko that derive the bars from the bars of
+// the underlying symbol. The ticker id of the synthetic chart like "renko(MSFT,ATR,14)" wraps
+// the underlying symbol, so that `security` builds the synthetic bars from its data.
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::types::{Float, RuntimeErr};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoxSize {
+    // The box size is the average true range of the first bars.
+    Atr(usize),
+    Fixed(f64),
+}
+
+impl BoxSize {
+    // Parse the box size from the style "ATR" or "Traditional" and the ATR length or the
+    // fixed box size.
+    pub fn parse(style: &str, param: f64) -> Result<BoxSize, RuntimeErr> {
+        match style {
+            "ATR" if param >= 1f64 => Ok(BoxSize::Atr(param as usize)),
+            "ATR" => Err(RuntimeErr::InvalidParameters(str_replace(
+                GE_1,
+                vec![String::from("param")],
+            ))),
+            "Traditional" if param > 0f64 => Ok(BoxSize::Fixed(param)),
+            "Traditional" => Err(RuntimeErr::InvalidParameters(str_replace(
+                INVALID_VALS,
+                vec![String::from("param")],
+            ))),
+            _ => Err(RuntimeErr::InvalidParameters(str_replace(
+                INVALID_VALS,
+                vec![String::from("style")],
+            ))),
+        }
+    }
+
+    fn to_args(self) -> String {
+        match self {
+            BoxSize::Atr(length) => format!("ATR,{}", length),
+            BoxSize::Fixed(size) => format!("Traditional,{}", size),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PnfSource {
+    HighLow,
+    Close,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartType {
+    HeikinAshi,
+    Renko(BoxSize),
+    // The count of the lines to break for the reversal.
+    LineBreak(usize),
+    // The reversal amount of the price.
+    Kagi(f64),
+    PointFigure {
+        source: PnfSource,
+        box_size: BoxSize,
+        // The count of the boxes for the reversal.
+        reversal: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticTicker {
+    pub symbol: String,
+    pub chart: ChartType,
+}
+
+fn parse_num<T: std::str::FromStr>(arg: Option<&&str>) -> Option<T> {
+    arg.and_then(|s| s.trim().parse().ok())
+}
+
+impl SyntheticTicker {
+    pub fn new(symbol: String, chart: ChartType) -> SyntheticTicker {
+        SyntheticTicker { symbol, chart }
+    }
+
+    pub fn to_tickerid(&self) -> String {
+        match self.chart {
+            ChartType::HeikinAshi => format!("heikinashi({})", self.symbol),
+            ChartType::Renko(box_size) => format!("renko({},{})", self.symbol, box_size.to_args()),
+            ChartType::LineBreak(lines) => format!("linebreak({},{})", self.symbol, lines),
+            ChartType::Kagi(reversal) => format!("kagi({},{})", self.symbol, reversal),
+            ChartType::PointFigure {
+                source,
+                box_size,
+                reversal,
+            } => {
+                let source = match source {
+                    PnfSource::HighLow => "hl",
+                    PnfSource::Close => "close",
+                };
+                format!(
+                    "pointfigure({},{},{},{})",
+                    self.symbol,
+                    source,
+                    box_size.to_args(),
+                    reversal
+                )
+            }
+        }
+    }
+
+    // Parse the ticker id generated by `to_tickerid`, it is None for the plain symbol.
+    pub fn parse(tickerid: &str) -> Option<SyntheticTicker> {
+        let start = tickerid.find('(')?;
+        if !tickerid.ends_with(')') {
+            return None;
+        }
+        let args: Vec<&str> = tickerid[start + 1..tickerid.len() - 1].split(',').collect();
+        let chart = match (&tickerid[..start], args.len()) {
+            ("heikinashi", 1) => ChartType::HeikinAshi,
+            ("renko", 3) => {
+                ChartType::Renko(BoxSize::parse(args[1], parse_num(args.get(2))?).ok()?)
+            }
+            ("linebreak", 2) => ChartType::LineBreak(parse_num(args.get(1))?),
+            ("kagi", 2) => ChartType::Kagi(parse_num(args.get(1))?),
+            ("pointfigure", 5) => ChartType::PointFigure {
+                source: match args[1] {
+                    "hl" => PnfSource::HighLow,
+                    "close" => PnfSource::Close,
+                    _ => return None,
+                },
+                box_size: BoxSize::parse(args[2], parse_num(args.get(3))?).ok()?,
+                reversal: parse_num(args.get(4))?,
+            },
+            _ => return None,
+        };
+        Some(SyntheticTicker::new(String::from(args[0]), chart))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+    pub time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: Float,
+}
+
+impl Bar {
+    pub fn new(time: i64, open: f64, high: f64, low: f64, close: f64, volume: Float) -> Bar {
+        Bar {
+            time,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        }
+    }
+
+    // The bar moving from the open price to the close price.
+    fn line(time: i64, open: f64, close: f64, volume: Float) -> Bar {
+        Bar::new(time, open, open.max(close), open.min(close), close, volume)
+    }
+}
+
+fn add_volume(v1: Float, v2: Float) -> Float {
+    match (v1, v2) {
+        (Some(v1), Some(v2)) => Some(v1 + v2),
+        (None, v) | (v, None) => v,
+    }
+}
+
+// Resolve the box size, the ATR box size is the average true range of the first bars.
+#[derive(Debug, Clone, PartialEq)]
+struct BoxSizer {
+    box_size: BoxSize,
+    size: Option<f64>,
+    prev_close: Option<f64>,
+    ranges: Vec<f64>,
+}
+
+impl BoxSizer {
+    fn new(box_size: BoxSize) -> BoxSizer {
+        let size = match box_size {
+            BoxSize::Fixed(size) => Some(size),
+            BoxSize::Atr(_) => None,
+        };
+        BoxSizer {
+            box_size,
+            size,
+            prev_close: None,
+            ranges: vec![],
+        }
+    }
+
+    fn push(&mut self, bar: &Bar) -> Option<f64> {
+        if let (None, BoxSize::Atr(length)) = (self.size, self.box_size) {
+            let range = match self.prev_close {
+                None => bar.high - bar.low,
+                Some(prev) => (bar.high - bar.low)
+                    .max((bar.high - prev).abs())
+                    .max((bar.low - prev).abs()),
+            };
+            self.prev_close = Some(bar.close);
+            self.ranges.push(range);
+            if self.ranges.len() >= length {
+                let atr = self.ranges.iter().sum::<f64>() / length as f64;
+                // Wait for the following bars if the bars are flat.
+                if atr > 0f64 {
+                    self.size = Some(atr);
+                } else {
+                    self.ranges.remove(0);
+                }
+            }
+        }
+        // The bar resolving the box size is the start of the boxes.
+        self.size
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum BuilderState {
+    HeikinAshi {
+        prev: Option<(f64, f64)>,
+    },
+    Renko {
+        sizer: BoxSizer,
+        // The top and bottom of the last brick.
+        range: Option<(f64, f64)>,
+    },
+    LineBreak {
+        count: usize,
+        lines: VecDeque<(f64, f64)>,
+    },
+    Kagi {
+        reversal: f64,
+        start: Option<f64>,
+        extreme: f64,
+        dir: Option<Direction>,
+    },
+    PointFigure {
+        source: PnfSource,
+        sizer: BoxSizer,
+        reversal: usize,
+        start: Option<f64>,
+        extreme: f64,
+        dir: Option<Direction>,
+    },
+}
+
+// Build the synthetic bars from the bars of the underlying symbol one by one. The bar of the
+// brick, line or column is built only when it is completed, and its time is the time of the
+// underlying bar that completes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticBuilder {
+    state: BuilderState,
+    // The volume of the underlying bars since the last synthetic bar.
+    volume: Float,
+}
+
+impl SyntheticBuilder {
+    pub fn new(chart: ChartType) -> SyntheticBuilder {
+        let state = match chart {
+            ChartType::HeikinAshi => BuilderState::HeikinAshi { prev: None },
+            ChartType::Renko(box_size) => BuilderState::Renko {
+                sizer: BoxSizer::new(box_size),
+                range: None,
+            },
+            ChartType::LineBreak(count) => BuilderState::LineBreak {
+                count: count.max(1),
+                lines: VecDeque::new(),
+            },
+            ChartType::Kagi(reversal) => BuilderState::Kagi {
+                reversal,
+                start: None,
+                extreme: 0f64,
+                dir: None,
+            },
+            ChartType::PointFigure {
+                source,
+                box_size,
+                reversal,
+            } => BuilderState::PointFigure {
+                source,
+                sizer: BoxSizer::new(box_size),
+                reversal: reversal.max(1),
+                start: None,
+                extreme: 0f64,
+                dir: None,
+            },
+        };
+        SyntheticBuilder {
+            state,
+            volume: None,
+        }
+    }
+
+    // Build all the synthetic bars of the underlying bars.
+    pub fn build(chart: ChartType, bars: &[Bar]) -> Vec<Bar> {
+        let mut builder = SyntheticBuilder::new(chart);
+        bars.iter().flat_map(|bar| builder.push(bar)).collect()
+    }
+
+    pub fn push(&mut self, bar: &Bar) -> Vec<Bar> {
+        let mut res = vec![];
+        self.volume = add_volume(self.volume, bar.volume);
+        match &mut self.state {
+            BuilderState::HeikinAshi { prev } => {
+                let close = (bar.open + bar.high + bar.low + bar.close) / 4f64;
+                let open = match prev {
+                    None => (bar.open + bar.close) / 2f64,
+                    Some((open, close)) => (*open + *close) / 2f64,
+                };
+                let high = bar.high.max(open).max(close);
+                let low = bar.low.min(open).min(close);
+                *prev = Some((open, close));
+                res.push(Bar::new(bar.time, open, high, low, close, bar.volume));
+            }
+            BuilderState::Renko { sizer, range } => {
+                let size = match sizer.push(bar) {
+                    None => return res,
+                    Some(size) => size,
+                };
+                let (mut top, mut bottom) = range.unwrap_or((bar.close, bar.close));
+                while bar.close >= top + size {
+                    res.push(Bar::line(bar.time, top, top + size, None));
+                    bottom = top;
+                    top += size;
+                }
+                while bar.close <= bottom - size {
+                    res.push(Bar::line(bar.time, bottom, bottom - size, None));
+                    top = bottom;
+                    bottom -= size;
+                }
+                *range = Some((top, bottom));
+            }
+            BuilderState::LineBreak { count, lines } => match lines.back().cloned() {
+                None => {
+                    if bar.close != bar.open {
+                        lines.push_back((bar.open, bar.close));
+                        res.push(Bar::line(bar.time, bar.open, bar.close, None));
+                    }
+                }
+                Some((open, close)) => {
+                    let highest = lines.iter().map(|l| l.0.max(l.1)).fold(f64::MIN, f64::max);
+                    let lowest = lines.iter().map(|l| l.0.min(l.1)).fold(f64::MAX, f64::min);
+                    let line = if close > open {
+                        if bar.close > close {
+                            Some((close, bar.close))
+                        } else if bar.close < lowest {
+                            Some((open, bar.close))
+                        } else {
+                            None
+                        }
+                    } else if bar.close < close {
+                        Some((close, bar.close))
+                    } else if bar.close > highest {
+                        Some((open, bar.close))
+                    } else {
+                        None
+                    };
+                    if let Some(line) = line {
+                        lines.push_back(line);
+                        if lines.len() > *count {
+                            lines.pop_front();
+                        }
+                        res.push(Bar::line(bar.time, line.0, line.1, None));
+                    }
+                }
+            },
+            BuilderState::Kagi {
+                reversal,
+                start,
+                extreme,
+                dir,
+            } => {
+                let price = bar.close;
+                match (*start, *dir) {
+                    (None, _) => {
+                        *start = Some(price);
+                        *extreme = price;
+                    }
+                    (Some(from), None) => {
+                        if (price - from).abs() >= *reversal {
+                            *dir = Some(if price > from {
+                                Direction::Up
+                            } else {
+                                Direction::Down
+                            });
+                            *extreme = price;
+                        }
+                    }
+                    (Some(from), Some(Direction::Up)) => {
+                        if price > *extreme {
+                            *extreme = price;
+                        } else if *extreme - price >= *reversal {
+                            res.push(Bar::line(bar.time, from, *extreme, None));
+                            *start = Some(*extreme);
+                            *extreme = price;
+                            *dir = Some(Direction::Down);
+                        }
+                    }
+                    (Some(from), Some(Direction::Down)) => {
+                        if price < *extreme {
+                            *extreme = price;
+                        } else if price - *extreme >= *reversal {
+                            res.push(Bar::line(bar.time, from, *extreme, None));
+                            *start = Some(*extreme);
+                            *extreme = price;
+                            *dir = Some(Direction::Up);
+                        }
+                    }
+                }
+            }
+            BuilderState::PointFigure {
+                source,
+                sizer,
+                reversal,
+                start,
+                extreme,
+                dir,
+            } => {
+                let size = match sizer.push(bar) {
+                    None => return res,
+                    Some(size) => size,
+                };
+                let (high, low) = match source {
+                    PnfSource::HighLow => (bar.high, bar.low),
+                    PnfSource::Close => (bar.close, bar.close),
+                };
+                // The prices are rounded to the boxes.
+                let up = (high / size).floor() * size;
+                let down = (low / size).ceil() * size;
+                let reversal = *reversal as f64 * size;
+                match (*start, *dir) {
+                    (None, _) => {
+                        let price = (bar.close / size).round() * size;
+                        *start = Some(price);
+                        *extreme = price;
+                    }
+                    (Some(from), None) => {
+                        if up >= from + size {
+                            *dir = Some(Direction::Up);
+                            *extreme = up;
+                        } else if down <= from - size {
+                            *dir = Some(Direction::Down);
+                            *extreme = down;
+                        }
+                    }
+                    (Some(from), Some(Direction::Up)) => {
+                        if up > *extreme {
+                            *extreme = up;
+                        } else if down <= *extreme - reversal {
+                            res.push(Bar::line(bar.time, from, *extreme, None));
+                            *start = Some(*extreme - size);
+                            *extreme = down;
+                            *dir = Some(Direction::Down);
+                        }
+                    }
+                    (Some(from), Some(Direction::Down)) => {
+                        if down < *extreme {
+                            *extreme = down;
+                        } else if up >= *extreme + reversal {
+                            res.push(Bar::line(bar.time, from, *extreme, None));
+                            *start = Some(*extreme + size);
+                            *extreme = up;
+                            *dir = Some(Direction::Up);
+                        }
+                    }
+                }
+            }
+        }
+        // The volume of the underlying bars goes to the last synthetic bar.
+        if let Some(last) = res.last_mut() {
+            last.volume = self.volume.take();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closes(vals: &[f64]) -> Vec<Bar> {
+        vals.iter()
+            .enumerate()
+            .map(|(i, v)| Bar::new(i as i64, *v, *v, *v, *v, Some(1f64)))
+            .collect()
+    }
+
+    fn lines(bars: Vec<Bar>) -> Vec<(i64, f64, f64)> {
+        bars.into_iter()
+            .map(|b| (b.time, b.open, b.close))
+            .collect()
+    }
+
+    #[test]
+    fn tickerid_test() {
+        let tickers = vec![
+            SyntheticTicker::new(String::from("NASDAQ:MSFT"), ChartType::HeikinAshi),
+            SyntheticTicker::new(String::from("MSFT"), ChartType::Renko(BoxSize::Atr(14))),
+            SyntheticTicker::new(String::from("MSFT"), ChartType::LineBreak(3)),
+            SyntheticTicker::new(String::from("MSFT"), ChartType::Kagi(1.5)),
+            SyntheticTicker::new(
+                String::from("MSFT"),
+                ChartType::PointFigure {
+                    source: PnfSource::HighLow,
+                    box_size: BoxSize::Fixed(0.5),
+                    reversal: 3,
+                },
+            ),
+        ];
+        for ticker in tickers {
+            assert_eq!(SyntheticTicker::parse(&ticker.to_tickerid()), Some(ticker));
+        }
+        assert_eq!(
+            SyntheticTicker::parse("renko(MSFT,Traditional,2)").map(|t| t.to_tickerid()),
+            Some(String::from("renko(MSFT,Traditional,2)"))
+        );
+        assert_eq!(SyntheticTicker::parse("MSFT"), None);
+        assert_eq!(SyntheticTicker::parse("renko(MSFT,Box,2)"), None);
+        assert!(BoxSize::parse("ATR", 0f64).is_err());
+        assert!(BoxSize::parse("Traditional", -1f64).is_err());
+    }
+
+    #[test]
+    fn heikinashi_test() {
+        let bars = vec![
+            Bar::new(0, 10f64, 14f64, 8f64, 12f64, Some(1f64)),
+            Bar::new(1, 12f64, 16f64, 12f64, 16f64, Some(2f64)),
+        ];
+        assert_eq!(
+            SyntheticBuilder::build(ChartType::HeikinAshi, &bars),
+            vec![
+                Bar::new(0, 11f64, 14f64, 8f64, 11f64, Some(1f64)),
+                Bar::new(1, 11f64, 16f64, 11f64, 14f64, Some(2f64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn renko_test() {
+        let bars = closes(&[10f64, 11f64, 13f64, 12f64, 9f64, 8.5f64]);
+        let renko = SyntheticBuilder::build(ChartType::Renko(BoxSize::Fixed(1f64)), &bars);
+        assert_eq!(
+            lines(renko.clone()),
+            vec![
+                (1, 10f64, 11f64),
+                (2, 11f64, 12f64),
+                (2, 12f64, 13f64),
+                // The reversal needs two boxes.
+                (4, 12f64, 11f64),
+                (4, 11f64, 10f64),
+                (4, 10f64, 9f64),
+            ]
+        );
+        // The volume goes to the last brick of the bar.
+        assert_eq!(
+            renko.iter().map(|b| b.volume).collect::<Vec<_>>(),
+            vec![Some(2f64), None, Some(1f64), None, None, Some(2f64)]
+        );
+
+        // The box size is the average range of the first two bars.
+        let bars = vec![
+            Bar::new(0, 10f64, 11f64, 9f64, 10f64, None),
+            Bar::new(1, 10f64, 12f64, 10f64, 12f64, None),
+            Bar::new(2, 12f64, 16f64, 12f64, 16f64, None),
+        ];
+        assert_eq!(
+            lines(SyntheticBuilder::build(
+                ChartType::Renko(BoxSize::Atr(2)),
+                &bars
+            )),
+            vec![(2, 12f64, 14f64), (2, 14f64, 16f64)]
+        );
+    }
+
+    #[test]
+    fn linebreak_test() {
+        let bars = vec![
+            Bar::new(0, 10f64, 10f64, 10f64, 11f64, None),
+            Bar::new(1, 11f64, 12f64, 11f64, 12f64, None),
+            Bar::new(2, 12f64, 13f64, 12f64, 13f64, None),
+            Bar::new(3, 13f64, 13f64, 11f64, 11.5f64, None),
+            Bar::new(4, 11.5f64, 11.5f64, 9f64, 9f64, None),
+            Bar::new(5, 9f64, 9f64, 8f64, 8f64, None),
+        ];
+        assert_eq!(
+            lines(SyntheticBuilder::build(ChartType::LineBreak(3), &bars)),
+            vec![
+                (0, 10f64, 11f64),
+                (1, 11f64, 12f64),
+                (2, 12f64, 13f64),
+                // The reversal breaks the lowest of the last three lines.
+                (4, 12f64, 9f64),
+                (5, 9f64, 8f64),
+            ]
+        );
+    }
+
+    #[test]
+    fn kagi_test() {
+        let bars = closes(&[10f64, 11f64, 13f64, 12.5f64, 11f64, 10f64, 12f64]);
+        assert_eq!(
+            lines(SyntheticBuilder::build(ChartType::Kagi(2f64), &bars)),
+            vec![(4, 10f64, 13f64), (6, 13f64, 10f64)]
+        );
+    }
+
+    #[test]
+    fn pointfigure_test() {
+        let bars = closes(&[10f64, 11.2f64, 13.4f64, 12.5f64, 10.5f64, 9f64, 12f64]);
+        let chart = ChartType::PointFigure {
+            source: PnfSource::Close,
+            box_size: BoxSize::Fixed(1f64),
+            reversal: 3,
+        };
+        assert_eq!(
+            lines(SyntheticBuilder::build(chart, &bars)),
+            vec![(5, 10f64, 13f64), (6, 12f64, 9f64)]
+        );
+    }
+}
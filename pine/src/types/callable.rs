@@ -7,6 +7,7 @@ use crate::ast::syntax_type::FunctionType;
 use crate::runtime::context::{
     commit_series_for_operator, rollback_series_for_operator, Ctx, VarOperate,
 };
+use crate::runtime::snapshot::{load_values, save_values, state_err, StateValue};
 use crate::runtime::statement::process_assign_val;
 use std::cell::{Cell, RefCell};
 use std::fmt;
@@ -41,6 +42,16 @@ pub trait SeriesCall<'a> {
         Ok(())
     }
 
+    // Save the state that changes with the bars, it's None if the state can't be saved.
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        None
+    }
+
+    // Load the state saved by save_state into the instance created by the same call.
+    fn load_state(&mut self, _state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        Err(state_err())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a>;
 }
 
@@ -202,6 +213,28 @@ impl<'a> SeriesCall<'a> for ParamCollectCall<'a> {
         Ok(())
     }
 
+    // The state is the parameters and the state of the caller.
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        let mut state = vec![StateValue::Tuple(save_values(&self.params)?)];
+        if let Some(caller) = &self.caller {
+            state.push(StateValue::Instance(caller.save_state()?));
+        }
+        Some(state)
+    }
+
+    fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        let mut state = state.into_iter();
+        match state.next() {
+            Some(StateValue::Tuple(params)) => load_values(&mut self.params, params)?,
+            _ => return Err(state_err()),
+        }
+        match (&mut self.caller, state.next()) {
+            (Some(caller), Some(StateValue::Instance(s))) => caller.load_state(s),
+            (None, None) => Ok(()),
+            _ => Err(state_err()),
+        }
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         let new_map: Vec<_> = self
             .params
@@ -236,6 +269,8 @@ pub struct Callable<'a> {
     >,
     caller: Option<Box<dyn SeriesCall<'a> + 'a>>,
     param_names: Option<Vec<&'a str>>,
+    // The restored state of the caller that is loaded after the parameters are initialized.
+    pending_state: Option<Vec<StateValue>>,
 }
 
 impl<'a> fmt::Debug for Callable<'a> {
@@ -255,6 +290,7 @@ impl<'a> Clone for Callable<'a> {
             func: self.func,
             caller: caller,
             param_names: self.param_names.clone(),
+            pending_state: self.pending_state.clone(),
         }
     }
 }
@@ -302,6 +338,26 @@ impl<'a> Callable<'a> {
             func,
             caller,
             param_names: None,
+            pending_state: None,
+        }
+    }
+
+    pub fn save_state(&self) -> Option<Vec<StateValue>> {
+        match &self.caller {
+            Some(caller) => caller.save_state(),
+            None => Some(vec![]),
+        }
+    }
+
+    // The state of the callable that has not been called is loaded on the first call.
+    pub fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        match &mut self.caller {
+            Some(_) if self.param_names.is_none() => {
+                self.pending_state = Some(state);
+                Ok(())
+            }
+            Some(caller) => caller.load_state(state),
+            None => Ok(()),
         }
     }
 
@@ -316,6 +372,9 @@ impl<'a> Callable<'a> {
             self.param_names = Some(func_type.arg_names());
             if let Some(ref mut caller) = self.caller {
                 caller.init_param_len(self.param_names.as_ref().unwrap().len());
+                if let Some(state) = self.pending_state.take() {
+                    caller.load_state(state)?;
+                }
             }
         }
         let param_names = self.param_names.as_ref().unwrap();
@@ -18,4 +18,25 @@ pub enum RuntimeErr {
     Break,
 
     ForRangeIndexIsNA, // The index of for-range is na
+
+    InvalidSnapshot(String), // The runtime snapshot can't be restored.
+
+    TraceVarNotFound(String), // The traced variable is not declared in the global scope.
+
+    NaObjectField, // The field of the na object is assigned.
+
+    NegativeHistoryRef(i64), // The offset of the history reference is negative.
+
+    ScriptNotParsed,           // The script is run before it is parsed successfully.
+    ScriptNotRun,              // The script is updated before it is run with the data.
+    InvalidDataLen,            // The data is shorter than the count of bars to run.
+    InvalidUpdateFrom(i32),    // The start index of the update is out of the run data.
+    UpdateDataMismatch,        // The update data sources are not the same as the run data.
+    UnknownDataSource(String), // The name of the data source is unknown.
+    DataTypeMismatch(String),  // The type of the data source is not the declared type.
+
+    InvalidInputVal(String, String), // The input value is out of the range or has a wrong type.
+
+    OutputDiffNotEnabled, // The output diff is requested before it is enabled.
+    InvalidRevision(u64), // The acknowledged revision is not generated yet.
 }
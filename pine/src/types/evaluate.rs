@@ -3,6 +3,7 @@ use crate::runtime::context::Ctx;
 use crate::types::traits::{
     Category, ComplexType, DataType, PineFrom, PineStaticType, PineType, SecondType,
 };
+use crate::runtime::snapshot::{state_err, StateValue};
 use crate::types::{PineRef, RuntimeErr};
 use std::fmt;
 
@@ -20,6 +21,15 @@ pub trait EvaluateVal<'a> {
         Ok(())
     }
 
+    // Save the state that changes with the bars, it's None if the state can't be saved.
+    fn save_state(&self) -> Option<Vec<StateValue>> {
+        None
+    }
+
+    fn load_state(&mut self, _state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        Err(state_err())
+    }
+
     fn copy(&self) -> Box<dyn EvaluateVal<'a>>;
 }
 
@@ -62,6 +72,14 @@ impl<'a> Evaluate<'a> {
     pub fn call(&mut self, ctx: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, RuntimeErr> {
         self.val.call(ctx)
     }
+
+    pub fn save_state(&self) -> Option<Vec<StateValue>> {
+        self.val.save_state()
+    }
+
+    pub fn load_state(&mut self, state: Vec<StateValue>) -> Result<(), RuntimeErr> {
+        self.val.load_state(state)
+    }
 }
 
 impl<'a> Runnable<'a> for Evaluate<'a> {
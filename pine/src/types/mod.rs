@@ -14,6 +14,7 @@ pub mod runnable;
 pub mod series;
 pub mod simple_callable_object;
 pub mod traits;
+pub mod user_object;
 
 pub use callable::*;
 pub use callable_evaluate::*;
@@ -30,3 +31,4 @@ pub use runnable::*;
 pub use series::*;
 pub use simple_callable_object::*;
 pub use traits::*;
+pub use user_object::*;
@@ -7,6 +7,7 @@ use super::traits::{
     Arithmetic, Category, Comparator, ComplexType, DataType, Negative, PineClass, PineFrom,
     PineStaticType, PineType, SecondType, SimpleType,
 };
+use std::borrow::Cow;
 
 // pine int type
 pub type Int = Option<i64>;
@@ -42,16 +43,17 @@ impl Arithmetic for Int {
         }
     }
 
+    // The division by zero is na.
     fn div(self, other: Self) -> Self {
         match (self, other) {
-            (Some(i1), Some(i2)) => Some(i1 / i2),
+            (Some(i1), Some(i2)) => i1.checked_div(i2),
             _ => None,
         }
     }
 
     fn rem(self, other: Self) -> Self {
         match (self, other) {
-            (Some(i1), Some(i2)) => Some(i1 % i2),
+            (Some(i1), Some(i2)) => i1.checked_rem(i2),
             _ => None,
         }
     }
@@ -346,9 +348,10 @@ impl<'a> PineFrom<'a, Bool> for Bool {
 
 impl SimpleType for Bool {}
 
-// pine color type
+// pine color type, the colors of the script are borrowed and the colors supplied by the client
+// or restored from the snapshot are owned.
 #[derive(Debug, PartialEq, Clone, Default)]
-pub struct Color<'a>(pub &'a str);
+pub struct Color<'a>(pub Cow<'a, str>);
 impl<'a> PineStaticType for Color<'a> {
     fn static_type() -> (DataType, SecondType) {
         (DataType::Color, SecondType::Simple)
@@ -372,7 +375,7 @@ impl<'a> PineFrom<'a, Color<'a>> for Color<'a> {
                 let f: RefData<Series<Color>> = downcast_pf::<Series<Color>>(t).unwrap();
                 Ok(RefData::new_box(f.get_current()))
             }
-            (DataType::NA, _) => Ok(RefData::new_box(Color(""))),
+            (DataType::NA, _) => Ok(RefData::new_box(Color(Cow::Borrowed("")))),
             _ => Err(RuntimeErr::UnknownRuntimeErr),
         }
     }
@@ -683,7 +686,7 @@ mod tests {
     fn color_test() {
         assert_eq!(Color::static_type(), (DataType::Color, SecondType::Simple));
         assert_eq!(
-            Color::get_type(&Color("")),
+            Color::get_type(&Color(Cow::Borrowed(""))),
             (DataType::Color, SecondType::Simple)
         );
     }
@@ -22,6 +22,7 @@ pub enum DataType {
     String,
     Line,
     Label,
+    UserObject, // The object of the user-defined type
     NA,
     PineVar,
     Tuple,
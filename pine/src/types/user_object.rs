@@ -0,0 +1,138 @@
+use super::{
+    downcast_pf, Bool, Color, DataType, Float, Int, PineFrom, PineRef, PineStaticType, PineType,
+    RefData, RuntimeErr, SecondType, Series, SimpleType, NA,
+};
+use crate::ast::stat_expr_types::{DataType as FieldType, TypeDef};
+use std::cell::RefCell;
+use std::mem;
+use std::ptr;
+use std::rc::Rc;
+
+// The object of the user-defined type, the fields are saved in the declared order.
+#[derive(Debug)]
+pub struct UserObject<'a> {
+    def: &'a TypeDef<'a>,
+    fields: RefCell<Vec<PineRef<'a>>>,
+}
+
+// The objects are references, so two objects are equal only if they are the same object.
+impl<'a> PartialEq for UserObject<'a> {
+    fn eq(&self, other: &UserObject<'a>) -> bool {
+        ptr::eq(self, other)
+    }
+}
+
+impl<'a> UserObject<'a> {
+    pub fn new(def: &'a TypeDef<'a>, fields: Vec<PineRef<'a>>) -> UserObject<'a> {
+        UserObject {
+            def,
+            fields: RefCell::new(fields),
+        }
+    }
+
+    pub fn get_def(&self) -> &'a TypeDef<'a> {
+        self.def
+    }
+
+    fn field_index(&self, name: &str) -> Result<usize, RuntimeErr> {
+        self.def
+            .field_index(name)
+            .ok_or(RuntimeErr::NotSupportOperator)
+    }
+
+    // Get the field value as series, so it can be used like the other series values.
+    pub fn get_field(&self, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        let val = self.fields.borrow()[self.field_index(name)?].clone();
+        field_series(val)
+    }
+
+    // Set the field value, the field index and the replaced value are returned,
+    // so the write can be undone by `restore_field`.
+    pub fn set_field(
+        &self,
+        name: &str,
+        val: PineRef<'a>,
+    ) -> Result<(usize, PineRef<'a>), RuntimeErr> {
+        let index = self.field_index(name)?;
+        let val = convert_field(&self.def.fields[index].data_type, val)?;
+        let old = mem::replace(&mut self.fields.borrow_mut()[index], val);
+        Ok((index, old))
+    }
+
+    pub fn restore_field(&self, index: usize, val: PineRef<'a>) {
+        self.fields.borrow_mut()[index] = val;
+    }
+}
+
+// Convert the value to the simple value of the field type.
+pub fn convert_field<'a>(
+    data_type: &FieldType<'a>,
+    val: PineRef<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    match data_type {
+        FieldType::Float => Ok(Float::implicity_from(val)?.into_pf()),
+        FieldType::Int => Ok(Int::implicity_from(val)?.into_pf()),
+        FieldType::Bool => Ok(Bool::implicity_from(val)?.into_pf()),
+        FieldType::Color => Ok(Color::implicity_from(val)?.into_pf()),
+        FieldType::String => Ok(String::implicity_from(val)?.into_pf()),
+        FieldType::Custom(_) => match val.get_type() {
+            (DataType::UserObject, _) => Ok(UserObjectItem::implicity_from(val)?.into_pf()),
+            _ => Ok(val.copy()),
+        },
+    }
+}
+
+fn field_series<'a>(val: PineRef<'a>) -> Result<PineRef<'a>, RuntimeErr> {
+    match val.get_type() {
+        (DataType::Float, _) => Ok(Series::<Float>::implicity_from(val)?.into_pf()),
+        (DataType::Int, _) => Ok(Series::<Int>::implicity_from(val)?.into_pf()),
+        (DataType::Bool, _) => Ok(Series::<Bool>::implicity_from(val)?.into_pf()),
+        (DataType::Color, _) => Ok(Series::<Color>::implicity_from(val)?.into_pf()),
+        (DataType::String, _) => Ok(Series::<String>::implicity_from(val)?.into_pf()),
+        (DataType::UserObject, _) => Ok(Series::<UserObjectItem>::implicity_from(val)?.into_pf()),
+        _ => Ok(val),
+    }
+}
+
+// The na value of the field type, it's the field value of the na object.
+pub fn na_field<'a>(data_type: &FieldType<'a>) -> Result<PineRef<'a>, RuntimeErr> {
+    field_series(convert_field(data_type, PineRef::new_box(NA))?)
+}
+
+// The reference of the user-defined object, `None` is the `na` reference.
+pub type UserObjectItem<'a> = Option<Rc<UserObject<'a>>>;
+
+impl<'a> PineStaticType for UserObjectItem<'a> {
+    fn static_type() -> (DataType, SecondType) {
+        (DataType::UserObject, SecondType::Simple)
+    }
+}
+
+impl<'a> PineFrom<'a, UserObjectItem<'a>> for UserObjectItem<'a> {
+    fn implicity_from(t: PineRef<'a>) -> Result<RefData<UserObjectItem<'a>>, RuntimeErr> {
+        match t.get_type() {
+            (DataType::UserObject, SecondType::Simple) => {
+                Ok(downcast_pf::<UserObjectItem>(t).unwrap())
+            }
+            (DataType::UserObject, SecondType::Series) => {
+                let f: RefData<Series<UserObjectItem>> =
+                    downcast_pf::<Series<UserObjectItem>>(t).unwrap();
+                Ok(RefData::new(f.get_current()))
+            }
+            (DataType::NA, _) => Ok(RefData::new(None)),
+            _ => Err(RuntimeErr::UnknownRuntimeErr),
+        }
+    }
+}
+
+impl<'a> PineType<'a> for UserObjectItem<'a> {
+    fn get_type(&self) -> (DataType, SecondType) {
+        <Self as PineStaticType>::static_type()
+    }
+
+    fn copy(&self) -> PineRef<'a> {
+        PineRef::new(self.clone())
+    }
+}
+
+impl<'a> SimpleType for UserObjectItem<'a> {}
@@ -0,0 +1,52 @@
+// The Pine version used by the script without the //@version directive.
+pub const DEFAULT_VERSION: i32 = 4;
+
+const VERSION_DIRECTIVE: &str = "//@version=";
+
+// Read the version from the `//@version=N` comment directive of the script.
+pub fn parse_version(src: &str) -> Option<i32> {
+    find_version(src).map(|(_, version)| version)
+}
+
+// Find the zero-based line number and the version of the `//@version=N` directive.
+pub fn find_version(src: &str) -> Option<(usize, i32)> {
+    src.lines().enumerate().find_map(|(i, line)| {
+        let version = line.trim().strip_prefix(VERSION_DIRECTIVE)?;
+        version.trim().parse().ok().map(|v| (i, v))
+    })
+}
+
+// Get the version of the script, the default version is used if there is no directive.
+pub fn script_version(src: &str) -> i32 {
+    parse_version(src).unwrap_or(DEFAULT_VERSION)
+}
+
+// Get the version of the builtin variables used by the script version, the scripts
+// before version 5 share the same builtins.
+pub fn builtins_version(version: i32) -> i32 {
+    if version >= 5 {
+        5
+    } else {
+        DEFAULT_VERSION
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_test() {
+        assert_eq!(parse_version("//@version=5\nplot(close)"), Some(5));
+        assert_eq!(
+            parse_version("// comment\n  //@version=3  \nm = 1"),
+            Some(3)
+        );
+        assert_eq!(parse_version("//@version=x\nm = 1"), None);
+        assert_eq!(parse_version("m = 1 //@version=5"), None);
+        assert_eq!(script_version("plot(close)"), DEFAULT_VERSION);
+        assert_eq!(find_version("m = 1\n//@version=5"), Some((1, 5)));
+        assert_eq!(builtins_version(3), DEFAULT_VERSION);
+        assert_eq!(builtins_version(6), 5);
+    }
+}
@@ -0,0 +1,547 @@
+use super::{ArithOp, BlockProgram, CmpOp, Instr, Program, Reg, RegKind, Step};
+use crate::ast::num::Numeral;
+use crate::ast::op::{BinaryOp, UnaryOp};
+use crate::ast::stat_expr_types::{
+    BinaryExp, Block, Condition, Exp, ForRange, FunctionCall, FunctionDef, IfThenElse, RefCall,
+    Statement, UnaryExp, VarIndex,
+};
+use crate::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+
+struct Compiler {
+    instrs: Vec<Instr>,
+    floats: usize,
+    ints: usize,
+    bools: usize,
+}
+
+// Get the register kind and whether it is series for the syntax type.
+fn reg_kind(syntax_type: &SyntaxType) -> Option<(RegKind, bool)> {
+    match syntax_type {
+        SyntaxType::Simple(SimpleSyntaxType::Float) => Some((RegKind::Float, false)),
+        SyntaxType::Series(SimpleSyntaxType::Float) => Some((RegKind::Float, true)),
+        SyntaxType::Simple(SimpleSyntaxType::Int) => Some((RegKind::Int, false)),
+        SyntaxType::Series(SimpleSyntaxType::Int) => Some((RegKind::Int, true)),
+        SyntaxType::Simple(SimpleSyntaxType::Bool) => Some((RegKind::Bool, false)),
+        SyntaxType::Series(SimpleSyntaxType::Bool) => Some((RegKind::Bool, true)),
+        _ => None,
+    }
+}
+
+fn arith_op(op: &BinaryOp) -> Option<ArithOp> {
+    match op {
+        BinaryOp::Plus => Some(ArithOp::Add),
+        BinaryOp::Minus => Some(ArithOp::Sub),
+        BinaryOp::Mul => Some(ArithOp::Mul),
+        BinaryOp::Div => Some(ArithOp::Div),
+        BinaryOp::Mod => Some(ArithOp::Rem),
+        _ => None,
+    }
+}
+
+fn cmp_op(op: &BinaryOp) -> Option<CmpOp> {
+    match op {
+        BinaryOp::Lt => Some(CmpOp::Lt),
+        BinaryOp::Leq => Some(CmpOp::Leq),
+        BinaryOp::Gt => Some(CmpOp::Gt),
+        BinaryOp::Geq => Some(CmpOp::Geq),
+        BinaryOp::Eq => Some(CmpOp::Eq),
+        BinaryOp::Neq => Some(CmpOp::Neq),
+        _ => None,
+    }
+}
+
+impl Compiler {
+    fn new() -> Compiler {
+        Compiler {
+            instrs: vec![],
+            floats: 0,
+            ints: 0,
+            bools: 0,
+        }
+    }
+
+    fn counts(&self) -> (usize, usize, usize) {
+        (self.floats, self.ints, self.bools)
+    }
+
+    // Drop the instructions and the registers emitted after the mark.
+    fn reset(&mut self, (len, counts): (usize, (usize, usize, usize))) {
+        self.instrs.truncate(len);
+        self.floats = counts.0;
+        self.ints = counts.1;
+        self.bools = counts.2;
+    }
+
+    fn alloc(&mut self, kind: RegKind) -> Reg {
+        let counter = match kind {
+            RegKind::Float => &mut self.floats,
+            RegKind::Int => &mut self.ints,
+            RegKind::Bool => &mut self.bools,
+        };
+        *counter += 1;
+        *counter - 1
+    }
+
+    fn emit(&mut self, instr: Instr) -> usize {
+        self.instrs.push(instr);
+        self.instrs.len() - 1
+    }
+
+    // Point the jump instruction at the next instruction to emit.
+    fn patch_jump(&mut self, pos: usize) {
+        let target = self.instrs.len();
+        match &mut self.instrs[pos] {
+            Instr::Jump(t) | Instr::JumpIf(_, t) | Instr::JumpIfNot(_, t) => *t = target,
+            _ => unreachable!(),
+        }
+    }
+
+    fn emit_move(&mut self, kind: RegKind, dst: Reg, src: Reg) {
+        self.emit(match kind {
+            RegKind::Float => Instr::MoveFloat(dst, src),
+            RegKind::Int => Instr::MoveInt(dst, src),
+            RegKind::Bool => Instr::MoveBool(dst, src),
+        });
+    }
+
+    // Convert the register to the wanted kind, only int can be converted to float implicitly.
+    fn coerce(&mut self, (kind, reg): (RegKind, Reg), want: RegKind) -> Option<Reg> {
+        match (kind, want) {
+            (k, w) if k == w => Some(reg),
+            (RegKind::Int, RegKind::Float) => {
+                let dst = self.alloc(RegKind::Float);
+                self.emit(Instr::IntToFloat(dst, reg));
+                Some(dst)
+            }
+            _ => None,
+        }
+    }
+
+    // Compile the expression and put the value into the register of the wanted kind.
+    // Return None if the expression can't be compiled.
+    fn compile(&mut self, exp: &Exp, want: RegKind) -> Option<Reg> {
+        match exp {
+            Exp::Na(_) if want != RegKind::Bool => {
+                let dst = self.alloc(want);
+                self.emit(match want {
+                    RegKind::Float => Instr::LoadFloat(dst, None),
+                    _ => Instr::LoadInt(dst, None),
+                });
+                Some(dst)
+            }
+            Exp::Bool(node) if want == RegKind::Bool => {
+                let dst = self.alloc(want);
+                self.emit(Instr::LoadBool(dst, node.value));
+                Some(dst)
+            }
+            Exp::Num(Numeral::Int(node)) if want != RegKind::Bool => {
+                let dst = self.alloc(want);
+                self.emit(match want {
+                    RegKind::Float => Instr::LoadFloat(dst, Some(node.value as f64)),
+                    _ => Instr::LoadInt(dst, Some(node.value)),
+                });
+                Some(dst)
+            }
+            Exp::Num(Numeral::Float(node)) if want == RegKind::Float => {
+                let dst = self.alloc(want);
+                self.emit(Instr::LoadFloat(dst, Some(node.value)));
+                Some(dst)
+            }
+            Exp::VarName(name) => {
+                let dst = self.alloc(want);
+                self.emit(match want {
+                    RegKind::Float => Instr::VarFloat(dst, name.var_index, None),
+                    RegKind::Int => Instr::VarInt(dst, name.var_index, None),
+                    RegKind::Bool => Instr::VarBool(dst, name.var_index, None),
+                });
+                Some(dst)
+            }
+            Exp::RefCall(ref_call) => self.compile_ref_call(ref_call, want),
+            Exp::UnaryExp(unary_exp) => self.compile_unary(unary_exp, want),
+            Exp::BinaryExp(binary_exp) => {
+                let res = self.compile_binary(binary_exp)?;
+                self.coerce(res, want)
+            }
+            Exp::Condition(cond) => {
+                let res = self.compile_condition(cond)?;
+                self.coerce(res, want)
+            }
+            _ => None,
+        }
+    }
+
+    fn compile_ref_call(&mut self, ref_call: &RefCall, want: RegKind) -> Option<Reg> {
+        match &ref_call.name {
+            Exp::VarName(name) => {
+                let offset = self.compile(&ref_call.arg, RegKind::Int)?;
+                let dst = self.alloc(want);
+                self.emit(match want {
+                    RegKind::Float => Instr::VarFloat(dst, name.var_index, Some(offset)),
+                    RegKind::Int => Instr::VarInt(dst, name.var_index, Some(offset)),
+                    RegKind::Bool => Instr::VarBool(dst, name.var_index, Some(offset)),
+                });
+                Some(dst)
+            }
+            _ => None,
+        }
+    }
+
+    fn compile_unary(&mut self, unary_exp: &UnaryExp, want: RegKind) -> Option<Reg> {
+        match (&unary_exp.op, want) {
+            (UnaryOp::Plus, _) => self.compile(&unary_exp.exp, want),
+            (UnaryOp::Minus, RegKind::Float) => {
+                let src = self.compile(&unary_exp.exp, want)?;
+                let dst = self.alloc(want);
+                self.emit(Instr::FloatNeg(dst, src));
+                Some(dst)
+            }
+            (UnaryOp::Minus, RegKind::Int) => {
+                let src = self.compile(&unary_exp.exp, want)?;
+                let dst = self.alloc(want);
+                self.emit(Instr::IntNeg(dst, src));
+                Some(dst)
+            }
+            (UnaryOp::BoolNot, RegKind::Bool) => {
+                let src = self.compile(&unary_exp.exp, want)?;
+                let dst = self.alloc(want);
+                self.emit(Instr::BoolNot(dst, src));
+                Some(dst)
+            }
+            _ => None,
+        }
+    }
+
+    fn compile_condition(&mut self, cond: &Condition) -> Option<(RegKind, Reg)> {
+        let (kind, _) = reg_kind(&cond.result_type)?;
+        let cond_reg = self.compile(&cond.cond, RegKind::Bool)?;
+        let dst = self.alloc(kind);
+        let else_jump = self.emit(Instr::JumpIfNot(cond_reg, 0));
+        let reg1 = self.compile(&cond.exp1, kind)?;
+        self.emit_move(kind, dst, reg1);
+        let end_jump = self.emit(Instr::Jump(0));
+        self.patch_jump(else_jump);
+        let reg2 = self.compile(&cond.exp2, kind)?;
+        self.emit_move(kind, dst, reg2);
+        self.patch_jump(end_jump);
+        Some((kind, dst))
+    }
+
+    fn compile_binary(&mut self, binary_exp: &BinaryExp) -> Option<(RegKind, Reg)> {
+        match binary_exp.op {
+            BinaryOp::BoolAnd | BinaryOp::BoolOr => {
+                let dst = self.alloc(RegKind::Bool);
+                let reg1 = self.compile(&binary_exp.exp1, RegKind::Bool)?;
+                self.emit(Instr::MoveBool(dst, reg1));
+                let end_jump = match binary_exp.op {
+                    BinaryOp::BoolAnd => self.emit(Instr::JumpIfNot(dst, 0)),
+                    _ => self.emit(Instr::JumpIf(dst, 0)),
+                };
+                let reg2 = self.compile(&binary_exp.exp2, RegKind::Bool)?;
+                self.emit(Instr::MoveBool(dst, reg2));
+                self.patch_jump(end_jump);
+                Some((RegKind::Bool, dst))
+            }
+            ref op => {
+                let (kind, _) = reg_kind(&binary_exp.ref_type)?;
+                let reg1 = self.compile(&binary_exp.exp1, kind)?;
+                let reg2 = self.compile(&binary_exp.exp2, kind)?;
+                if let Some(op) = arith_op(op) {
+                    let dst = self.alloc(kind);
+                    self.emit(match kind {
+                        RegKind::Float => Instr::FloatOp(op, dst, reg1, reg2),
+                        RegKind::Int => Instr::IntOp(op, dst, reg1, reg2),
+                        RegKind::Bool => return None,
+                    });
+                    Some((kind, dst))
+                } else {
+                    let op = cmp_op(op)?;
+                    let dst = self.alloc(RegKind::Bool);
+                    self.emit(match (kind, op) {
+                        (RegKind::Float, op) => Instr::FloatCmp(op, dst, reg1, reg2),
+                        (RegKind::Int, op) => Instr::IntCmp(op, dst, reg1, reg2),
+                        (RegKind::Bool, CmpOp::Eq) | (RegKind::Bool, CmpOp::Neq) => {
+                            Instr::BoolCmp(op, dst, reg1, reg2)
+                        }
+                        _ => return None,
+                    });
+                    Some((RegKind::Bool, dst))
+                }
+            }
+        }
+    }
+
+    // Compile the value of the assignment that can be written into the series variable
+    // directly. The declaration with var or the type, the tuple and the field assignments
+    // are left to the tree-walking runtime.
+    fn compile_store(&mut self, stmt: &Statement) -> Option<((RegKind, Reg), VarIndex)> {
+        let (val, var_index) = match stmt {
+            Statement::Assignment(assign)
+                if assign.names.len() == 1
+                    && assign.names[0].value != "_"
+                    && !assign.var
+                    && assign.var_type.is_none() =>
+            {
+                (&assign.val, VarIndex::new(assign.varids.as_ref()?[0], 0))
+            }
+            Statement::VarAssignment(assign) if assign.fields.is_empty() => {
+                (&assign.val, assign.var_index)
+            }
+            _ => return None,
+        };
+        let result = match val {
+            Exp::BinaryExp(binary_exp) => self.compile_binary(binary_exp)?,
+            Exp::Condition(cond) => self.compile_condition(cond)?,
+            _ => return None,
+        };
+        Some((result, var_index))
+    }
+}
+
+// Compile the binary expression to the program. The result of program is boxed the same
+// way as the tree-walking runtime, e.g. the comparison always generates the simple bool.
+pub fn compile_binary_exp(binary_exp: &BinaryExp) -> Option<Program> {
+    let series = match binary_exp.op {
+        BinaryOp::Lt | BinaryOp::Leq | BinaryOp::Gt | BinaryOp::Geq => false,
+        BinaryOp::BoolAnd | BinaryOp::BoolOr => reg_kind(&binary_exp.result_type)?.1,
+        _ => reg_kind(&binary_exp.ref_type)?.1,
+    };
+    let mut compiler = Compiler::new();
+    let result = compiler.compile_binary(binary_exp)?;
+    let counts = compiler.counts();
+    Some(Program::new(compiler.instrs, result, series, counts))
+}
+
+// Compile the statements of the block to the block program. Return None if no statement
+// can be compiled, then the block is run by the tree-walking runtime.
+pub fn compile_block(blk: &Block) -> Option<BlockProgram> {
+    let mut compiler = Compiler::new();
+    let mut steps = Vec::with_capacity(blk.stmts.len());
+    for (i, stmt) in blk.stmts.iter().enumerate() {
+        let mark = (compiler.instrs.len(), compiler.counts());
+        match compiler.compile_store(stmt) {
+            Some((result, var_index)) => steps.push(Step::Store {
+                stmt: i,
+                start: mark.0,
+                end: compiler.instrs.len(),
+                result,
+                var_index,
+            }),
+            None => {
+                compiler.reset(mark);
+                steps.push(Step::Stmt(i));
+            }
+        }
+    }
+    if steps.iter().all(|step| matches!(step, Step::Stmt(_))) {
+        return None;
+    }
+    let counts = compiler.counts();
+    Some(BlockProgram::new(compiler.instrs, steps, counts))
+}
+
+fn lower_exp(exp: &mut Exp) {
+    match exp {
+        Exp::Tuple(tuple) => tuple.exps.iter_mut().for_each(lower_exp),
+        Exp::TypeCast(type_cast) => lower_exp(&mut type_cast.exp),
+        Exp::FuncCall(func_call) => lower_func_call(func_call),
+        Exp::RefCall(ref_call) => {
+            lower_exp(&mut ref_call.name);
+            lower_exp(&mut ref_call.arg);
+        }
+        Exp::PrefixExp(prefix_exp) => lower_exp(&mut prefix_exp.left_exp),
+        Exp::Condition(cond) => {
+            lower_exp(&mut cond.cond);
+            lower_exp(&mut cond.exp1);
+            lower_exp(&mut cond.exp2);
+        }
+        Exp::Ite(ite) => lower_ite(ite),
+        Exp::ForRange(for_range) => lower_for_range(for_range),
+        Exp::Assignment(assign) => lower_exp(&mut assign.val),
+        Exp::VarAssignment(assign) => lower_exp(&mut assign.val),
+        Exp::UnaryExp(unary_exp) => lower_exp(&mut unary_exp.exp),
+        Exp::BinaryExp(binary_exp) => match compile_binary_exp(binary_exp) {
+            Some(program) => binary_exp.program = Some(Box::new(program)),
+            None => {
+                lower_exp(&mut binary_exp.exp1);
+                lower_exp(&mut binary_exp.exp2);
+            }
+        },
+        _ => {}
+    }
+}
+
+fn lower_func_call(func_call: &mut FunctionCall) {
+    lower_exp(&mut func_call.method);
+    func_call.pos_args.iter_mut().for_each(lower_exp);
+    func_call
+        .dict_args
+        .iter_mut()
+        .for_each(|(_, exp)| lower_exp(exp));
+}
+
+fn lower_func_def(func_def: &mut FunctionDef) {
+    if let Some(spec_defs) = &mut func_def.spec_defs {
+        spec_defs
+            .iter_mut()
+            .for_each(|def| lower_block(&mut def.body));
+    }
+}
+
+fn lower_ite(ite: &mut IfThenElse) {
+    lower_exp(&mut ite.cond);
+    lower_block(&mut ite.then_blk);
+    if let Some(blk) = &mut ite.else_blk {
+        lower_block(blk);
+    }
+}
+
+fn lower_for_range(for_range: &mut ForRange) {
+    lower_exp(&mut for_range.start);
+    lower_exp(&mut for_range.end);
+    if let Some(step) = &mut for_range.step {
+        lower_exp(step);
+    }
+    lower_block(&mut for_range.do_blk);
+}
+
+// Attach the compiled programs to all the blocks and the binary expressions of the
+// type-checked block. The binary expressions of the compiled statements are also lowered
+// for the statements run by the tree-walking runtime when the block program gives up.
+// Only the specific function definitions are lowered because the generic definition
+// is never run directly.
+pub fn lower_block(blk: &mut Block) {
+    for stmt in blk.stmts.iter_mut() {
+        match stmt {
+            Statement::Assignment(assign) => lower_exp(&mut assign.val),
+            Statement::VarAssignment(assign) => lower_exp(&mut assign.val),
+            Statement::Ite(ite) => lower_ite(ite),
+            Statement::ForRange(for_range) => lower_for_range(for_range),
+            Statement::FuncCall(func_call) => lower_func_call(func_call),
+            Statement::FuncDef(func_def) => lower_func_def(func_def),
+            Statement::Import(import) => import.defs.iter_mut().for_each(lower_func_def),
+            Statement::TypeDef(type_def) => type_def
+                .fields
+                .iter_mut()
+                .filter_map(|f| f.default.as_mut())
+                .for_each(lower_exp),
+            Statement::Exp(exp) => lower_exp(exp),
+            Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::None(_)
+            | Statement::Error(_) => {}
+        }
+    }
+    if let Some(exp) = &mut blk.ret_stmt {
+        lower_exp(exp);
+    }
+    blk.program = compile_block(blk).map(Box::new);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+    use crate::{default_lib_info, LibInfo, PineParser};
+
+    fn parse_blk<'a>(src: &'a str, lib_info: &LibInfo<'a>) -> Block<'a> {
+        let mut parser = PineParser::new(src, lib_info);
+        parser.set_vm(false);
+        parser.parse_blk().unwrap()
+    }
+
+    #[test]
+    fn compile_block_test() {
+        let lib_info = LibInfo::new(
+            vec![],
+            vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+        );
+        let blk = parse_blk(
+            "m = close * 2\nn = m > 1 ? m : 1\nk = 1\nm := n - 1",
+            &lib_info,
+        );
+        let program = compile_block(&blk).unwrap();
+        assert_eq!(
+            &program.get_steps()[..3],
+            &[
+                Step::Store {
+                    stmt: 0,
+                    start: 0,
+                    end: 3,
+                    result: (RegKind::Float, 2),
+                    var_index: VarIndex::new(0, 0),
+                },
+                Step::Store {
+                    stmt: 1,
+                    start: 3,
+                    end: 12,
+                    result: (RegKind::Float, 5),
+                    var_index: VarIndex::new(1, 0),
+                },
+                Step::Stmt(2),
+            ]
+        );
+        assert_eq!(
+            &program.get_instrs()[..3],
+            &[
+                Instr::VarFloat(0, VarIndex::new(0, 1), None),
+                Instr::LoadFloat(1, Some(2f64)),
+                Instr::FloatOp(ArithOp::Mul, 2, 0, 1),
+            ]
+        );
+        match program.get_steps()[3] {
+            Step::Store {
+                stmt: 3, var_index, ..
+            } => assert_eq!(var_index, VarIndex::new(0, 0)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn compile_block_reset_test() {
+        let lib_info = default_lib_info();
+        // The statement is dropped after some of its instructions are emitted.
+        let blk = parse_blk(
+            "m = (close > 1 ? 1 : 2) + nz(close)\nn = close + 1",
+            &lib_info,
+        );
+        let program = compile_block(&blk).unwrap();
+        assert_eq!(program.get_steps()[0], Step::Stmt(0));
+        match program.get_steps()[1] {
+            Step::Store { start, end, .. } => assert_eq!((start, end), (0, 3)),
+            _ => unreachable!(),
+        }
+        assert_eq!(program.get_instrs().len(), 3);
+
+        // The declarations with var or type, the tuples and the plain values are not compiled.
+        for src in &[
+            "var m = close + 1",
+            "float m = close + 1",
+            "[a, b] = [close, close + 1]",
+            "m = close",
+            "m = nz(close + 1)",
+        ] {
+            assert_eq!(compile_block(&parse_blk(src, &lib_info)), None);
+        }
+    }
+
+    #[test]
+    fn lower_nested_block_test() {
+        let lib_info = default_lib_info();
+        let mut parser = PineParser::new(
+            "m = 0.0\nif close > 1\n    m := close * 2\nplot(m)",
+            &lib_info,
+        );
+        let blk = parser.parse_blk().unwrap();
+        assert_eq!(blk.program, None);
+        match &blk.stmts[1] {
+            Statement::Ite(ite) => {
+                let program = ite.then_blk.program.as_ref().unwrap();
+                assert!(matches!(
+                    program.get_steps()[0],
+                    Step::Store { stmt: 0, .. }
+                ));
+            }
+            _ => unreachable!(),
+        }
+    }
+}
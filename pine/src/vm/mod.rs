@@ -0,0 +1,619 @@
+// The bytecode compiler and virtual machine for the pure expressions.
+//
+// The tree-walking runtime boxes every intermediate value into PineRef and downcasts it
+// again in the next operator. The expressions only made up of literals, variables,
+// history references, unary, binary and condition operators are lowered to a flat
+// instruction list that works on the unboxed float, int and bool registers.
+//
+// Every block is lowered to a block program. The assignments of these expressions are
+// compiled into one instruction list for the whole block, and the values are written into
+// the series variables created on the first bar without boxing. The other statements and
+// the binary expressions nested in them are still run by the tree-walking runtime. If the
+// variables don't have the expected types at runtime, the program gives up and the
+// tree-walking runtime runs the statement or evaluates the expression instead.
+use crate::ast::stat_expr_types::{Block, VarIndex};
+use crate::runtime::context::{Ctx, PineRuntimeError, StmtRunner};
+use crate::types::{
+    downcast_pf_mut, downcast_pf_ref, Arithmetic, Bool, DataType as FirstType, Float, Int,
+    Negative, PineRef, RuntimeErr, SecondType, Series,
+};
+use std::cell::RefCell;
+use std::fmt;
+
+mod compiler;
+
+pub use compiler::{compile_binary_exp, compile_block, lower_block};
+
+// The index of register.
+pub type Reg = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Lt,
+    Leq,
+    Gt,
+    Geq,
+    Eq,
+    Neq,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    LoadFloat(Reg, Float),
+    LoadInt(Reg, Int),
+    LoadBool(Reg, Bool),
+    // Load the variable's value with the history offset stored in the int register.
+    VarFloat(Reg, VarIndex, Option<Reg>),
+    VarInt(Reg, VarIndex, Option<Reg>),
+    VarBool(Reg, VarIndex, Option<Reg>),
+    IntToFloat(Reg, Reg),
+    FloatOp(ArithOp, Reg, Reg, Reg),
+    IntOp(ArithOp, Reg, Reg, Reg),
+    FloatCmp(CmpOp, Reg, Reg, Reg),
+    IntCmp(CmpOp, Reg, Reg, Reg),
+    BoolCmp(CmpOp, Reg, Reg, Reg),
+    FloatNeg(Reg, Reg),
+    IntNeg(Reg, Reg),
+    BoolNot(Reg, Reg),
+    MoveFloat(Reg, Reg),
+    MoveInt(Reg, Reg),
+    MoveBool(Reg, Reg),
+    Jump(usize),
+    JumpIf(Reg, usize),
+    JumpIfNot(Reg, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegKind {
+    Float,
+    Int,
+    Bool,
+}
+
+#[derive(Debug, Clone)]
+struct Registers {
+    floats: Vec<Float>,
+    ints: Vec<Int>,
+    bools: Vec<Bool>,
+}
+
+impl Registers {
+    fn new(counts: (usize, usize, usize)) -> Registers {
+        Registers {
+            floats: vec![None; counts.0],
+            ints: vec![None; counts.1],
+            bools: vec![false; counts.2],
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Program {
+    instrs: Vec<Instr>,
+    // The result register is boxed into the same value type with the tree-walking runtime.
+    result: (RegKind, Reg),
+    series: bool,
+    // The registers are allocated once and reused for every bar.
+    registers: RefCell<Registers>,
+}
+
+impl fmt::Debug for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Program")
+            .field("instrs", &self.instrs)
+            .field("result", &self.result)
+            .field("series", &self.series)
+            .finish()
+    }
+}
+
+impl PartialEq for Program {
+    fn eq(&self, other: &Program) -> bool {
+        self.instrs == other.instrs && self.result == other.result && self.series == other.series
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    // Run the statement of the block with the tree-walking runtime.
+    Stmt(usize),
+    // Run the instructions in the range and write the result into the series variable.
+    Store {
+        stmt: usize,
+        start: usize,
+        end: usize,
+        result: (RegKind, Reg),
+        var_index: VarIndex,
+    },
+}
+
+// The program of the block, the steps run the statements of the block in order.
+#[derive(Clone)]
+pub struct BlockProgram {
+    instrs: Vec<Instr>,
+    steps: Vec<Step>,
+    // The registers are shared by all the statements and reused for every bar.
+    registers: RefCell<Registers>,
+}
+
+impl fmt::Debug for BlockProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockProgram")
+            .field("instrs", &self.instrs)
+            .field("steps", &self.steps)
+            .finish()
+    }
+}
+
+impl PartialEq for BlockProgram {
+    fn eq(&self, other: &BlockProgram) -> bool {
+        self.instrs == other.instrs && self.steps == other.steps
+    }
+}
+
+fn arith<D: Arithmetic>(op: ArithOp, v1: D, v2: D) -> D {
+    match op {
+        ArithOp::Add => v1.add(v2),
+        ArithOp::Sub => v1.minus(v2),
+        ArithOp::Mul => v1.mul(v2),
+        ArithOp::Div => v1.div(v2),
+        ArithOp::Rem => v1.rem(v2),
+    }
+}
+
+fn compare<D: PartialOrd>(op: CmpOp, v1: &D, v2: &D) -> bool {
+    match op {
+        CmpOp::Lt => v1 < v2,
+        CmpOp::Leq => v1 <= v2,
+        CmpOp::Gt => v1 > v2,
+        CmpOp::Geq => v1 >= v2,
+        CmpOp::Eq => v1 == v2,
+        CmpOp::Neq => v1 != v2,
+    }
+}
+
+// The negative and na offsets are left to the tree-walking runtime that reports the error
+// with the range of the history reference.
+fn history_offset(regs: &Registers, offset: Option<Reg>) -> Option<Option<usize>> {
+    match offset {
+        None => Some(None),
+        Some(r) => match regs.ints[r] {
+            Some(i) if i >= 0 => Some(Some(i as usize)),
+            _ => None,
+        },
+    }
+}
+
+// Read the value of variable without moving it out of the context. The type is checked
+// before downcasting, and the simple values can't be referenced by history offset here.
+fn load_float<'a>(ctx: &dyn Ctx<'a>, index: VarIndex, offset: Option<usize>) -> Option<Float> {
+    let val = ctx.get_var(index).as_ref()?;
+    match (val.get_type(), offset) {
+        ((FirstType::NA, SecondType::Simple), None) => Some(None),
+        ((FirstType::Float, SecondType::Simple), None) => {
+            Some(*downcast_pf_ref::<Float>(val).ok()?)
+        }
+        ((FirstType::Int, SecondType::Simple), None) => {
+            Some(downcast_pf_ref::<Int>(val).ok()?.map(|i| i as f64))
+        }
+        ((FirstType::Float, SecondType::Series), offset) => Some(
+            downcast_pf_ref::<Series<Float>>(val)
+                .ok()?
+                .at(offset.unwrap_or(0)),
+        ),
+        ((FirstType::Int, SecondType::Series), offset) => {
+            let series = downcast_pf_ref::<Series<Int>>(val).ok()?;
+            Some(series.at(offset.unwrap_or(0)).map(|i| i as f64))
+        }
+        _ => None,
+    }
+}
+
+fn load_int<'a>(ctx: &dyn Ctx<'a>, index: VarIndex, offset: Option<usize>) -> Option<Int> {
+    let val = ctx.get_var(index).as_ref()?;
+    match (val.get_type(), offset) {
+        ((FirstType::NA, SecondType::Simple), None) => Some(None),
+        ((FirstType::Int, SecondType::Simple), None) => Some(*downcast_pf_ref::<Int>(val).ok()?),
+        ((FirstType::Int, SecondType::Series), offset) => Some(
+            downcast_pf_ref::<Series<Int>>(val)
+                .ok()?
+                .at(offset.unwrap_or(0)),
+        ),
+        _ => None,
+    }
+}
+
+fn load_bool<'a>(ctx: &dyn Ctx<'a>, index: VarIndex, offset: Option<usize>) -> Option<Bool> {
+    let val = ctx.get_var(index).as_ref()?;
+    match (val.get_type(), offset) {
+        ((FirstType::Bool, SecondType::Simple), None) => Some(*downcast_pf_ref::<Bool>(val).ok()?),
+        ((FirstType::Bool, SecondType::Series), offset) => Some(
+            downcast_pf_ref::<Series<Bool>>(val)
+                .ok()?
+                .at(offset.unwrap_or(0)),
+        ),
+        _ => None,
+    }
+}
+
+// Run the instructions from start to end, the jump targets are the indexes of the whole
+// instruction list. Return None if the variables don't have the expected types.
+fn execute<'a>(
+    instrs: &[Instr],
+    start: usize,
+    end: usize,
+    regs: &mut Registers,
+    ctx: &dyn Ctx<'a>,
+) -> Option<()> {
+    let mut pc = start;
+    while pc < end {
+        match instrs[pc] {
+            Instr::LoadFloat(dst, v) => regs.floats[dst] = v,
+            Instr::LoadInt(dst, v) => regs.ints[dst] = v,
+            Instr::LoadBool(dst, v) => regs.bools[dst] = v,
+            Instr::VarFloat(dst, index, offset) => {
+                let offset = history_offset(regs, offset)?;
+                regs.floats[dst] = load_float(ctx, index, offset)?;
+            }
+            Instr::VarInt(dst, index, offset) => {
+                let offset = history_offset(regs, offset)?;
+                regs.ints[dst] = load_int(ctx, index, offset)?;
+            }
+            Instr::VarBool(dst, index, offset) => {
+                let offset = history_offset(regs, offset)?;
+                regs.bools[dst] = load_bool(ctx, index, offset)?;
+            }
+            Instr::IntToFloat(dst, src) => regs.floats[dst] = regs.ints[src].map(|i| i as f64),
+            Instr::FloatOp(op, dst, r1, r2) => {
+                regs.floats[dst] = arith(op, regs.floats[r1], regs.floats[r2])
+            }
+            Instr::IntOp(op, dst, r1, r2) => {
+                regs.ints[dst] = arith(op, regs.ints[r1], regs.ints[r2])
+            }
+            Instr::FloatCmp(op, dst, r1, r2) => {
+                regs.bools[dst] = compare(op, &regs.floats[r1], &regs.floats[r2])
+            }
+            Instr::IntCmp(op, dst, r1, r2) => {
+                regs.bools[dst] = compare(op, &regs.ints[r1], &regs.ints[r2])
+            }
+            Instr::BoolCmp(op, dst, r1, r2) => {
+                regs.bools[dst] = compare(op, &regs.bools[r1], &regs.bools[r2])
+            }
+            Instr::FloatNeg(dst, src) => regs.floats[dst] = regs.floats[src].negative(),
+            Instr::IntNeg(dst, src) => regs.ints[dst] = regs.ints[src].negative(),
+            Instr::BoolNot(dst, src) => regs.bools[dst] = !regs.bools[src],
+            Instr::MoveFloat(dst, src) => regs.floats[dst] = regs.floats[src],
+            Instr::MoveInt(dst, src) => regs.ints[dst] = regs.ints[src],
+            Instr::MoveBool(dst, src) => regs.bools[dst] = regs.bools[src],
+            Instr::Jump(target) => {
+                pc = target;
+                continue;
+            }
+            Instr::JumpIf(cond, target) => {
+                if regs.bools[cond] {
+                    pc = target;
+                    continue;
+                }
+            }
+            Instr::JumpIfNot(cond, target) => {
+                if !regs.bools[cond] {
+                    pc = target;
+                    continue;
+                }
+            }
+        }
+        pc += 1;
+    }
+    Some(())
+}
+
+// Update the current value of the series variable in place. Return false if the variable
+// is not created yet or it is not the series that takes the value without conversion
+// except int to float, then the statement should be run by the tree-walking runtime.
+fn store_series<'a>(
+    ctx: &mut dyn Ctx<'a>,
+    index: VarIndex,
+    regs: &Registers,
+    (kind, reg): (RegKind, Reg),
+) -> bool {
+    let mut var = match ctx.move_var(index) {
+        Some(var) => var,
+        None => return false,
+    };
+    let stored = match (var.get_type(), kind) {
+        ((FirstType::Float, SecondType::Series), RegKind::Float) => {
+            downcast_pf_mut::<Series<Float>>(&mut var).map(|s| s.update(regs.floats[reg]))
+        }
+        ((FirstType::Float, SecondType::Series), RegKind::Int) => {
+            let val = regs.ints[reg].map(|i| i as f64);
+            downcast_pf_mut::<Series<Float>>(&mut var).map(|s| s.update(val))
+        }
+        ((FirstType::Int, SecondType::Series), RegKind::Int) => {
+            downcast_pf_mut::<Series<Int>>(&mut var).map(|s| s.update(regs.ints[reg]))
+        }
+        ((FirstType::Bool, SecondType::Series), RegKind::Bool) => {
+            downcast_pf_mut::<Series<Bool>>(&mut var).map(|s| s.update(regs.bools[reg]))
+        }
+        _ => Err(RuntimeErr::NotSupportOperator),
+    };
+    ctx.update_var(index, var);
+    stored.is_ok()
+}
+
+impl Program {
+    fn new(
+        instrs: Vec<Instr>,
+        result: (RegKind, Reg),
+        series: bool,
+        counts: (usize, usize, usize),
+    ) -> Program {
+        Program {
+            instrs,
+            result,
+            series,
+            registers: RefCell::new(Registers::new(counts)),
+        }
+    }
+
+    pub fn get_instrs(&self) -> &Vec<Instr> {
+        &self.instrs
+    }
+
+    // Run the program and box the result. Return None if the program can't handle the
+    // runtime values, then the caller should fall back to the tree-walking runtime.
+    pub fn run<'a>(&self, ctx: &dyn Ctx<'a>) -> Option<PineRef<'a>> {
+        let mut regs = self.registers.borrow_mut();
+        execute(&self.instrs, 0, self.instrs.len(), &mut regs, ctx)?;
+
+        let (kind, reg) = self.result;
+        Some(match (kind, self.series) {
+            (RegKind::Float, true) => PineRef::new_rc(Series::from(regs.floats[reg])),
+            (RegKind::Float, false) => PineRef::new_box(regs.floats[reg]),
+            (RegKind::Int, true) => PineRef::new_rc(Series::from(regs.ints[reg])),
+            (RegKind::Int, false) => PineRef::new_box(regs.ints[reg]),
+            (RegKind::Bool, true) => PineRef::new_rc(Series::from(regs.bools[reg])),
+            (RegKind::Bool, false) => PineRef::new_box(regs.bools[reg]),
+        })
+    }
+}
+
+impl BlockProgram {
+    fn new(instrs: Vec<Instr>, steps: Vec<Step>, counts: (usize, usize, usize)) -> BlockProgram {
+        BlockProgram {
+            instrs,
+            steps,
+            registers: RefCell::new(Registers::new(counts)),
+        }
+    }
+
+    pub fn get_instrs(&self) -> &Vec<Instr> {
+        &self.instrs
+    }
+
+    pub fn get_steps(&self) -> &Vec<Step> {
+        &self.steps
+    }
+
+    // Run the statements of the block. The compiled statement is run by the tree-walking
+    // runtime if the program gives up, the statement has no side effect before the store.
+    pub fn run<'a>(
+        &self,
+        blk: &'a Block<'a>,
+        ctx: &mut dyn Ctx<'a>,
+    ) -> Result<(), PineRuntimeError> {
+        for step in self.steps.iter() {
+            match *step {
+                Step::Stmt(i) => blk.stmts[i].st_run(ctx)?,
+                Step::Store {
+                    stmt,
+                    start,
+                    end,
+                    result,
+                    var_index,
+                } => {
+                    let stored = {
+                        let mut regs = self.registers.borrow_mut();
+                        execute(&self.instrs, start, end, &mut regs, &*ctx).is_some()
+                            && store_series(ctx, var_index, &regs, result)
+                    };
+                    if !stored {
+                        blk.stmts[stmt].st_run(ctx)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::input::StrRange;
+    use crate::ast::num::Numeral;
+    use crate::ast::op::BinaryOp;
+    use crate::ast::stat_expr_types::{BinaryExp, Exp, RVVarName, Statement};
+    use crate::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+    use crate::runtime::context::{Context, ContextType, VarOperate};
+    use crate::runtime::data_src::NoneCallback;
+    use crate::runtime::AnySeries;
+    use crate::{LibInfo, PineParser, PineScript};
+
+    fn close_lib_info<'a>() -> LibInfo<'a> {
+        LibInfo::new(
+            vec![],
+            vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+        )
+    }
+
+    #[test]
+    fn lower_block_test() {
+        let lib_info = close_lib_info();
+        let mut parser = PineParser::new("m = close[1] * 2 + 1", &lib_info);
+        let blk = parser.parse_blk().unwrap();
+        match &blk.stmts[0] {
+            Statement::Assignment(assign) => match &assign.val {
+                Exp::BinaryExp(exp) => assert_eq!(
+                    exp.program.as_ref().unwrap().get_instrs(),
+                    &vec![
+                        Instr::LoadInt(0, Some(1)),
+                        Instr::VarFloat(0, VarIndex::new(0, 1), Some(0)),
+                        Instr::LoadFloat(1, Some(2f64)),
+                        Instr::FloatOp(ArithOp::Mul, 2, 0, 1),
+                        Instr::LoadFloat(3, Some(1f64)),
+                        Instr::FloatOp(ArithOp::Add, 4, 2, 3),
+                    ]
+                ),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        let mut parser = PineParser::new("m = close + 1", &lib_info);
+        parser.set_vm(false);
+        match &parser.parse_blk().unwrap().stmts[0] {
+            Statement::Assignment(assign) => match &assign.val {
+                Exp::BinaryExp(exp) => assert_eq!(exp.program, None),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn program_fallback_test() {
+        let mut exp = BinaryExp::new(
+            BinaryOp::Plus,
+            Exp::VarName(RVVarName::new_with_index("close", VarIndex::new(0, 0))),
+            Exp::Num(Numeral::from_i64(1)),
+            StrRange::new_empty(),
+        );
+        exp.ref_type = SyntaxType::Series(SimpleSyntaxType::Float);
+        let program = compile_binary_exp(&exp).unwrap();
+
+        let mut context = Context::new(None, ContextType::Normal);
+        context.init_vars(vec![Some(PineRef::new(Series::from(Some(1f64))))]);
+        assert_eq!(
+            downcast_pf_ref::<Series<Float>>(&program.run(&context).unwrap()),
+            Ok(&Series::from(Some(2f64)))
+        );
+
+        // The string and uninitialized variables are left to the tree-walking runtime.
+        context.init_vars(vec![Some(PineRef::new_rc(String::from("hello")))]);
+        assert!(program.run(&context).is_none());
+        context.init_vars(vec![None]);
+        assert!(program.run(&context).is_none());
+    }
+
+    #[test]
+    fn store_series_test() {
+        let regs = Registers {
+            floats: vec![Some(2f64)],
+            ints: vec![Some(3)],
+            bools: vec![true],
+        };
+        let index = VarIndex::new(0, 0);
+        let store = |context: &mut Context, kind| store_series(context, index, &regs, (kind, 0));
+        let mut context = Context::new(None, ContextType::Normal);
+        context.init_vars(vec![None]);
+        assert!(!store(&mut context, RegKind::Float));
+
+        // The value is written into the series created before without boxing.
+        let series = PineRef::new_rc(Series::from(Some(1f64)));
+        context.init_vars(vec![Some(series.clone())]);
+        assert!(store(&mut context, RegKind::Float));
+        assert_eq!(
+            downcast_pf_ref::<Series<Float>>(&series),
+            Ok(&Series::from(Some(2f64)))
+        );
+        assert!(store(&mut context, RegKind::Int));
+        assert_eq!(
+            downcast_pf_ref::<Series<Float>>(&series),
+            Ok(&Series::from(Some(3f64)))
+        );
+        assert!(!store(&mut context, RegKind::Bool));
+        assert!(context.get_var(index).is_some());
+
+        // The simple variables are left to the tree-walking runtime.
+        context.init_vars(vec![Some(PineRef::new_box(Some(1f64)))]);
+        assert!(!store(&mut context, RegKind::Float));
+        assert_eq!(
+            downcast_pf_ref::<Float>(context.get_var(index).as_ref().unwrap()),
+            Ok(&Some(1f64))
+        );
+    }
+
+    #[test]
+    fn negative_history_ref_test() {
+        let run = |vm: bool| {
+            let mut script = PineScript::new(Some(&NoneCallback()));
+            script.set_vm(vm);
+            script
+                .parse_src(String::from("m = close[bar_index - 1] + 1\nplot(m)"))
+                .unwrap();
+            let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64); 3]))];
+            script
+                .run_with_data(data, None)
+                .map(|_| ())
+                .map_err(|e| e.message)
+        };
+        assert_eq!(
+            run(true),
+            Err(String::from(
+                "The offset -1 of the history reference can't be negative."
+            ))
+        );
+        assert_eq!(run(true), run(false));
+    }
+
+    #[test]
+    fn vm_ast_same_output_test() {
+        const SCRIPT: &str = r#"
+a = close + open * 2 - 1
+b = close[1] / open
+c = close > open ? a : b
+d = (close >= open and not (open < 2)) or close == open
+i = 7 % 3 + bar_index
+e = -close + (i == 2 ? 1 : na)
+f = bar_index[i] * 2 + i / 2
+g = 0.0
+g := g[1] + close
+if close > open
+    g := g * 2
+plot(a)
+plot(b)
+plot(c)
+plot(d ? 1 : 0)
+plot(e)
+plot(f)
+plot(g)
+"#;
+        let run = |vm: bool| {
+            let mut script = PineScript::new(Some(&NoneCallback()));
+            script.set_vm(vm);
+            script.parse_src(String::from(SCRIPT)).unwrap();
+            let data = vec![
+                (
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), None, Some(3f64), Some(2f64)]),
+                ),
+                (
+                    "open",
+                    AnySeries::from_float_vec(vec![Some(2f64), Some(1f64), Some(0f64), None]),
+                ),
+            ];
+            script.run_with_data(data, None)
+        };
+        assert!(run(true).is_ok());
+        assert_eq!(run(true), run(false));
+    }
+}
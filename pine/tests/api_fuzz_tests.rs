@@ -0,0 +1,233 @@
+extern crate pine;
+use pine::runtime::{AnySeries, InputVal, NoneCallback, RuntimeSnapshot};
+use pine::PineScript;
+use proptest::prelude::*;
+
+const SOURCES: [&str; 6] = [
+    "m = input(2, 'len', 'int')\nplot(sma(close, m) + volume)",
+    "plot(close[3] + high - low)",
+    "a = close > open ? 1 : na\nplot(a)",
+    "plot(close +)",
+    "f(x) => x * 2\nplot(f(time))",
+    "",
+];
+
+// The tokens to build the random sources for the parser.
+const TOKENS: [&str; 40] = [
+    "plot",
+    "close",
+    "sma",
+    "input",
+    "if ",
+    "for ",
+    "i = 0 to 2",
+    "=>",
+    ":=",
+    "var ",
+    "type ",
+    "method ",
+    "[",
+    "]",
+    "(",
+    ")",
+    "1",
+    "2.5",
+    "\n",
+    "\n    ",
+    "x",
+    "y",
+    " = ",
+    "+",
+    "-",
+    "*",
+    "/",
+    "?",
+    ":",
+    ",",
+    "na",
+    "true",
+    "'s'",
+    "#ff0000",
+    "f",
+    "security",
+    "ema",
+    "float ",
+    "int ",
+    ".",
+];
+
+const NAMES: [&str; 8] = [
+    "close", "open", "high", "low", "volume", "time", "_time", "unknown",
+];
+
+#[derive(Debug, Clone)]
+enum Op {
+    Parse(usize),
+    Run(Vec<(usize, bool, usize)>, usize),
+    Update(Vec<(usize, bool, usize)>, usize),
+    UpdateFrom(Vec<(usize, bool, usize)>, i32, usize),
+    RunWithInput(Vec<Option<i64>>),
+    RunWithOdi,
+    GenIoInfo,
+    MoveOutput,
+    Snapshot,
+    Restore,
+    TraceVars,
+    RenderOutputs(bool),
+    OutputDiff(bool),
+    AckRevision(u64),
+}
+
+// The data sources with the random names, types and lengths.
+fn gen_data(sources: &[(usize, bool, usize)]) -> Vec<(&'static str, AnySeries)> {
+    sources
+        .iter()
+        .map(|&(name, is_float, len)| {
+            let series = if is_float {
+                AnySeries::from_float_vec((0..len).map(|i| Some(i as f64)).collect())
+            } else {
+                AnySeries::from_int_vec((0..len).map(|i| Some(i as i64)).collect())
+            };
+            (NAMES[name], series)
+        })
+        .collect()
+}
+
+fn data_strategy() -> impl Strategy<Value = Vec<(usize, bool, usize)>> {
+    prop::collection::vec((0..NAMES.len(), any::<bool>(), 0usize..6), 0..4)
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..SOURCES.len()).prop_map(Op::Parse),
+        (data_strategy(), 0usize..8).prop_map(|(d, l)| Op::Run(d, l)),
+        (data_strategy(), 0usize..8).prop_map(|(d, l)| Op::Update(d, l)),
+        (data_strategy(), -3i32..10, 0usize..8).prop_map(|(d, f, l)| Op::UpdateFrom(d, f, l)),
+        prop::collection::vec(prop::option::of(-5i64..5), 0..3).prop_map(Op::RunWithInput),
+        Just(Op::RunWithOdi),
+        Just(Op::GenIoInfo),
+        Just(Op::MoveOutput),
+        Just(Op::Snapshot),
+        Just(Op::Restore),
+        Just(Op::TraceVars),
+        any::<bool>().prop_map(Op::RenderOutputs),
+        any::<bool>().prop_map(Op::OutputDiff),
+        (0u64..5).prop_map(Op::AckRevision),
+    ]
+}
+
+fn run_ops(ops: Vec<Op>) {
+    let mut script = PineScript::new(Some(&NoneCallback()));
+    let mut snapshot: Option<RuntimeSnapshot> = None;
+    for op in ops {
+        match op {
+            Op::Parse(i) => {
+                let _ = script.parse_src(String::from(SOURCES[i]));
+            }
+            Op::Run(data, len) => {
+                let _ = script.run_with_data(gen_data(&data), None);
+                let _ = script.run_with_datal(gen_data(&data), len, None);
+            }
+            Op::Update(data, len) => {
+                let _ = script.update(gen_data(&data));
+                let _ = script.updatel(gen_data(&data), len);
+            }
+            Op::UpdateFrom(data, from, len) => {
+                let _ = script.update_from(gen_data(&data), from);
+                let _ = script.update_froml(gen_data(&data), from, len);
+            }
+            Op::RunWithInput(inputs) => {
+                let inputs = inputs.into_iter().map(|i| i.map(InputVal::Int)).collect();
+                let _ = script.run_with_input(inputs);
+            }
+            Op::RunWithOdi => {
+                let _ = script.run_with_odi();
+            }
+            Op::GenIoInfo => {
+                let _ = script.gen_io_info();
+            }
+            Op::MoveOutput => {
+                let _ = script.move_output_data();
+            }
+            Op::Snapshot => {
+                snapshot = script.snapshot().ok();
+            }
+            Op::Restore => {
+                if let Some(snapshot) = snapshot.clone() {
+                    let _ = script.restore(snapshot);
+                }
+            }
+            Op::TraceVars => {
+                let _ = script.trace_vars(&["m", "a"]);
+            }
+            Op::RenderOutputs(enable) => {
+                script.set_render_outputs(enable);
+            }
+            Op::OutputDiff(enable) => {
+                script.set_output_diff(enable);
+                let _ = script.output_diff();
+            }
+            Op::AckRevision(revision) => {
+                let _ = script.ack_revision(revision);
+            }
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    // Any sequence of the public API calls returns errors instead of panic.
+    #[test]
+    fn api_never_panics(ops in prop::collection::vec(op_strategy(), 1..12)) {
+        run_ops(ops);
+    }
+
+    // Any source returns the syntax errors instead of panic.
+    #[test]
+    fn parse_never_panics(tokens in prop::collection::vec(0..TOKENS.len(), 0..25)) {
+        let src: String = tokens.iter().map(|&i| TOKENS[i]).collect();
+        let mut script = PineScript::new(Some(&NoneCallback()));
+        if script.parse_src(src).is_ok() {
+            let data = vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), None]),
+            )];
+            let _ = script.run_with_data(data, None);
+        }
+    }
+}
+
+#[test]
+fn api_errors_test() {
+    let mut script = PineScript::new(None);
+    // The script is not parsed.
+    assert!(script.run_with_data(vec![], None).is_err());
+    assert!(script.run_with_odi().is_err());
+    assert!(script.move_output_data().is_err());
+    assert!(script.snapshot().is_err());
+
+    script.parse_src(String::from("plot(close)")).unwrap();
+    // The script is not run with the data.
+    let data = || vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+    assert!(script.updatel(data(), 1).is_err());
+    assert!(script.update_froml(data(), 0, 1).is_err());
+
+    // The data is shorter than the count of bars.
+    assert!(script.run_with_datal(data(), 3, None).is_err());
+    assert!(script.run_with_datal(data(), 1, None).is_ok());
+    assert!(script.updatel(data(), 0).is_err());
+    assert!(script.update_froml(data(), 5, 1).is_err());
+    assert!(script.update_froml(data(), -1, 1).is_err());
+    // The data sources are not the same as the run data.
+    let other = vec![("open", AnySeries::from_float_vec(vec![Some(1f64)]))];
+    assert!(script.updatel(other, 1).is_err());
+    let int_close = vec![("close", AnySeries::from_int_vec(vec![Some(1i64)]))];
+    assert!(script.run_with_datal(int_close, 1, None).is_err());
+    assert!(script.updatel(data(), 1).is_ok());
+
+    // The invalid sources that the parser panicked on.
+    for src in &["na := na", "xif type = type", "plot(xif(na))"] {
+        assert!(script.parse_src(String::from(*src)).is_err());
+    }
+}
@@ -346,10 +346,12 @@ fn alma_test() {
 
     let result1 = parser
         .get_runner()
+        .unwrap()
         .get_context()
         .move_var(VarIndex::new(0, 0));
     let result2 = parser
         .get_runner()
+        .unwrap()
         .get_context()
         .move_var(VarIndex::new(2, 0));
     let val1 = pine_ref_to_f64_series(result1);
@@ -395,10 +397,12 @@ fn sma_test() {
 
     let result1 = parser
         .get_runner()
+        .unwrap()
         .get_context()
         .move_var(VarIndex::new(0, 0));
     let result2 = parser
         .get_runner()
+        .unwrap()
         .get_context()
         .move_var(VarIndex::new(2, 0));
     let val1 = pine_ref_to_f64_series(result1);
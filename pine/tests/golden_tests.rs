@@ -0,0 +1,416 @@
+// The conformance tests that run every script in tests/golden and compare the outputs with
+// the expected outputs in the same directory.
+//
+// For the script `name.pine`, the input data is read from `name.input.csv` if it exists,
+// otherwise from the shared `bars.csv`. The expected outputs are read from `name.out.json`
+// (the serialized output data list, with the drawings like labels and lines and the printed
+// strings if the script draws or prints any) if it exists, otherwise from `name.out.csv` (one
+// column for every series of the outputs). The
+// floats are compared with the tolerance 1e-9 relative to the expected value, a script can
+// override it by the comment like `//@tolerance=1e-6`.
+//
+// Run `cargo test --test golden_tests -- --bless` to regenerate the expected outputs, and
+// pass the names like `-- sma ema` to only run the scripts whose names contain them. See
+// tests/golden/README.md for where the expected values come from.
+extern crate pine;
+use pine::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+use pine::libs::{
+    barstate, declare_vars, extend, label, line, plotarrow, plotbar, plotcandle, plotchar,
+    plotshape, print, size, text, xloc, yloc,
+};
+use pine::runtime::data_src::Callback;
+use pine::runtime::output::OutputData;
+use pine::runtime::AnySeries;
+use pine::version::script_version;
+use pine::{LibInfo, PineScript};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+const DEFAULT_TOLERANCE: f64 = 1e-9;
+const BAR_INDEX: &str = "bar_index";
+const REFERENCE_FILE: &str = "reference.csv";
+
+// The input sources and whether the source is int.
+const SOURCES: [(&str, bool); 6] = [
+    ("time", true),
+    ("open", false),
+    ("high", false),
+    ("low", false),
+    ("close", false),
+    ("volume", true),
+];
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+}
+
+// Collect the strings printed by the script.
+#[derive(Default)]
+struct PrintCollector {
+    prints: RefCell<Vec<String>>,
+}
+
+impl Callback for PrintCollector {
+    fn print(&self, s: String) {
+        self.prints.borrow_mut().push(s);
+    }
+}
+
+// The builtin variables of v4 and the plotting, state, drawing and print variables that are only
+// used by the customized library information.
+fn golden_lib_info<'a>() -> LibInfo<'a> {
+    let mut vars = declare_vars();
+    vars.extend(vec![
+        plotarrow::declare_var(),
+        plotbar::declare_var(),
+        plotcandle::declare_var(),
+        plotchar::declare_var(),
+        plotshape::declare_var(),
+        barstate::declare_var(),
+        label::declare_var(),
+        line::declare_var(),
+        print::declare_var(),
+        extend::declare_var(),
+        size::declare_var(),
+        text::declare_var(),
+        xloc::declare_var(),
+        yloc::declare_var(),
+    ]);
+    let float = SyntaxType::Series(SimpleSyntaxType::Float);
+    let int = SyntaxType::Series(SimpleSyntaxType::Int);
+    LibInfo::new(
+        vars,
+        vec![
+            ("close", float.clone()),
+            ("open", float.clone()),
+            ("high", float.clone()),
+            ("low", float),
+            ("volume", int.clone()),
+            ("_time", int.clone()),
+            (BAR_INDEX, int),
+        ],
+    )
+}
+
+fn read_input(path: &Path) -> Result<Vec<(&'static str, AnySeries)>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next().ok_or("The input has no header")?;
+    let sources = header
+        .split(',')
+        .map(|name| {
+            SOURCES
+                .iter()
+                .find(|(n, _)| *n == name.trim())
+                .ok_or(format!("Unknown input source {}", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut columns: Vec<Vec<Option<f64>>> = sources.iter().map(|_| vec![]).collect();
+    for line in lines {
+        for (column, cell) in columns.iter_mut().zip(line.split(',')) {
+            column.push(parse_cell(cell)?);
+        }
+    }
+    Ok(sources
+        .into_iter()
+        .zip(columns)
+        .map(|(&(name, is_int), column)| {
+            let series = if is_int {
+                AnySeries::from_int_vec(column.into_iter().map(|v| v.map(|v| v as i64)).collect())
+            } else {
+                AnySeries::from_float_vec(column)
+            };
+            (name, series)
+        })
+        .collect())
+}
+
+// The empty cell is na.
+fn parse_cell(cell: &str) -> Result<Option<f64>, String> {
+    match cell.trim() {
+        "" => Ok(None),
+        s => s
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid number {}", s)),
+    }
+}
+
+fn parse_tolerance(src: &str) -> f64 {
+    src.lines()
+        .filter_map(|l| l.trim().strip_prefix("//@tolerance="))
+        .filter_map(|v| v.trim().parse().ok())
+        .next()
+        .unwrap_or(DEFAULT_TOLERANCE)
+}
+
+fn float_eq(actual: f64, expected: f64, tolerance: f64) -> bool {
+    actual == expected || (actual - expected).abs() <= tolerance * expected.abs().max(1f64)
+}
+
+// The header and the rows of the series of the outputs, the na values are empty cells.
+fn to_csv(data_list: &[Option<OutputData>], from: i32) -> String {
+    let mut header = vec![String::from(BAR_INDEX)];
+    let mut series = vec![];
+    for (i, data) in data_list.iter().enumerate() {
+        if let Some(data) = data {
+            for (j, s) in data
+                .series
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| !s.is_empty())
+            {
+                header.push(format!("output_{}_{}", i, j));
+                series.push(s);
+            }
+        }
+    }
+    let rows = series.iter().map(|s| s.len()).max().unwrap_or(0);
+    let mut lines = vec![header.join(",")];
+    for row in 0..rows {
+        let mut cells = vec![(from as usize + row).to_string()];
+        cells.extend(series.iter().map(|s| match s.get(row) {
+            Some(Some(v)) => v.to_string(),
+            _ => String::new(),
+        }));
+        lines.push(cells.join(","));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn compare_csv(actual: &str, expected: &str, tolerance: f64) -> Result<(), String> {
+    let actual: Vec<_> = actual.lines().collect();
+    let expected: Vec<_> = expected.lines().filter(|l| !l.trim().is_empty()).collect();
+    if actual.first() != expected.first() {
+        return Err(format!(
+            "The columns {:?} are not the expected columns {:?}",
+            actual.first(),
+            expected.first()
+        ));
+    }
+    if actual.len() != expected.len() {
+        return Err(format!(
+            "The count of rows {} is not the expected count {}",
+            actual.len() - 1,
+            expected.len() - 1
+        ));
+    }
+    let columns: Vec<_> = expected[0].split(',').collect();
+    for (row, (a, e)) in actual.iter().zip(expected.iter()).enumerate().skip(1) {
+        for ((column, a), e) in columns.iter().zip(a.split(',')).zip(e.split(',')) {
+            let is_eq = match (parse_cell(a)?, parse_cell(e)?) {
+                (Some(a), Some(e)) => float_eq(a, e, tolerance),
+                (a, e) => a == e,
+            };
+            if !is_eq {
+                return Err(format!(
+                    "The value of {} at the row {} is {:?}, expected {:?}",
+                    column, row, a, e
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Check the outputs against the rows like `sma,output_0_0,9,98.932` of reference.csv, the values
+// are computed from the formulas of the Pine reference manual instead of this engine, so the
+// blessed outputs can't drift from them.
+fn check_reference(dir: &Path, name: &str, actual: &str, tolerance: f64) -> Result<(), String> {
+    let content = match fs::read_to_string(dir.join(REFERENCE_FILE)) {
+        Ok(content) => content,
+        Err(_) => return Ok(()),
+    };
+    let rows: Vec<Vec<&str>> = actual.lines().map(|l| l.split(',').collect()).collect();
+    for line in content.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+        let cells: Vec<_> = line.split(',').map(|c| c.trim()).collect();
+        if cells.len() != 4 {
+            return Err(format!("Invalid reference row {}", line));
+        }
+        if cells[0] != name {
+            continue;
+        }
+        let column = rows[0].iter().position(|c| *c == cells[1]).ok_or(format!(
+            "The reference column {} is not in the outputs",
+            cells[1]
+        ))?;
+        let value = rows
+            .iter()
+            .skip(1)
+            .find(|row| row[0] == cells[2])
+            .map_or(Ok(None), |row| parse_cell(row.get(column).unwrap_or(&"")))?;
+        let is_eq = match (value, parse_cell(cells[3])?) {
+            (Some(a), Some(e)) => float_eq(a, e, tolerance),
+            (a, e) => a == e,
+        };
+        if !is_eq {
+            return Err(format!(
+                "The value of {} at the bar {} is {:?}, the reference value is {}",
+                cells[1], cells[2], value, cells[3]
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn compare_json(
+    actual: &Value,
+    expected: &Value,
+    tolerance: f64,
+    path: &str,
+) -> Result<(), String> {
+    let is_eq = match (actual, expected) {
+        (Value::Number(a), Value::Number(e)) => {
+            float_eq(a.as_f64().unwrap(), e.as_f64().unwrap(), tolerance)
+        }
+        (Value::Array(a), Value::Array(e)) if a.len() == e.len() => {
+            for (i, (a, e)) in a.iter().zip(e).enumerate() {
+                compare_json(a, e, tolerance, &format!("{}[{}]", path, i))?;
+            }
+            true
+        }
+        (Value::Object(a), Value::Object(e)) if a.len() == e.len() => {
+            for (k, e) in e {
+                let a = a.get(k).unwrap_or(&Value::Null);
+                compare_json(a, e, tolerance, &format!("{}.{}", path, k))?;
+            }
+            true
+        }
+        (a, e) => a == e,
+    };
+    if is_eq {
+        Ok(())
+    } else {
+        Err(format!(
+            "The value of {} is {}, expected {}",
+            path, actual, expected
+        ))
+    }
+}
+
+// Run the script and compare the outputs with the expected outputs, or write the outputs
+// as the expected outputs if `bless` is true.
+fn run_golden(script_path: &Path, bless: bool) -> Result<(), String> {
+    let dir = script_path.parent().unwrap();
+    let name = script_path.file_stem().unwrap().to_str().unwrap();
+    let src = fs::read_to_string(script_path).map_err(|e| e.to_string())?;
+    let input_path = dir.join(format!("{}.input.csv", name));
+    let input_path = if input_path.exists() {
+        input_path
+    } else {
+        dir.join("bars.csv")
+    };
+    let data = read_input(&input_path)?;
+
+    // The v5 scripts use the builtin variables of v5 with the namespaces like ta and math.
+    let callback = PrintCollector::default();
+    let mut script = if script_version(&src) >= 5 {
+        PineScript::new(Some(&callback))
+    } else {
+        PineScript::new_with_libinfo(golden_lib_info(), Some(&callback))
+    };
+    // The drawings are collected by the output diff.
+    script.set_output_diff(true);
+    script
+        .parse_src(src.clone())
+        .map_err(|errs| format!("Parse errors {:?}", errs))?;
+    let output = script
+        .run_with_data(data, None)
+        .map_err(|err| format!("Runtime error {:?}", err))?;
+
+    let json_path = dir.join(format!("{}.out.json", name));
+    let csv_path = dir.join(format!("{}.out.csv", name));
+    let tolerance = parse_tolerance(&src);
+    if json_path.exists() {
+        let drawings = script
+            .output_diff()
+            .map_err(|err| format!("Output diff error {:?}", err))?
+            .drawings;
+        let prints = callback.prints.borrow();
+        let actual = if drawings.is_empty() && prints.is_empty() {
+            serde_json::to_value(&output.data_list).unwrap()
+        } else {
+            let mut actual = serde_json::json!({ "data_list": output.data_list });
+            if !drawings.is_empty() {
+                actual["drawings"] = serde_json::to_value(&drawings).unwrap();
+            }
+            if !prints.is_empty() {
+                actual["prints"] = serde_json::to_value(&*prints).unwrap();
+            }
+            actual
+        };
+        if bless {
+            let content = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+            return fs::write(&json_path, content).map_err(|e| e.to_string());
+        }
+        let expected = fs::read_to_string(&json_path).map_err(|e| e.to_string())?;
+        let expected: Value = serde_json::from_str(&expected).map_err(|e| e.to_string())?;
+        compare_json(&actual, &expected, tolerance, "outputs")
+    } else {
+        let actual = to_csv(&output.data_list, output.from);
+        check_reference(dir, name, &actual, tolerance)?;
+        if bless {
+            return fs::write(&csv_path, actual).map_err(|e| e.to_string());
+        }
+        let expected = fs::read_to_string(&csv_path).map_err(|_| {
+            format!(
+                "The expected outputs {} are missing, run with --bless to generate them",
+                csv_path.display()
+            )
+        })?;
+        compare_csv(&actual, &expected, tolerance)
+    }
+}
+
+fn main() {
+    // The arguments start with "-" like the test harness flags are ignored except --bless.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let bless = args.iter().any(|a| a == "--bless");
+    let filters: Vec<&String> = args.iter().filter(|a| !a.starts_with('-')).collect();
+
+    let mut scripts: Vec<PathBuf> = fs::read_dir(golden_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pine"))
+        .filter(|path| {
+            let name = path.file_stem().unwrap().to_str().unwrap();
+            filters.is_empty() || filters.iter().any(|f| name.contains(f.as_str()))
+        })
+        .collect();
+    scripts.sort();
+
+    println!("\nrunning {} golden tests", scripts.len());
+    let mut failures = vec![];
+    for path in scripts.iter() {
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        match run_golden(path, bless) {
+            Ok(_) => println!(
+                "test golden::{} ... {}",
+                name,
+                if bless { "blessed" } else { "ok" }
+            ),
+            Err(err) => {
+                println!("test golden::{} ... FAILED", name);
+                failures.push((name, err));
+            }
+        }
+    }
+    for (name, err) in failures.iter() {
+        println!("\n---- golden::{} ----\n{}", name, err);
+    }
+    println!(
+        "\ngolden test result: {}. {} passed; {} failed\n",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        scripts.len() - failures.len(),
+        failures.len()
+    );
+    if !failures.is_empty() {
+        process::exit(1);
+    }
+}